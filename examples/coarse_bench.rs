@@ -0,0 +1,79 @@
+//! 多分辨率分析 (`coarse_hop_factor`) 的耗时对比
+//!
+//! 合成一段 10 分钟的 44.1 kHz 录音: 每 10 秒中前若干秒为 -12 dB 噪声，其余为 -70 dB 底噪，
+//! 分别在不同的有声占比下比较逐帧分析与粗扫描的 `frame_silence` 耗时。
+//!
+//! 本机 release 构建的测量: 步长 x8 在有声占比 0%/10%/50%/90% 下分别快约 3.3/3.1/3.1/3.2 倍
+//! (RMS) 与 4.0/3.1/3.2/3.3 倍 (Hybrid)。
+//!
+//! ```text
+//! cargo run --release --example coarse_bench
+//! ```
+
+use audio_learning::{DetectionMetric, Slicer, SlicerConfig};
+use std::time::Instant;
+
+const SAMPLE_RATE: usize = 44100;
+const SECONDS: usize = 600;
+const FACTORS: [u32; 3] = [1, 8, 32];
+
+/// 固定种子的均匀噪声，取值范围 [-1, 1)
+fn noise(state: &mut u64) -> f32 {
+    *state = state
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    (*state >> 40) as f32 / (1u32 << 23) as f32 - 1.0
+}
+
+fn synth(voiced_secs_per_10: usize) -> Vec<f32> {
+    let mut state = 12345;
+    (0..SAMPLE_RATE * SECONDS)
+        .map(|i| {
+            let voiced = (i / SAMPLE_RATE) % 10 < voiced_secs_per_10;
+            let amp = if voiced { 0.25 } else { 0.0003 };
+            amp * noise(&mut state)
+        })
+        .collect()
+}
+
+/// 三次运行中最短的耗时 (ms)
+fn time_ms(slicer: &Slicer, samples: &[f32]) -> f64 {
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(slicer.frame_silence(samples));
+            start.elapsed().as_secs_f64() * 1000.0
+        })
+        .fold(f64::MAX, f64::min)
+}
+
+fn main() -> anyhow::Result<()> {
+    for voiced in [0, 1, 5, 9] {
+        let samples = synth(voiced);
+        for metric in [DetectionMetric::Rms, DetectionMetric::Hybrid] {
+            let mut row = Vec::new();
+            let mut base = 0.0;
+            for factor in FACTORS {
+                let slicer = Slicer::new(SlicerConfig {
+                    sample_rate: SAMPLE_RATE as u32,
+                    threshold_db: -40.0,
+                    min_length_ms: 5000,
+                    min_interval_ms: 300,
+                    hop_size_ms: 10,
+                    max_silence_ms: 1000,
+                    metric,
+                    peak_threshold_db: -35.0,
+                    coarse_hop_factor: factor,
+                    refine_cuts: false,
+                })?;
+                let ms = time_ms(&slicer, &samples);
+                if factor == 1 {
+                    base = ms;
+                }
+                row.push(format!("x{factor}: {ms:.0} ms ({:.2}x)", base / ms));
+            }
+            println!("有声 {:>2}% {metric:?}: {}", voiced * 10, row.join(", "));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,239 @@
+/// 混响估计使用的能量帧长 (ms)
+const RT60_FRAME_MS: u32 = 10;
+/// 参与拟合的衰减段最少连续帧数
+const MIN_DECAY_FRAMES: usize = 5;
+/// 参与拟合的衰减段最小动态范围 (dB)
+const MIN_DECAY_RANGE_DB: f32 = 10.0;
+/// 能量取对数时的下限，避免 log(0)
+const ENERGY_FLOOR: f32 = 1e-12;
+
+/// 粗略估计切片的混响时间 RT60 (秒)
+///
+/// 在 10ms 帧能量包络上寻找连续下降的自由衰减段，对每段做线性回归得到衰减斜率 (dB/s)，
+/// 外推到衰减 60dB 所需的时间，取各段结果的中位数。找不到有效衰减段时返回 `None`。
+pub fn estimate_rt60(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let frame_len = (sample_rate * RT60_FRAME_MS / 1000) as usize;
+    if frame_len == 0 || samples.len() < frame_len * MIN_DECAY_FRAMES {
+        return None;
+    }
+
+    // 帧能量 (dB)
+    let energy_db: Vec<f32> = samples
+        .chunks_exact(frame_len)
+        .map(|frame| {
+            let energy = frame.iter().map(|&x| x * x).sum::<f32>() / frame_len as f32;
+            10.0 * energy.max(ENERGY_FLOOR).log10()
+        })
+        .collect();
+
+    let frame_secs = RT60_FRAME_MS as f32 / 1000.0;
+    let mut estimates = Vec::new();
+    let mut run_start = 0;
+
+    for i in 1..=energy_db.len() {
+        // 连续下降段在此处结束
+        if i < energy_db.len() && energy_db[i] < energy_db[i - 1] {
+            continue;
+        }
+
+        let run = &energy_db[run_start..i];
+        if run.len() >= MIN_DECAY_FRAMES && run[0] - run[run.len() - 1] >= MIN_DECAY_RANGE_DB {
+            let slope = decay_slope(run, frame_secs);
+            if slope < 0.0 {
+                estimates.push(-60.0 / slope);
+            }
+        }
+        run_start = i;
+    }
+
+    if estimates.is_empty() {
+        return None;
+    }
+
+    estimates.sort_by(|a, b| a.total_cmp(b));
+    Some(estimates[estimates.len() / 2])
+}
+
+/// 最小二乘拟合衰减段斜率 (dB/s)
+fn decay_slope(run: &[f32], frame_secs: f32) -> f32 {
+    let n = run.len() as f32;
+    let mean_t = (n - 1.0) / 2.0 * frame_secs;
+    let mean_db = run.iter().sum::<f32>() / n;
+
+    let mut cov = 0.0;
+    let mut var = 0.0;
+    for (i, &db) in run.iter().enumerate() {
+        let dt = i as f32 * frame_secs - mean_t;
+        cov += dt * (db - mean_db);
+        var += dt * dt;
+    }
+
+    if var > 0.0 { cov / var } else { 0.0 }
+}
+
+/// 掌声/笑声分类的分析帧长 (ms)
+const APPLAUSE_FRAME_MS: u32 = 20;
+/// 参与分类的帧相对切片最响帧的电平下限 (dB)
+const APPLAUSE_ACTIVE_RANGE_DB: f32 = 30.0;
+/// 参与分类的帧的绝对电平下限 (dBFS)
+const APPLAUSE_ACTIVE_FLOOR_DB: f32 = -60.0;
+/// 噪声状帧的最低过零率 (每样本)；白噪声约为 0.5，浊音通常低于 0.15
+const NOISE_MIN_ZCR: f32 = 0.25;
+/// 噪声状帧在基音范围内的最大归一化自相关；浊音通常高于 0.5
+const NOISE_MAX_PERIODICITY: f32 = 0.3;
+/// 自相关分析的等效采样率，高采样率输入按整数步长抽取
+const PERIODICITY_RATE: u32 = 8000;
+
+/// 估计切片为掌声/笑声等宽带噪声爆发的可能性 (0~1)
+///
+/// 以 20ms 帧分析，过零率高且在 80~400Hz 基音范围内没有明显周期性的帧视为噪声状，
+/// 返回噪声状帧在有效帧 (不低于最响帧 30dB 且高于 -60dBFS) 中的占比。
+/// 语音中的清辅音也是噪声状的，但只占少数帧。没有有效帧时返回 `None`。
+pub fn applause_score(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let frame_len = (sample_rate * APPLAUSE_FRAME_MS / 1000) as usize;
+    if frame_len == 0 {
+        return None;
+    }
+    let energy_db = |frame: &[f32]| {
+        let energy = frame.iter().map(|&x| x * x).sum::<f32>() / frame.len() as f32;
+        10.0 * energy.max(ENERGY_FLOOR).log10()
+    };
+    let frames: Vec<(&[f32], f32)> = samples
+        .chunks_exact(frame_len)
+        .map(|frame| (frame, energy_db(frame)))
+        .collect();
+    let loudest = frames.iter().map(|&(_, db)| db).fold(f32::MIN, f32::max);
+    let floor = (loudest - APPLAUSE_ACTIVE_RANGE_DB).max(APPLAUSE_ACTIVE_FLOOR_DB);
+
+    let stride = (sample_rate / PERIODICITY_RATE).max(1) as usize;
+    let min_lag = (sample_rate / 400) as usize / stride;
+    let max_lag = (sample_rate / 80) as usize / stride;
+
+    let mut active = 0;
+    let mut noisy = 0;
+    for &(frame, db) in &frames {
+        if db < floor {
+            continue;
+        }
+        active += 1;
+        let crossings = frame
+            .windows(2)
+            .filter(|pair| (pair[0] >= 0.0) != (pair[1] >= 0.0))
+            .count();
+        if (crossings as f32 / frame_len as f32) < NOISE_MIN_ZCR {
+            continue;
+        }
+        let decimated: Vec<f32> = frame.iter().step_by(stride).copied().collect();
+        if periodicity(&decimated, min_lag.max(1), max_lag) < NOISE_MAX_PERIODICITY {
+            noisy += 1;
+        }
+    }
+    (active > 0).then(|| noisy as f32 / active as f32)
+}
+
+/// 在 `min_lag..=max_lag` 范围内的最大归一化自相关
+fn periodicity(frame: &[f32], min_lag: usize, max_lag: usize) -> f32 {
+    let energy: f32 = frame.iter().map(|&x| x * x).sum();
+    if energy <= 0.0 {
+        return 0.0;
+    }
+    (min_lag..=max_lag.min(frame.len().saturating_sub(1)))
+        .map(|lag| {
+            let correlation: f32 = frame.iter().zip(&frame[lag..]).map(|(a, b)| a * b).sum();
+            correlation / energy
+        })
+        .fold(0.0, f32::max)
+}
+
+/// 拼接静音区间生成噪声样本，总长度不超过 `max_samples`
+///
+/// `ranges` 为样本区间 `(start, end)`，超出 `samples` 的部分会被截断。
+pub fn extract_noise_profile(
+    samples: &[f32],
+    ranges: &[(usize, usize)],
+    max_samples: usize,
+) -> Vec<f32> {
+    let mut profile = Vec::new();
+    for &(start, end) in ranges {
+        let end = end.min(samples.len());
+        if start >= end {
+            continue;
+        }
+        let take = (end - start).min(max_samples - profile.len());
+        profile.extend_from_slice(&samples[start..start + take]);
+        if profile.len() >= max_samples {
+            break;
+        }
+    }
+    profile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{applause_score, estimate_rt60, extract_noise_profile};
+
+    /// 生成按指定 RT60 指数衰减的信号
+    fn decaying_signal(rt60: f32, sample_rate: u32, secs: f32) -> Vec<f32> {
+        let len = (sample_rate as f32 * secs) as usize;
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+                sign * 10f32.powf(-3.0 * t / rt60)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn silence_has_no_estimate() {
+        assert_eq!(estimate_rt60(&vec![0.0; 16000], 16000), None);
+    }
+
+    #[test]
+    fn too_short_has_no_estimate() {
+        assert_eq!(estimate_rt60(&[0.5; 100], 16000), None);
+    }
+
+    #[test]
+    fn recovers_synthetic_decay() {
+        let samples = decaying_signal(0.5, 16000, 0.6);
+        let rt60 = estimate_rt60(&samples, 16000).unwrap();
+        assert!((rt60 - 0.5).abs() < 0.05, "估计值 {rt60:.3}s 偏差过大");
+    }
+
+    #[test]
+    fn applause_is_noise_like_and_voice_is_not() {
+        let sample_rate = 16000;
+        let mut rng = 1u32;
+        let noise: Vec<f32> = (0..sample_rate)
+            .map(|_| {
+                rng = rng.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (rng >> 16) as f32 / 32768.0 - 1.0
+            })
+            .collect();
+        // 150Hz 的谐波信号近似浊音
+        let voiced: Vec<f32> = (0..sample_rate)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (1..8)
+                    .map(|k| (std::f32::consts::TAU * 150.0 * k as f32 * t).sin() / k as f32)
+                    .sum::<f32>()
+                    * 0.3
+            })
+            .collect();
+
+        assert!(applause_score(&noise, sample_rate).unwrap() > 0.9);
+        assert!(applause_score(&voiced, sample_rate).unwrap() < 0.1);
+        assert_eq!(applause_score(&[0.0; 16000], sample_rate), None);
+        assert_eq!(applause_score(&[0.5; 10], sample_rate), None);
+    }
+
+    #[test]
+    fn noise_profile_respects_limit() {
+        let samples: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let profile = extract_noise_profile(&samples, &[(10, 20), (50, 200)], 15);
+        assert_eq!(profile.len(), 15);
+        assert_eq!(profile[0], 10.0);
+        assert_eq!(profile[10], 50.0);
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::manifest::{FileManifest, MANIFEST_FILE_NAME, read_manifest};
+
+/// 写到通告通道的一个切片
+#[derive(Debug, Serialize)]
+pub struct SliceAnnouncement<'a> {
+    /// 切片文件的完整路径 (位于最终输出目录)
+    pub path: String,
+    pub source: &'a str,
+    pub index: usize,
+    pub start_sec: f64,
+    pub end_sec: f64,
+    pub duration_sec: f64,
+    pub rms_db: f32,
+    pub peak_db: f32,
+    pub voiced_ratio: f32,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: &'a BTreeMap<String, String>,
+}
+
+/// 切片通告通道: 每个完成的切片以一行 JSON 写到 Unix 套接字或 Windows 命名管道
+///
+/// 本地的消费进程无需轮询文件系统。写入失败 (如消费端退出) 后不再通告，不影响处理。
+pub struct Announcer {
+    channel: Mutex<Option<Box<dyn Write + Send>>>,
+}
+
+impl Announcer {
+    /// 连接到 Unix 套接字
+    #[cfg(unix)]
+    pub fn connect(path: &Path) -> Result<Self> {
+        let stream = std::os::unix::net::UnixStream::connect(path)
+            .map_err(|e| anyhow::anyhow!("无法连接通告套接字 {}: {e}", path.display()))?;
+        Ok(Self::new(Box::new(stream)))
+    }
+
+    /// 打开命名管道 (如 `\\.\pipe\slices`)
+    #[cfg(not(unix))]
+    pub fn connect(path: &Path) -> Result<Self> {
+        let pipe = std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| anyhow::anyhow!("无法打开通告管道 {}: {e}", path.display()))?;
+        Ok(Self::new(Box::new(pipe)))
+    }
+
+    fn new(channel: Box<dyn Write + Send>) -> Self {
+        Self {
+            channel: Mutex::new(Some(channel)),
+        }
+    }
+
+    /// 通告 `dir` 中清单记录的所有切片，切片路径以 `dir` 为准
+    pub fn announce_dir(&self, dir: &Path) -> Result<()> {
+        let manifest = read_manifest(&dir.join(MANIFEST_FILE_NAME))?;
+        self.announce(dir, &manifest);
+        Ok(())
+    }
+
+    fn announce(&self, dir: &Path, manifest: &FileManifest) {
+        let mut lines = String::new();
+        for (index, record) in manifest.slices.iter().enumerate() {
+            let announcement = SliceAnnouncement {
+                path: dir.join(&record.file).to_string_lossy().into_owned(),
+                source: &manifest.source,
+                index,
+                start_sec: record.start_sec,
+                end_sec: record.end_sec,
+                duration_sec: record.duration_sec,
+                rms_db: record.rms_db,
+                peak_db: record.peak_db,
+                voiced_ratio: record.voiced_ratio,
+                metadata: &record.metadata,
+            };
+            if let Ok(line) = serde_json::to_string(&announcement) {
+                lines.push_str(&line);
+                lines.push('\n');
+            }
+        }
+        // 同一文件的切片一次写入，多个处理线程的通告不会交错
+        let mut channel = self.channel.lock().unwrap();
+        if let Some(writer) = channel.as_mut()
+            && writer
+                .write_all(lines.as_bytes())
+                .and_then(|_| writer.flush())
+                .is_err()
+        {
+            *channel = None;
+        }
+    }
+
+    /// 通道是否已因写入失败而关闭
+    pub fn is_closed(&self) -> bool {
+        self.channel.lock().unwrap().is_none()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::Announcer;
+    use crate::manifest::test_manifest;
+    use std::io::{BufRead, BufReader};
+    use std::path::Path;
+
+    #[test]
+    fn writes_one_json_line_per_slice() {
+        let path = std::env::temp_dir().join(format!("announce_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let announcer = Announcer::connect(&path).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let manifest = test_manifest(&["slice_000.wav", "slice_001.wav"]);
+        announcer.announce(Path::new("/out/a"), &manifest);
+        drop(announcer);
+
+        let lines: Vec<serde_json::Value> = BufReader::new(stream)
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["path"], "/out/a/slice_001.wav");
+        assert_eq!(lines[1]["index"], 1);
+        assert_eq!(lines[0]["source"], "a.wav");
+    }
+}
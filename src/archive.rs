@@ -0,0 +1,642 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use symphonia::core::io::MediaSource;
+
+use crate::audio::{
+    ContainerTimeline, DecodedAudio, DecodedMonoI16, decode_mono_i16_until, decode_mono_streaming,
+    decode_source_head, decode_source_until,
+};
+use crate::scan::is_audio_file;
+
+/// 可以直接读取的压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// 检查文件是否为支持的压缩包 (zip、tar、tar.gz/tgz)
+pub fn is_archive_file(path: &Path) -> bool {
+    ArchiveKind::of(path).is_some()
+}
+
+/// 压缩包成员名转换为相对路径；绝对路径或含 `..` 的成员 (可能写到输出目录之外) 返回 `None`
+fn member_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+fn open_tar(archive: &Path, kind: ArchiveKind) -> Result<tar::Archive<Box<dyn Read>>> {
+    let file = BufReader::new(File::open(archive)?);
+    let reader: Box<dyn Read> = match kind {
+        ArchiveKind::TarGz => Box::new(flate2::read::GzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// 成员数据在压缩包中的存放位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MemberData {
+    /// 未压缩 (tar 成员、zip 中存储的成员)：数据在压缩包文件中的偏移与长度
+    Stored { offset: u64, len: u64 },
+    /// zip 中 deflate 压缩的成员：压缩数据在压缩包文件中的偏移与长度，以及解压后的长度
+    Deflated {
+        offset: u64,
+        compressed_len: u64,
+        len: u64,
+    },
+    /// tar.gz 成员：数据在解压后的 tar 流中的偏移与长度
+    Gzipped { offset: u64, len: u64 },
+    /// 加密或使用了其他压缩方法的 zip 成员
+    Unsupported,
+}
+
+/// 压缩包中各文件成员的位置，成员名已转换为相对路径
+type ArchiveIndex = HashMap<PathBuf, MemberData>;
+
+/// 已建立的压缩包索引；扫描时建立，解码成员时按索引直接定位，不再从头遍历压缩包
+static INDEXES: Mutex<BTreeMap<PathBuf, Arc<ArchiveIndex>>> = Mutex::new(BTreeMap::new());
+
+/// 按成员顺序列出压缩包中的文件成员及其位置
+///
+/// 只读取 zip 的中央目录或 tar 的各成员头，不解压音频数据 (tar.gz 需要解压一遍)。
+fn read_index(archive: &Path) -> Result<Vec<(PathBuf, MemberData)>> {
+    let kind = ArchiveKind::of(archive)
+        .ok_or_else(|| anyhow::anyhow!("不支持的压缩包格式: {}", archive.display()))?;
+    let mut members = Vec::new();
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(BufReader::new(File::open(archive)?))?;
+            for i in 0..zip.len() {
+                let file = zip.by_index_raw(i)?;
+                let Some(path) = member_path(file.name()).filter(|_| !file.is_dir()) else {
+                    continue;
+                };
+                let data = match file.compression() {
+                    _ if file.encrypted() => MemberData::Unsupported,
+                    zip::CompressionMethod::Stored => MemberData::Stored {
+                        offset: file.data_start(),
+                        len: file.size(),
+                    },
+                    zip::CompressionMethod::Deflated => MemberData::Deflated {
+                        offset: file.data_start(),
+                        compressed_len: file.compressed_size(),
+                        len: file.size(),
+                    },
+                    _ => MemberData::Unsupported,
+                };
+                members.push((path, data));
+            }
+        }
+        ArchiveKind::Tar | ArchiveKind::TarGz => {
+            let mut tar = open_tar(archive, kind)?;
+            for entry in tar.entries()? {
+                let entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let Some(path) = member_path(&entry.path()?.to_string_lossy()) else {
+                    continue;
+                };
+                let (offset, len) = (entry.raw_file_position(), entry.size());
+                let data = if kind == ArchiveKind::TarGz {
+                    MemberData::Gzipped { offset, len }
+                } else {
+                    MemberData::Stored { offset, len }
+                };
+                members.push((path, data));
+            }
+        }
+    }
+    Ok(members)
+}
+
+/// 记录压缩包索引；同名成员以第一个为准
+fn remember_index(archive: &Path, members: &[(PathBuf, MemberData)]) -> Arc<ArchiveIndex> {
+    let mut index = ArchiveIndex::new();
+    for (path, data) in members {
+        index.entry(path.clone()).or_insert(*data);
+    }
+    let index = Arc::new(index);
+    INDEXES
+        .lock()
+        .unwrap()
+        .insert(archive.to_path_buf(), index.clone());
+    index
+}
+
+/// 压缩包的索引，尚未建立时读取一遍压缩包
+fn archive_index(archive: &Path) -> Result<Arc<ArchiveIndex>> {
+    if let Some(index) = INDEXES.lock().unwrap().get(archive) {
+        return Ok(index.clone());
+    }
+    Ok(remember_index(archive, &read_index(archive)?))
+}
+
+/// 列出压缩包中的音频成员，返回 `压缩包路径/成员路径` 形式的虚拟路径 (按成员顺序)
+///
+/// 同时记下各成员的位置，之后解码成员时直接定位。
+pub fn list_audio_members(archive: &Path) -> Result<Vec<PathBuf>> {
+    let members = read_index(archive)?;
+    remember_index(archive, &members);
+    Ok(members
+        .into_iter()
+        .map(|(member, _)| member)
+        .filter(|member| is_audio_file(member))
+        .map(|member| archive.join(member))
+        .collect())
+}
+
+/// 虚拟路径所在的压缩包与成员的相对路径，不在压缩包内时返回 `None`
+pub fn split_archive_path(path: &Path) -> Option<(&Path, &Path)> {
+    path.ancestors()
+        .skip(1)
+        .find(|ancestor| is_archive_file(ancestor) && ancestor.is_file())
+        .map(|archive| (archive, path.strip_prefix(archive).unwrap_or(path)))
+}
+
+/// 按 `SeekFrom` 计算长度为 `len` 的数据中的新位置
+fn seek_position(pos: u64, len: u64, to: SeekFrom) -> io::Result<u64> {
+    match to {
+        SeekFrom::Start(offset) => Some(offset),
+        SeekFrom::End(delta) => len.checked_add_signed(delta),
+        SeekFrom::Current(delta) => pos.checked_add_signed(delta),
+    }
+    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "定位到数据开头之前"))
+}
+
+/// 未压缩的成员：直接在压缩包文件中读取与定位
+struct StoredMember {
+    file: File,
+    offset: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl StoredMember {
+    fn open(archive: &Path, offset: u64, len: u64) -> io::Result<Self> {
+        let mut file = File::open(archive)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(Self {
+            file,
+            offset,
+            len,
+            pos: 0,
+        })
+    }
+}
+
+impl Read for StoredMember {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let want = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        let read = self.file.read(&mut buf[..want])?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for StoredMember {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        self.pos = seek_position(self.pos, self.len, to)?;
+        self.file.seek(SeekFrom::Start(self.offset + self.pos))?;
+        Ok(self.pos)
+    }
+}
+
+impl MediaSource for StoredMember {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.len)
+    }
+}
+
+/// 只能顺序读取的解压流，记录已读出的字节数
+struct Inflater {
+    reader: Box<dyn Read + Send + Sync>,
+    pos: u64,
+}
+
+impl Inflater {
+    /// 向前跳到解压流中的 `target` 位置
+    fn skip_to(&mut self, target: u64) -> io::Result<()> {
+        let skip = target - self.pos;
+        io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+        if self.pos < target {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+}
+
+impl Read for Inflater {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+/// 归还的 tar.gz 解压流 (压缩包, 流)，按归还顺序排列
+///
+/// 处理队列按成员顺序取出同一压缩包的成员，下一个成员从上一个成员用过的流的当前位置
+/// 继续读取，每个压缩包只需解压一遍，而不是每个成员都从头解压。
+static GZIP_STREAMS: Mutex<Vec<(PathBuf, Inflater)>> = Mutex::new(Vec::new());
+
+/// 最多保留的归还流数 (每个流占用一个文件句柄与解压窗口)，超出时丢弃最早归还的
+const MAX_GZIP_STREAMS: usize = 16;
+
+/// 取出位置不超过 `target` 且最靠后的 tar.gz 解压流，没有时从头打开
+fn take_gzip_stream(archive: &Path, target: u64) -> io::Result<Inflater> {
+    let mut streams = GZIP_STREAMS.lock().unwrap();
+    let best = streams
+        .iter()
+        .enumerate()
+        .filter(|(_, (path, stream))| path == archive && stream.pos <= target)
+        .max_by_key(|(_, (_, stream))| stream.pos)
+        .map(|(i, _)| i);
+    if let Some(i) = best {
+        return Ok(streams.remove(i).1);
+    }
+    drop(streams);
+    let file = BufReader::new(File::open(archive)?);
+    Ok(Inflater {
+        reader: Box::new(flate2::read::GzDecoder::new(file)),
+        pos: 0,
+    })
+}
+
+fn return_gzip_stream(archive: PathBuf, stream: Inflater) {
+    let mut streams = GZIP_STREAMS.lock().unwrap();
+    streams.push((archive, stream));
+    if streams.len() > MAX_GZIP_STREAMS {
+        streams.remove(0);
+    }
+}
+
+/// 压缩的成员 (zip deflate、tar.gz)：边读边解压
+///
+/// 向前定位时跳过解压数据；向后定位 (如 moov 在文件末尾的 MP4) 时换用位置更靠前的流
+/// 或重新打开。
+struct InflatedMember {
+    archive: PathBuf,
+    data: MemberData,
+    len: u64,
+    pos: u64,
+    stream: Option<Inflater>,
+}
+
+impl InflatedMember {
+    fn new(archive: &Path, data: MemberData) -> Self {
+        let len = match data {
+            MemberData::Deflated { len, .. } | MemberData::Gzipped { len, .. } => len,
+            _ => 0,
+        };
+        Self {
+            archive: archive.to_path_buf(),
+            data,
+            len,
+            pos: 0,
+            stream: None,
+        }
+    }
+
+    /// 当前位置在解压流中的偏移
+    fn target(&self) -> u64 {
+        match self.data {
+            MemberData::Gzipped { offset, .. } => offset + self.pos,
+            _ => self.pos,
+        }
+    }
+
+    fn open_stream(&self, target: u64) -> io::Result<Inflater> {
+        match self.data {
+            MemberData::Gzipped { .. } => take_gzip_stream(&self.archive, target),
+            MemberData::Deflated {
+                offset,
+                compressed_len,
+                ..
+            } => {
+                let mut file = File::open(&self.archive)?;
+                file.seek(SeekFrom::Start(offset))?;
+                Ok(Inflater {
+                    reader: Box::new(flate2::read::DeflateDecoder::new(
+                        BufReader::new(file).take(compressed_len),
+                    )),
+                    pos: 0,
+                })
+            }
+            _ => Err(io::ErrorKind::Unsupported.into()),
+        }
+    }
+
+    /// 定位到当前位置的解压流
+    fn stream(&mut self) -> io::Result<&mut Inflater> {
+        let target = self.target();
+        if self
+            .stream
+            .as_ref()
+            .is_some_and(|stream| stream.pos > target)
+        {
+            self.release();
+        }
+        let stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => self.open_stream(target)?,
+        };
+        let stream = self.stream.insert(stream);
+        stream.skip_to(target)?;
+        Ok(stream)
+    }
+
+    /// 归还 tar.gz 解压流供之后的成员继续使用
+    fn release(&mut self) {
+        if let Some(stream) = self.stream.take()
+            && matches!(self.data, MemberData::Gzipped { .. })
+        {
+            return_gzip_stream(self.archive.clone(), stream);
+        }
+    }
+}
+
+impl Drop for InflatedMember {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+impl Read for InflatedMember {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let want = buf
+            .len()
+            .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        if want == 0 {
+            return Ok(0);
+        }
+        let read = self.stream()?.read(&mut buf[..want])?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for InflatedMember {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        // 实际的跳过推迟到下一次读取
+        self.pos = seek_position(self.pos, self.len, to)?;
+        Ok(self.pos)
+    }
+}
+
+impl MediaSource for InflatedMember {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.len)
+    }
+}
+
+/// 打开压缩包中的一个成员，边读边解压，不把整个成员读入内存
+fn open_member(archive: &Path, member: &Path) -> Result<Box<dyn MediaSource>> {
+    let index = archive_index(archive)?;
+    let data = *index
+        .get(member)
+        .ok_or_else(|| anyhow::anyhow!("压缩包中没有 {}", member.display()))?;
+    Ok(match data {
+        MemberData::Stored { offset, len } => Box::new(StoredMember::open(archive, offset, len)?),
+        MemberData::Deflated { .. } | MemberData::Gzipped { .. } => {
+            Box::new(InflatedMember::new(archive, data))
+        }
+        MemberData::Unsupported => {
+            anyhow::bail!("{} 已加密或使用了不支持的压缩方法", member.display())
+        }
+    })
+}
+
+/// 打开输入文件: 压缩包成员的虚拟路径从压缩包中读取 (不解压到磁盘)，其余按普通文件打开
+fn open_input(path: &Path) -> Result<Box<dyn MediaSource>> {
+    match split_archive_path(path) {
+        Some((archive, member)) => open_member(archive, member)
+            .map_err(|e| anyhow::anyhow!("无法从压缩包 {} 读取成员: {e}", archive.display())),
+        None => Ok(Box::new(File::open(path)?)),
+    }
+}
+
+/// 按声道解码输入文件 (可以是压缩包成员)
+pub fn decode_input(path: &Path, deadline: Option<Instant>) -> Result<DecodedAudio> {
+    decode_source_until(open_input(path)?, deadline)
+}
+
+/// 只解码输入文件 (可以是压缩包成员) 开头约 `secs` 秒
+pub fn decode_input_head(path: &Path, secs: f64) -> Result<DecodedAudio> {
+    decode_source_head(open_input(path)?, secs)
+}
+
+/// 流式解码输入文件 (可以是压缩包成员)，逐包交出单声道样本，见 `decode_mono_streaming`
+pub fn decode_input_streaming(
+    path: &Path,
+    deadline: Option<Instant>,
+    on_samples: impl FnMut(&[f32], u32) -> Result<()>,
+) -> Result<(u32, Option<ContainerTimeline>)> {
+    decode_mono_streaming(open_input(path)?, deadline, on_samples)
+}
+
+/// 解码输入文件 (可以是压缩包成员) 并在解码时混音为单声道 16 位整数
+pub fn decode_input_i16(path: &Path, deadline: Option<Instant>) -> Result<DecodedMonoI16> {
+    decode_mono_i16_until(open_input(path)?, deadline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        GZIP_STREAMS, decode_input, list_audio_members, member_path, open_input, split_archive_path,
+    };
+    use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+    use std::path::{Path, PathBuf};
+
+    fn wav_bytes(samples: &[i16]) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn rejects_members_outside_the_archive() {
+        assert_eq!(member_path("./a/b.wav"), Some(PathBuf::from("a/b.wav")));
+        assert_eq!(member_path("../b.wav"), None);
+        assert_eq!(member_path("/etc/b.wav"), None);
+        assert_eq!(member_path("./"), None);
+    }
+
+    #[test]
+    fn lists_and_decodes_zip_and_tar_members() {
+        let dir = std::env::temp_dir().join(format!("slicer_archive_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wav = wav_bytes(&[0, 16384, -16384, 8192]);
+        let files: [(&str, &[u8]); 4] = [
+            ("speaker/a.wav", &wav),
+            ("notes.txt", b"text"),
+            ("../evil.wav", &wav),
+            ("b.wav", &wav),
+        ];
+
+        let zip_path = dir.join("corpus.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        for (i, (name, bytes)) in files.into_iter().enumerate() {
+            // 交替使用 deflate 压缩与直接存储
+            let method = if i % 2 == 0 {
+                zip::CompressionMethod::Deflated
+            } else {
+                zip::CompressionMethod::Stored
+            };
+            let options = zip::write::SimpleFileOptions::default().compression_method(method);
+            zip.start_file(name, options).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let tar_path = dir.join("corpus.tar");
+        write_tar(&tar_path, &files);
+        let tgz_path = dir.join("corpus.tgz");
+        let tar_bytes = std::fs::read(&tar_path).unwrap();
+        let mut gz = flate2::write::GzEncoder::new(
+            std::fs::File::create(&tgz_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        gz.write_all(&tar_bytes).unwrap();
+        gz.finish().unwrap();
+
+        for archive in [&zip_path, &tar_path, &tgz_path] {
+            let members = list_audio_members(archive).unwrap();
+            assert_eq!(
+                members,
+                vec![archive.join("speaker/a.wav"), archive.join("b.wav")]
+            );
+            let (found, member) = split_archive_path(&members[0]).unwrap();
+            assert_eq!(
+                (found, member),
+                (archive.as_path(), Path::new("speaker/a.wav"))
+            );
+            let decoded = decode_input(&members[0], None).unwrap();
+            assert_eq!(decoded.sample_rate, 16000);
+            assert_eq!(decoded.channels[0], [0.0, 0.5, -0.5, 0.25]);
+            assert!(decode_input(&archive.join("missing.wav"), None).is_err());
+        }
+        assert!(split_archive_path(&dir.join("plain/a.wav")).is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn write_tar(path: &Path, files: &[(&str, &[u8])]) {
+        let mut tar = tar::Builder::new(std::fs::File::create(path).unwrap());
+        for (name, bytes) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            // append_data 拒绝 `..`，直接写入成员名以模拟恶意压缩包
+            header.as_gnu_mut().unwrap().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_cksum();
+            tar.append(&header, *bytes).unwrap();
+        }
+        tar.finish().unwrap();
+    }
+
+    #[test]
+    fn members_stream_and_seek() {
+        let dir = std::env::temp_dir().join(format!("slicer_archive_seek_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let members: Vec<(String, Vec<u8>)> = (0..5)
+            .map(|i| {
+                let bytes = (0..20000u32).map(|j| (j * 7 + i) as u8).collect();
+                (format!("{i}.wav"), bytes)
+            })
+            .collect();
+        let files: Vec<(&str, &[u8])> = members
+            .iter()
+            .map(|(name, bytes)| (name.as_str(), bytes.as_slice()))
+            .collect();
+        let tar_path = dir.join("seek.tar");
+        write_tar(&tar_path, &files);
+        let tgz_path = dir.join("seek.tar.gz");
+        let mut gz = flate2::write::GzEncoder::new(
+            std::fs::File::create(&tgz_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        gz.write_all(&std::fs::read(&tar_path).unwrap()).unwrap();
+        gz.finish().unwrap();
+
+        for archive in [&tar_path, &tgz_path] {
+            list_audio_members(archive).unwrap();
+            for (name, bytes) in &members {
+                let mut source = open_input(&archive.join(name)).unwrap();
+                assert_eq!(source.byte_len(), Some(bytes.len() as u64));
+                let mut head = [0; 100];
+                source.seek(SeekFrom::End(-100)).unwrap();
+                source.read_exact(&mut head).unwrap();
+                assert_eq!(head, bytes[bytes.len() - 100..]);
+                source.seek(SeekFrom::Start(0)).unwrap();
+                let mut all = Vec::new();
+                source.read_to_end(&mut all).unwrap();
+                assert_eq!(&all, bytes);
+            }
+        }
+        // 按顺序读取 tar.gz 的成员时复用同一个解压流
+        let pooled = GZIP_STREAMS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(path, _)| path == &tgz_path)
+            .count();
+        assert!(pooled <= 2, "{pooled}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
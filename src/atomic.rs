@@ -0,0 +1,266 @@
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+use std::fs::{File, TryLockError};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// 数据集根目录下的锁文件名
+pub const LOCK_FILE_NAME: &str = ".slicer.lock";
+
+/// 整个运行期间持有的输出目录锁文件名
+pub const RUN_LOCK_FILE_NAME: &str = ".slicer.run.lock";
+/// append 模式下认领单个文件输出目录的锁文件名
+pub const CLAIM_FILE_NAME: &str = ".slicer.claim";
+
+/// 本进程内临时文件名的序号
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 安全模式 (`--safe`): 开启后本进程只创建新文件，覆盖或删除已有文件的操作一律报错
+static SAFE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// 开启本进程的安全模式
+pub fn enable_safe_mode() {
+    SAFE_MODE.store(true, Ordering::Relaxed);
+}
+
+/// 安全模式下 `path` 已存在 (含失效的符号链接) 时报错，写入目标之前调用
+pub fn check_overwrite(path: &Path) -> Result<()> {
+    if SAFE_MODE.load(Ordering::Relaxed) && path.symlink_metadata().is_ok() {
+        bail!("安全模式: 拒绝覆盖已存在的文件 {}", path.display());
+    }
+    Ok(())
+}
+
+/// 安全模式下拒绝删除已有文件
+pub fn check_removal(path: &Path) -> Result<()> {
+    if SAFE_MODE.load(Ordering::Relaxed) {
+        bail!("安全模式: 拒绝删除 {}", path.display());
+    }
+    Ok(())
+}
+
+/// 创建文件用于写入；安全模式下以独占方式创建，目标已存在时报错而不截断
+pub fn create_file(path: &Path) -> Result<File> {
+    if !SAFE_MODE.load(Ordering::Relaxed) {
+        return Ok(File::create(path)?);
+    }
+    File::create_new(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => {
+            anyhow::anyhow!("安全模式: 拒绝覆盖已存在的文件 {}", path.display())
+        }
+        _ => e.into(),
+    })
+}
+
+/// 原子地写入文件: 先写到同一目录下的临时文件并落盘，再重命名覆盖目标
+///
+/// 临时文件与目标位于同一目录 (同一文件系统)，重命名是原子的: 读取方只会看到
+/// 完整的旧文件或完整的新文件，写入中途崩溃也不会留下截断的索引。
+/// 安全模式下目标已存在时报错。
+pub fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<()> {
+    check_overwrite(path)?;
+    let temp = temp_path(path);
+    let result = (|| -> Result<()> {
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        write(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        std::fs::rename(&temp, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let serial = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{name}.{}.{serial}.tmp", std::process::id()))
+}
+
+/// 数据集级别的排他锁，持有期间其他进程 (及本进程的其他线程) 对同一数据集的
+/// 清单与索引更新会等待；释放 (drop) 时自动解锁
+///
+/// 锁文件保留在目录中，删除它会让等待中的进程锁住已失效的文件。
+pub struct IndexLock {
+    _file: File,
+}
+
+impl IndexLock {
+    /// 阻塞直到获得 `root` 目录的锁
+    pub fn acquire(root: &Path) -> Result<Self> {
+        std::fs::create_dir_all(root)?;
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(root.join(LOCK_FILE_NAME))?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// 输出目录已被另一运行占用时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OnLocked {
+    /// 立即报错退出
+    #[default]
+    Fail,
+    /// 等待另一运行结束
+    Wait,
+    /// 以追加模式加入: 可与其他 append 模式的运行同时写入，每个文件由先认领的运行处理
+    Append,
+}
+
+/// 单写入者保护: 运行期间持有的输出目录锁
+///
+/// 普通运行持有排他锁并把进程号写入锁文件；append 模式的运行持有共享锁，
+/// 彼此可以共存，但不能与普通运行同时进行。
+pub struct RunLock {
+    _file: File,
+}
+
+impl RunLock {
+    /// 获取输出目录锁；`OnLocked::Wait` 需要等待时先以持有者描述调用 `on_wait`，
+    /// 由调用方决定提示的输出位置
+    pub fn acquire(output: &Path, on_locked: OnLocked, on_wait: impl FnOnce(&str)) -> Result<Self> {
+        std::fs::create_dir_all(output)?;
+        let path = output.join(RUN_LOCK_FILE_NAME);
+        let mut file = File::options()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+        let attempt = if on_locked == OnLocked::Append {
+            file.try_lock_shared()
+        } else {
+            file.try_lock()
+        };
+        match attempt {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) if on_locked == OnLocked::Wait => {
+                on_wait(&holder(&mut file));
+                file.lock()?;
+            }
+            Err(TryLockError::WouldBlock) => {
+                let hint = if on_locked == OnLocked::Append {
+                    "，append 模式只能与其他 append 模式的运行同时进行"
+                } else {
+                    "；可使用 --on-locked wait 等待，或让所有运行都使用 --on-locked append"
+                };
+                return Err(anyhow::anyhow!(
+                    "输出目录正被另一运行使用{} ({}){hint}",
+                    holder(&mut file),
+                    path.display()
+                ));
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        if on_locked != OnLocked::Append {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            writeln!(file, "{}", std::process::id())?;
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+/// 锁文件中记录的持有者进程号
+fn holder(file: &mut File) -> String {
+    let mut contents = String::new();
+    let _ = file.read_to_string(&mut contents);
+    match contents.trim() {
+        "" => String::new(),
+        pid => format!(" (进程 {pid})"),
+    }
+}
+
+/// append 模式下认领一个文件的输出目录；已被另一运行认领时返回 `None`
+///
+/// 返回的锁在该文件处理完成 (输出移动到位) 前应一直持有。锁只表示正在处理，
+/// 处理是否已完成由调用方按目录中的清单判断。认领文件已存在时不截断 (安全模式下也可认领)。
+pub fn claim_dir(dir: &Path) -> Result<Option<File>> {
+    std::fs::create_dir_all(dir)?;
+    let file = File::options()
+        .create(true)
+        .append(true)
+        .open(dir.join(CLAIM_FILE_NAME))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CLAIM_FILE_NAME, IndexLock, LOCK_FILE_NAME, OnLocked, RunLock, claim_dir, write_atomic,
+    };
+    use std::fs::File;
+    use std::io::Write;
+
+    #[test]
+    fn failed_write_keeps_old_contents() {
+        let dir = std::env::temp_dir().join(format!("slicer_atomic_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("manifest.json");
+        write_atomic(&path, |writer| Ok(writer.write_all(b"old")?)).unwrap();
+
+        let failed = write_atomic(&path, |writer| {
+            writer.write_all(b"partial")?;
+            Err(anyhow::anyhow!("中途失败"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"old");
+        // 只剩目标文件，没有残留的临时文件
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lock_is_exclusive() {
+        let dir = std::env::temp_dir().join(format!("slicer_lock_{}", std::process::id()));
+        let lock = IndexLock::acquire(&dir).unwrap();
+        let other = File::open(dir.join(LOCK_FILE_NAME)).unwrap();
+        assert!(other.try_lock().is_err());
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_lock_modes() {
+        let dir = std::env::temp_dir().join(format!("slicer_run_lock_{}", std::process::id()));
+        let first = RunLock::acquire(&dir, OnLocked::Fail, |_| {}).unwrap();
+        let error = RunLock::acquire(&dir, OnLocked::Fail, |_| {})
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains(&std::process::id().to_string()));
+        assert!(RunLock::acquire(&dir, OnLocked::Append, |_| {}).is_err());
+        drop(first);
+
+        let a = RunLock::acquire(&dir, OnLocked::Append, |_| {}).unwrap();
+        let b = RunLock::acquire(&dir, OnLocked::Append, |_| {}).unwrap();
+        assert!(RunLock::acquire(&dir, OnLocked::Fail, |_| {}).is_err());
+        drop((a, b));
+
+        let file_dir = dir.join("speaker/rec");
+        let claim = claim_dir(&file_dir).unwrap();
+        assert!(claim.is_some());
+        assert!(claim_dir(&file_dir).unwrap().is_none());
+        drop(claim);
+        // 再次认领不截断已有的认领文件
+        std::fs::write(file_dir.join(CLAIM_FILE_NAME), b"x").unwrap();
+        assert!(claim_dir(&file_dir).unwrap().is_some());
+        assert_eq!(std::fs::read(file_dir.join(CLAIM_FILE_NAME)).unwrap(), b"x");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,239 +1,1053 @@
 use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs::File;
+use std::ops::Range;
 use std::path::Path;
-use symphonia::core::audio::{AudioBufferRef, Signal};
+use std::time::Instant;
+use symphonia::core::audio::{AudioBuffer, Signal};
 use symphonia::core::codecs::DecoderOptions;
 use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision};
 use symphonia::core::probe::Hint;
 
-/// 读取音频文件并解码
-pub fn load_audio<P: AsRef<Path>>(path: P) -> Result<(Vec<f32>, u32)> {
-    let file = File::open(path)?;
-    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+/// 检测与统计可以直接读取的样本类型，读取时逐个换算为 [-1, 1] 的浮点值
+///
+/// 整数样本按需换算，检测不必为整个文件保留一份浮点副本。
+pub trait Sample: Copy + Send + Sync {
+    fn to_f32(self) -> f32;
+}
 
-    let mut hint = Hint::new();
-    hint.with_extension("wav");
+impl Sample for f32 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
 
-    let probed = symphonia::default::get_probe().format(
-        &hint,
-        mss,
-        &FormatOptions::default(),
-        &MetadataOptions::default(),
-    )?;
+impl Sample for i16 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self as f32 / 32768.0
+    }
+}
 
-    let mut format = probed.format;
-    let track = format.default_track().unwrap();
-    let mut decoder =
-        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+/// 加载后整个源文件在内存中的样本格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SampleStorage {
+    /// 32 位浮点，保留各声道，支持所有功能
+    #[default]
+    F32,
+    /// 解码时直接混音为单声道 16 位整数，内存占用为浮点单声道的一半；
+    /// 检测时逐窗口换算，只在切片时把切片范围转换为浮点
+    I16,
+}
 
-    let sample_rate = track.codec_params.sample_rate.unwrap();
-    let mut samples = Vec::new();
+/// 整个源文件的单声道样本
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceSamples {
+    F32(Vec<f32>),
+    I16(Vec<i16>),
+}
 
-    while let Ok(packet) = format.next_packet() {
-        let buffer = decoder.decode(&packet)?;
-        match buffer {
-            AudioBufferRef::F32(buf) => {
-                process_f32_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::U8(buf) => {
-                process_u8_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::U16(buf) => {
-                process_u16_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::U24(buf) => {
-                process_u24_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::U32(buf) => {
-                process_u32_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::S8(buf) => {
-                process_s8_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::S16(buf) => {
-                process_s16_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::S24(buf) => {
-                process_s24_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::S32(buf) => {
-                process_s32_buffer(&buf, &mut samples);
-            }
-            AudioBufferRef::F64(buf) => {
-                process_f64_buffer(&buf, &mut samples);
-            }
+impl SourceSamples {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::F32(samples) => samples.len(),
+            Self::I16(samples) => samples.len(),
         }
     }
 
-    Ok((samples, sample_rate))
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `range` 内的浮点样本，浮点存储时直接借用
+    pub fn range(&self, range: Range<usize>) -> Cow<'_, [f32]> {
+        match self {
+            Self::F32(samples) => Cow::Borrowed(&samples[range]),
+            Self::I16(samples) => samples[range].iter().map(|x| x.to_f32()).collect(),
+        }
+    }
+
+    /// 全部浮点样本，整数存储时需要转换出一份完整副本
+    pub fn to_f32(&self) -> Cow<'_, [f32]> {
+        self.range(0..self.len())
+    }
 }
 
-fn process_f32_buffer(buf: &symphonia::core::audio::AudioBuffer<f32>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += buf.chan(c)[i];
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
+/// 解码时混音为 16 位整数的单声道音频
+#[derive(Debug, Clone)]
+pub struct DecodedMonoI16 {
+    pub samples: Vec<i16>,
+    pub sample_rate: u32,
+    pub timeline: Option<ContainerTimeline>,
+    /// 混音时按 0 计入的非有限样本数
+    pub non_finite: usize,
+}
+
+/// 非有限样本 (NaN/inf) 的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum NanPolicy {
+    /// 替换为 0
+    #[default]
+    Zero,
+    /// 视为错误，该文件处理失败
+    Fail,
+    /// 检测时跳过包含非有限样本的帧 (视为静音)，输出时替换为 0
+    SkipFrame,
+}
+
+/// 按策略处理非有限样本，返回发现的非有限样本数
+///
+/// `SkipFrame` 策略下不修改样本，由切片器在检测时跳过相应帧。
+pub fn apply_nan_policy(samples: &mut [f32], policy: NanPolicy) -> Result<usize> {
+    let count = samples.iter().filter(|x| !x.is_finite()).count();
+    if count == 0 {
+        return Ok(0);
+    }
+    match policy {
+        NanPolicy::Zero => replace_non_finite(samples),
+        NanPolicy::Fail => return Err(anyhow::anyhow!("音频包含 {count} 个 NaN/inf 样本")),
+        NanPolicy::SkipFrame => {}
+    }
+    Ok(count)
+}
+
+/// 将非有限样本替换为 0
+pub fn replace_non_finite(samples: &mut [f32]) {
+    for sample in samples.iter_mut().filter(|x| !x.is_finite()) {
+        *sample = 0.0;
+    }
+}
+
+/// 静音检测使用的信号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum DetectOn {
+    /// 所有声道的平均 (与输出相同)
+    #[default]
+    Mix,
+    /// 中置信号 (L+R)/2，适合人声居中的音乐素材
+    Mid,
+    /// 侧信号 (L-R)/2
+    Side,
+    /// 指定声道 (从 0 开始)，如领夹麦所在的声道
+    Channel(usize),
+    /// 每个样本取绝对值最大的声道，任一声道有声即视为有声
+    Max,
+}
+
+impl std::str::FromStr for DetectOn {
+    type Err = String;
+
+    /// 解析 `mix`、`mid`、`side`、`max` 或 `ch:N`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mix" => Ok(DetectOn::Mix),
+            "mid" => Ok(DetectOn::Mid),
+            "side" => Ok(DetectOn::Side),
+            "max" => Ok(DetectOn::Max),
+            other => other
+                .strip_prefix("ch:")
+                .and_then(|index| index.parse().ok())
+                .map(DetectOn::Channel)
+                .ok_or_else(|| format!("无效的检测信号: {s} (可选 mix、mid、side、max、ch:N)")),
         }
-    } else {
-        samples.extend_from_slice(buf.chan(0));
     }
 }
 
-fn process_u8_buffer(buf: &symphonia::core::audio::AudioBuffer<u8>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += (buf.chan(c)[i] as f32 - 128.0) / 128.0;
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
+impl std::fmt::Display for DetectOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DetectOn::Mix => write!(f, "mix"),
+            DetectOn::Mid => write!(f, "mid"),
+            DetectOn::Side => write!(f, "side"),
+            DetectOn::Channel(index) => write!(f, "ch:{index}"),
+            DetectOn::Max => write!(f, "max"),
         }
+    }
+}
+
+/// 单声道混音的相位抵消检查
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum PhaseCheck {
+    /// 不检查
+    Off,
+    /// 抵消严重时给出警告
+    #[default]
+    Warn,
+    /// 抵消严重时给出警告，并改为按各声道最大值检测 (等同 `--detect-on max`)
+    Auto,
+}
+
+/// 输出切片的声道布局，静音检测不受影响 (仍按 `--detect-on` / `--channel-map`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum OutputChannels {
+    /// 所有声道平均为单声道
+    #[default]
+    Mono,
+    /// 保留源文件的所有声道
+    Keep,
+    /// 只输出第一个声道
+    Left,
+    /// 只输出第二个声道 (单声道输入时为唯一的声道)
+    Right,
+}
+
+/// 一侧人声、一侧伴奏的双声道音频 (卡拉OK音轨) 的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum KaraokeMode {
+    /// 不判断
+    Off,
+    /// 自动判断，判定为卡拉OK音轨时改用人声声道
+    #[default]
+    Auto,
+    /// 人声固定在第一个声道
+    Left,
+    /// 人声固定在第二个声道
+    Right,
+}
+
+/// 卡拉OK判定的能量帧长 (ms)
+const KARAOKE_FRAME_MS: u32 = 50;
+/// 两声道相关系数的绝对值不超过该值才视为内容不同
+const KARAOKE_MAX_CORRELATION: f64 = 0.3;
+/// 比声道的响帧 (95 百分位) 低该值以上的帧视为安静帧 (dB)
+const KARAOKE_QUIET_DB: f32 = 20.0;
+/// 人声声道与伴奏声道安静帧占比之差的下限
+const KARAOKE_MIN_QUIET_GAP: f32 = 0.2;
+
+/// 判定为卡拉OK音轨的双声道音频
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KaraokeSplit {
+    /// 人声所在的声道 (0 或 1)
+    pub vocal_channel: usize,
+    /// 两声道的相关系数
+    pub correlation: f32,
+    /// 人声声道与伴奏声道的安静帧占比之差
+    pub quiet_gap: f32,
+}
+
+/// 判断双声道音频是否一侧为人声、另一侧为伴奏
+///
+/// 两声道内容不同 (相关系数低)，且一侧明显更常出现安静帧 (人声有换气与间奏，
+/// 伴奏几乎连续) 时，该侧为人声声道。非双声道、某一声道全静音或不满足条件时返回 `None`。
+pub fn detect_karaoke(audio: &DecodedAudio) -> Option<KaraokeSplit> {
+    let [left, right] = audio.channels.as_slice() else {
+        return None;
+    };
+    let correlation = correlation(left, right)?;
+    if correlation.abs() > KARAOKE_MAX_CORRELATION {
+        return None;
+    }
+    let frame_len = (audio.sample_rate * KARAOKE_FRAME_MS / 1000).max(1) as usize;
+    let left_quiet = quiet_fraction(left, frame_len)?;
+    let right_quiet = quiet_fraction(right, frame_len)?;
+    let (vocal_channel, quiet_gap) = if left_quiet >= right_quiet {
+        (0, left_quiet - right_quiet)
     } else {
-        for &sample in buf.chan(0) {
-            samples.push((sample as f32 - 128.0) / 128.0);
-        }
+        (1, right_quiet - left_quiet)
+    };
+    (quiet_gap >= KARAOKE_MIN_QUIET_GAP).then_some(KaraokeSplit {
+        vocal_channel,
+        correlation: correlation as f32,
+        quiet_gap,
+    })
+}
+
+/// 皮尔逊相关系数，任一信号为常数时返回 `None`
+fn correlation(a: &[f32], b: &[f32]) -> Option<f64> {
+    let n = a.len().min(b.len());
+    if n == 0 {
+        return None;
     }
+    let mean = |x: &[f32]| x[..n].iter().map(|&v| f64::from(v)).sum::<f64>() / n as f64;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (&x, &y) in a[..n].iter().zip(&b[..n]) {
+        let (dx, dy) = (f64::from(x) - mean_a, f64::from(y) - mean_b);
+        cov += dx * dy;
+        var_a += dx * dx;
+        var_b += dy * dy;
+    }
+    Some(cov / (var_a * var_b).sqrt()).filter(|r| r.is_finite() && var_a > 0.0 && var_b > 0.0)
 }
 
-fn process_u16_buffer(buf: &symphonia::core::audio::AudioBuffer<u16>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += (buf.chan(c)[i] as f32 - 32768.0) / 32768.0;
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
+/// 安静帧 (比 95 百分位帧能量低 `KARAOKE_QUIET_DB` 以上) 的占比，声道全静音时返回 `None`
+fn quiet_fraction(samples: &[f32], frame_len: usize) -> Option<f32> {
+    let mut energy_db: Vec<f32> = samples
+        .chunks_exact(frame_len)
+        .map(|frame| {
+            let energy = frame.iter().map(|&x| x * x).sum::<f32>() / frame_len as f32;
+            10.0 * energy.max(1e-12).log10()
+        })
+        .collect();
+    if energy_db.is_empty() {
+        return None;
+    }
+    energy_db.sort_by(f32::total_cmp);
+    let loud = energy_db[(energy_db.len() - 1) * 95 / 100];
+    if loud <= -110.0 {
+        return None;
+    }
+    let quiet = energy_db.partition_point(|&db| db < loud - KARAOKE_QUIET_DB);
+    Some(quiet as f32 / energy_db.len() as f32)
+}
+
+/// 解码后的多声道音频 (按声道平面存储)
+pub struct DecodedAudio {
+    pub channels: Vec<Vec<f32>>,
+    pub sample_rate: u32,
+    /// 容器时间戳，容器不提供时间基时为 `None`
+    pub timeline: Option<ContainerTimeline>,
+}
+
+/// 解码样本位置与容器时间戳的对应关系
+///
+/// 只在数据包的时间戳与按采样率推算的值不连续 (如可变速率流、流中的间断) 时
+/// 记录锚点，连续的流只有一个锚点。
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerTimeline {
+    /// 时间基 `[分子, 分母]`，一个时间戳单位为 分子/分母 秒
+    pub time_base: [u32; 2],
+    sample_rate: u32,
+    /// (样本位置, 时间戳)，按样本位置递增
+    anchors: Vec<(usize, u64)>,
+}
+
+impl ContainerTimeline {
+    pub fn new(time_base: [u32; 2], sample_rate: u32) -> Self {
+        Self {
+            time_base,
+            sample_rate,
+            anchors: Vec::new(),
         }
-    } else {
-        for &sample in buf.chan(0) {
-            samples.push((sample as f32 - 32768.0) / 32768.0);
+    }
+
+    /// 记录从样本位置 `sample` 开始的数据包的时间戳，与推算值相差超过 1 个单位时才新增锚点
+    pub fn record(&mut self, sample: usize, ts: u64) {
+        if self.anchors.is_empty() || self.timestamp(sample).abs_diff(ts) > 1 {
+            self.anchors.push((sample, ts));
         }
     }
+
+    /// 样本位置对应的容器时间戳 (四舍五入到时间基)
+    pub fn timestamp(&self, sample: usize) -> u64 {
+        let index = self.anchors.partition_point(|&(start, _)| start <= sample);
+        let Some(&(start, ts)) = index.checked_sub(1).and_then(|i| self.anchors.get(i)) else {
+            return 0;
+        };
+        let [numer, denom] = self.time_base.map(u128::from);
+        let scale = numer * u128::from(self.sample_rate);
+        let offset = ((sample - start) as u128 * denom + scale / 2) / scale;
+        ts + offset as u64
+    }
+
+    /// 时间戳与样本位置一一对应 (如 WAV)，无需另外记录
+    pub fn is_identity(&self) -> bool {
+        self.time_base == [1, self.sample_rate]
+            && self.anchors.iter().all(|&(start, ts)| start as u64 == ts)
+    }
 }
 
-fn process_u24_buffer(
-    buf: &symphonia::core::audio::AudioBuffer<symphonia::core::sample::u24>,
-    samples: &mut Vec<f32>,
-) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                let sample_value = buf.chan(c)[i].inner() as f32;
-                sum += (sample_value - 8388608.0) / 8388608.0;
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
+impl DecodedAudio {
+    /// 所有声道取平均得到单声道信号
+    pub fn downmix(&self) -> Vec<f32> {
+        mix_channels(&self.channels)
+    }
+
+    /// 按 `--channels` 取出单个输出声道，`Mono` 与 `Keep` 时为 `None`
+    pub fn select_channel(&self, output: OutputChannels) -> Result<Option<Vec<f32>>> {
+        let index = match output {
+            OutputChannels::Mono | OutputChannels::Keep => return Ok(None),
+            OutputChannels::Left => 0,
+            OutputChannels::Right if self.channels.len() == 1 => 0,
+            OutputChannels::Right => 1,
+        };
+        match self.channels.get(index) {
+            Some(channel) => Ok(Some(channel.clone())),
+            None => Err(anyhow::anyhow!("输入没有声道 {index}")),
         }
-    } else {
-        for &sample in buf.chan(0) {
-            let sample_value = sample.inner() as f32;
-            samples.push((sample_value - 8388608.0) / 8388608.0);
+    }
+}
+
+/// 平面存储的各声道取平均得到单声道信号
+pub fn mix_channels(channels: &[Vec<f32>]) -> Vec<f32> {
+    match channels {
+        [] => Vec::new(),
+        [mono] => mono.clone(),
+        channels => {
+            let scale = 1.0 / channels.len() as f32;
+            (0..channels[0].len())
+                .map(|i| channels.iter().map(|c| c[i]).sum::<f32>() * scale)
+                .collect()
         }
     }
 }
 
-fn process_u32_buffer(buf: &symphonia::core::audio::AudioBuffer<u32>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += (buf.chan(c)[i] as f32 - 2147483648.0) / 2147483648.0;
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
+/// 单声道混音相对各声道平均能量的衰减 (dB，正值表示混音更弱)
+///
+/// 反相的立体声在混音时相互抵消，整个文件会被判定为静音。单声道或全部静音时返回 `None`。
+pub fn downmix_cancellation_db(audio: &DecodedAudio, mix: &[f32]) -> Option<f32> {
+    if audio.channels.len() < 2 {
+        return None;
+    }
+    let mean_square = |samples: &[f32]| {
+        samples
+            .iter()
+            .map(|&x| f64::from(x) * f64::from(x))
+            .sum::<f64>()
+            / samples.len().max(1) as f64
+    };
+    let channels =
+        audio.channels.iter().map(|c| mean_square(c)).sum::<f64>() / audio.channels.len() as f64;
+    if channels <= 0.0 {
+        return None;
+    }
+    let ratio = mean_square(mix).max(1e-20) / channels;
+    Some((-10.0 * ratio.log10()) as f32)
+}
+
+/// 生成用于检测的信号，与输出的单声道混音相同时返回 `None`
+///
+/// 中置/侧信号取前两个声道计算，单声道输入时退化为混音；指定的声道不存在时返回错误。
+pub fn detection_signal(audio: &DecodedAudio, detect_on: DetectOn) -> Result<Option<Vec<f32>>> {
+    let channels = audio.channels.as_slice();
+    if let DetectOn::Channel(index) = detect_on
+        && index >= channels.len().max(1)
+    {
+        return Err(anyhow::anyhow!(
+            "检测声道 ch:{index} 不存在 (输入共 {} 个声道)",
+            channels.len()
+        ));
+    }
+    let [left, right, ..] = channels else {
+        return Ok(None);
+    };
+    let sign = match detect_on {
+        DetectOn::Mix => return Ok(None),
+        DetectOn::Channel(index) => return Ok(Some(channels[index].clone())),
+        DetectOn::Max => {
+            return Ok(Some(
+                (0..left.len())
+                    .map(|i| {
+                        channels
+                            .iter()
+                            .map(|channel| channel[i])
+                            .fold(0.0f32, |a, b| if b.abs() > a.abs() { b } else { a })
+                    })
+                    .collect(),
+            ));
         }
-    } else {
-        for &sample in buf.chan(0) {
-            samples.push((sample as f32 - 2147483648.0) / 2147483648.0);
+        DetectOn::Mid => 1.0,
+        DetectOn::Side => -1.0,
+    };
+    Ok(Some(
+        left.iter()
+            .zip(right)
+            .map(|(&l, &r)| (l + sign * r) * 0.5)
+            .collect(),
+    ))
+}
+
+/// 声道的加权混合，权重归一化为和 1
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelMix {
+    weights: Vec<(usize, f32)>,
+}
+
+impl ChannelMix {
+    /// 按权重混合为单声道，引用的声道不存在时返回错误
+    pub fn apply(&self, audio: &DecodedAudio) -> Result<Vec<f32>> {
+        let channels = audio.channels.as_slice();
+        if let Some(&(index, _)) = self
+            .weights
+            .iter()
+            .find(|(index, _)| *index >= channels.len())
+        {
+            return Err(anyhow::anyhow!(
+                "声道映射引用的声道 {index} 不存在 (输入共 {} 个声道)",
+                channels.len()
+            ));
         }
+        let length = channels.first().map_or(0, Vec::len);
+        Ok((0..length)
+            .map(|i| {
+                self.weights
+                    .iter()
+                    .map(|&(index, weight)| channels[index][i] * weight)
+                    .sum()
+            })
+            .collect())
     }
 }
 
-fn process_s8_buffer(buf: &symphonia::core::audio::AudioBuffer<i8>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += buf.chan(c)[i] as f32 / 128.0;
+impl std::str::FromStr for ChannelMix {
+    type Err = String;
+
+    /// 解析以逗号分隔的 `N` 或 `N:权重`，省略的权重为 1
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Vec::new();
+        for part in s.split(',') {
+            let (index, weight) = part.split_once(':').unwrap_or((part, "1"));
+            let index: usize = index
+                .trim()
+                .parse()
+                .map_err(|_| format!("无效的声道序号: {part}"))?;
+            let weight: f32 = weight
+                .trim()
+                .parse()
+                .ok()
+                .filter(|weight: &f32| weight.is_finite() && *weight >= 0.0)
+                .ok_or_else(|| format!("无效的声道权重: {part}"))?;
+            if weights.iter().any(|&(other, _)| other == index) {
+                return Err(format!("声道 {index} 重复出现"));
             }
-            samples.push(sum / buf.spec().channels.count() as f32);
+            weights.push((index, weight));
         }
-    } else {
-        for &sample in buf.chan(0) {
-            samples.push(sample as f32 / 128.0);
+        let total: f32 = weights.iter().map(|(_, weight)| weight).sum();
+        if total <= 0.0 {
+            return Err(format!("声道权重之和必须大于 0: {s}"));
+        }
+        for (_, weight) in &mut weights {
+            *weight /= total;
         }
+        Ok(Self { weights })
     }
 }
 
-fn process_s16_buffer(buf: &symphonia::core::audio::AudioBuffer<i16>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += buf.chan(c)[i] as f32 / 32768.0;
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
+impl std::fmt::Display for ChannelMix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self
+            .weights
+            .iter()
+            .map(|(index, weight)| format!("{index}:{weight:.3}"))
+            .collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// 多声道输入的声道映射: 检测信号与输出各自使用的声道及权重
+///
+/// 未指定的一侧保持默认行为 (输出为所有声道平均，检测按 `--detect-on`)。
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChannelMap {
+    pub detect: Option<ChannelMix>,
+    pub output: Option<ChannelMix>,
+}
+
+impl std::str::FromStr for ChannelMap {
+    type Err = String;
+
+    /// 解析 `detect=<声道>;output=<声道>`，两部分均可省略其一
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut map = Self::default();
+        for part in s.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+            let (target, mix) = part
+                .split_once('=')
+                .ok_or_else(|| format!("无效的声道映射: {part} (应为 detect=... 或 output=...)"))?;
+            let slot = match target.trim().to_ascii_lowercase().as_str() {
+                "detect" => &mut map.detect,
+                "output" => &mut map.output,
+                other => return Err(format!("未知的声道映射目标: {other} (可选 detect、output)")),
+            };
+            *slot = Some(mix.parse()?);
         }
-    } else {
-        for &sample in buf.chan(0) {
-            samples.push(sample as f32 / 32768.0);
+        if map == Self::default() {
+            return Err(format!("声道映射为空: {s}"));
         }
+        Ok(map)
     }
 }
 
-fn process_s24_buffer(
-    buf: &symphonia::core::audio::AudioBuffer<symphonia::core::sample::i24>,
-    samples: &mut Vec<f32>,
-) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                let sample_value = buf.chan(c)[i].inner() as f32;
-                sum += sample_value / 8388608.0;
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
-        }
-    } else {
-        for &sample in buf.chan(0) {
-            let sample_value = sample.inner() as f32;
-            samples.push(sample_value / 8388608.0);
+impl std::fmt::Display for ChannelMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = [("detect", &self.detect), ("output", &self.output)]
+            .into_iter()
+            .filter_map(|(target, mix)| mix.as_ref().map(|mix| format!("{target}={mix}")))
+            .collect();
+        write!(f, "{}", parts.join(";"))
+    }
+}
+
+/// 不解码音频即可读取的技术信息与标签
+#[derive(Debug, Clone, Default)]
+pub struct AudioInfo {
+    /// 编码格式的短名称 (如 `pcm_s16le`、`mp3`)
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+    pub bits_per_sample: Option<u32>,
+    /// 由容器声明的总帧数推算的时长，容器未声明时为 `None`
+    pub duration_sec: Option<f64>,
+    /// 标签 (标准键名优先，否则为容器中的原始键名)
+    pub tags: BTreeMap<String, String>,
+}
+
+/// 只读取容器头部与标签，不解码音频
+pub fn probe_audio(path: &Path) -> Result<AudioInfo> {
+    let file = File::open(path)?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let mut probed = symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+
+    let mut tags = BTreeMap::new();
+    let mut collect = |revision: &MetadataRevision| {
+        for tag in revision.tags() {
+            let key = match tag.std_key {
+                Some(key) => format!("{key:?}"),
+                None => tag.key.clone(),
+            };
+            tags.insert(key, tag.value.to_string());
         }
+    };
+    // 容器前的元数据 (如 ID3v2) 在前，容器内的元数据优先
+    if let Some(metadata) = probed.metadata.get()
+        && let Some(revision) = metadata.current()
+    {
+        collect(revision);
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        collect(revision);
+    }
+
+    let track = probed
+        .format
+        .default_track()
+        .ok_or_else(|| anyhow::anyhow!("文件中没有音轨"))?;
+    let params = &track.codec_params;
+    Ok(AudioInfo {
+        codec: symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map_or_else(
+                || params.codec.to_string(),
+                |codec| codec.short_name.to_string(),
+            ),
+        sample_rate: params.sample_rate,
+        channels: params.channels.map(|channels| channels.count()),
+        bits_per_sample: params.bits_per_sample,
+        duration_sec: params
+            .n_frames
+            .zip(params.sample_rate)
+            .map(|(frames, rate)| frames as f64 / rate as f64),
+        tags,
+    })
+}
+
+/// 原样读取 32 位浮点单声道 WAV 的样本，不经过解码器的格式转换
+///
+/// 文件不是 32 位浮点单声道 WAV 时返回 `None`。
+pub fn read_float_mono_wav(path: &Path) -> Result<Option<DecodedAudio>> {
+    let Ok(reader) = hound::WavReader::open(path) else {
+        return Ok(None);
+    };
+    let spec = reader.spec();
+    if spec.channels != 1
+        || spec.bits_per_sample != 32
+        || spec.sample_format != hound::SampleFormat::Float
+    {
+        return Ok(None);
     }
+    let samples = reader
+        .into_samples::<f32>()
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(DecodedAudio {
+        channels: vec![samples],
+        sample_rate: spec.sample_rate,
+        timeline: None,
+    }))
+}
+
+/// 读取音频文件并混音为单声道，返回 (样本, 采样率)
+pub fn load_audio<P: AsRef<Path>>(path: P) -> Result<(Vec<f32>, u32)> {
+    let decoded = decode_audio_until(path, None)?;
+    Ok((decoded.downmix(), decoded.sample_rate))
+}
+
+/// 读取音频文件并按声道解码，超过 `deadline` 时中止并返回错误
+pub fn decode_audio_until<P: AsRef<Path>>(
+    path: P,
+    deadline: Option<Instant>,
+) -> Result<DecodedAudio> {
+    decode_source_until(Box::new(File::open(path)?), deadline)
+}
+
+/// 从任意数据源 (如内存中的压缩包成员) 按声道解码，超过 `deadline` 时中止并返回错误
+pub fn decode_source_until(
+    source: Box<dyn MediaSource>,
+    deadline: Option<Instant>,
+) -> Result<DecodedAudio> {
+    decode_channels(source, deadline, None)
+}
+
+/// 只按声道解码开头约 `secs` 秒 (按整包截止)，用于不必完整解码的快速检查
+pub fn decode_source_head(source: Box<dyn MediaSource>, secs: f64) -> Result<DecodedAudio> {
+    decode_channels(source, None, Some(secs))
+}
+
+/// 按声道解码，超过 `deadline` 时中止；`max_secs` 为解码时长上限
+fn decode_channels(
+    source: Box<dyn MediaSource>,
+    deadline: Option<Instant>,
+    max_secs: Option<f64>,
+) -> Result<DecodedAudio> {
+    let mut channels: Vec<Vec<f32>> = Vec::new();
+    let (sample_rate, timeline) = decode_packets(source, deadline, max_secs, |buffer| {
+        let channel_count = buffer.spec().channels.count();
+        if channels.len() < channel_count {
+            channels.resize_with(channel_count, Vec::new);
+        }
+        for (c, channel) in channels.iter_mut().enumerate().take(channel_count) {
+            channel.extend_from_slice(buffer.chan(c));
+        }
+        Ok(())
+    })?;
+    Ok(DecodedAudio {
+        channels,
+        sample_rate,
+        timeline,
+    })
 }
 
-fn process_s32_buffer(buf: &symphonia::core::audio::AudioBuffer<i32>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
+/// 从任意数据源解码，解码过程中逐包把各声道平均并量化为 16 位整数
+///
+/// 不保留各声道的浮点样本，内存占用只有单声道 16 位样本本身。非有限样本按 0 计入混音。
+pub fn decode_mono_i16_until(
+    source: Box<dyn MediaSource>,
+    deadline: Option<Instant>,
+) -> Result<DecodedMonoI16> {
+    let mut samples = Vec::new();
+    let mut non_finite = 0;
+    let (sample_rate, timeline) = decode_packets(source, deadline, None, |buffer| {
+        let channel_count = buffer.spec().channels.count();
+        for i in 0..buffer.frames() {
             let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += buf.chan(c)[i] as f32 / 2147483648.0;
+            for c in 0..channel_count {
+                let sample = buffer.chan(c)[i];
+                if sample.is_finite() {
+                    sum += sample;
+                } else {
+                    non_finite += 1;
+                }
             }
-            samples.push(sum / buf.spec().channels.count() as f32);
+            samples.push(quantize_i16(sum / channel_count.max(1) as f32));
         }
-    } else {
-        for &sample in buf.chan(0) {
-            samples.push(sample as f32 / 2147483648.0);
+        Ok(())
+    })?;
+    Ok(DecodedMonoI16 {
+        samples,
+        sample_rate,
+        timeline,
+        non_finite,
+    })
+}
+
+/// 从任意数据源流式解码: 逐包把各声道平均为单声道后交给 `on_samples(样本, 采样率)`，
+/// 不保留已解码的样本，返回 (采样率, 容器时间戳)
+///
+/// 非有限样本按 0 计入混音。`on_samples` 返回错误时停止解码并返回该错误。
+pub fn decode_mono_streaming(
+    source: Box<dyn MediaSource>,
+    deadline: Option<Instant>,
+    mut on_samples: impl FnMut(&[f32], u32) -> Result<()>,
+) -> Result<(u32, Option<ContainerTimeline>)> {
+    let mut mono = Vec::new();
+    decode_packets(source, deadline, None, |buffer| {
+        let channel_count = buffer.spec().channels.count();
+        mono.clear();
+        mono.extend((0..buffer.frames()).map(|i| {
+            let sum: f32 = (0..channel_count)
+                .map(|c| buffer.chan(c)[i])
+                .filter(|x| x.is_finite())
+                .sum();
+            sum / channel_count.max(1) as f32
+        }));
+        on_samples(&mono, buffer.spec().rate)
+    })
+}
+
+/// [-1, 1] 的浮点样本量化为 16 位整数，超出范围的截断到满幅
+pub fn quantize_i16(sample: f32) -> i16 {
+    (sample * 32768.0).round().clamp(-32768.0, 32767.0) as i16
+}
+
+/// 逐个数据包解码默认音轨，每包转换为 f32 (整数格式按满幅归一化到 [-1, 1]) 后交给 `on_buffer`，
+/// 返回 (采样率, 容器时间戳)；给出 `max_secs` 时解码到该时长即停止
+fn decode_packets(
+    source: Box<dyn MediaSource>,
+    deadline: Option<Instant>,
+    max_secs: Option<f64>,
+    mut on_buffer: impl FnMut(&AudioBuffer<f32>) -> Result<()>,
+) -> Result<(u32, Option<ContainerTimeline>)> {
+    let mss = MediaSourceStream::new(source, Default::default());
+
+    let mut hint = Hint::new();
+    hint.with_extension("wav");
+
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        mss,
+        &FormatOptions::default(),
+        &MetadataOptions::default(),
+    )?;
+
+    let mut format = probed.format;
+    let track = format.default_track().unwrap();
+    let mut decoder =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let sample_rate = track.codec_params.sample_rate.unwrap();
+    let mut timeline = track
+        .codec_params
+        .time_base
+        .map(|base| ContainerTimeline::new([base.numer, base.denom], sample_rate));
+    let track_id = track.id;
+    let mut decoded_frames = 0;
+    let max_frames = max_secs.map(|secs| (secs * sample_rate as f64).ceil() as usize);
+
+    while let Ok(packet) = format.next_packet() {
+        if max_frames.is_some_and(|max_frames| decoded_frames >= max_frames) {
+            break;
+        }
+        check_deadline(deadline)?;
+        let buffer = decoder.decode(&packet)?;
+        if let Some(timeline) = &mut timeline
+            && packet.track_id() == track_id
+        {
+            timeline.record(decoded_frames, packet.ts());
         }
+        // 统一转换为 f32，整数格式按满幅归一化到 [-1, 1]
+        let mut converted = buffer.make_equivalent::<f32>();
+        buffer.convert(&mut converted);
+        decoded_frames += converted.frames();
+        on_buffer(&converted)?;
     }
+
+    Ok((sample_rate, timeline))
 }
 
-fn process_f64_buffer(buf: &symphonia::core::audio::AudioBuffer<f64>, samples: &mut Vec<f32>) {
-    if buf.spec().channels.count() > 1 {
-        for i in 0..buf.frames() {
-            let mut sum = 0.0;
-            for c in 0..buf.spec().channels.count() {
-                sum += buf.chan(c)[i] as f32;
-            }
-            samples.push(sum / buf.spec().channels.count() as f32);
+/// 超过截止时间时返回错误
+pub fn check_deadline(deadline: Option<Instant>) -> Result<()> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => Err(anyhow::anyhow!("处理超时")),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ChannelMap, ChannelMix, ContainerTimeline, DecodedAudio, DetectOn, SourceSamples,
+        decode_mono_i16_until, decode_source_until, detect_karaoke, detection_signal,
+        downmix_cancellation_db, quantize_i16,
+    };
+
+    fn stereo() -> DecodedAudio {
+        DecodedAudio {
+            channels: vec![vec![0.5, 0.2], vec![0.5, -0.2]],
+            sample_rate: 16000,
+            timeline: None,
         }
-    } else {
-        for &sample in buf.chan(0) {
-            samples.push(sample as f32);
+    }
+
+    #[test]
+    fn downmix_averages_channels() {
+        assert_eq!(stereo().downmix(), vec![0.5, 0.0]);
+    }
+
+    #[test]
+    fn mid_and_side_signals() {
+        let audio = stereo();
+        assert_eq!(detection_signal(&audio, DetectOn::Mix).unwrap(), None);
+        assert_eq!(
+            detection_signal(&audio, DetectOn::Mid).unwrap(),
+            Some(vec![0.5, 0.0])
+        );
+        assert_eq!(
+            detection_signal(&audio, DetectOn::Side).unwrap(),
+            Some(vec![0.0, 0.2])
+        );
+    }
+
+    #[test]
+    fn channel_and_max_signals() {
+        let audio = stereo();
+        assert_eq!(
+            detection_signal(&audio, DetectOn::Channel(1)).unwrap(),
+            Some(vec![0.5, -0.2])
+        );
+        assert!(detection_signal(&audio, DetectOn::Channel(2)).is_err());
+        let audio = DecodedAudio {
+            channels: vec![vec![0.1, -0.6], vec![-0.3, 0.2]],
+            sample_rate: 16000,
+            timeline: None,
+        };
+        assert_eq!(
+            detection_signal(&audio, DetectOn::Max).unwrap(),
+            Some(vec![-0.3, -0.6])
+        );
+    }
+
+    #[test]
+    fn container_timestamps() {
+        // 毫秒时间基，48kHz，每包 960 样本 (20ms)，第三包后有 1 秒间断
+        let mut timeline = ContainerTimeline::new([1, 1000], 48000);
+        for (i, ts) in [0, 20, 40, 1060, 1080].into_iter().enumerate() {
+            timeline.record(i * 960, ts);
+        }
+        assert_eq!(timeline.anchors.len(), 2);
+        assert_eq!(timeline.timestamp(480), 10);
+        assert_eq!(timeline.timestamp(3 * 960 + 480), 1070);
+        assert!(!timeline.is_identity());
+
+        let mut wav = ContainerTimeline::new([1, 16000], 16000);
+        wav.record(0, 0);
+        wav.record(1024, 1024);
+        assert!(wav.is_identity());
+        assert_eq!(wav.timestamp(5000), 5000);
+    }
+
+    #[test]
+    fn detects_phase_cancellation() {
+        let audio = DecodedAudio {
+            channels: vec![vec![0.5, -0.5, 0.5], vec![-0.49, 0.49, -0.49]],
+            sample_rate: 16000,
+            timeline: None,
+        };
+        let cancelled = downmix_cancellation_db(&audio, &audio.downmix()).unwrap();
+        assert!(cancelled > 30.0, "{cancelled}");
+
+        let audio = stereo();
+        let in_phase = downmix_cancellation_db(&audio, &audio.downmix()).unwrap();
+        assert!(in_phase.abs() < 3.0, "{in_phase}");
+        let mono = DecodedAudio {
+            channels: vec![vec![0.5]],
+            sample_rate: 16000,
+            timeline: None,
+        };
+        assert_eq!(downmix_cancellation_db(&mono, &[0.5]), None);
+    }
+
+    #[test]
+    fn parses_detect_on() {
+        assert_eq!("ch:0".parse(), Ok(DetectOn::Channel(0)));
+        assert_eq!("MAX".parse(), Ok(DetectOn::Max));
+        assert!("ch:x".parse::<DetectOn>().is_err());
+        assert_eq!(DetectOn::Channel(3).to_string(), "ch:3");
+    }
+
+    #[test]
+    fn channel_map_weights_and_validates() {
+        let map: ChannelMap = "detect=2;output=0,1,2:2".parse().unwrap();
+        assert_eq!(
+            map.to_string(),
+            "detect=2:1.000;output=0:0.250,1:0.250,2:0.500"
+        );
+        assert!("detect=0:-1".parse::<ChannelMap>().is_err());
+        assert!("detect=0,0".parse::<ChannelMap>().is_err());
+        assert!("mix=0".parse::<ChannelMap>().is_err());
+
+        let audio = DecodedAudio {
+            channels: vec![vec![0.4, 0.0], vec![0.0, 0.4], vec![0.2, 0.2]],
+            sample_rate: 16000,
+            timeline: None,
+        };
+        let output = map.output.unwrap().apply(&audio).unwrap();
+        assert!((output[0] - 0.2).abs() < 1e-6 && (output[1] - 0.2).abs() < 1e-6);
+        let lfe: ChannelMix = "3".parse().unwrap();
+        assert!(lfe.apply(&audio).is_err());
+    }
+
+    #[test]
+    fn finds_the_vocal_side_of_a_karaoke_track() {
+        let sample_rate = 16000;
+        // 人声: 0.5 秒唱、0.5 秒停；伴奏: 连续的噪声
+        let mut rng = 1u32;
+        let vocal: Vec<f32> = (0..sample_rate * 10)
+            .map(|i| {
+                let on = (i / (sample_rate / 2)) % 2 == 0;
+                if on {
+                    0.3 * (i as f32 * 0.07).sin()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let backing: Vec<f32> = (0..vocal.len())
+            .map(|_| {
+                rng = rng.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                ((rng >> 16) as f32 / 32768.0 - 1.0) * 0.1
+            })
+            .collect();
+        let audio = |channels| DecodedAudio {
+            channels,
+            sample_rate: sample_rate as u32,
+            timeline: None,
+        };
+
+        let split = detect_karaoke(&audio(vec![backing.clone(), vocal.clone()])).unwrap();
+        assert_eq!(split.vocal_channel, 1);
+        assert!(split.correlation.abs() < 0.1 && split.quiet_gap > 0.4);
+        // 两声道内容相同的普通立体声与单声道不判定
+        assert_eq!(
+            detect_karaoke(&audio(vec![vocal.clone(), vocal.clone()])),
+            None
+        );
+        assert_eq!(detect_karaoke(&audio(vec![vocal])), None);
+    }
+
+    #[test]
+    fn mono_falls_back_to_mix() {
+        let audio = DecodedAudio {
+            channels: vec![vec![0.1, 0.2]],
+            sample_rate: 16000,
+            timeline: None,
+        };
+        assert_eq!(detection_signal(&audio, DetectOn::Side).unwrap(), None);
+        assert_eq!(
+            detection_signal(&audio, DetectOn::Channel(0)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn decodes_stereo_into_mixed_integer_samples() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for [left, right] in [[1000i16, 3000], [-16384, -16384], [32767, -32768]] {
+            writer.write_sample(left).unwrap();
+            writer.write_sample(right).unwrap();
         }
+        writer.finalize().unwrap();
+        let bytes = cursor.into_inner();
+
+        let float =
+            decode_source_until(Box::new(std::io::Cursor::new(bytes.clone())), None).unwrap();
+        let mono = decode_mono_i16_until(Box::new(std::io::Cursor::new(bytes)), None).unwrap();
+        assert_eq!(mono.sample_rate, 8000);
+        assert_eq!(mono.samples, [2000, -16384, -1]);
+        let expected: Vec<i16> = float.downmix().into_iter().map(quantize_i16).collect();
+        assert_eq!(mono.samples, expected);
+
+        let samples = SourceSamples::I16(mono.samples);
+        assert_eq!(samples.len(), 3);
+        assert_eq!(*samples.range(1..2), [-0.5]);
     }
 }
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::atomic::{check_overwrite, create_file};
+use crate::manifest::SliceRecord;
+
+/// 抽检目录名
+pub const AUDIT_DIR_NAME: &str = "audit";
+/// 抽检指标文件名
+pub const AUDIT_CSV_FILE_NAME: &str = "audit.csv";
+
+/// 可供抽检的切片
+pub struct AuditCandidate {
+    /// 源音频文件路径
+    pub source: String,
+    /// 切片文件所在目录
+    pub dir: PathBuf,
+    pub record: SliceRecord,
+}
+
+/// SplitMix64，固定种子下结果可复现
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// 从 `0..len` 中不放回地随机抽取至多 `count` 个下标 (升序)
+pub fn sample_indices(len: usize, count: usize, seed: u64) -> Vec<usize> {
+    let mut rng = SplitMix64(seed);
+    let mut indices: Vec<usize> = (0..len).collect();
+    let count = count.min(len);
+    // 部分 Fisher-Yates 洗牌
+    for i in 0..count {
+        let j = i + (rng.next() % (len - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    indices.truncate(count);
+    indices.sort_unstable();
+    indices
+}
+
+/// 随机抽取切片复制到 `output/audit/`，并写出其指标 (audit.csv)，返回抽取数量
+///
+/// 抽检文件按序号重命名为 `NNN_<原文件名>`，CSV 中记录对应的源文件与原切片路径。
+pub fn write_audit_sample(
+    output: &Path,
+    candidates: &[AuditCandidate],
+    count: usize,
+    seed: u64,
+) -> Result<usize> {
+    let audit_dir = output.join(AUDIT_DIR_NAME);
+    std::fs::create_dir_all(&audit_dir)?;
+
+    let mut writer = BufWriter::new(create_file(&audit_dir.join(AUDIT_CSV_FILE_NAME))?);
+    writeln!(
+        writer,
+        "audit_file,source,slice_file,start_sec,end_sec,duration_sec,rms_db,peak_db,voiced_ratio,rt60,replaygain_db"
+    )?;
+
+    let picked = sample_indices(candidates.len(), count, seed);
+    for (n, &index) in picked.iter().enumerate() {
+        let candidate = &candidates[index];
+        let record = &candidate.record;
+        let slice_path = candidate.dir.join(&record.file);
+        let file_name = Path::new(&record.file).file_name().unwrap_or_default();
+        let audit_file = format!("{n:03}_{}", file_name.to_string_lossy());
+        let audit_path = audit_dir.join(&audit_file);
+        check_overwrite(&audit_path)?;
+        std::fs::copy(&slice_path, audit_path)?;
+
+        let optional = |value: Option<f32>| value.map(|v| format!("{v:.3}")).unwrap_or_default();
+        writeln!(
+            writer,
+            "{audit_file},{},{},{:.3},{:.3},{:.3},{:.2},{:.2},{:.3},{},{}",
+            csv_field(&candidate.source),
+            csv_field(&slice_path.to_string_lossy()),
+            record.start_sec,
+            record.end_sec,
+            record.duration_sec,
+            record.rms_db,
+            record.peak_db,
+            record.voiced_ratio,
+            optional(record.rt60),
+            optional(record.replaygain_db),
+        )?;
+    }
+    writer.flush()?;
+    Ok(picked.len())
+}
+
+/// 路径中可能含有逗号或引号，按 CSV 规则转义
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_field, sample_indices};
+
+    #[test]
+    fn sampling_is_seeded_and_distinct() {
+        let a = sample_indices(100, 10, 42);
+        assert_eq!(a, sample_indices(100, 10, 42));
+        assert_ne!(a, sample_indices(100, 10, 43));
+        assert_eq!(a.len(), 10);
+        assert!(a.windows(2).all(|w| w[0] < w[1]));
+        assert!(a.iter().all(|&i| i < 100));
+    }
+
+    #[test]
+    fn sampling_caps_at_population() {
+        assert_eq!(sample_indices(3, 10, 0), vec![0, 1, 2]);
+        assert!(sample_indices(0, 5, 0).is_empty());
+    }
+
+    #[test]
+    fn escapes_csv_fields() {
+        assert_eq!(csv_field("a/b.wav"), "a/b.wav");
+        assert_eq!(csv_field("a,\"b\".wav"), "\"a,\"\"b\"\".wav\"");
+    }
+}
@@ -0,0 +1,476 @@
+use anyhow::Result;
+use rusqlite::{Connection, params, params_from_iter, types::Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::QueryArgs;
+use crate::events::RunSummary;
+use crate::manifest::FileManifest;
+
+/// 等待其他进程释放数据库锁的最长时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(60);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at INTEGER NOT NULL,
+    input TEXT NOT NULL,
+    output TEXT NOT NULL,
+    params_hash TEXT NOT NULL,
+    files INTEGER NOT NULL,
+    succeeded INTEGER NOT NULL,
+    failed INTEGER NOT NULL,
+    slices INTEGER NOT NULL,
+    elapsed_sec REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sources (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    sample_rate INTEGER NOT NULL,
+    content_hash TEXT,
+    last_run_id INTEGER
+);
+CREATE TABLE IF NOT EXISTS failures (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    path TEXT NOT NULL,
+    error TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS slices (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    source_id INTEGER NOT NULL REFERENCES sources(id),
+    file TEXT NOT NULL,
+    start_sec REAL NOT NULL,
+    end_sec REAL NOT NULL,
+    duration_sec REAL NOT NULL,
+    rms_db REAL NOT NULL,
+    peak_db REAL NOT NULL,
+    voiced_ratio REAL NOT NULL,
+    rt60 REAL,
+    replaygain_db REAL,
+    limiter_reduction_db REAL
+);
+CREATE INDEX IF NOT EXISTS slices_source ON slices(source_id, run_id);
+CREATE INDEX IF NOT EXISTS slices_file ON slices(file);
+";
+
+/// 跨多次运行累积的语料库目录 (SQLite)
+///
+/// 记录每次运行、每个源文件与每个切片及其指标；同一源文件被重新处理时保留历史记录，
+/// 查询默认只返回每个源文件最近一次运行的切片。
+pub struct Catalog {
+    conn: Connection,
+}
+
+/// 一次运行的基本信息
+pub struct RunInfo<'a> {
+    pub input: &'a Path,
+    pub output: &'a Path,
+    pub params_hash: &'a str,
+    pub summary: &'a RunSummary,
+    /// 处理失败的文件及错误信息
+    pub failures: &'a [(PathBuf, String)],
+}
+
+impl Catalog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        // 多个进程同时写入同一数据库时等待对方的事务结束，而不是立即失败
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch(SCHEMA)?;
+        // 早期版本创建的数据库缺少后来加入的列
+        for (column, declaration) in [("content_hash", "TEXT"), ("last_run_id", "INTEGER")] {
+            let exists = conn
+                .prepare("SELECT 1 FROM pragma_table_info('sources') WHERE name = ?1")?
+                .exists([column])?;
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE sources ADD COLUMN {column} {declaration}"),
+                    [],
+                )?;
+            }
+        }
+        Ok(Self { conn })
+    }
+
+    /// 记录一次运行及其所有文件的切片清单，返回运行 ID
+    ///
+    /// `manifests` 中每项为切片所在目录与该目录的清单。
+    pub fn record_run(
+        &mut self,
+        run: &RunInfo,
+        manifests: &[(PathBuf, FileManifest)],
+    ) -> Result<i64> {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64 - run.summary.elapsed_sec as i64);
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO runs (started_at, input, output, params_hash, files, succeeded, failed, slices, elapsed_sec)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                started_at,
+                run.input.to_string_lossy(),
+                run.output.to_string_lossy(),
+                run.params_hash,
+                run.summary.files as i64,
+                run.summary.succeeded as i64,
+                run.summary.failed as i64,
+                run.summary.slices as i64,
+                run.summary.elapsed_sec,
+            ],
+        )?;
+        let run_id = tx.last_insert_rowid();
+
+        for (dir, manifest) in manifests {
+            tx.execute(
+                "INSERT INTO sources (path, sample_rate, content_hash, last_run_id) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(path) DO UPDATE SET sample_rate = excluded.sample_rate,
+                     content_hash = COALESCE(excluded.content_hash, content_hash),
+                     last_run_id = excluded.last_run_id",
+                params![
+                    manifest.source,
+                    manifest.sample_rate,
+                    manifest.content_hash,
+                    run_id
+                ],
+            )?;
+            let source_id: i64 = tx.query_row(
+                "SELECT id FROM sources WHERE path = ?1",
+                params![manifest.source],
+                |row| row.get(0),
+            )?;
+
+            let dir = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.clone());
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO slices (run_id, source_id, file, start_sec, end_sec, duration_sec,
+                                     rms_db, peak_db, voiced_ratio, rt60, replaygain_db, limiter_reduction_db)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            )?;
+            for record in &manifest.slices {
+                insert.execute(params![
+                    run_id,
+                    source_id,
+                    dir.join(&record.file).to_string_lossy(),
+                    record.start_sec,
+                    record.end_sec,
+                    record.duration_sec,
+                    record.rms_db,
+                    record.peak_db,
+                    record.voiced_ratio,
+                    record.rt60,
+                    record.replaygain_db,
+                    record.limiter_reduction_db,
+                ])?;
+            }
+        }
+        for (path, error) in run.failures {
+            tx.execute(
+                "INSERT INTO failures (run_id, path, error) VALUES (?1, ?2, ?3)",
+                params![run_id, path.to_string_lossy(), error],
+            )?;
+        }
+        tx.commit()?;
+        Ok(run_id)
+    }
+
+    /// 已记录的源文件内容哈希 -> 源文件路径
+    pub fn content_hashes(&self) -> Result<HashMap<String, String>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT content_hash, path FROM sources WHERE content_hash IS NOT NULL")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 已记录的所有源文件路径
+    pub fn source_paths(&self) -> Result<Vec<String>> {
+        let mut statement = self.conn.prepare("SELECT path FROM sources")?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 最近一次失败之后没有再成功处理过的源文件路径
+    pub fn failed_sources(&self) -> Result<Vec<String>> {
+        let mut statement = self.conn.prepare(
+            "SELECT DISTINCT f.path FROM failures f
+             WHERE f.run_id = (SELECT MAX(run_id) FROM failures WHERE path = f.path)
+               AND f.run_id > COALESCE((SELECT last_run_id FROM sources WHERE path = f.path), 0)
+             ORDER BY f.path",
+        )?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 每个源文件最近一次运行中、路径位于 `prefix` 下的切片文件
+    pub fn latest_slice_files(&self, prefix: &Path) -> Result<Vec<String>> {
+        let mut statement = self.conn.prepare(
+            "SELECT s.file FROM slices s
+             WHERE s.run_id = (SELECT MAX(run_id) FROM slices WHERE source_id = s.source_id)
+             ORDER BY s.file",
+        )?;
+        let files = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(files
+            .into_iter()
+            .filter(|file| Path::new(file).starts_with(prefix))
+            .collect())
+    }
+
+    /// 某次运行记录的切片文件，以及各文件是否已被之后的运行在同一路径重新写出
+    pub fn run_slice_files(&self, run_id: i64) -> Result<Vec<(String, bool)>> {
+        let exists = self
+            .conn
+            .prepare("SELECT 1 FROM runs WHERE id = ?1")?
+            .exists([run_id])?;
+        if !exists {
+            anyhow::bail!("目录数据库中没有运行 {run_id}");
+        }
+        let mut statement = self.conn.prepare(
+            "SELECT s.file, EXISTS(SELECT 1 FROM slices t WHERE t.file = s.file AND t.run_id > s.run_id)
+             FROM slices s WHERE s.run_id = ?1
+             ORDER BY s.file",
+        )?;
+        let rows = statement.query_map([run_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// 按条件查询切片
+    pub fn query(&self, args: &QueryArgs) -> Result<Vec<QueryRow>> {
+        let mut conditions = Vec::new();
+        // 指定运行时直接按运行筛选，旧运行的切片不会被“最近一次运行”条件排除
+        if !args.all_runs && args.run.is_none() {
+            conditions.push(
+                "s.run_id = (SELECT MAX(run_id) FROM slices WHERE source_id = s.source_id)"
+                    .to_string(),
+            );
+        }
+        let mut values: Vec<Value> = Vec::new();
+        let mut bind = |condition: &str, value: Value| {
+            values.push(value);
+            conditions.push(condition.replace('?', &format!("?{}", values.len())));
+        };
+        if let Some(run) = args.run {
+            bind("s.run_id = ?", Value::Integer(run));
+        }
+        if let Some(pattern) = &args.source {
+            bind("src.path LIKE ?", Value::Text(format!("%{pattern}%")));
+        }
+        if let Some(ms) = args.min_duration_ms {
+            bind("s.duration_sec >= ?", Value::Real(ms as f64 / 1000.0));
+        }
+        if let Some(ms) = args.max_duration_ms {
+            bind("s.duration_sec <= ?", Value::Real(ms as f64 / 1000.0));
+        }
+        if let Some(db) = args.min_rms_db {
+            bind("s.rms_db >= ?", Value::Real(db as f64));
+        }
+        if let Some(db) = args.max_peak_db {
+            bind("s.peak_db <= ?", Value::Real(db as f64));
+        }
+        if let Some(ratio) = args.min_voiced_ratio {
+            bind("s.voiced_ratio >= ?", Value::Real(ratio as f64));
+        }
+
+        let mut sql =
+            "SELECT s.file, src.path, s.run_id, s.duration_sec, s.rms_db, s.peak_db, s.voiced_ratio
+                       FROM slices s JOIN sources src ON src.id = s.source_id"
+                .to_string();
+        if !conditions.is_empty() {
+            sql += &format!(" WHERE {}", conditions.join(" AND "));
+        }
+        sql += " ORDER BY src.path, s.run_id, s.start_sec";
+        if let Some(limit) = args.limit {
+            sql += &format!(" LIMIT {limit}");
+        }
+
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| {
+            Ok(QueryRow {
+                file: row.get(0)?,
+                source: row.get(1)?,
+                run_id: row.get(2)?,
+                duration_sec: row.get(3)?,
+                rms_db: row.get(4)?,
+                peak_db: row.get(5)?,
+                voiced_ratio: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// 查询结果中的一个切片
+#[derive(Debug, Clone)]
+pub struct QueryRow {
+    pub file: String,
+    pub source: String,
+    pub run_id: i64,
+    pub duration_sec: f64,
+    pub rms_db: f64,
+    pub peak_db: f64,
+    pub voiced_ratio: f64,
+}
+
+/// `query` 子命令: 按条件列出目录中的切片 (制表符分隔)
+pub fn run_query(args: QueryArgs) -> Result<()> {
+    if !args.catalog.is_file() {
+        return Err(anyhow::anyhow!(
+            "目录数据库不存在: {}",
+            args.catalog.display()
+        ));
+    }
+    let catalog = Catalog::open(&args.catalog)?;
+    let rows = catalog.query(&args)?;
+    if args.paths_only {
+        for row in &rows {
+            println!("{}", row.file);
+        }
+        return Ok(());
+    }
+    println!("file\tsource\trun\tduration_sec\trms_db\tpeak_db\tvoiced_ratio");
+    for row in &rows {
+        println!(
+            "{}\t{}\t{}\t{:.3}\t{:.2}\t{:.2}\t{:.3}",
+            row.file,
+            row.source,
+            row.run_id,
+            row.duration_sec,
+            row.rms_db,
+            row.peak_db,
+            row.voiced_ratio
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Catalog, RunInfo};
+    use crate::events::RunSummary;
+    use crate::manifest::{FileManifest, test_manifest};
+    use crate::{Cli, Commands};
+    use clap::Parser;
+    use std::path::{Path, PathBuf};
+
+    fn manifest(durations: &[f64]) -> FileManifest {
+        let files: Vec<String> = (0..durations.len())
+            .map(|i| format!("slice_{i:03}.wav"))
+            .collect();
+        let files: Vec<&str> = files.iter().map(String::as_str).collect();
+        let mut manifest = test_manifest(&files);
+        manifest.source = "/data/a.wav".to_string();
+        manifest.content_hash = Some("00ff".to_string());
+        for (record, &duration_sec) in manifest.slices.iter_mut().zip(durations) {
+            record.end_sec = duration_sec;
+            record.duration_sec = duration_sec;
+        }
+        manifest
+    }
+
+    fn record(catalog: &mut Catalog, durations: &[f64]) -> i64 {
+        let manifests = [(PathBuf::from("/out/a"), manifest(durations))];
+        record_run(catalog, &manifests, &[])
+    }
+
+    fn record_run(
+        catalog: &mut Catalog,
+        manifests: &[(PathBuf, FileManifest)],
+        failures: &[(PathBuf, String)],
+    ) -> i64 {
+        let slices = manifests
+            .iter()
+            .map(|(_, manifest)| manifest.slices.len())
+            .sum();
+        let summary = RunSummary {
+            files: 1,
+            succeeded: 1,
+            failed: 0,
+            skipped: 0,
+            slices,
+            audio_duration_sec: 10.0,
+            saved_duration_sec: 8.0,
+            elapsed_sec: 1.0,
+        };
+        let run = RunInfo {
+            input: Path::new("/data"),
+            output: Path::new("/out"),
+            params_hash: "0",
+            summary: &summary,
+            failures,
+        };
+        catalog.record_run(&run, manifests).unwrap()
+    }
+
+    fn query(catalog: &Catalog, extra: &[&str]) -> usize {
+        let mut argv = vec!["audio-slicer", "query", "--catalog", "corpus.db"];
+        argv.extend_from_slice(extra);
+        let Commands::Query(args) = Cli::parse_from(argv).command else {
+            unreachable!()
+        };
+        catalog.query(&args).unwrap().len()
+    }
+
+    #[test]
+    fn queries_latest_run_by_default() {
+        let mut catalog = Catalog::open(Path::new(":memory:")).unwrap();
+        let first = record(&mut catalog, &[1.0, 2.0, 3.0]);
+        record(&mut catalog, &[2.5, 4.0]);
+
+        assert_eq!(query(&catalog, &[]), 2);
+        assert_eq!(query(&catalog, &["--all-runs"]), 5);
+        assert_eq!(query(&catalog, &["--run", &first.to_string()]), 3);
+        assert_eq!(
+            query(&catalog, &["--all-runs", "--run", &first.to_string()]),
+            3
+        );
+        assert_eq!(query(&catalog, &["--min-duration-ms", "3000"]), 1);
+        assert_eq!(query(&catalog, &["--source", "b.wav"]), 0);
+        assert_eq!(
+            catalog.content_hashes().unwrap()["00ff"],
+            "/data/a.wav".to_string()
+        );
+    }
+
+    #[test]
+    fn run_files_note_later_rewrites() {
+        let mut catalog = Catalog::open(Path::new(":memory:")).unwrap();
+        let first = record(&mut catalog, &[1.0, 2.0, 3.0]);
+        let second = record(&mut catalog, &[2.5, 4.0]);
+        let superseded: Vec<bool> = catalog
+            .run_slice_files(first)
+            .unwrap()
+            .into_iter()
+            .map(|(file, superseded)| {
+                assert!(file.starts_with("/out/a/slice_"), "{file}");
+                superseded
+            })
+            .collect();
+        assert_eq!(superseded, [true, true, false]);
+        assert_eq!(catalog.run_slice_files(second).unwrap().len(), 2);
+        assert!(catalog.run_slice_files(second + 1).is_err());
+    }
+
+    #[test]
+    fn failures_clear_after_success() {
+        let mut catalog = Catalog::open(Path::new(":memory:")).unwrap();
+        let a = (PathBuf::from("/out/a"), manifest(&[1.0]));
+        let mut b = a.clone();
+        b.1.source = "/data/b.wav".to_string();
+        let fail = |path: &str| [(PathBuf::from(path), "解码失败".to_string())];
+
+        record_run(&mut catalog, std::slice::from_ref(&a), &fail("/data/b.wav"));
+        assert_eq!(catalog.failed_sources().unwrap(), vec!["/data/b.wav"]);
+        assert_eq!(catalog.source_paths().unwrap(), vec!["/data/a.wav"]);
+
+        record_run(&mut catalog, &[b], &[]);
+        assert!(catalog.failed_sources().unwrap().is_empty());
+
+        record_run(&mut catalog, &[], &fail("/data/a.wav"));
+        assert_eq!(catalog.failed_sources().unwrap(), vec!["/data/a.wav"]);
+    }
+}
@@ -0,0 +1,275 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::dsp::{Biquad, FadeShape, attenuate_edge_artifacts, fade_edges};
+
+/// 单个处理阶段
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Stage {
+    /// 去除直流偏置 (减去均值)
+    Dc,
+    /// 二阶高通滤波 (截止频率 Hz)
+    Highpass(f32),
+    /// 二阶低通滤波 (截止频率 Hz)
+    Lowpass(f32),
+    /// 增益 (dB)
+    Gain(f32),
+    /// 首尾线性淡入淡出 (ms)
+    Fade(f32),
+    /// 衰减首尾指定范围 (ms) 内的呼吸声与咔嗒声
+    Declick(f32),
+}
+
+/// 处理阶段注册表项
+struct StageSpec {
+    name: &'static str,
+    /// 参数说明，`None` 表示该阶段不接受参数
+    arg: Option<&'static str>,
+    build: fn(f32) -> Stage,
+}
+
+/// 所有可用的处理阶段
+const REGISTRY: &[StageSpec] = &[
+    StageSpec {
+        name: "dc",
+        arg: None,
+        build: |_| Stage::Dc,
+    },
+    StageSpec {
+        name: "hpf",
+        arg: Some("截止频率 Hz"),
+        build: Stage::Highpass,
+    },
+    StageSpec {
+        name: "lpf",
+        arg: Some("截止频率 Hz"),
+        build: Stage::Lowpass,
+    },
+    StageSpec {
+        name: "gain",
+        arg: Some("增益 dB"),
+        build: Stage::Gain,
+    },
+    StageSpec {
+        name: "fade",
+        arg: Some("淡入淡出时长 ms"),
+        build: Stage::Fade,
+    },
+    StageSpec {
+        name: "declick",
+        arg: Some("首尾检测范围 ms"),
+        build: Stage::Declick,
+    },
+];
+
+impl std::str::FromStr for Stage {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg.trim())),
+            None => (s.trim(), None),
+        };
+        let spec = REGISTRY
+            .iter()
+            .find(|spec| spec.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = REGISTRY.iter().map(|spec| spec.name).collect();
+                format!("未知的处理阶段: {name} (可用: {})", names.join(", "))
+            })?;
+
+        let value = match (spec.arg, arg) {
+            (None, None) => 0.0,
+            (None, Some(_)) => return Err(format!("处理阶段 {name} 不接受参数")),
+            (Some(desc), None) => return Err(format!("处理阶段 {name} 需要参数: {desc}")),
+            (Some(desc), Some(arg)) => arg
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("处理阶段 {name} 的参数无效 ({desc}): {arg}"))?,
+        };
+        let stage = (spec.build)(value);
+        match stage {
+            Stage::Highpass(hz) | Stage::Lowpass(hz) if hz <= 0.0 => {
+                Err(format!("处理阶段 {name} 的截止频率应大于 0: {hz}"))
+            }
+            Stage::Fade(ms) | Stage::Declick(ms) if ms < 0.0 => {
+                Err(format!("处理阶段 {name} 的时长不能为负数: {ms}"))
+            }
+            _ => Ok(stage),
+        }
+    }
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Dc => write!(f, "dc"),
+            Stage::Highpass(hz) => write!(f, "hpf:{hz}"),
+            Stage::Lowpass(hz) => write!(f, "lpf:{hz}"),
+            Stage::Gain(db) => write!(f, "gain:{db}"),
+            Stage::Fade(ms) => write!(f, "fade:{ms}"),
+            Stage::Declick(ms) => write!(f, "declick:{ms}"),
+        }
+    }
+}
+
+impl Stage {
+    /// 截止频率须低于奈奎斯特频率，取决于输出采样率，只能在应用时检查
+    fn apply(&self, samples: &mut [f32], sample_rate: u32) -> Result<()> {
+        if let Stage::Highpass(cutoff_hz) | Stage::Lowpass(cutoff_hz) = *self {
+            let nyquist = sample_rate as f32 / 2.0;
+            anyhow::ensure!(
+                cutoff_hz > 0.0 && cutoff_hz < nyquist,
+                "处理阶段 {self} 的截止频率应在 0 与奈奎斯特频率 {nyquist} Hz 之间 (采样率 {sample_rate} Hz)"
+            );
+        }
+        match *self {
+            Stage::Dc => {
+                if samples.is_empty() {
+                    return Ok(());
+                }
+                let mean = samples.iter().map(|&x| x as f64).sum::<f64>() / samples.len() as f64;
+                for sample in samples.iter_mut() {
+                    *sample -= mean as f32;
+                }
+            }
+            Stage::Highpass(cutoff_hz) => {
+                filter(samples, &mut Biquad::highpass(cutoff_hz, sample_rate))
+            }
+            Stage::Lowpass(cutoff_hz) => {
+                filter(samples, &mut Biquad::lowpass(cutoff_hz, sample_rate))
+            }
+            Stage::Gain(db) => {
+                let gain = 10f32.powf(db / 20.0);
+                for sample in samples.iter_mut() {
+                    *sample *= gain;
+                }
+            }
+            Stage::Fade(ms) => fade_edges(samples, sample_rate, ms, FadeShape::Linear),
+            Stage::Declick(ms) => {
+                attenuate_edge_artifacts(samples, sample_rate, ms);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn filter(samples: &mut [f32], biquad: &mut Biquad) {
+    for sample in samples.iter_mut() {
+        *sample = biquad.process(*sample as f64) as f32;
+    }
+}
+
+/// 按顺序执行的处理链，形如 `dc,hpf:80,gain:3,fade:10`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DspChain {
+    stages: Vec<Stage>,
+}
+
+impl std::str::FromStr for DspChain {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let stages = s
+            .split(',')
+            .filter(|part| !part.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { stages })
+    }
+}
+
+impl std::fmt::Display for DspChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stages: Vec<String> = self.stages.iter().map(Stage::to_string).collect();
+        write!(f, "{}", stages.join(","))
+    }
+}
+
+impl DspChain {
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// 依次应用所有阶段；滤波器截止频率不低于奈奎斯特频率时返回错误
+    pub fn apply(&self, samples: &mut [f32], sample_rate: u32) -> Result<()> {
+        for stage in &self.stages {
+            stage.apply(samples, sample_rate)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DspChain, Stage};
+
+    #[test]
+    fn parses_chain_in_order() {
+        let chain: DspChain = "dc, hpf:80,gain:-3,fade:10".parse().unwrap();
+        assert_eq!(
+            chain.stages,
+            vec![
+                Stage::Dc,
+                Stage::Highpass(80.0),
+                Stage::Gain(-3.0),
+                Stage::Fade(10.0)
+            ]
+        );
+        assert_eq!(chain.to_string(), "dc,hpf:80,gain:-3,fade:10");
+        assert!("".parse::<DspChain>().unwrap().is_empty());
+        assert_eq!("declick:200".parse(), Ok(Stage::Declick(200.0)));
+    }
+
+    #[test]
+    fn rejects_bad_stages() {
+        assert!("reverb".parse::<DspChain>().is_err());
+        assert!("hpf".parse::<DspChain>().is_err());
+        assert!("dc:1".parse::<DspChain>().is_err());
+        assert!("gain:loud".parse::<DspChain>().is_err());
+        assert!("hpf:0".parse::<DspChain>().is_err());
+        assert!("lpf:-100".parse::<DspChain>().is_err());
+        assert!("fade:-5".parse::<DspChain>().is_err());
+        assert!("declick:-1".parse::<DspChain>().is_err());
+        assert_eq!("fade:0".parse(), Ok(Stage::Fade(0.0)));
+    }
+
+    #[test]
+    fn rejects_cutoff_above_nyquist() {
+        let chain: DspChain = "lpf:8000".parse().unwrap();
+        let mut samples = vec![0.5; 100];
+        assert!(chain.apply(&mut samples, 16000).is_err());
+        assert_eq!(samples, vec![0.5; 100]);
+        assert!(chain.apply(&mut samples, 44100).is_ok());
+    }
+
+    #[test]
+    fn dc_then_gain() {
+        let chain: DspChain = "dc,gain:6.0206".parse().unwrap();
+        let mut samples = vec![0.6, 0.4, 0.6, 0.4];
+        chain.apply(&mut samples, 1000).unwrap();
+        for (value, expected) in samples.iter().zip([0.2, -0.2, 0.2, -0.2]) {
+            assert!((value - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn fade_ramps_both_ends() {
+        let chain: DspChain = "fade:2".parse().unwrap();
+        let mut samples = vec![1.0; 10];
+        chain.apply(&mut samples, 1000).unwrap();
+        assert_eq!(
+            samples,
+            vec![0.0, 0.5, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.0]
+        );
+    }
+
+    #[test]
+    fn highpass_removes_dc() {
+        let chain: DspChain = "hpf:100".parse().unwrap();
+        let mut samples = vec![0.5; 4000];
+        chain.apply(&mut samples, 8000).unwrap();
+        assert!(samples[3999].abs() < 1e-3);
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+
+use crate::CleanArgs;
+use crate::catalog::Catalog;
+use crate::export::{LABELS_FILE_NAME, MASK_FILE_NAME};
+use crate::layout::is_shard_dir;
+use crate::manifest::{FileManifest, MANIFEST_FILE_NAME, read_manifest};
+use crate::refilter::find_manifests;
+use crate::trace::TRACE_FILE_NAME;
+use crate::verify::referenced_files;
+use crate::viz::VizFormat;
+
+/// 切片运行在切片目录中写出、但清单中没有记录的文件
+fn side_files() -> [&'static str; 5] {
+    [
+        MASK_FILE_NAME,
+        LABELS_FILE_NAME,
+        TRACE_FILE_NAME,
+        VizFormat::Png.file_name(),
+        VizFormat::Html.file_name(),
+    ]
+}
+
+/// 清单中的相对路径在切片目录中的位置；绝对路径或含 `..` 的路径 (指向目录之外) 返回 `None`
+fn inside(dir: &Path, file: &str) -> Option<PathBuf> {
+    Path::new(file)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        .then(|| dir.join(file))
+}
+
+/// 一个清单对应的全部输出中磁盘上存在的文件: 切片、增强版本、波形峰值、噪声样本、
+/// 附加文件与清单本身
+fn manifest_outputs(manifest_path: &Path, manifest: &FileManifest) -> Vec<PathBuf> {
+    let dir = manifest_path.parent().unwrap_or(Path::new("."));
+    let mut files: BTreeSet<PathBuf> = referenced_files(manifest)
+        .into_iter()
+        .filter_map(|file| inside(dir, file))
+        .collect();
+    files.extend(side_files().map(|name| dir.join(name)));
+    files.insert(manifest_path.to_path_buf());
+    files.into_iter().filter(|path| path.is_file()).collect()
+}
+
+/// 切片文件所在的切片目录 (分片目录的上一级)
+fn slice_dir(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(Path::new("."));
+    let sharded = parent
+        .file_name()
+        .is_some_and(|name| is_shard_dir(&name.to_string_lossy()));
+    match parent.parent() {
+        Some(grandparent) if sharded => grandparent.to_path_buf(),
+        _ => parent.to_path_buf(),
+    }
+}
+
+/// `--manifest` 给出的清单 (目录展开为其中的所有清单) 对应的输出
+fn outputs_by_manifest(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = BTreeSet::new();
+    for path in paths {
+        let manifests = if path.is_dir() {
+            let found = find_manifests(path);
+            if found.is_empty() {
+                anyhow::bail!("目录中没有切片清单: {}", path.display());
+            }
+            found
+        } else {
+            vec![path.clone()]
+        };
+        for manifest_path in manifests {
+            let manifest = read_manifest(&manifest_path)
+                .map_err(|e| anyhow::anyhow!("无法读取清单 {}: {e}", manifest_path.display()))?;
+            files.extend(manifest_outputs(&manifest_path, &manifest));
+        }
+    }
+    Ok(files.into_iter().collect())
+}
+
+/// 目录数据库中某次运行写出的输出，返回待删除的文件与跳过的 (已被之后的运行重新写出的) 切片数
+///
+/// 切片目录的清单仍与该运行记录的切片一致时连同清单与附加文件一起删除；
+/// 清单已被之后的运行改写时只删除该运行独有 (清单不再引用) 的切片文件。
+fn outputs_by_run(catalog: &Path, run_id: i64) -> Result<(Vec<PathBuf>, usize)> {
+    let recorded = Catalog::open(catalog)?.run_slice_files(run_id)?;
+    let mut superseded = 0;
+    let mut by_dir: BTreeMap<PathBuf, BTreeSet<PathBuf>> = BTreeMap::new();
+    for (file, rewritten) in recorded {
+        if rewritten {
+            superseded += 1;
+            continue;
+        }
+        let file = PathBuf::from(file);
+        by_dir.entry(slice_dir(&file)).or_default().insert(file);
+    }
+
+    let mut files = Vec::new();
+    for (dir, run_files) in by_dir {
+        files.extend(run_outputs_in_dir(&dir, run_files));
+    }
+    Ok((files, superseded))
+}
+
+/// 一个切片目录中某次运行写出、仍然存在的文件
+///
+/// 清单仍与该运行记录的切片一致时连同清单与附加文件一起删除；清单已被之后的运行改写
+/// (该运行未记入目录数据库) 时，清单仍引用的文件属于之后的运行，不删除。
+fn run_outputs_in_dir(dir: &Path, run_files: BTreeSet<PathBuf>) -> Vec<PathBuf> {
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    let Ok(manifest) = read_manifest(&manifest_path) else {
+        return run_files
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect();
+    };
+    let listed: BTreeSet<PathBuf> = manifest
+        .slices
+        .iter()
+        .filter_map(|record| inside(dir, &record.file))
+        .collect();
+    if listed == run_files {
+        return manifest_outputs(&manifest_path, &manifest);
+    }
+    let referenced: BTreeSet<PathBuf> = referenced_files(&manifest)
+        .into_iter()
+        .filter_map(|file| inside(dir, file))
+        .collect();
+    run_files
+        .into_iter()
+        .filter(|path| !referenced.contains(path) && path.is_file())
+        .collect()
+}
+
+/// 删除文件，之后删除因此变空的分片目录与切片目录
+fn remove_files(files: &[PathBuf]) -> Result<()> {
+    let mut dirs = BTreeSet::new();
+    for file in files {
+        std::fs::remove_file(file)?;
+        if let Some(parent) = file.parent() {
+            dirs.insert(parent.to_path_buf());
+            dirs.insert(slice_dir(file));
+        }
+    }
+    // 倒序: 分片目录排在所属切片目录之后，先被删除
+    for dir in dirs.iter().rev() {
+        let empty = std::fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_none());
+        if empty {
+            std::fs::remove_dir(dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// `clean` 子命令: 按清单或目录数据库中的运行编号删除某次运行写出的文件
+///
+/// 只删除清单 (或目录数据库) 中记录的输出及切片目录中的附加文件，不删除其他文件；
+/// 目录数据库中的记录保留。
+pub fn run_clean(args: CleanArgs) -> Result<()> {
+    let (files, superseded) = match (args.run, &args.catalog) {
+        (Some(run_id), Some(catalog)) => outputs_by_run(catalog, run_id)?,
+        _ => (outputs_by_manifest(&args.manifest)?, 0),
+    };
+
+    let verb = if args.dry_run { "将删除" } else { "删除" };
+    for file in &files {
+        println!("   🗑️ {verb}: {}", file.display());
+    }
+    if !args.dry_run {
+        remove_files(&files)?;
+    }
+
+    println!("📊 清理结果:");
+    if args.dry_run {
+        println!("   - 试运行，未删除任何文件");
+    }
+    println!("   - {verb}文件: {} 个", files.len());
+    if superseded > 0 {
+        println!("   - 跳过已被之后的运行重新写出的切片: {superseded} 个");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inside, outputs_by_manifest, remove_files, run_outputs_in_dir, slice_dir};
+    use crate::manifest::{MANIFEST_FILE_NAME, test_manifest, write_manifest};
+    use std::collections::BTreeSet;
+    use std::path::{Path, PathBuf};
+
+    fn write_test_manifest(dir: &Path, files: &[&str]) {
+        write_manifest(&dir.join(MANIFEST_FILE_NAME), &test_manifest(files)).unwrap();
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_slice_dir() {
+        let dir = Path::new("/out/a");
+        assert_eq!(
+            inside(dir, "001/slice_000.wav"),
+            Some(dir.join("001/slice_000.wav"))
+        );
+        assert_eq!(inside(dir, "../b/slice_000.wav"), None);
+        assert_eq!(inside(dir, "/etc/passwd"), None);
+        assert_eq!(slice_dir(Path::new("/out/a/001/x.wav")), dir);
+        assert_eq!(slice_dir(Path::new("/out/a/x.wav")), dir);
+    }
+
+    #[test]
+    fn removes_only_the_run_outputs() {
+        let root = std::env::temp_dir().join(format!("slicer_clean_{}", std::process::id()));
+        let dir = root.join("a");
+        std::fs::create_dir_all(dir.join("001")).unwrap();
+        write_test_manifest(
+            &dir,
+            &["slice_000.wav", "001/slice_001.wav", "../escape.wav"],
+        );
+        for file in [
+            "a/slice_000.wav",
+            "a/001/slice_001.wav",
+            "a/mask.csv",
+            "a/notes.txt",
+            "escape.wav",
+        ] {
+            std::fs::write(root.join(file), b"").unwrap();
+        }
+
+        let files = outputs_by_manifest(std::slice::from_ref(&root)).unwrap();
+        let expected: Vec<PathBuf> = [
+            "001/slice_001.wav",
+            "manifest.json",
+            "mask.csv",
+            "slice_000.wav",
+        ]
+        .map(|file| dir.join(file))
+        .into();
+        assert_eq!(files, expected);
+
+        remove_files(&files).unwrap();
+        assert!(!dir.join("001").exists());
+        assert!(dir.join("notes.txt").is_file());
+        assert!(root.join("escape.wav").is_file());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn keeps_files_of_a_later_run_without_catalog() {
+        let dir = std::env::temp_dir().join(format!("slicer_clean_run_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in [
+            "slice_000.wav",
+            "slice_001.wav",
+            "slice_002.wav",
+            "mask.csv",
+        ] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+        let run_files: BTreeSet<PathBuf> = ["slice_000.wav", "slice_001.wav", "slice_002.wav"]
+            .map(|file| dir.join(file))
+            .into();
+
+        // 清单仍属于该运行: 连同清单与附加文件一起删除
+        write_test_manifest(&dir, &["slice_000.wav", "slice_001.wav", "slice_002.wav"]);
+        assert_eq!(run_outputs_in_dir(&dir, run_files.clone()).len(), 5);
+
+        // 之后的运行 (未记入目录数据库) 改写了目录，只写出两个切片
+        write_test_manifest(&dir, &["slice_000.wav", "slice_001.wav"]);
+        assert_eq!(
+            run_outputs_in_dir(&dir, run_files),
+            vec![dir.join("slice_002.wav")]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
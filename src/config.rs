@@ -0,0 +1,215 @@
+use anyhow::{Result, bail};
+use clap::Command;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::Path;
+
+/// 当前的配置文件结构版本，文件中可用 `schema_version` 声明
+const CONFIG_SCHEMA_VERSION: i64 = 1;
+
+/// 配置文件中的一个值
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ConfigValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    List(Vec<ConfigValue>),
+}
+
+impl ConfigValue {
+    fn scalar(&self, key: &str) -> Result<String> {
+        Ok(match self {
+            Self::Bool(value) => value.to_string(),
+            Self::Integer(value) => value.to_string(),
+            Self::Float(value) => value.to_string(),
+            Self::String(value) => value.clone(),
+            Self::List(_) => bail!("配置项 {key} 不支持嵌套列表"),
+        })
+    }
+}
+
+/// 读取 TOML 或 YAML 配置文件 (按扩展名区分，`.yaml`/`.yml` 为 YAML，其余为 TOML)
+fn read_config(path: &Path) -> Result<BTreeMap<String, ConfigValue>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("无法读取配置文件 {}: {e}", path.display()))?;
+    let is_yaml = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+    let parsed = if is_yaml {
+        serde_yaml::from_str(&text).map_err(anyhow::Error::from)
+    } else {
+        toml::from_str(&text).map_err(anyhow::Error::from)
+    };
+    parsed.map_err(|e| anyhow::anyhow!("配置文件 {} 格式错误: {e}", path.display()))
+}
+
+/// 把配置项转换为 `command` 的命令行参数
+///
+/// 键名与长参数名相同 (下划线与连字符均可)。开关参数取布尔值，`false` 即不启用；
+/// 可重复的参数可写成列表。
+fn config_args(config: &BTreeMap<String, ConfigValue>, command: &Command) -> Result<Vec<OsString>> {
+    let mut args = Vec::new();
+    for (key, value) in config {
+        if key == "schema_version" {
+            match value {
+                ConfigValue::Integer(version) if *version <= CONFIG_SCHEMA_VERSION => continue,
+                ConfigValue::Integer(version) => bail!(
+                    "配置文件结构版本 {version} 高于本程序支持的版本 {CONFIG_SCHEMA_VERSION}，请升级程序"
+                ),
+                _ => bail!("schema_version 应为整数"),
+            }
+        }
+        let long = key.replace('_', "-");
+        let Some(arg) = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()) && !arg.is_hide_set())
+        else {
+            bail!("配置文件中的未知选项: {key}");
+        };
+        if long == "config" {
+            bail!("配置文件不能再引用其他配置文件");
+        }
+        let is_switch = !arg.get_action().takes_values();
+        match value {
+            ConfigValue::Bool(enabled) if is_switch => {
+                if *enabled {
+                    args.push(format!("--{long}").into());
+                }
+            }
+            _ if is_switch => bail!("配置项 {key} 是开关，取值应为 true 或 false"),
+            ConfigValue::List(values) => {
+                for value in values {
+                    args.push(format!("--{long}={}", value.scalar(key)?).into());
+                }
+            }
+            value => args.push(format!("--{long}={}", value.scalar(key)?).into()),
+        }
+    }
+    Ok(args)
+}
+
+/// 展开命令行中的 `--config <路径>`: 配置文件的选项插入在子命令之后、
+/// 其余命令行参数之前，因此命令行上显式给出的参数覆盖配置文件中的值
+///
+/// 仅对带有 `--config` 参数的子命令生效，其参数需允许覆盖自身 (`args_override_self`)。
+pub fn expand_config_args(args: Vec<OsString>, command: &Command) -> Result<Vec<OsString>> {
+    let Some(subcommand) = args
+        .get(1)
+        .and_then(|arg| arg.to_str())
+        .and_then(|name| command.find_subcommand(name))
+        .filter(|subcommand| {
+            subcommand
+                .get_arguments()
+                .any(|arg| arg.get_id() == "config")
+        })
+    else {
+        return Ok(args);
+    };
+    let mut path = None;
+    let mut iter = args.iter().skip(2);
+    while let Some(arg) = iter.next() {
+        match arg.to_str() {
+            Some("--") => break,
+            Some("--config") => path = iter.next().cloned(),
+            Some(arg) if arg.starts_with("--config=") => {
+                path = Some(arg["--config=".len()..].into());
+            }
+            _ => {}
+        }
+    }
+    let Some(path) = path else {
+        return Ok(args);
+    };
+
+    let config = read_config(Path::new(&path))?;
+    let file_args = config_args(&config, subcommand)?;
+    let mut expanded = args[..2].to_vec();
+    expanded.extend(file_args);
+    expanded.extend_from_slice(&args[2..]);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_config_args;
+    use clap::{Arg, ArgAction, Command};
+    use std::ffi::OsString;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn slice_command() -> Command {
+        Command::new("slice")
+            .args_override_self(true)
+            .arg(Arg::new("config").long("config"))
+            .arg(Arg::new("threshold_db").long("threshold-db"))
+            .arg(Arg::new("tag").long("tag").action(ArgAction::Append))
+            .arg(Arg::new("trace").long("trace").action(ArgAction::SetTrue))
+            .arg(
+                Arg::new("dry_run")
+                    .long("dry-run")
+                    .action(ArgAction::SetTrue),
+            )
+    }
+
+    fn command() -> Command {
+        Command::new("app")
+            .subcommand(slice_command())
+            .subcommand(Command::new("scan").arg(Arg::new("input").long("input")))
+    }
+
+    fn expand(config: &str, extension: &str, cli: &[&str]) -> anyhow::Result<Vec<String>> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "config_{}_{}.{extension}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, config).unwrap();
+        let mut args: Vec<OsString> = vec!["app".into(), "slice".into()];
+        args.push(format!("--config={}", path.display()).into());
+        args.extend(cli.iter().map(OsString::from));
+        let expanded = expand_config_args(args, &command());
+        std::fs::remove_file(&path).unwrap();
+        Ok(expanded?
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn file_values_come_before_command_line() {
+        let toml = "schema_version = 1\nthreshold_db = -40.5\ntag = [\"a=1\", \"b=2\"]\ntrace = true\ndry-run = false\n";
+        let args = expand(toml, "toml", &["--threshold-db=-30"]).unwrap();
+        assert_eq!(
+            &args[2..6],
+            ["--tag=a=1", "--tag=b=2", "--threshold-db=-40.5", "--trace"]
+        );
+        let matches = slice_command().get_matches_from(&args[1..]);
+        // 命令行上的值覆盖配置文件
+        assert_eq!(matches.get_one::<String>("threshold_db").unwrap(), "-30");
+        assert_eq!(matches.get_many::<String>("tag").unwrap().count(), 2);
+        assert!(!matches.get_flag("dry_run"));
+
+        let yaml = "threshold-db: -42\ntrace: true\n";
+        let args = expand(yaml, "yaml", &[]).unwrap();
+        assert_eq!(&args[2..4], ["--threshold-db=-42", "--trace"]);
+
+        // 没有 --config 参数的子命令保持原样
+        let scan: Vec<OsString> = ["app", "scan", "--config=x.toml"]
+            .map(OsString::from)
+            .into();
+        assert_eq!(expand_config_args(scan.clone(), &command()).unwrap(), scan);
+    }
+
+    #[test]
+    fn rejects_unknown_and_malformed_options() {
+        assert!(expand("bogus = 1\n", "toml", &[]).is_err());
+        assert!(expand("schema_version = 2\n", "toml", &[]).is_err());
+        assert!(expand("trace = \"yes\"\n", "toml", &[]).is_err());
+        assert!(expand("threshold_db = [[1]]\n", "toml", &[]).is_err());
+        assert!(expand("threshold_db = ", "toml", &[]).is_err());
+    }
+}
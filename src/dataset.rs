@@ -0,0 +1,215 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::atomic::write_atomic;
+use crate::manifest::{FileManifest, read_manifest};
+use crate::refilter::find_manifests;
+
+/// 数据集描述文件名 (位于输出目录根)
+pub const DATASET_CARD_FILE_NAME: &str = "dataset.json";
+/// 数据集描述的结构版本
+const DATASET_CARD_SCHEMA_VERSION: u32 = 1;
+/// 切片时长分布的区间上界 (秒)，最后一个区间没有上界
+pub const DURATION_BUCKETS_SEC: &[f64] = &[1.0, 2.0, 5.0, 10.0, 15.0, 20.0, 30.0, 60.0];
+
+/// 输出目录中整个语料的机器可读描述
+#[derive(Debug, Serialize)]
+pub struct DatasetCard {
+    pub schema_version: u32,
+    /// 生成本描述的程序及版本
+    pub generator: String,
+    /// 生成时间 (Unix 秒)
+    pub generated_at: u64,
+    pub total_hours: f64,
+    pub total_duration_sec: f64,
+    pub slice_count: usize,
+    pub source_count: usize,
+    pub duration: DurationStats,
+    /// 时长分布: 每个区间 `[上一个上界, upper_sec)` 内的切片数，`upper_sec` 为空表示没有上界
+    pub duration_histogram: Vec<HistogramBucket>,
+    /// 各输出采样率的切片数
+    pub sample_rates: BTreeMap<u32, usize>,
+    /// 各输出格式 (文件扩展名) 的切片数
+    pub formats: BTreeMap<String, usize>,
+    /// 各参数指纹的切片数，语料由不同参数的多次运行组成时有多项
+    pub params_hashes: BTreeMap<String, usize>,
+    /// 各源文件格式 (扩展名) 的源文件数
+    pub source_formats: BTreeMap<String, usize>,
+    /// 各源采样率的源文件数
+    pub source_sample_rates: BTreeMap<u32, usize>,
+}
+
+/// 切片时长的统计 (秒)
+#[derive(Debug, Default, Serialize)]
+pub struct DurationStats {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p10: f64,
+    pub median: f64,
+    pub p90: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBucket {
+    pub upper_sec: Option<f64>,
+    pub count: usize,
+}
+
+impl DatasetCard {
+    /// 由各清单汇总 (已标记 stale 的切片不计入)
+    pub fn from_manifests(manifests: &[FileManifest]) -> Self {
+        let mut card = Self {
+            schema_version: DATASET_CARD_SCHEMA_VERSION,
+            generator: concat!("audio-learning ", env!("CARGO_PKG_VERSION")).to_string(),
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            total_hours: 0.0,
+            total_duration_sec: 0.0,
+            slice_count: 0,
+            source_count: 0,
+            duration: DurationStats::default(),
+            duration_histogram: Vec::new(),
+            sample_rates: BTreeMap::new(),
+            formats: BTreeMap::new(),
+            params_hashes: BTreeMap::new(),
+            source_formats: BTreeMap::new(),
+            source_sample_rates: BTreeMap::new(),
+        };
+        let mut durations = Vec::new();
+        for manifest in manifests {
+            let slices: Vec<_> = manifest.slices.iter().filter(|s| !s.stale).collect();
+            if slices.is_empty() {
+                continue;
+            }
+            card.source_count += 1;
+            *card
+                .source_formats
+                .entry(extension(&manifest.source))
+                .or_default() += 1;
+            *card
+                .source_sample_rates
+                .entry(manifest.sample_rate)
+                .or_default() += 1;
+            *card
+                .sample_rates
+                .entry(manifest.output_sample_rate)
+                .or_default() += slices.len();
+            *card
+                .params_hashes
+                .entry(manifest.params_hash.clone())
+                .or_default() += slices.len();
+            for slice in slices {
+                *card.formats.entry(extension(&slice.file)).or_default() += 1;
+                durations.push(slice.duration_sec);
+            }
+        }
+
+        card.slice_count = durations.len();
+        card.total_duration_sec = durations.iter().sum();
+        card.total_hours = card.total_duration_sec / 3600.0;
+        card.duration_histogram = histogram(&durations);
+        durations.sort_by(f64::total_cmp);
+        if let (Some(&min), Some(&max)) = (durations.first(), durations.last()) {
+            let at = |p: usize| durations[(durations.len() - 1) * p / 100];
+            card.duration = DurationStats {
+                min,
+                max,
+                mean: card.total_duration_sec / durations.len() as f64,
+                p10: at(10),
+                median: at(50),
+                p90: at(90),
+            };
+        }
+        card
+    }
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn histogram(durations: &[f64]) -> Vec<HistogramBucket> {
+    let mut counts = vec![0; DURATION_BUCKETS_SEC.len() + 1];
+    for &duration in durations {
+        let bucket = DURATION_BUCKETS_SEC.partition_point(|&upper| upper <= duration);
+        counts[bucket] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| HistogramBucket {
+            upper_sec: DURATION_BUCKETS_SEC.get(i).copied(),
+            count,
+        })
+        .collect()
+}
+
+/// 读取输出目录中的所有清单，把整个语料的描述写到 `dataset.json`
+///
+/// 无法读取的清单跳过，返回写出的描述与跳过的清单路径。
+pub fn write_dataset_card(output: &Path) -> Result<(DatasetCard, Vec<PathBuf>)> {
+    let mut manifests = Vec::new();
+    let mut unreadable = Vec::new();
+    for path in find_manifests(output) {
+        match read_manifest(&path) {
+            Ok(manifest) => manifests.push(manifest),
+            Err(_) => unreadable.push(path),
+        }
+    }
+    let card = DatasetCard::from_manifests(&manifests);
+    write_atomic(&output.join(DATASET_CARD_FILE_NAME), |writer| {
+        Ok(serde_json::to_writer_pretty(writer, &card)?)
+    })?;
+    Ok((card, unreadable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DatasetCard;
+    use crate::manifest::{FileManifest, test_manifest};
+
+    fn manifest(source: &str, durations: &[f64]) -> FileManifest {
+        let files: Vec<String> = (0..durations.len())
+            .map(|i| format!("slice_{i:03}.flac"))
+            .collect();
+        let files: Vec<&str> = files.iter().map(String::as_str).collect();
+        let mut manifest = test_manifest(&files);
+        manifest.source = source.to_string();
+        manifest.sample_rate = 44100;
+        manifest.params_hash = "abc".to_string();
+        for (slice, &duration) in manifest.slices.iter_mut().zip(durations) {
+            slice.end_sec = duration;
+            slice.duration_sec = duration;
+        }
+        manifest
+    }
+
+    #[test]
+    fn summarizes_the_corpus() {
+        let card = DatasetCard::from_manifests(&[
+            manifest("a.WAV", &[0.5, 3.0, 12.0]),
+            manifest("b.mp3", &[3600.0 - 15.5]),
+            manifest("empty.wav", &[]),
+        ]);
+        assert_eq!(card.slice_count, 4);
+        assert_eq!(card.source_count, 2);
+        assert!((card.total_hours - 1.0).abs() < 1e-9);
+        assert_eq!(card.duration.min, 0.5);
+        assert_eq!(card.duration.median, 3.0);
+        assert_eq!(card.sample_rates[&16000], 4);
+        assert_eq!(card.formats["flac"], 4);
+        assert_eq!(card.source_formats["wav"], 1);
+        assert_eq!(card.params_hashes["abc"], 4);
+        let counts: Vec<usize> = card.duration_histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, [1, 0, 1, 0, 1, 0, 0, 0, 1]);
+        assert_eq!(card.duration_histogram.last().unwrap().upper_sec, None);
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::audio::Sample;
+
+/// 内容哈希只覆盖开头的这么多秒，再加上总时长
+pub const HASH_SECONDS: usize = 30;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// 字符串的 FNV-1a 哈希 (十六进制)，用于由路径生成稳定的短标识
+pub fn path_hash(text: &str) -> String {
+    format!("{:016x}", fnv1a(FNV_OFFSET, text.as_bytes()))
+}
+
+/// 快速音频内容哈希: 开头 `HASH_SECONDS` 秒的 PCM (量化为 16 位) + 总样本数 + 采样率
+///
+/// 量化到 16 位可以忽略不同解码器之间的浮点误差；文件名、容器与元数据不影响结果。
+pub fn content_hash<S: Sample>(samples: &[S], sample_rate: u32) -> String {
+    let head = &samples[..samples.len().min(HASH_SECONDS * sample_rate as usize)];
+    let mut hash = FNV_OFFSET;
+    for &sample in head {
+        let quantized = (sample.to_f32().clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        hash = fnv1a(hash, &quantized.to_le_bytes());
+    }
+    hash = fnv1a(hash, &(samples.len() as u64).to_le_bytes());
+    hash = fnv1a(hash, &sample_rate.to_le_bytes());
+    format!("{hash:016x}")
+}
+
+/// 按内容去重的索引: 本次运行中已处理的文件，以及目录数据库中已有的内容
+pub struct ContentIndex {
+    /// 目录数据库中已有的内容哈希 -> 源文件路径
+    known: HashMap<String, String>,
+    /// 本次运行中已认领的内容哈希 -> 源文件路径
+    seen: Mutex<HashMap<String, PathBuf>>,
+}
+
+impl ContentIndex {
+    pub fn new(known: HashMap<String, String>) -> Self {
+        Self {
+            known,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 认领一个内容哈希；内容已处理过时返回跳过原因
+    ///
+    /// 并行处理时，内容相同的多个文件中先完成加载的一个被保留。
+    pub fn claim(&self, hash: &str, file: &Path) -> Option<String> {
+        if let Some(source) = self.known.get(hash) {
+            return Some(format!("内容与目录数据库中的 {source} 相同"));
+        }
+        let mut seen = self.seen.lock().unwrap();
+        match seen.get(hash) {
+            Some(first) => Some(format!("内容与本次运行中的 {} 相同", first.display())),
+            None => {
+                seen.insert(hash.to_string(), file.to_path_buf());
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContentIndex, content_hash};
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[test]
+    fn hash_depends_on_content_and_duration() {
+        let a: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.01).sin()).collect();
+        assert_eq!(content_hash(&a, 16000), content_hash(&a.clone(), 16000));
+        assert_ne!(content_hash(&a, 16000), content_hash(&a[..15999], 16000));
+        assert_ne!(content_hash(&a, 16000), content_hash(&a, 8000));
+        let mut b = a.clone();
+        b[100] += 0.1;
+        assert_ne!(content_hash(&a, 16000), content_hash(&b, 16000));
+    }
+
+    #[test]
+    fn claims_first_occurrence() {
+        let known = HashMap::from([("aa".to_string(), "/old/x.wav".to_string())]);
+        let index = ContentIndex::new(known);
+        assert!(index.claim("aa", Path::new("/new/x.wav")).is_some());
+        assert!(index.claim("bb", Path::new("/new/y.wav")).is_none());
+        let reason = index.claim("bb", Path::new("/new/z.wav")).unwrap();
+        assert!(reason.contains("/new/y.wav"));
+    }
+}
@@ -0,0 +1,1026 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::f32::consts::PI;
+use std::ops::Range;
+
+use crate::audio::Sample;
+
+/// 真峰值检测的过采样倍数
+const OVERSAMPLE: usize = 4;
+/// 插值滤波器单侧抽头数
+const HALF_TAPS: isize = 8;
+/// 限幅器前瞻/起动时间 (ms)
+const LIMITER_ATTACK_MS: f32 = 1.5;
+/// 限幅器释放时间常数 (ms)
+const LIMITER_RELEASE_MS: f32 = 50.0;
+
+/// 软削波起始电平，以下保持线性
+const SOFT_CLIP_KNEE: f32 = 0.8;
+
+/// 超出满幅样本的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum ClipMode {
+    /// 直接截断到满幅
+    #[default]
+    Hard,
+    /// tanh 软饱和，超过拐点后平滑趋近满幅
+    Soft,
+}
+
+impl ClipMode {
+    /// 将样本限制在 [-1, 1] 内
+    pub fn apply(self, sample: f32) -> f32 {
+        match self {
+            ClipMode::Hard => sample.clamp(-1.0, 1.0),
+            ClipMode::Soft => soft_clip(sample),
+        }
+    }
+}
+
+/// 拐点以下线性、以上 tanh 饱和的软削波，在拐点处斜率连续
+fn soft_clip(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= SOFT_CLIP_KNEE {
+        return sample;
+    }
+    let headroom = 1.0 - SOFT_CLIP_KNEE;
+    let saturated = SOFT_CLIP_KNEE + headroom * ((magnitude - SOFT_CLIP_KNEE) / headroom).tanh();
+    saturated.copysign(sample)
+}
+
+/// 切片首尾淡入淡出的曲线
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum FadeShape {
+    /// 增益线性变化
+    #[default]
+    Linear,
+    /// 升余弦 (汉宁窗半边)，两端斜率为零，听感更平滑
+    Cosine,
+}
+
+impl FadeShape {
+    /// 淡入进度 `t` (0~1) 处的增益
+    fn gain(self, t: f32) -> f32 {
+        match self {
+            FadeShape::Linear => t,
+            FadeShape::Cosine => 0.5 - 0.5 * (PI * t).cos(),
+        }
+    }
+}
+
+/// 对首尾各 `fade_ms` 毫秒施加淡入与淡出，消除在波形中间切断造成的爆音
+///
+/// 淡变长度不超过样本数的一半，首尾样本的增益为 0。
+pub fn fade_edges(samples: &mut [f32], sample_rate: u32, fade_ms: f32, shape: FadeShape) {
+    let len = samples.len();
+    let fade_len = ((sample_rate as f32 * fade_ms / 1000.0) as usize).min(len / 2);
+    for i in 0..fade_len {
+        let gain = shape.gain(i as f32 / fade_len as f32);
+        samples[i] *= gain;
+        samples[len - 1 - i] *= gain;
+    }
+}
+
+/// 首尾伪迹检测的分析帧长 (ms)
+const ARTIFACT_FRAME_MS: f32 = 5.0;
+/// 电平达到切片响度 (帧电平的第 90 百分位) 减去该值 (dB) 处视为语音开始
+const SPEECH_ONSET_DB: f32 = 12.0;
+/// 呼吸声的最短持续帧数 (20ms)
+const BREATH_MIN_FRAMES: usize = 4;
+/// 谱质心不高于该值 (Hz) 的能量突起视为呼吸声
+const BREATH_MAX_CENTROID_HZ: f32 = 1500.0;
+/// 谱质心不低于该值 (Hz) 的单帧瞬态视为咔嗒声
+const CLICK_MIN_CENTROID_HZ: f32 = 3000.0;
+/// 咔嗒声帧高出前后帧的最小幅度 (dB)
+const CLICK_JUMP_DB: f32 = 15.0;
+/// 呼吸声与咔嗒声的衰减量 (dB)
+const ARTIFACT_ATTENUATION_DB: f32 = 24.0;
+
+/// `samples[range]` 一帧的电平 (dB) 与谱质心 (Hz)
+///
+/// 谱质心由一阶差分与信号的能量比估计: 频率为 f 的正弦波的比值为 4·sin²(πf/fs)。
+/// 差分包含帧前一个样本，落在帧首的瞬态不会被低估。
+fn frame_level(samples: &[f32], range: Range<usize>, sample_rate: u32) -> (f32, f32) {
+    let energy: f32 = samples[range.clone()].iter().map(|x| x * x).sum();
+    let difference: f32 = samples[range.start.saturating_sub(1)..range.end]
+        .windows(2)
+        .map(|w| (w[1] - w[0]).powi(2))
+        .sum();
+    let db = 10.0 * (energy / range.len() as f32).max(1e-10).log10();
+    let ratio = if energy > 0.0 {
+        difference / energy
+    } else {
+        0.0
+    };
+    let centroid_hz = sample_rate as f32 / PI * (ratio.sqrt() / 2.0).min(1.0).asin();
+    (db, centroid_hz)
+}
+
+/// 在按时间顺序排列 (从切片边界向内) 的边缘帧中查找伪迹，把对应帧的增益设为衰减值，
+/// 返回找到的伪迹数
+fn edge_artifacts(frames: &[(f32, f32)], loud_db: f32, gains: &mut [f32]) -> usize {
+    let attenuation = 10f32.powf(-ARTIFACT_ATTENUATION_DB / 20.0);
+    let mut found = 0;
+    for (i, &(db, centroid_hz)) in frames.iter().enumerate() {
+        let neighbors = [i.checked_sub(1), Some(i + 1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|j| frames.get(j))
+            .map(|&(db, _)| db)
+            .reduce(f32::max);
+        if neighbors.is_some_and(|neighbors| db >= neighbors + CLICK_JUMP_DB)
+            && centroid_hz >= CLICK_MIN_CENTROID_HZ
+        {
+            gains[i] = attenuation;
+            found += 1;
+        }
+    }
+
+    // 呼吸声: 语音开始之前、与语音之间隔着一段安静的能量突起
+    let onset = frames
+        .iter()
+        .position(|&(db, _)| db >= loud_db - SPEECH_ONSET_DB)
+        .unwrap_or(frames.len());
+    let before = &frames[..onset];
+    let Some(floor_db) = before.iter().map(|&(db, _)| db).reduce(f32::min) else {
+        return found;
+    };
+    let Some(dip) = before.iter().rposition(|&(db, _)| db <= floor_db + 6.0) else {
+        return found;
+    };
+    let burst: Vec<usize> = (0..dip)
+        .filter(|&i| {
+            let (db, centroid_hz) = frames[i];
+            db > floor_db + 10.0 && db < loud_db - 6.0 && centroid_hz <= BREATH_MAX_CENTROID_HZ
+        })
+        .collect();
+    if let (Some(&first), Some(&last)) = (burst.first(), burst.last())
+        && burst.len() >= BREATH_MIN_FRAMES
+    {
+        for gain in &mut gains[first..=last] {
+            *gain = gain.min(attenuation);
+        }
+        found += 1;
+    }
+    found
+}
+
+/// 衰减切片首尾各 `edge_ms` 毫秒内的呼吸声与咔嗒声，返回处理的伪迹数
+///
+/// 呼吸声为语音开始之前、与语音之间隔着一段安静、比语音弱至少 6 dB、持续不短于 20ms
+/// 且谱质心较低的能量突起；咔嗒声为比前后帧高出 15 dB 以上的单帧宽带瞬态。
+/// 检测范围不超过切片长度的一半，衰减在伪迹前后各一帧 (5ms) 内平滑过渡。
+pub fn attenuate_edge_artifacts(samples: &mut [f32], sample_rate: u32, edge_ms: f32) -> usize {
+    let frame_len = ((sample_rate as f32 * ARTIFACT_FRAME_MS / 1000.0) as usize).max(1);
+    let frames: Vec<(f32, f32)> = (0..samples.len())
+        .step_by(frame_len)
+        .map(|start| {
+            let end = (start + frame_len).min(samples.len());
+            frame_level(samples, start..end, sample_rate)
+        })
+        .collect();
+    let mut levels: Vec<f32> = frames.iter().map(|&(db, _)| db).collect();
+    levels.sort_by(f32::total_cmp);
+    let Some(&loud_db) = levels.get(levels.len().saturating_sub(1) * 9 / 10) else {
+        return 0;
+    };
+    let edge = ((edge_ms / ARTIFACT_FRAME_MS) as usize).min(frames.len() / 2);
+
+    let mut gains = vec![1.0f32; frames.len()];
+    let mut found = edge_artifacts(&frames[..edge], loud_db, &mut gains[..edge]);
+    // 尾部从切片末尾向内分析
+    let tail: Vec<(f32, f32)> = frames[frames.len() - edge..]
+        .iter()
+        .rev()
+        .copied()
+        .collect();
+    let mut tail_gains = vec![1.0f32; edge];
+    found += edge_artifacts(&tail, loud_db, &mut tail_gains);
+    for (i, gain) in tail_gains.into_iter().enumerate() {
+        let index = frames.len() - 1 - i;
+        gains[index] = gains[index].min(gain);
+    }
+    if found == 0 {
+        return 0;
+    }
+
+    // 衰减范围向两侧各扩展一帧，增益在相邻帧内过渡 (一帧长的滑动平均)，伪迹本身完整衰减
+    let widened: Vec<f32> = (0..gains.len())
+        .map(|k| {
+            let window = &gains[k.saturating_sub(1)..(k + 2).min(gains.len())];
+            window.iter().copied().fold(1.0, f32::min)
+        })
+        .collect();
+    let len = samples.len();
+    let gain_at = |i: usize| widened[i / frame_len];
+    let half = frame_len / 2;
+    let smoothed = |i: usize| {
+        let (lo, hi) = (i.saturating_sub(half), (i + half + 1).min(len));
+        (lo..hi).map(gain_at).sum::<f32>() / (hi - lo) as f32
+    };
+    // 只处理首尾 (检测范围加上扩展与过渡的两帧)，中间部分的增益恒为 1
+    let reach = ((edge + 2) * frame_len).min(len);
+    for range in [0..reach, (len - reach).max(reach)..len] {
+        let gains: Vec<f32> = range.clone().map(smoothed).collect();
+        for (sample, gain) in samples[range].iter_mut().zip(gains) {
+            *sample *= gain;
+        }
+    }
+    found
+}
+
+/// 切片首尾补白的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum PadMode {
+    /// 数字静音
+    #[default]
+    Silence,
+    /// 源文件中切片前后的真实音频，到达文件首尾时再补静音
+    Context,
+}
+
+/// 取出 `source` 中的 `range` 并在首尾各补 `pad` 个样本
+pub fn pad_slice<S: Sample>(
+    source: &[S],
+    range: Range<usize>,
+    pad: usize,
+    mode: PadMode,
+) -> Vec<f32> {
+    let (start, end) = match mode {
+        PadMode::Silence => (range.start, range.end),
+        PadMode::Context => (
+            range.start.saturating_sub(pad),
+            (range.end + pad).min(source.len()),
+        ),
+    };
+    let mut padded = Vec::with_capacity(range.len() + 2 * pad);
+    padded.resize(pad - (range.start - start), 0.0);
+    padded.extend(source[start..end].iter().map(|x| x.to_f32()));
+    padded.resize(padded.len() + pad - (end - range.end), 0.0);
+    padded
+}
+
+/// 整数量化时的抖动方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum Dither {
+    /// 直接四舍五入
+    #[default]
+    None,
+    /// 三角概率密度 (TPDF) 抖动，带一阶噪声整形
+    Tpdf,
+}
+
+/// 浮点到整数的量化器，保存抖动随机数状态与噪声整形误差
+pub struct Quantizer {
+    dither: Dither,
+    rng: u64,
+    error: f32,
+}
+
+impl Quantizer {
+    pub fn new(dither: Dither) -> Self {
+        Self {
+            dither,
+            rng: 0x9E37_79B9_7F4A_7C15,
+            error: 0.0,
+        }
+    }
+
+    /// 将 [-1, 1] 内的样本量化为 `full_scale` 满幅的整数
+    pub fn quantize(&mut self, sample: f32, full_scale: f32) -> i32 {
+        let scaled = sample * full_scale;
+        let quantized = match self.dither {
+            Dither::None => scaled.round(),
+            Dither::Tpdf => {
+                // 一阶误差反馈把量化噪声推向高频，安静的尾音中更不易察觉
+                let target = scaled - self.error;
+                let noise = self.next_uniform() - self.next_uniform();
+                let quantized = (target + noise)
+                    .round()
+                    .clamp(-full_scale - 1.0, full_scale);
+                self.error = quantized - target;
+                quantized
+            }
+        };
+        quantized.clamp(-full_scale - 1.0, full_scale) as i32
+    }
+
+    /// [0, 1) 均匀分布随机数 (xorshift64*)
+    fn next_uniform(&mut self) -> f32 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let value = self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (value >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// 二阶 IIR 滤波器 (直接 II 型转置)
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    /// 由归一化系数构造 (`a0` 已除去)
+    pub fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self { b, a, z: [0.0; 2] }
+    }
+
+    /// 二阶 Butterworth 高通
+    pub fn highpass(cutoff_hz: f32, sample_rate: u32) -> Self {
+        let (cos, alpha) = Self::rbj_terms(cutoff_hz, sample_rate);
+        let a0 = 1.0 + alpha;
+        Self::new(
+            [
+                (1.0 + cos) / 2.0 / a0,
+                -(1.0 + cos) / a0,
+                (1.0 + cos) / 2.0 / a0,
+            ],
+            [-2.0 * cos / a0, (1.0 - alpha) / a0],
+        )
+    }
+
+    /// 二阶 Butterworth 低通
+    pub fn lowpass(cutoff_hz: f32, sample_rate: u32) -> Self {
+        let (cos, alpha) = Self::rbj_terms(cutoff_hz, sample_rate);
+        let a0 = 1.0 + alpha;
+        Self::new(
+            [
+                (1.0 - cos) / 2.0 / a0,
+                (1.0 - cos) / a0,
+                (1.0 - cos) / 2.0 / a0,
+            ],
+            [-2.0 * cos / a0, (1.0 - alpha) / a0],
+        )
+    }
+
+    /// RBJ 滤波器公式中的 cos(w0) 与 alpha (Q = 1/sqrt(2))
+    fn rbj_terms(cutoff_hz: f32, sample_rate: u32) -> (f64, f64) {
+        let w0 = 2.0 * std::f64::consts::PI * cutoff_hz as f64 / sample_rate as f64;
+        (w0.cos(), w0.sin() / std::f64::consts::SQRT_2)
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// 重采样滤波器单侧零交叉数
+const RESAMPLE_HALF_TAPS: isize = 16;
+/// `sinc-fast` 重采样滤波器的单侧零交叉数
+const RESAMPLE_FAST_HALF_TAPS: isize = 4;
+/// rubato 每次处理的输入帧数
+const RUBATO_CHUNK_FRAMES: usize = 4096;
+
+/// 重采样的算法与质量
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum ResamplerQuality {
+    /// 长 sinc 滤波器，通带最平坦、混叠最少
+    #[default]
+    SincHigh,
+    /// 短 sinc 滤波器，速度数倍于 sinc-high，截止频率附近滚降更早
+    SincFast,
+    /// 线性插值，最快；降采样时没有抗混叠滤波
+    Linear,
+}
+
+/// 重采样的实现
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum ResamplerBackend {
+    /// 内置的逐样本窗函数 sinc 插值
+    #[default]
+    Builtin,
+    /// rubato 库，sinc 滤波器预先计算并过采样，长文件上更快
+    Rubato,
+}
+
+/// 重采样器: 实现与质量的组合
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Resampler {
+    pub backend: ResamplerBackend,
+    pub quality: ResamplerQuality,
+}
+
+impl Resampler {
+    /// 把 `samples` 从 `from_rate` 重采样到 `to_rate`，输出长度与内置实现一致
+    pub fn process(&self, samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+        if from_rate == to_rate || samples.is_empty() {
+            return samples.to_vec();
+        }
+        match (self.backend, self.quality) {
+            (ResamplerBackend::Builtin, ResamplerQuality::SincHigh) => {
+                sinc_resample(samples, from_rate, to_rate, RESAMPLE_HALF_TAPS)
+            }
+            (ResamplerBackend::Builtin, ResamplerQuality::SincFast) => {
+                sinc_resample(samples, from_rate, to_rate, RESAMPLE_FAST_HALF_TAPS)
+            }
+            (ResamplerBackend::Builtin, ResamplerQuality::Linear) => {
+                linear_resample(samples, from_rate, to_rate)
+            }
+            (ResamplerBackend::Rubato, quality) => {
+                rubato_resample(samples, from_rate, to_rate, quality)
+            }
+        }
+    }
+
+    /// 记录到清单中的名称，如 `rubato/sinc-fast`
+    pub fn name(&self) -> String {
+        let name = |value: Option<clap::builder::PossibleValue>| {
+            value.map_or_else(String::new, |value| value.get_name().to_string())
+        };
+        format!(
+            "{}/{}",
+            name(self.backend.to_possible_value()),
+            name(self.quality.to_possible_value())
+        )
+    }
+}
+
+/// 重采样后的样本数
+fn resampled_len(len: usize, from_rate: u32, to_rate: u32) -> usize {
+    (len as f64 * to_rate as f64 / from_rate as f64).round() as usize
+}
+
+/// 带限 sinc 插值重采样 (内置实现的 sinc-high 质量)
+///
+/// 降采样时按目标采样率的奈奎斯特频率截止，避免混叠。
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    Resampler::default().process(samples, from_rate, to_rate)
+}
+
+/// 单侧 `half_taps` 个零交叉的窗函数 sinc 插值
+fn sinc_resample(samples: &[f32], from_rate: u32, to_rate: u32, half_taps: isize) -> Vec<f32> {
+    let ratio = from_rate as f64 / to_rate as f64;
+    // 截止频率 (相对于输入奈奎斯特频率)
+    let cutoff = (to_rate as f32 / from_rate as f32).min(1.0);
+    let out_len = resampled_len(samples.len(), from_rate, to_rate);
+    let reach = (half_taps as f32 / cutoff).ceil() as isize;
+    let n = samples.len() as isize;
+
+    (0..out_len)
+        .map(|i| {
+            let t = i as f64 * ratio;
+            let center = t.floor() as isize;
+            let frac = (t - center as f64) as f32;
+            let mut acc = 0.0;
+            let mut norm = 0.0;
+            for k in (center - reach + 1).max(0)..=(center + reach).min(n - 1) {
+                let x = (k - center) as f32 - frac;
+                let weight = cutoff * windowed_sinc(x * cutoff, half_taps as f32);
+                acc += samples[k as usize] * weight;
+                norm += weight;
+            }
+            // 边界处按实际权重归一化，避免首尾增益偏低
+            if norm.abs() > 1e-6 { acc / norm } else { acc }
+        })
+        .collect()
+}
+
+/// 相邻样本间线性插值
+fn linear_resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let ratio = from_rate as f64 / to_rate as f64;
+    let last = samples.len() - 1;
+    (0..resampled_len(samples.len(), from_rate, to_rate))
+        .map(|i| {
+            let t = i as f64 * ratio;
+            let k = (t.floor() as usize).min(last);
+            let frac = (t - k as f64) as f32;
+            let (a, b) = (samples[k], samples[(k + 1).min(last)]);
+            a + (b - a) * frac
+        })
+        .collect()
+}
+
+/// 用 rubato 重采样: 按块送入整个信号后用零冲刷，截取到与内置实现相同的长度
+///
+/// rubato 的插值位置从滤波器长度的一半之前开始，首个输出已与输入对齐 (偏差不到一个输出样本)，
+/// 因此不按 `output_delay` 丢弃开头。
+fn rubato_resample(
+    samples: &[f32],
+    from_rate: u32,
+    to_rate: u32,
+    quality: ResamplerQuality,
+) -> Vec<f32> {
+    use rubato::{
+        FastFixedIn, PolynomialDegree, SincFixedIn, SincInterpolationParameters,
+        SincInterpolationType, WindowFunction,
+    };
+
+    let ratio = to_rate as f64 / from_rate as f64;
+    let sinc = |sinc_len, f_cutoff, interpolation| {
+        let parameters = SincInterpolationParameters {
+            sinc_len,
+            f_cutoff,
+            oversampling_factor: 256,
+            interpolation,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        SincFixedIn::new(ratio, 1.0, parameters, RUBATO_CHUNK_FRAMES, 1)
+            .expect("采样率均大于 0，重采样比有效")
+    };
+    let out_len = resampled_len(samples.len(), from_rate, to_rate);
+    match quality {
+        ResamplerQuality::SincHigh => run_rubato(
+            sinc(256, 0.95, SincInterpolationType::Cubic),
+            samples,
+            out_len,
+        ),
+        ResamplerQuality::SincFast => run_rubato(
+            sinc(64, 0.91, SincInterpolationType::Linear),
+            samples,
+            out_len,
+        ),
+        ResamplerQuality::Linear => run_rubato(
+            FastFixedIn::new(ratio, 1.0, PolynomialDegree::Linear, RUBATO_CHUNK_FRAMES, 1)
+                .expect("采样率均大于 0，重采样比有效"),
+            samples,
+            out_len,
+        ),
+    }
+}
+
+fn run_rubato(
+    mut resampler: impl rubato::Resampler<f32>,
+    samples: &[f32],
+    out_len: usize,
+) -> Vec<f32> {
+    const BUFFER_SIZES: &str = "输入块与输出缓冲区由重采样器自身给出，长度总是匹配";
+    let mut output = Vec::with_capacity(out_len);
+    let mut chunks = samples.chunks_exact(RUBATO_CHUNK_FRAMES);
+    for chunk in &mut chunks {
+        let [out] =
+            <[Vec<f32>; 1]>::try_from(resampler.process(&[chunk], None).expect(BUFFER_SIZES))
+                .expect(BUFFER_SIZES);
+        output.extend(out);
+    }
+    let mut rest = Some(chunks.remainder());
+    while output.len() < out_len {
+        let out = resampler
+            .process_partial(rest.take().as_ref().map(std::slice::from_ref), None)
+            .expect(BUFFER_SIZES);
+        output.extend(out.into_iter().flatten());
+    }
+    output.truncate(out_len);
+    output
+}
+
+/// 变速扰动 (同时改变语速与音高)
+///
+/// 把信号视为以 `sample_rate * speed` 采样，再重采样回 `sample_rate`，
+/// `speed > 1` 时输出变短变快。
+pub fn speed_perturb(samples: &[f32], sample_rate: u32, speed: f32) -> Vec<f32> {
+    let source_rate = (sample_rate as f64 * speed as f64).round() as u32;
+    resample(samples, source_rate, sample_rate)
+}
+
+/// 变速因子的取值范围: 过小的因子使换算出的源采样率舍入为 0，过大的因子几乎不剩样本
+const SPEED_RANGE: std::ops::RangeInclusive<f32> = 0.1..=10.0;
+
+/// 数据增强配置，形如 `speed=0.9,1.0,1.1`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AugmentSpec {
+    /// 变速因子，1.0 对应原始切片，不会重复输出
+    pub speeds: Vec<f32>,
+}
+
+impl std::str::FromStr for AugmentSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, values) = s
+            .split_once('=')
+            .ok_or_else(|| format!("增强配置格式应为 speed=<因子列表>: {s}"))?;
+        if kind.trim() != "speed" {
+            return Err(format!("不支持的增强类型: {kind}"));
+        }
+
+        let mut speeds = Vec::new();
+        for value in values.split(',') {
+            let speed: f32 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("无效的变速因子: {value}"))?;
+            if !SPEED_RANGE.contains(&speed) {
+                return Err(format!(
+                    "变速因子必须在 {} ~ {} 之间: {value}",
+                    SPEED_RANGE.start(),
+                    SPEED_RANGE.end()
+                ));
+            }
+            if !speeds.contains(&speed) {
+                speeds.push(speed);
+            }
+        }
+        Ok(Self { speeds })
+    }
+}
+
+/// Hann 窗 sinc，`half_width` 为单侧零交叉数
+fn windowed_sinc(t: f32, half_width: f32) -> f32 {
+    if t.abs() >= half_width {
+        return 0.0;
+    }
+    let sinc = if t == 0.0 {
+        1.0
+    } else {
+        (PI * t).sin() / (PI * t)
+    };
+    sinc * 0.5 * (1.0 + (PI * t / half_width).cos())
+}
+
+/// 限幅器在单个切片上的动作统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LimiterReport {
+    /// 最大增益衰减 (dB, 非负)
+    pub max_reduction_db: f32,
+    /// 被衰减的样本数
+    pub limited_samples: usize,
+}
+
+/// 真峰值过采样插值核
+fn interpolation_kernel(t: f32) -> f32 {
+    windowed_sinc(t, HALF_TAPS as f32)
+}
+
+/// 估计每个样本位置 (含其与下一样本之间的过采样点) 的真峰值
+pub fn true_peaks(samples: &[f32]) -> Vec<f32> {
+    let n = samples.len() as isize;
+    (0..n)
+        .map(|i| {
+            let mut peak = samples[i as usize].abs();
+            for phase in 1..OVERSAMPLE {
+                let frac = phase as f32 / OVERSAMPLE as f32;
+                let value: f32 = (-HALF_TAPS + 1..=HALF_TAPS)
+                    .filter(|k| (0..n).contains(&(i + k)))
+                    .map(|k| samples[(i + k) as usize] * interpolation_kernel(frac - k as f32))
+                    .sum();
+                peak = peak.max(value.abs());
+            }
+            peak
+        })
+        .collect()
+}
+
+/// 带前瞻的真峰值限幅器，保证各声道输出真峰值不超过 `ceiling_db`
+///
+/// 各声道联动: 按每个位置上所有声道的最大真峰值计算增益，对所有声道施加同一增益，
+/// 声像保持不变。所需增益先做前瞻最小值滤波，再做同长度滑动平均以平滑起动
+/// （平均值不会超过窗口内的最小需求），最后按释放时间常数缓慢回升。
+pub fn true_peak_limit(
+    channels: &mut [Vec<f32>],
+    sample_rate: u32,
+    ceiling_db: f32,
+) -> LimiterReport {
+    let ceiling = 10f32.powf(ceiling_db / 20.0);
+    let n = channels.iter().map(Vec::len).max().unwrap_or(0);
+    let mut peaks = vec![0.0f32; n];
+    for channel in channels.iter() {
+        for (peak, p) in peaks.iter_mut().zip(true_peaks(channel)) {
+            *peak = peak.max(p);
+        }
+    }
+    if peaks.iter().all(|&p| p <= ceiling) {
+        return LimiterReport::default();
+    }
+
+    let required: Vec<f32> = peaks
+        .iter()
+        .map(|&p| if p > ceiling { ceiling / p } else { 1.0 })
+        .collect();
+
+    let attack = ((sample_rate as f32 * LIMITER_ATTACK_MS / 1000.0) as usize).max(1);
+
+    // 前瞻最小值: target[j] = min(required[j..j + attack])
+    let target: Vec<f32> = (0..n)
+        .map(|j| {
+            required[j..(j + attack).min(n)]
+                .iter()
+                .fold(1.0f32, |acc, &g| acc.min(g))
+        })
+        .collect();
+
+    // 滑动平均: gain[i] = mean(target[i + 1 - attack..=i])，窗口不足时按 1.0 补齐
+    let mut smoothed = Vec::with_capacity(n);
+    let mut sum = attack as f32;
+    for i in 0..n {
+        sum += target[i] - if i >= attack { target[i - attack] } else { 1.0 };
+        smoothed.push((sum / attack as f32).min(1.0));
+    }
+
+    let release = (-1.0 / (sample_rate as f32 * LIMITER_RELEASE_MS / 1000.0)).exp();
+    let mut report = LimiterReport::default();
+    let mut gain = 1.0f32;
+    let mut min_gain = 1.0f32;
+
+    for (i, &wanted) in smoothed.iter().enumerate() {
+        gain = wanted.min(1.0 - (1.0 - gain) * release);
+        if gain < 1.0 {
+            report.limited_samples += 1;
+        }
+        min_gain = min_gain.min(gain);
+        for sample in channels.iter_mut().filter_map(|channel| channel.get_mut(i)) {
+            *sample = (*sample * gain).clamp(-ceiling, ceiling);
+        }
+    }
+
+    report.max_reduction_db = -20.0 * min_gain.log10();
+    report
+}
+
+/// 对各声道施加同一增益，使所有声道中绝对值最大的样本达到 `target` (线性幅度)，
+/// 返回施加的增益 (dB)；全静音时不做处理并返回 `None`
+pub fn normalize_peak(channels: &mut [Vec<f32>], target: f32) -> Option<f32> {
+    let peak = channels
+        .iter()
+        .flatten()
+        .fold(0.0f32, |peak, x| peak.max(x.abs()));
+    if peak <= 0.0 || !peak.is_finite() {
+        return None;
+    }
+    let gain = target / peak;
+    for sample in channels.iter_mut().flatten() {
+        *sample *= gain;
+    }
+    Some(20.0 * gain.log10())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AugmentSpec, ClipMode, Dither, FadeShape, PadMode, Quantizer, Resampler, ResamplerBackend,
+        ResamplerQuality, attenuate_edge_artifacts, fade_edges, normalize_peak, pad_slice,
+        resample, speed_perturb, true_peak_limit, true_peaks,
+    };
+
+    #[test]
+    fn attenuates_breath_and_click_at_slice_edges() {
+        let sample_rate = 16000;
+        let ms = |ms: usize| ms * sample_rate as usize / 1000;
+        let tone = |i: usize, hz: f32, amplitude: f32| {
+            amplitude * (2.0 * std::f32::consts::PI * hz * i as f32 / sample_rate as f32).sin()
+        };
+        // 20-80ms 低频呼吸声，120-350ms 语音，380ms 处咔嗒声
+        let original: Vec<f32> = (0..ms(400))
+            .map(|i| match i {
+                _ if (ms(20)..ms(80)).contains(&i) => tone(i, 300.0, 0.03),
+                _ if (ms(120)..ms(350)).contains(&i) => tone(i, 220.0, 0.3),
+                _ if i == ms(380) => 0.5,
+                _ => 1e-4 * (i % 7) as f32,
+            })
+            .collect();
+        let energy = |samples: &[f32], range: std::ops::Range<usize>| -> f32 {
+            samples[range].iter().map(|x| x * x).sum()
+        };
+
+        let mut samples = original.clone();
+        assert_eq!(
+            attenuate_edge_artifacts(&mut samples, sample_rate, 200.0),
+            2
+        );
+        let breath = ms(30)..ms(70);
+        assert!(energy(&samples, breath.clone()) < energy(&original, breath) / 100.0);
+        assert!(samples[ms(380)].abs() < 0.1);
+        assert_eq!(samples[ms(150)..ms(320)], original[ms(150)..ms(320)]);
+
+        // 超过 2^24 个样本的长切片: 只有首尾受影响
+        let long_len = (1 << 24) + ms(1000);
+        let mut long: Vec<f32> = (0..long_len)
+            .map(|i| {
+                if i < ms(400) {
+                    original[i]
+                } else {
+                    tone(i, 220.0, 0.3)
+                }
+            })
+            .collect();
+        assert_eq!(attenuate_edge_artifacts(&mut long, sample_rate, 200.0), 1);
+        assert!(energy(&long, ms(30)..ms(70)) < energy(&original, ms(30)..ms(70)) / 100.0);
+        let tail = long_len - ms(1000)..long_len;
+        assert!(
+            long[tail.clone()]
+                .iter()
+                .zip(tail)
+                .all(|(&x, i)| x == tone(i, 220.0, 0.3))
+        );
+
+        // 语音从边界直接开始时不做处理
+        let mut speech: Vec<f32> = (0..ms(400)).map(|i| tone(i, 220.0, 0.3)).collect();
+        assert_eq!(attenuate_edge_artifacts(&mut speech, sample_rate, 200.0), 0);
+    }
+
+    #[test]
+    fn true_peak_exceeds_sample_peak_between_samples() {
+        // fs/4 正弦相位偏移 45°: 采样点幅度约 0.707，真峰值约 1.0
+        let samples: Vec<f32> = (0..64)
+            .map(|i| (std::f32::consts::FRAC_PI_2 * i as f32 + std::f32::consts::FRAC_PI_4).sin())
+            .collect();
+        let peak = true_peaks(&samples)[20..44]
+            .iter()
+            .fold(0.0f32, |acc, &p| acc.max(p));
+        assert!(peak > 0.95, "真峰值 {peak} 过低");
+    }
+
+    #[test]
+    fn limiter_keeps_quiet_signal_untouched() {
+        let mut channels = vec![vec![0.1, -0.2, 0.3, -0.1]];
+        let original = channels.clone();
+        let report = true_peak_limit(&mut channels, 16000, -1.0);
+        assert_eq!(channels, original);
+        assert_eq!(report.limited_samples, 0);
+    }
+
+    #[test]
+    fn limiter_brings_peaks_below_ceiling() {
+        let mut channels = vec![(0..4000).map(|i| 1.5 * (i as f32 * 0.05).sin()).collect()];
+        let report = true_peak_limit(&mut channels, 16000, -1.0);
+        let ceiling = 10f32.powf(-1.0 / 20.0);
+        assert!(channels[0].iter().all(|x| x.abs() <= ceiling));
+        assert!(report.max_reduction_db > 3.0);
+        assert!(report.limited_samples > 0);
+    }
+
+    #[test]
+    fn limiter_links_channels() {
+        // 只有左声道超过上限，右声道也施加同一增益，两声道的比例不变
+        let left: Vec<f32> = (0..4000).map(|i| 1.5 * (i as f32 * 0.05).sin()).collect();
+        let right: Vec<f32> = left.iter().map(|x| x * 0.1).collect();
+        let mut channels = vec![left, right];
+        let report = true_peak_limit(&mut channels, 16000, -1.0);
+        assert!(report.max_reduction_db > 3.0);
+        for (l, r) in channels[0].iter().zip(&channels[1]) {
+            assert!((l * 0.1 - r).abs() < 1e-5, "{l} {r}");
+        }
+    }
+
+    #[test]
+    fn resample_preserves_duration_and_tone() {
+        let tone: Vec<f32> = (0..48000)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 48000.0).sin())
+            .collect();
+        let out = resample(&tone, 48000, 16000);
+        assert_eq!(out.len(), 16000);
+
+        // 与直接在 16kHz 下生成的正弦比较 (忽略边界)
+        for (i, &value) in out.iter().enumerate().skip(100).take(15800) {
+            let expected = (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 16000.0).sin();
+            assert!(
+                (value - expected).abs() < 0.01,
+                "样本 {i}: {value} vs {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn resampler_backends_agree_on_a_tone() {
+        let tone: Vec<f32> = (0..44100)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 44100.0).sin())
+            .collect();
+        for backend in [ResamplerBackend::Builtin, ResamplerBackend::Rubato] {
+            for quality in [
+                ResamplerQuality::SincHigh,
+                ResamplerQuality::SincFast,
+                ResamplerQuality::Linear,
+            ] {
+                let resampler = Resampler { backend, quality };
+                let out = resampler.process(&tone, 44100, 16000);
+                assert_eq!(out.len(), 16000, "{}", resampler.name());
+                // 与直接在 16kHz 下生成的正弦的归一化相关 (允许不到一个样本的偏移)
+                let (mut dot, mut energy) = (0.0, 0.0);
+                for (i, &value) in out.iter().enumerate().skip(200).take(15600) {
+                    let expected = (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 16000.0).sin();
+                    dot += value * expected;
+                    energy += value * value;
+                }
+                let correlation = dot / (energy * 7800.0).sqrt();
+                assert!(correlation > 0.99, "{}: {correlation}", resampler.name());
+            }
+        }
+        let resampler = Resampler {
+            backend: ResamplerBackend::Rubato,
+            quality: ResamplerQuality::SincFast,
+        };
+        assert_eq!(resampler.name(), "rubato/sinc-fast");
+    }
+
+    #[test]
+    fn resample_same_rate_is_identity() {
+        let samples = vec![0.1, 0.2, 0.3];
+        assert_eq!(resample(&samples, 16000, 16000), samples);
+    }
+
+    #[test]
+    fn speed_perturb_changes_length() {
+        let samples = vec![0.0; 16000];
+        assert_eq!(speed_perturb(&samples, 16000, 1.1).len(), 14545);
+        assert_eq!(speed_perturb(&samples, 16000, 0.9).len(), 17778);
+        assert_eq!(speed_perturb(&samples, 16000, 1.0).len(), 16000);
+    }
+
+    #[test]
+    fn parses_augment_spec() {
+        let spec: AugmentSpec = "speed=0.9, 1.0,1.1,0.9".parse().unwrap();
+        assert_eq!(spec.speeds, vec![0.9, 1.0, 1.1]);
+        assert!("pitch=2".parse::<AugmentSpec>().is_err());
+        assert!("speed=0".parse::<AugmentSpec>().is_err());
+        assert!("speed=1e-9".parse::<AugmentSpec>().is_err());
+        assert!("speed=0.9,20".parse::<AugmentSpec>().is_err());
+        assert!("speed=NaN".parse::<AugmentSpec>().is_err());
+        assert!("speed=0.1,10".parse::<AugmentSpec>().is_ok());
+        assert!("0.9,1.1".parse::<AugmentSpec>().is_err());
+    }
+
+    #[test]
+    fn soft_clip_is_linear_below_knee_and_bounded() {
+        assert_eq!(ClipMode::Soft.apply(0.5), 0.5);
+        assert_eq!(ClipMode::Soft.apply(-0.8), -0.8);
+        let mut prev = 0.8;
+        for i in 1..100 {
+            let value = ClipMode::Soft.apply(0.8 + i as f32 * 0.05);
+            assert!(value >= prev && value <= 1.0);
+            prev = value;
+        }
+        assert_eq!(ClipMode::Soft.apply(-3.0), -ClipMode::Soft.apply(3.0));
+        assert_eq!(ClipMode::Hard.apply(1.5), 1.0);
+    }
+
+    #[test]
+    fn peak_normalization_scales_all_channels_together() {
+        let mut channels = vec![vec![0.1, -0.5], vec![0.25, 0.0]];
+        let gain_db = normalize_peak(&mut channels, 0.95).unwrap();
+        assert!((gain_db - 20.0 * 1.9f32.log10()).abs() < 1e-4);
+        assert!((channels[0][1] + 0.95).abs() < 1e-6);
+        assert!((channels[1][0] - 0.475).abs() < 1e-6);
+        assert_eq!(normalize_peak(&mut [vec![0.0; 4]], 0.95), None);
+    }
+
+    #[test]
+    fn undithered_quantization_rounds() {
+        let mut quantizer = Quantizer::new(Dither::None);
+        assert_eq!(quantizer.quantize(0.5, 32767.0), 16384);
+        assert_eq!(quantizer.quantize(-1.0, 32767.0), -32767);
+    }
+
+    #[test]
+    fn tpdf_dither_is_unbiased_and_small() {
+        let mut quantizer = Quantizer::new(Dither::Tpdf);
+        let level = 0.3 / 32767.0;
+        let values: Vec<i32> = (0..10000)
+            .map(|_| quantizer.quantize(level, 32767.0))
+            .collect();
+        assert!(values.iter().all(|v| v.abs() <= 3));
+        // 亚 LSB 信号经过抖动后平均值得以保留，而不是被截断为 0
+        let mean = values.iter().sum::<i32>() as f32 / values.len() as f32;
+        assert!((mean - 0.3).abs() < 0.05, "均值 {mean}");
+    }
+
+    #[test]
+    fn fades_both_edges_with_either_shape() {
+        for shape in [FadeShape::Linear, FadeShape::Cosine] {
+            let mut samples = vec![1.0; 1000];
+            // 16kHz 下 5ms 为 80 个样本
+            fade_edges(&mut samples, 16000, 5.0, shape);
+            assert_eq!((samples[0], samples[999]), (0.0, 0.0));
+            assert!(samples[40] > 0.0 && samples[40] < 1.0);
+            assert_eq!(samples[40], samples[959]);
+            assert!(samples[80..920].iter().all(|&x| x == 1.0));
+        }
+        // 余弦曲线在起始处比线性更缓
+        let mut linear = vec![1.0; 1000];
+        let mut cosine = vec![1.0; 1000];
+        fade_edges(&mut linear, 16000, 5.0, FadeShape::Linear);
+        fade_edges(&mut cosine, 16000, 5.0, FadeShape::Cosine);
+        assert!(cosine[10] < linear[10]);
+        // 淡变长度不超过一半
+        let mut short = vec![1.0; 10];
+        fade_edges(&mut short, 16000, 50.0, FadeShape::Linear);
+        assert_eq!(short[4], 0.8);
+    }
+
+    #[test]
+    fn pads_with_silence_or_source_context() {
+        let source: Vec<f32> = (1..=10).map(|i| i as f32).collect();
+        assert_eq!(
+            pad_slice(&source, 4..6, 2, PadMode::Silence),
+            [0.0, 0.0, 5.0, 6.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            pad_slice(&source, 4..6, 2, PadMode::Context),
+            [3.0, 4.0, 5.0, 6.0, 7.0, 8.0]
+        );
+        // 文件开头没有足够的上下文时补静音，总长度不变
+        assert_eq!(
+            pad_slice(&source, 1..3, 3, PadMode::Context),
+            [0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+        let integers = [16384i16, -16384];
+        assert_eq!(
+            pad_slice(&integers, 0..2, 1, PadMode::Context),
+            [0.0, 0.5, -0.5, 0.0]
+        );
+    }
+}
@@ -0,0 +1,635 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::atomic::create_file;
+
+/// 切片输出的文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum FileFormat {
+    /// WAV，采样格式由 `--wav-format` 决定
+    #[default]
+    Wav,
+    /// FLAC 无损压缩，只能存储整数样本
+    Flac,
+    /// MP3 (CBR)，由外部编码器 lame 编码
+    Mp3,
+    /// Ogg Opus，由外部编码器 opusenc 编码
+    Opus,
+}
+
+impl FileFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileFormat::Wav => "wav",
+            FileFormat::Flac => "flac",
+            FileFormat::Mp3 => "mp3",
+            FileFormat::Opus => "opus",
+        }
+    }
+
+    /// 有损格式的外部编码器程序名
+    fn encoder(self) -> Option<&'static str> {
+        match self {
+            FileFormat::Mp3 => Some("lame"),
+            FileFormat::Opus => Some("opusenc"),
+            FileFormat::Wav | FileFormat::Flac => None,
+        }
+    }
+
+    /// 编码器支持的码率范围 (kbps，单声道)
+    fn bitrate_range(self) -> std::ops::RangeInclusive<u32> {
+        match self {
+            FileFormat::Mp3 => 8..=320,
+            _ => 6..=256,
+        }
+    }
+}
+
+/// 有损编码的码率 (kbps)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Bitrate(pub u32);
+
+impl std::str::FromStr for Bitrate {
+    type Err = String;
+
+    /// 解析 `128k` 或以 bps 为单位的 `128000`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let kbps = match s.strip_suffix('k') {
+            Some(kbps) => kbps.parse().ok(),
+            None => s.parse::<u32>().ok().map(|bps| bps / 1000),
+        };
+        kbps.filter(|&kbps| kbps > 0)
+            .map(Bitrate)
+            .ok_or_else(|| format!("无效的码率: {s} (如 128k)"))
+    }
+}
+
+impl std::fmt::Display for Bitrate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}k", self.0)
+    }
+}
+
+/// 运行前检查有损格式的编码器是否可用、码率是否在编码器支持的范围内
+pub fn check_encoder(format: FileFormat, bitrate: Bitrate) -> Result<()> {
+    let Some(program) = format.encoder() else {
+        return Ok(());
+    };
+    let range = format.bitrate_range();
+    anyhow::ensure!(
+        range.contains(&bitrate.0),
+        "{program} 不支持码率 {bitrate} (范围 {}k~{}k)",
+        range.start(),
+        range.end()
+    );
+    Command::new(program)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("找不到编码器 {program} ({e})，请安装后加入 PATH"))?;
+    Ok(())
+}
+
+/// 将 16 位样本 (每个声道一个数组) 交给外部编码器写成有损格式，`tags` 写入 ID3v2 TXXX 帧 (MP3) 或 Vorbis 注释 (Opus)
+///
+/// 编码器从标准输入读取 WAV、向标准输出写出编码结果，由本进程写入 `path`
+/// (安全模式下以独占方式创建，编码失败时删除)；编码器引入的起始延迟由 LAME 头或 Opus pre-skip 记录，
+/// 支持无缝播放的播放器会自动去除。
+pub fn write_lossy(
+    path: &Path,
+    channels: &[Vec<i16>],
+    sample_rate: u32,
+    format: FileFormat,
+    bitrate: Bitrate,
+    tags: &[(String, String)],
+) -> Result<()> {
+    let program = format
+        .encoder()
+        .ok_or_else(|| anyhow::anyhow!("{format:?} 不是有损格式"))?;
+    if format == FileFormat::Mp3 && channels.len() > 2 {
+        return Err(anyhow::anyhow!("MP3 最多支持 2 个声道"));
+    }
+    let mut wav = std::io::Cursor::new(Vec::new());
+    let spec = hound::WavSpec {
+        channels: channels.len() as u16,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::new(&mut wav, spec)?;
+    for i in 0..channels[0].len() {
+        for channel in channels {
+            writer.write_sample(channel[i])?;
+        }
+    }
+    writer.finalize()?;
+
+    let mut command = Command::new(program);
+    match format {
+        FileFormat::Mp3 => {
+            command.args(["--quiet", "--cbr", "-b", &bitrate.0.to_string()]);
+            for (key, value) in tags {
+                command.arg("--tv").arg(format!("TXXX={key}={value}"));
+            }
+        }
+        _ => {
+            command.args(["--quiet", "--bitrate", &bitrate.0.to_string()]);
+            for (key, value) in tags {
+                command.arg("--comment").arg(format!("{key}={value}"));
+            }
+        }
+    }
+    command.args(["-", "-"]);
+    encode_to(path, &mut command, program, wav.into_inner())
+}
+
+/// 运行编码器，把 `input` 送入标准输入、标准输出写入新建的 `path`；
+/// 编码器无法启动、失败或写入出错时删除 `path`，不留下空的或截断的文件
+fn encode_to(path: &Path, command: &mut Command, program: &str, input: Vec<u8>) -> Result<()> {
+    let mut file = create_file(path)?;
+    let encoded = run_encoder(command, program, input, &mut file);
+    if encoded.is_err() {
+        drop(file);
+        let _ = std::fs::remove_file(path);
+    }
+    encoded
+}
+
+/// 启动编码器并等待其结束，编码结果写入 `file`
+fn run_encoder(
+    command: &mut Command,
+    program: &str,
+    input: Vec<u8>,
+    file: &mut std::fs::File,
+) -> Result<()> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("无法启动编码器 {program}: {e}"))?;
+
+    // 写入、读取编码结果与读取错误输出同时进行，避免管道写满时双方互相等待
+    let mut stdin = child.stdin.take().expect("stdin 已设为管道");
+    let mut stdout = child.stdout.take().expect("stdout 已设为管道");
+    let (written, copied, output) = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(&input));
+        let copier = scope.spawn(|| std::io::copy(&mut stdout, file));
+        let output = child.wait_with_output();
+        (
+            writer.join().expect("写入线程不会 panic"),
+            copier.join().expect("复制线程不会 panic"),
+            output,
+        )
+    });
+    let output = output?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "编码器 {program} 失败 ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    written?;
+    copied?;
+    Ok(())
+}
+
+/// 每帧的样本数 (libFLAC 默认值)
+const BLOCK_SIZE: usize = 4096;
+/// 最大的固定预测阶数
+const MAX_FIXED_ORDER: usize = 4;
+/// 最大的 Rice 分区阶数
+const MAX_PARTITION_ORDER: u32 = 8;
+/// 4 位 Rice 参数的上限，15 为转义码
+const MAX_RICE_PARAM: u32 = 14;
+
+/// 将整数样本 (每个声道一个数组，1~8 声道) 编码为 FLAC 文件，`tags` 写入 VORBIS_COMMENT 块
+///
+/// 各声道独立编码 (不做立体声去相关)。每个子帧在常量、原样与 0~4 阶固定预测中取最小的编码，残差按分区 Rice 编码。
+/// 不计算 MD5 (STREAMINFO 中为 0，表示未知)。
+pub fn write_flac(
+    path: &Path,
+    channels: &[Vec<i32>],
+    sample_rate: u32,
+    bits_per_sample: u32,
+    tags: &[(String, String)],
+) -> Result<()> {
+    anyhow::ensure!(
+        (1..=655_350).contains(&sample_rate),
+        "FLAC 不支持采样率 {sample_rate}Hz"
+    );
+    anyhow::ensure!(
+        bits_per_sample == 16 || bits_per_sample == 24,
+        "FLAC 输出只支持 16/24 位"
+    );
+    anyhow::ensure!(
+        (1..=8).contains(&channels.len()),
+        "FLAC 不支持 {} 个声道",
+        channels.len()
+    );
+
+    let length = channels[0].len();
+    let frames: Vec<Vec<u8>> = (0..length.div_ceil(BLOCK_SIZE))
+        .map(|number| {
+            let range = number * BLOCK_SIZE..((number + 1) * BLOCK_SIZE).min(length);
+            let blocks: Vec<&[i32]> = channels
+                .iter()
+                .map(|channel| &channel[range.clone()])
+                .collect();
+            encode_frame(&blocks, number as u64, bits_per_sample)
+        })
+        .collect();
+
+    let mut out = b"fLaC".to_vec();
+    let mut streaminfo = BitWriter::new();
+    streaminfo.write(BLOCK_SIZE as u64, 16);
+    streaminfo.write(BLOCK_SIZE as u64, 16);
+    streaminfo.write(frames.iter().map(Vec::len).min().unwrap_or(0) as u64, 24);
+    streaminfo.write(frames.iter().map(Vec::len).max().unwrap_or(0) as u64, 24);
+    streaminfo.write(sample_rate as u64, 20);
+    streaminfo.write(channels.len() as u64 - 1, 3);
+    streaminfo.write(bits_per_sample as u64 - 1, 5);
+    streaminfo.write(length as u64, 36);
+    streaminfo.write(0, 64);
+    streaminfo.write(0, 64);
+    push_metadata_block(&mut out, 0, false, &streaminfo.into_bytes());
+    push_metadata_block(&mut out, 4, true, &vorbis_comment(tags));
+
+    for frame in &frames {
+        out.extend_from_slice(frame);
+    }
+    create_file(path)?.write_all(&out)?;
+    Ok(())
+}
+
+fn push_metadata_block(out: &mut Vec<u8>, block_type: u8, last: bool, body: &[u8]) {
+    out.push(block_type | if last { 0x80 } else { 0 });
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    out.extend_from_slice(body);
+}
+
+/// VORBIS_COMMENT 块 (小端长度前缀，`KEY=value` 形式)
+fn vorbis_comment(tags: &[(String, String)]) -> Vec<u8> {
+    let vendor = concat!("audio-learning ", env!("CARGO_PKG_VERSION"));
+    let mut body = Vec::new();
+    body.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    body.extend_from_slice(vendor.as_bytes());
+    body.extend_from_slice(&(tags.len() as u32).to_le_bytes());
+    for (key, value) in tags {
+        let comment = format!("{key}={value}");
+        body.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        body.extend_from_slice(comment.as_bytes());
+    }
+    body
+}
+
+fn encode_frame(blocks: &[&[i32]], number: u64, bits_per_sample: u32) -> Vec<u8> {
+    let mut frame = BitWriter::new();
+    // 同步码 + 保留位 + 固定块大小
+    frame.write(0xFFF8, 16);
+    // 块大小放在帧头末尾 (16 位, 块大小 - 1)；采样率取自 STREAMINFO
+    frame.write(0b0111, 4);
+    frame.write(0b0000, 4);
+    frame.write(blocks.len() as u64 - 1, 4); // 声道数 - 1，各声道独立
+    frame.write(if bits_per_sample == 16 { 0b100 } else { 0b110 }, 3);
+    frame.write(0, 1);
+    write_utf8_number(&mut frame, number);
+    frame.write(blocks[0].len() as u64 - 1, 16);
+    let crc = crc8(frame.bytes());
+    frame.write(crc as u64, 8);
+
+    for block in blocks {
+        encode_subframe(&mut frame, block, bits_per_sample);
+    }
+    frame.align();
+    let crc = crc16(frame.bytes());
+    frame.write(crc as u64, 16);
+    frame.into_bytes()
+}
+
+fn encode_subframe(out: &mut BitWriter, block: &[i32], bits: u32) {
+    if block.iter().all(|&x| x == block[0]) {
+        out.write(0b0000_0000, 8);
+        out.write_signed(block[0] as i64, bits);
+        return;
+    }
+
+    let verbatim_bits = block.len() as u64 * bits as u64;
+    let best = (0..=MAX_FIXED_ORDER.min(block.len() - 1))
+        .map(|order| {
+            let residual = fixed_residual(block, order);
+            let partitions = best_partitions(&residual, block.len(), order);
+            let size = order as u64 * bits as u64 + partitions.bits;
+            (order, residual, partitions, size)
+        })
+        .min_by_key(|(_, _, _, size)| *size);
+
+    match best {
+        Some((order, residual, partitions, size)) if size < verbatim_bits => {
+            out.write(0b0001_0000 | ((order as u64) << 1), 8);
+            for &warmup in &block[..order] {
+                out.write_signed(warmup as i64, bits);
+            }
+            write_residual(out, &residual, block.len(), order, &partitions);
+        }
+        _ => {
+            out.write(0b0000_0010, 8);
+            for &sample in block {
+                out.write_signed(sample as i64, bits);
+            }
+        }
+    }
+}
+
+/// 固定多项式预测的残差 (从第 `order` 个样本开始)
+fn fixed_residual(block: &[i32], order: usize) -> Vec<i64> {
+    (order..block.len())
+        .map(|i| {
+            let x = |k: usize| block[i - k] as i64;
+            match order {
+                0 => x(0),
+                1 => x(0) - x(1),
+                2 => x(0) - 2 * x(1) + x(2),
+                3 => x(0) - 3 * x(1) + 3 * x(2) - x(3),
+                _ => x(0) - 4 * x(1) + 6 * x(2) - 4 * x(3) + x(4),
+            }
+        })
+        .collect()
+}
+
+/// 选定的分区阶数与各分区的 Rice 参数
+struct Partitions {
+    order: u32,
+    params: Vec<u32>,
+    /// 残差部分的总位数 (含编码方式与分区头)
+    bits: u64,
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// 分区 `index` 在残差序列中的范围 (第一个分区扣除预热样本)
+fn partition_range(
+    block_len: usize,
+    order: usize,
+    partition_order: u32,
+    index: usize,
+) -> (usize, usize) {
+    let size = block_len >> partition_order;
+    let start = (index * size).saturating_sub(order);
+    let end = (index + 1) * size - order;
+    (start, end)
+}
+
+fn best_partitions(residual: &[i64], block_len: usize, order: usize) -> Partitions {
+    let values: Vec<u64> = residual.iter().map(|&r| zigzag(r)).collect();
+    (0..=MAX_PARTITION_ORDER)
+        .take_while(|&p| block_len.is_multiple_of(1 << p) && (block_len >> p) > order)
+        .map(|partition_order| {
+            let mut params = Vec::new();
+            let mut bits = 6;
+            for index in 0..1usize << partition_order {
+                let (start, end) = partition_range(block_len, order, partition_order, index);
+                let (param, size) = best_rice_param(&values[start..end]);
+                params.push(param);
+                bits += 4 + size;
+            }
+            Partitions {
+                order: partition_order,
+                params,
+                bits,
+            }
+        })
+        .min_by_key(|partitions| partitions.bits)
+        .expect("分区阶数 0 总是可用")
+}
+
+/// 编码位数最少的 Rice 参数及对应位数
+fn best_rice_param(values: &[u64]) -> (u32, u64) {
+    (0..=MAX_RICE_PARAM)
+        .map(|k| {
+            let size = values.iter().map(|&u| (u >> k) + 1 + k as u64).sum();
+            (k, size)
+        })
+        .min_by_key(|&(_, size)| size)
+        .unwrap()
+}
+
+fn write_residual(
+    out: &mut BitWriter,
+    residual: &[i64],
+    block_len: usize,
+    order: usize,
+    partitions: &Partitions,
+) {
+    out.write(0b00, 2); // 4 位 Rice 参数
+    out.write(partitions.order as u64, 4);
+    for (index, &k) in partitions.params.iter().enumerate() {
+        out.write(k as u64, 4);
+        let (start, end) = partition_range(block_len, order, partitions.order, index);
+        for &r in &residual[start..end] {
+            let u = zigzag(r);
+            out.write_unary(u >> k);
+            out.write(u & ((1 << k) - 1), k);
+        }
+    }
+}
+
+/// FLAC 帧号使用的扩展 UTF-8 编码
+fn write_utf8_number(out: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        out.write(value, 8);
+        return;
+    }
+    let continuation = match value {
+        0x80..0x800 => 1,
+        0x800..0x1_0000 => 2,
+        0x1_0000..0x20_0000 => 3,
+        0x20_0000..0x400_0000 => 4,
+        _ => 5,
+    };
+    let lead_marker = (0xFF00u64 >> (continuation + 1)) & 0xFF;
+    out.write(lead_marker | (value >> (6 * continuation)), 8);
+    for i in (0..continuation).rev() {
+        out.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// 高位在前的位写入器
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    pending: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            accumulator: 0,
+            pending: 0,
+        }
+    }
+
+    /// 写入 `value` 的低 `bits` 位 (bits <= 32)
+    fn write(&mut self, value: u64, bits: u32) {
+        if bits > 32 {
+            self.write(value >> 32, bits - 32);
+            self.write(value & 0xFFFF_FFFF, 32);
+            return;
+        }
+        if bits == 0 {
+            return;
+        }
+        self.accumulator = (self.accumulator << bits) | (value & ((1 << bits) - 1));
+        self.pending += bits;
+        while self.pending >= 8 {
+            self.pending -= 8;
+            self.bytes.push((self.accumulator >> self.pending) as u8);
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// `zeros` 个 0 后跟一个 1
+    fn write_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    /// 补零到字节边界
+    fn align(&mut self) {
+        if self.pending > 0 {
+            self.write(0, 8 - self.pending);
+        }
+    }
+
+    /// 已写满的字节
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bitrate, crc8, encode_to, write_flac};
+    use crate::audio::decode_audio_until;
+
+    #[test]
+    fn failed_encoder_leaves_no_file() {
+        let path = std::env::temp_dir().join(format!("encoder_{}.mp3", std::process::id()));
+        let mut missing = std::process::Command::new("no-such-encoder-program");
+        assert!(encode_to(&path, &mut missing, "no-such-encoder-program", Vec::new()).is_err());
+        assert!(!path.exists());
+
+        // 已写出部分结果后以非零状态退出
+        #[cfg(unix)]
+        {
+            let mut failing = std::process::Command::new("sh");
+            failing.args(["-c", "cat >/dev/null; echo partial; exit 1"]);
+            assert!(encode_to(&path, &mut failing, "sh", b"RIFF".to_vec()).is_err());
+            assert!(!path.exists());
+        }
+    }
+
+    #[test]
+    fn flac_round_trips_through_decoder() {
+        let path = std::env::temp_dir().join(format!("encoder_{}.flac", std::process::id()));
+        // 超过一帧，含静音段 (常量子帧)、正弦 (预测) 与噪声 (接近原样)
+        let mut rng = 12345u32;
+        let samples: Vec<i32> = (0..10_000)
+            .map(|i| match i {
+                0..3000 => 0,
+                3000..7000 => ((i as f32 * 0.05).sin() * 20000.0) as i32,
+                _ => {
+                    rng = rng.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (rng >> 16) as i32 - 32768
+                }
+            })
+            .collect();
+        let tags = [("SOURCE".to_string(), "a.wav".to_string())];
+        // 第二声道为反相的第一声道
+        let inverted: Vec<i32> = samples.iter().map(|&x| -x.max(-32767)).collect();
+        write_flac(
+            &path,
+            &[samples.clone(), inverted.clone()],
+            16000,
+            16,
+            &tags,
+        )
+        .unwrap();
+
+        let decoded = decode_audio_until(&path, None).unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(decoded.sample_rate, 16000);
+        let restored: Vec<i32> = decoded.channels[0]
+            .iter()
+            .map(|&x| (x * 32768.0).round() as i32)
+            .collect();
+        assert_eq!(restored, samples);
+        let restored: Vec<i32> = decoded.channels[1]
+            .iter()
+            .map(|&x| (x * 32768.0).round() as i32)
+            .collect();
+        assert_eq!(restored, inverted);
+        assert!(size < samples.len() as u64 * 4);
+    }
+
+    #[test]
+    fn parses_bitrate() {
+        assert_eq!("128k".parse(), Ok(Bitrate(128)));
+        assert_eq!("96000".parse(), Ok(Bitrate(96)));
+        assert!("0k".parse::<Bitrate>().is_err());
+        assert!("fast".parse::<Bitrate>().is_err());
+        assert_eq!(Bitrate(64).to_string(), "64k");
+    }
+
+    #[test]
+    fn crc8_matches_reference() {
+        // CRC-8 (多项式 0x07) 的标准校验值
+        assert_eq!(crc8(b"123456789"), 0xF4);
+    }
+}
@@ -0,0 +1,109 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::Write;
+
+/// 机器可读事件的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EventFormat {
+    /// 每行一个 JSON 对象 (JSON Lines)
+    Jsonl,
+}
+
+/// 处理过程中的事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// 文件扫描完成
+    ScanDone {
+        input: String,
+        files: usize,
+        duration_sec: f64,
+    },
+    /// 开始处理单个文件
+    FileStart { file: String },
+    /// 单个文件处理结束
+    FileDone {
+        file: String,
+        success: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        /// 跳过时的原因 (内容重复或已由另一运行认领)
+        #[serde(skip_serializing_if = "Option::is_none")]
+        skipped: Option<String>,
+        slices: usize,
+        audio_duration_sec: f64,
+        saved_duration_sec: f64,
+        processing_time_sec: f64,
+    },
+    /// 整个批次的汇总
+    RunSummary(RunSummary),
+}
+
+/// 整个批次的汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub files: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// 跳过的文件数
+    pub skipped: usize,
+    pub slices: usize,
+    pub audio_duration_sec: f64,
+    pub saved_duration_sec: f64,
+    pub elapsed_sec: f64,
+}
+
+/// 进度与结果事件的接收端，处理线程会并发调用
+pub trait ProgressSink: Send + Sync {
+    fn emit(&self, event: Event);
+}
+
+/// 丢弃所有事件
+pub struct NullSink;
+
+impl ProgressSink for NullSink {
+    fn emit(&self, _event: Event) {}
+}
+
+/// 以 JSON Lines 形式写到标准输出，每个事件独占一行
+pub struct JsonLinesSink;
+
+impl ProgressSink for JsonLinesSink {
+    fn emit(&self, event: Event) {
+        let Ok(line) = serde_json::to_string(&event) else {
+            return;
+        };
+        let mut stdout = std::io::stdout().lock();
+        // 标准输出被关闭时 (如管道另一端退出) 不影响处理
+        let _ = writeln!(stdout, "{line}");
+        let _ = stdout.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Event;
+
+    #[test]
+    fn events_are_tagged() {
+        let json = serde_json::to_string(&Event::FileStart {
+            file: "a.wav".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"event":"file_start","file":"a.wav"}"#);
+
+        let json = serde_json::to_value(Event::FileDone {
+            file: "a.wav".to_string(),
+            success: false,
+            error: Some("处理超时".to_string()),
+            skipped: None,
+            slices: 0,
+            audio_duration_sec: 0.0,
+            saved_duration_sec: 0.0,
+            processing_time_sec: 1.5,
+        })
+        .unwrap();
+        assert_eq!(json["event"], "file_done");
+        assert_eq!(json["error"], "处理超时");
+    }
+}
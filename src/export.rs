@@ -0,0 +1,321 @@
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::atomic::{create_file, write_atomic};
+use crate::dataset::DURATION_BUCKETS_SEC;
+use crate::manifest::FileManifest;
+
+/// 帧级检测结果文件名
+pub const MASK_FILE_NAME: &str = "mask.csv";
+/// 整个输入文件的波形峰值文件名
+pub const PEAKS_FILE_NAME: &str = "peaks.dat";
+/// Audacity 标签轨文件名
+pub const LABELS_FILE_NAME: &str = "labels.txt";
+/// 自动标签: 有声帧占比不低于该值时为 speech，否则为 sparse
+const AUTO_LABEL_SPEECH_RATIO: f32 = 0.5;
+/// 自动标签: 帧级 RMS 低于该值 (dBFS) 时为 quiet
+const AUTO_LABEL_QUIET_DB: f32 = -35.0;
+/// 自动标签: 帧级 RMS 高于该值 (dBFS) 时为 loud
+const AUTO_LABEL_LOUD_DB: f32 = -15.0;
+
+/// 导出逐帧的语音/静音判定与RMS能量 (CSV)
+///
+/// 列: `frame,time_sec,rms,speech`，`speech` 为 1 表示有声帧。
+pub fn write_frame_mask(
+    path: &Path,
+    rms: &[f32],
+    silence: &[bool],
+    hop_size: usize,
+    sample_rate: u32,
+) -> Result<()> {
+    let mut writer = BufWriter::new(create_file(path)?);
+    writeln!(writer, "frame,time_sec,rms,speech")?;
+    for (i, (&rms_val, &silent)) in rms.iter().zip(silence).enumerate() {
+        let time_sec = (i * hop_size) as f64 / sample_rate as f64;
+        writeln!(
+            writer,
+            "{i},{time_sec:.6},{rms_val:.6},{}",
+            if silent { 0 } else { 1 }
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 由切片指标生成的描述标签: 内容 (speech/sparse)、电平 (quiet/normal/loud) 与
+/// 时长区间 (与数据集描述的时长分布相同的区间)，以空格分隔，如 `speech loud 2-5s`
+pub fn auto_label(duration_sec: f64, rms_db: f32, voiced_ratio: f32) -> String {
+    let content = if voiced_ratio >= AUTO_LABEL_SPEECH_RATIO {
+        "speech"
+    } else {
+        "sparse"
+    };
+    let level = if rms_db < AUTO_LABEL_QUIET_DB {
+        "quiet"
+    } else if rms_db > AUTO_LABEL_LOUD_DB {
+        "loud"
+    } else {
+        "normal"
+    };
+    let bucket = DURATION_BUCKETS_SEC.partition_point(|&upper| upper <= duration_sec);
+    let lower = bucket.checked_sub(1).map(|i| DURATION_BUCKETS_SEC[i]);
+    let duration = match (lower, DURATION_BUCKETS_SEC.get(bucket)) {
+        (Some(lower), Some(upper)) => format!("{lower}-{upper}s"),
+        (None, Some(upper)) => format!("<{upper}s"),
+        (Some(lower), None) => format!(">={lower}s"),
+        (None, None) => String::new(),
+    };
+    format!("{content} {level} {duration}")
+}
+
+/// 导出 Audacity 标签轨 (制表符分隔的起始秒、结束秒与标签)
+///
+/// 在 Audacity 中通过 "文件 > 导入 > 标签" 加载，与源音频对齐后可直观检查切点。
+pub fn write_audacity_labels(path: &Path, labels: &[(f64, f64, String)]) -> Result<()> {
+    write_atomic(path, |writer| {
+        for (start_sec, end_sec, label) in labels {
+            writeln!(writer, "{start_sec:.6}\t{end_sec:.6}\t{label}")?;
+        }
+        Ok(())
+    })
+}
+
+/// 切片的波形峰值文件名: 与切片同名，扩展名为 `.dat`
+pub fn slice_peaks_name(slice_file: &str) -> String {
+    Path::new(slice_file)
+        .with_extension("dat")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 导出 audiowaveform 二进制格式 (版本 1，16 位) 的波形峰值
+///
+/// 每 `samples_per_pixel` 个样本记录一对最小值/最大值，peaks.js 等波形组件可以直接绘制，
+/// 无需在浏览器中解码音频。
+pub fn write_peaks(
+    path: &Path,
+    samples: &[f32],
+    sample_rate: u32,
+    samples_per_pixel: usize,
+) -> Result<()> {
+    let to_i16 = |x: f32| (x.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+    let buckets = samples.chunks(samples_per_pixel.max(1));
+    let mut writer = BufWriter::new(create_file(path)?);
+    writer.write_all(&1i32.to_le_bytes())?;
+    // flags: 0 表示 16 位数据
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(&(sample_rate as i32).to_le_bytes())?;
+    writer.write_all(&(samples_per_pixel as i32).to_le_bytes())?;
+    writer.write_all(&(buckets.len() as u32).to_le_bytes())?;
+    for bucket in buckets {
+        let (min, max) = bucket
+            .iter()
+            .filter(|x| x.is_finite())
+            .fold((0.0f32, 0.0f32), |(min, max), &x| (min.min(x), max.max(x)));
+        writer.write_all(&to_i16(min).to_le_bytes())?;
+        writer.write_all(&to_i16(max).to_le_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Label Studio 标注配置中区域标签控件的名称 (`<Labels name="label">`)
+const LABEL_STUDIO_FROM_NAME: &str = "label";
+/// Label Studio 标注配置中音频对象的名称 (`<Audio name="audio">`)
+const LABEL_STUDIO_TO_NAME: &str = "audio";
+/// 预标注区域使用的标签
+const LABEL_STUDIO_LABEL: &str = "Speech";
+
+/// 一个源文件的 Label Studio 任务: 音频地址与每个切片对应的预标注区域
+///
+/// 区域的置信度为切片的有声帧占比，任务的整体置信度取其平均值。`auto_labels` 为真时
+/// 区域的备注 (`meta.text`) 为切片的自动标签。
+fn label_studio_task(audio: String, manifest: &FileManifest, auto_labels: bool) -> Value {
+    let results: Vec<Value> = manifest
+        .slices
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let mut region = json!({
+                "id": format!("slice{index:03}"),
+                "from_name": LABEL_STUDIO_FROM_NAME,
+                "to_name": LABEL_STUDIO_TO_NAME,
+                "type": "labels",
+                "origin": "prediction",
+                "score": record.voiced_ratio,
+                "value": {
+                    "start": record.start_sec,
+                    "end": record.end_sec,
+                    "channel": 0,
+                    "labels": [LABEL_STUDIO_LABEL],
+                },
+            });
+            if auto_labels {
+                region["meta"] = json!({
+                    "text": [auto_label(record.duration_sec, record.rms_db, record.voiced_ratio)],
+                });
+            }
+            region
+        })
+        .collect();
+    let score = if manifest.slices.is_empty() {
+        0.0
+    } else {
+        manifest
+            .slices
+            .iter()
+            .map(|record| record.voiced_ratio)
+            .sum::<f32>()
+            / manifest.slices.len() as f32
+    };
+    json!({
+        "data": {
+            "audio": audio,
+            "source": manifest.source,
+        },
+        "predictions": [{
+            "model_version": concat!("audio-slicer-", env!("CARGO_PKG_VERSION")),
+            "score": score,
+            "result": results,
+        }],
+    })
+}
+
+/// 把检测到的切片写成 Label Studio 的预标注任务列表 (JSON)，返回任务数
+///
+/// 给出 `audio_prefix` 时音频地址为前缀加上源文件相对于 `input_base` 的路径
+/// (如本地存储的 `/data/local-files/?d=corpus/`)，否则为源文件路径。
+pub fn write_label_studio(
+    path: &Path,
+    manifests: &[(PathBuf, FileManifest)],
+    input_base: &Path,
+    audio_prefix: Option<&str>,
+    auto_labels: bool,
+) -> Result<usize> {
+    let tasks: Vec<Value> = manifests
+        .iter()
+        .map(|(_, manifest)| {
+            let audio = match audio_prefix {
+                Some(prefix) => {
+                    let source = Path::new(&manifest.source);
+                    let relative = source.strip_prefix(input_base).unwrap_or(source);
+                    let parts: Vec<_> = relative
+                        .components()
+                        .map(|component| component.as_os_str().to_string_lossy())
+                        .collect();
+                    format!("{prefix}{}", parts.join("/"))
+                }
+                None => manifest.source.clone(),
+            };
+            label_studio_task(audio, manifest, auto_labels)
+        })
+        .collect();
+    write_atomic(path, |writer| {
+        serde_json::to_writer_pretty(&mut *writer, &tasks)?;
+        Ok(())
+    })?;
+    Ok(tasks.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        auto_label, slice_peaks_name, write_audacity_labels, write_label_studio, write_peaks,
+    };
+    use crate::manifest::test_manifest;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn writes_audiowaveform_peaks() {
+        let path = std::env::temp_dir().join(format!("peaks_{}.dat", std::process::id()));
+        let samples = [0.5, -0.25, 1.0, 0.1, -1.0];
+        write_peaks(&path, &samples, 16000, 2).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let word = |i: usize| i32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap());
+        assert_eq!(
+            [word(0), word(1), word(2), word(3), word(4)],
+            [1, 0, 16000, 2, 3]
+        );
+        let data: Vec<i16> = bytes[20..]
+            .chunks(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(data, vec![-8192, 16384, 0, 32767, -32767, 0]);
+        assert_eq!(slice_peaks_name("slice_000.wav"), "slice_000.dat");
+    }
+
+    #[test]
+    fn writes_audacity_labels() {
+        let path = std::env::temp_dir().join(format!("labels_{}.txt", std::process::id()));
+        let labels = vec![
+            (0.5, 2.25, "slice_000".to_string()),
+            (3.0, 4.125, "slice_001".to_string()),
+        ];
+        write_audacity_labels(&path, &labels).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            text,
+            "0.500000\t2.250000\tslice_000\n3.000000\t4.125000\tslice_001\n"
+        );
+    }
+
+    #[test]
+    fn writes_label_studio_predictions() {
+        let mut manifest = test_manifest(&["slice_000.wav", "slice_001.wav"]);
+        manifest.source = "/data/talks/a.wav".to_string();
+        for (slice, (start, end, voiced)) in manifest
+            .slices
+            .iter_mut()
+            .zip([(0.5, 2.0, 0.8), (3.0, 4.5, 0.6)])
+        {
+            slice.start_sec = start;
+            slice.end_sec = end;
+            slice.duration_sec = end - start;
+            slice.rms_db = -20.0;
+            slice.voiced_ratio = voiced;
+        }
+        let manifests = vec![(PathBuf::from("/out/talks/a"), manifest)];
+
+        let path = std::env::temp_dir().join(format!("label_studio_{}.json", std::process::id()));
+        let count = write_label_studio(
+            &path,
+            &manifests,
+            Path::new("/data"),
+            Some("/data/local-files/?d=corpus/"),
+            true,
+        )
+        .unwrap();
+        let tasks: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(count, 1);
+        let task = &tasks[0];
+        assert_eq!(
+            task["data"]["audio"],
+            "/data/local-files/?d=corpus/talks/a.wav"
+        );
+        let prediction = &task["predictions"][0];
+        assert!((prediction["score"].as_f64().unwrap() - 0.7).abs() < 1e-6);
+        let regions = prediction["result"].as_array().unwrap();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[1]["value"]["start"], 3.0);
+        assert_eq!(regions[1]["value"]["end"], 4.5);
+        assert_eq!(regions[1]["value"]["labels"][0], "Speech");
+        assert_eq!(regions[1]["from_name"], "label");
+        assert_eq!(regions[1]["meta"]["text"][0], "speech normal 1-2s");
+    }
+
+    #[test]
+    fn auto_labels_from_slice_metrics() {
+        assert_eq!(auto_label(0.4, -10.0, 0.9), "speech loud <1s");
+        assert_eq!(auto_label(3.0, -20.0, 0.5), "speech normal 2-5s");
+        assert_eq!(auto_label(12.0, -40.0, 0.2), "sparse quiet 10-15s");
+        assert_eq!(auto_label(75.0, -25.0, 0.7), "speech normal >=60s");
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::Result;
+use clap::Parser;
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+use crate::events::{Event, ProgressSink, RunSummary};
+use crate::{Cli, Commands, run_slice_command};
+
+/// 常见系统上的中文字体路径，egui 自带字体不含 CJK 字形
+const CJK_FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Light.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+];
+
+/// 后台处理线程发往界面的消息
+enum Message {
+    Event(Event),
+    Finished(Result<RunSummary, String>),
+}
+
+/// 把处理事件转发到界面线程，并唤醒界面重绘
+struct ChannelSink {
+    sender: Sender<Message>,
+    ctx: egui::Context,
+}
+
+impl ProgressSink for ChannelSink {
+    fn emit(&self, event: Event) {
+        let _ = self.sender.send(Message::Event(event));
+        self.ctx.request_repaint();
+    }
+}
+
+/// 一次运行的界面状态
+struct RunState {
+    receiver: Receiver<Message>,
+    total_files: usize,
+    done_files: usize,
+    current: Option<String>,
+    failures: Vec<(String, String)>,
+    result: Option<Result<RunSummary, String>>,
+}
+
+/// 切片参数表单与运行状态
+struct SlicerApp {
+    input: String,
+    output: String,
+    threshold_db: f32,
+    min_length_ms: u32,
+    min_interval_ms: u32,
+    hop_size_ms: u32,
+    max_silence_ms: u32,
+    enable_merge: bool,
+    max_merge_duration_ms: u32,
+    run: Option<RunState>,
+}
+
+impl Default for SlicerApp {
+    fn default() -> Self {
+        Self {
+            input: String::new(),
+            output: String::new(),
+            threshold_db: -55.0,
+            min_length_ms: 1000,
+            min_interval_ms: 100,
+            hop_size_ms: 5,
+            max_silence_ms: 800,
+            enable_merge: false,
+            max_merge_duration_ms: 8000,
+            run: None,
+        }
+    }
+}
+
+impl SlicerApp {
+    fn is_running(&self) -> bool {
+        self.run.as_ref().is_some_and(|run| run.result.is_none())
+    }
+
+    /// 按表单内容构造命令行参数并在后台线程启动处理
+    fn start(&mut self, ctx: &egui::Context) {
+        let mut argv = vec![
+            "audio-slicer".to_string(),
+            "slice".to_string(),
+            "--input".to_string(),
+            self.input.clone(),
+            "--output".to_string(),
+            self.output.clone(),
+            format!("--threshold-db={}", self.threshold_db),
+            format!("--min-length-ms={}", self.min_length_ms),
+            format!("--min-interval-ms={}", self.min_interval_ms),
+            format!("--hop-size-ms={}", self.hop_size_ms),
+            format!("--max-silence-ms={}", self.max_silence_ms),
+            format!("--max-merge-duration-ms={}", self.max_merge_duration_ms),
+        ];
+        if self.enable_merge {
+            argv.push("--enable-merge".to_string());
+        }
+
+        let (sender, receiver) = channel();
+        self.run = Some(RunState {
+            receiver,
+            total_files: 0,
+            done_files: 0,
+            current: None,
+            failures: Vec::new(),
+            result: None,
+        });
+
+        let args = match Cli::try_parse_from(argv).map(|cli| cli.command) {
+            Ok(Commands::Slice(args)) => args,
+            Ok(_) => unreachable!(),
+            Err(e) => {
+                let _ = sender.send(Message::Finished(Err(e.to_string())));
+                return;
+            }
+        };
+
+        let sink = ChannelSink {
+            sender: sender.clone(),
+            ctx: ctx.clone(),
+        };
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let result = run_slice_command(args, &sink).map_err(|e| e.to_string());
+            let _ = sender.send(Message::Finished(result));
+            ctx.request_repaint();
+        });
+    }
+
+    /// 处理后台线程发来的消息
+    fn poll(&mut self) {
+        let Some(run) = &mut self.run else {
+            return;
+        };
+        while let Ok(message) = run.receiver.try_recv() {
+            match message {
+                Message::Event(Event::ScanDone { files, .. }) => run.total_files = files,
+                Message::Event(Event::FileStart { file }) => run.current = Some(file),
+                Message::Event(Event::FileDone {
+                    file,
+                    success,
+                    error,
+                    ..
+                }) => {
+                    run.done_files += 1;
+                    if !success {
+                        run.failures
+                            .push((file, error.unwrap_or_else(|| "未知错误".to_string())));
+                    }
+                }
+                Message::Event(Event::RunSummary(_)) => {}
+                Message::Finished(result) => run.result = Some(result),
+            }
+        }
+    }
+}
+
+/// 目录输入框与选择按钮
+fn directory_row(ui: &mut egui::Ui, label: &str, path: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.text_edit_singleline(path);
+        if ui.button("选择…").clicked()
+            && let Some(dir) = rfd::FileDialog::new().pick_folder()
+        {
+            *path = dir.display().to_string();
+        }
+    });
+}
+
+impl eframe::App for SlicerApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll();
+        let running = self.is_running();
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("🎵 音频切片");
+            ui.add_space(8.0);
+
+            ui.add_enabled_ui(!running, |ui| {
+                directory_row(ui, "输入目录", &mut self.input);
+                directory_row(ui, "输出目录", &mut self.output);
+                ui.add_space(8.0);
+
+                egui::Grid::new("params").num_columns(2).show(ui, |ui| {
+                    ui.label("静音阈值");
+                    ui.add(
+                        egui::DragValue::new(&mut self.threshold_db)
+                            .range(-100.0..=0.0)
+                            .suffix(" dB"),
+                    );
+                    ui.end_row();
+
+                    let ms_field = |ui: &mut egui::Ui, label: &str, value: &mut u32| {
+                        ui.label(label);
+                        ui.add(egui::DragValue::new(value).range(1..=600_000).suffix(" ms"));
+                        ui.end_row();
+                    };
+                    ms_field(ui, "最小片段长度", &mut self.min_length_ms);
+                    ms_field(ui, "最小间隔", &mut self.min_interval_ms);
+                    ms_field(ui, "跳跃大小", &mut self.hop_size_ms);
+                    ms_field(ui, "最大静音长度", &mut self.max_silence_ms);
+
+                    ui.label("合并短片段");
+                    ui.checkbox(&mut self.enable_merge, "");
+                    ui.end_row();
+                    if self.enable_merge {
+                        ms_field(ui, "最大合并时长", &mut self.max_merge_duration_ms);
+                    }
+                });
+                ui.add_space(8.0);
+
+                let ready = !self.input.is_empty() && !self.output.is_empty();
+                if ui.add_enabled(ready, egui::Button::new("开始处理")).clicked() {
+                    self.start(ctx);
+                }
+            });
+
+            let Some(run) = &self.run else {
+                return;
+            };
+            ui.separator();
+
+            if run.result.is_none() {
+                let fraction = if run.total_files > 0 {
+                    run.done_files as f32 / run.total_files as f32
+                } else {
+                    0.0
+                };
+                ui.add(
+                    egui::ProgressBar::new(fraction)
+                        .text(format!("{}/{}", run.done_files, run.total_files)),
+                );
+                if let Some(file) = &run.current {
+                    ui.label(format!("处理: {file}"));
+                }
+            }
+
+            match &run.result {
+                Some(Ok(summary)) => {
+                    ui.label(format!(
+                        "✨ 完成: {}/{} 个文件成功，共 {} 个切片，有效音频 {:.1}s / {:.1}s，用时 {:.1}s",
+                        summary.succeeded,
+                        summary.files,
+                        summary.slices,
+                        summary.saved_duration_sec,
+                        summary.audio_duration_sec,
+                        summary.elapsed_sec
+                    ));
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(egui::Color32::RED, format!("❌ 运行失败: {error}"));
+                }
+                None => {}
+            }
+
+            if !run.failures.is_empty() {
+                ui.label(format!("❌ 处理失败的文件 ({} 个):", run.failures.len()));
+                egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                    for (file, error) in &run.failures {
+                        ui.label(format!("{file}: {error}"));
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// 加载系统中可用的第一个中文字体作为后备字体
+fn install_cjk_font(ctx: &egui::Context) {
+    let Some(bytes) = CJK_FONT_CANDIDATES
+        .iter()
+        .find_map(|path| std::fs::read(PathBuf::from(path)).ok())
+    else {
+        return;
+    };
+
+    let mut fonts = egui::FontDefinitions::default();
+    fonts.font_data.insert(
+        "cjk".to_string(),
+        Arc::new(egui::FontData::from_owned(bytes)),
+    );
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        fonts
+            .families
+            .entry(family)
+            .or_default()
+            .push("cjk".to_string());
+    }
+    ctx.set_fonts(fonts);
+}
+
+/// 启动桌面界面
+pub fn run_gui() -> Result<()> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([560.0, 520.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "音频切片",
+        options,
+        Box::new(|cc| {
+            install_cjk_font(&cc.egui_ctx);
+            Ok(Box::new(SlicerApp::default()))
+        }),
+    )
+    .map_err(|e| anyhow::anyhow!("界面启动失败: {e}"))
+}
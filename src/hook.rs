@@ -0,0 +1,272 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::slicer::SegmentStats;
+
+/// 保存前传给切片钩子的切片信息
+#[derive(Debug, Clone, Copy)]
+pub struct Segment<'a> {
+    /// 源音频文件
+    pub source: &'a Path,
+    /// 该切片在本文件已保存切片中的序号
+    pub index: usize,
+    pub sample_rate: u32,
+    pub start_sample: usize,
+    pub end_sample: usize,
+    pub stats: &'a SegmentStats,
+    /// 默认的输出文件名
+    pub file_name: &'a str,
+}
+
+/// 切片钩子对一个切片的处理决定
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SegmentDecision {
+    /// 按默认方式保存
+    #[default]
+    Keep,
+    /// 保存，可以改用其他文件名，并附加写入 `manifest.json` 的自定义元数据
+    Annotate {
+        file_name: Option<String>,
+        metadata: BTreeMap<String, String>,
+    },
+    /// 不保存该切片
+    Veto,
+}
+
+impl SegmentDecision {
+    /// 按决定得到切片的文件名与自定义元数据，`file_name` 为默认文件名；否决时返回 `None`
+    pub fn apply(self, file_name: String) -> Result<Option<(String, BTreeMap<String, String>)>> {
+        match self {
+            SegmentDecision::Keep => Ok(Some((file_name, BTreeMap::new()))),
+            SegmentDecision::Annotate {
+                file_name: renamed,
+                metadata,
+            } => {
+                let file_name = match renamed {
+                    Some(renamed) => {
+                        validate_file_name(&renamed)?;
+                        renamed
+                    }
+                    None => file_name,
+                };
+                Ok(Some((file_name, metadata)))
+            }
+            SegmentDecision::Veto => Ok(None),
+        }
+    }
+}
+
+/// 每个切片保存前调用的钩子，参数为切片信息与切片样本 (源采样率，单声道)
+///
+/// 并行处理时会在多个线程中同时调用。
+pub type SegmentHook = dyn Fn(&Segment, &[f32]) -> SegmentDecision + Send + Sync;
+
+/// 经过静音过滤与切片钩子后保留的切片 (见 `Slicer::segments_with`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookedSegment {
+    /// 切片在输入样本中的区间
+    pub range: Range<usize>,
+    pub stats: SegmentStats,
+    /// 钩子给出的文件名，未改名时为默认文件名
+    pub file_name: String,
+    /// 钩子附加的自定义元数据
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// 命令行在切片目录中写出的其他文件 (清单、标注、调试输出等)，钩子不能改用这些文件名，
+/// 否则会被覆盖或被 `clean` 当作附加文件删除
+pub const RESERVED_FILE_NAMES: &[&str] = &[
+    "manifest.json",
+    "noise_profile.wav",
+    "peaks.dat",
+    "mask.csv",
+    "labels.txt",
+    "detection_trace.log",
+    "timeline.png",
+    "timeline.html",
+    "errors.json",
+    "per_file_stats.csv",
+    "dataset.json",
+    "audit",
+    "audit.csv",
+    "refilter_keep.txt",
+    "refilter_drop.txt",
+    ".slicer.lock",
+    ".slicer.run.lock",
+    ".slicer.claim",
+];
+
+/// 检查钩子给出的文件名: 只能是输出目录中的普通文件名，且不能是保留文件名
+///
+/// 除 [`RESERVED_FILE_NAMES`] 外，`.dat` 文件 (切片的波形峰值) 与 `slice_*_speed*`
+/// (变速增强版本) 也是保留的。
+pub fn validate_file_name(name: &str) -> Result<()> {
+    let path = Path::new(name);
+    let is_plain = path
+        .file_name()
+        .is_some_and(|file_name| file_name == path.as_os_str());
+    if name.is_empty() || !is_plain {
+        return Err(anyhow::anyhow!("切片钩子给出的文件名无效: {name:?}"));
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let is_reserved = RESERVED_FILE_NAMES.contains(&name)
+        || path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("dat"))
+        || (stem.starts_with("slice_") && stem.contains("_speed"));
+    if is_reserved {
+        return Err(anyhow::anyhow!(
+            "切片钩子给出的文件名是保留文件名: {name:?}"
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Segment, SegmentDecision, SegmentHook, validate_file_name};
+    use crate::slicer::{BoundaryConfig, RatioMode, SilenceFilter, Slicer, SlicerConfig};
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    #[test]
+    fn rejects_paths_and_reserved_names() {
+        assert!(validate_file_name("intro_001.wav").is_ok());
+        assert!(validate_file_name("slice_001.wav").is_ok());
+        for name in [
+            "",
+            "../x.wav",
+            "sub/x.wav",
+            "/tmp/x.wav",
+            "..",
+            "manifest.json",
+            "labels.txt",
+            "timeline.html",
+            "intro.dat",
+            "slice_000_speed1.1.wav",
+        ] {
+            assert!(validate_file_name(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn hook_vetoes_and_renames_segments() {
+        // 三段响亮音频，第二段较轻
+        let mut samples = Vec::new();
+        for level in [0.5, 0.1, 0.5] {
+            samples.extend(vec![level; 2000]);
+            samples.extend(vec![0.0; 1000]);
+        }
+        let slicer = Slicer::new(SlicerConfig {
+            sample_rate: 1000,
+            threshold_db: -40.0,
+            min_length_ms: 500,
+            min_interval_ms: 100,
+            hop_size_ms: 10,
+            max_silence_ms: 300,
+            ..Default::default()
+        })
+        .unwrap();
+        let filter = SilenceFilter {
+            threshold: 0.001,
+            min_audio_ratio: 0.1,
+            ratio_mode: RatioMode::Voiced,
+        };
+
+        let hook = |segment: &Segment, samples: &[f32]| {
+            assert_eq!(segment.source, Path::new("take.wav"));
+            if samples.iter().all(|&x| x.abs() < 0.2) {
+                return SegmentDecision::Veto;
+            }
+            SegmentDecision::Annotate {
+                file_name: Some(format!("take_{}.wav", segment.index)),
+                metadata: BTreeMap::from([("speaker".to_string(), "a".to_string())]),
+            }
+        };
+        let kept = slicer
+            .segments_with(
+                &samples,
+                &BoundaryConfig::default(),
+                &filter,
+                Path::new("take.wav"),
+                &hook,
+            )
+            .unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].range.start, 0);
+        // 切点在静音开始后 min_interval 处
+        assert_eq!(kept[1].range.start, 5100);
+        assert_eq!(kept[0].file_name, "take_0.wav");
+        assert_eq!(kept[1].file_name, "take_1.wav");
+        assert_eq!(kept[1].metadata["speaker"], "a");
+
+        let rename_outside = |_: &Segment, _: &[f32]| SegmentDecision::Annotate {
+            file_name: Some("../x.wav".to_string()),
+            metadata: BTreeMap::new(),
+        };
+        assert!(
+            slicer
+                .segments_with(
+                    &samples,
+                    &BoundaryConfig::default(),
+                    &filter,
+                    Path::new("take.wav"),
+                    &rename_outside,
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let mut samples = Vec::new();
+        for _ in 0..2 {
+            samples.extend(vec![0.5; 2000]);
+            samples.extend(vec![0.0; 1000]);
+        }
+        let slicer = Slicer::new(SlicerConfig {
+            sample_rate: 1000,
+            threshold_db: -40.0,
+            min_length_ms: 500,
+            min_interval_ms: 100,
+            hop_size_ms: 10,
+            max_silence_ms: 300,
+            ..Default::default()
+        })
+        .unwrap();
+        let filter = SilenceFilter {
+            threshold: 0.001,
+            min_audio_ratio: 0.1,
+            ratio_mode: RatioMode::Voiced,
+        };
+        let run = |hook: &SegmentHook| {
+            slicer.segments_with(
+                &samples,
+                &BoundaryConfig::default(),
+                &filter,
+                Path::new("take.wav"),
+                hook,
+            )
+        };
+        fn rename(name: &str) -> SegmentDecision {
+            SegmentDecision::Annotate {
+                file_name: Some(name.to_string()),
+                metadata: BTreeMap::new(),
+            }
+        }
+
+        // 两个切片改用同一个文件名
+        assert!(run(&|_, _| rename("take.wav")).is_err());
+        // 第一个切片占用了第二个切片的默认文件名
+        assert!(
+            run(&|segment, _| match segment.index {
+                0 => rename("slice_001.wav"),
+                _ => SegmentDecision::Keep,
+            })
+            .is_err()
+        );
+        assert_eq!(run(&|_, _| SegmentDecision::Keep).unwrap().len(), 2);
+    }
+}
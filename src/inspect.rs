@@ -0,0 +1,262 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::ScanArgs;
+use crate::atomic::write_atomic;
+use crate::audio::{decode_audio_until, probe_audio};
+use crate::audit::csv_field;
+use crate::loudness::integrated_loudness;
+use crate::report::NumberFormat;
+use crate::scan::scan_audio_files;
+
+/// `scan` 子命令的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ScanFormat {
+    /// 每个文件一行，每个标签键一列
+    Csv,
+    /// 对象数组，标签为嵌套对象
+    Json,
+}
+
+impl ScanFormat {
+    /// 按输出文件扩展名选择，无法判断时为 CSV
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// 单个音频文件的技术元数据与标签
+#[derive(Debug, Default, Serialize)]
+pub struct FileInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<usize>,
+    pub bits_per_sample: Option<u32>,
+    /// 解码时为实际时长，否则为容器声明的时长
+    pub duration_sec: Option<f64>,
+    /// 单声道混音的积分响度 (LUFS)
+    pub loudness_lufs: Option<f32>,
+    /// 样本峰值 (dBFS)
+    pub peak_db: Option<f32>,
+    pub tags: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl FileInfo {
+    /// 读取失败的文件只有路径与错误信息
+    pub fn failed(path: &Path, error: &str) -> Self {
+        Self {
+            path: path.to_string_lossy().into_owned(),
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// 由单声道样本测量时长、响度与峰值
+    fn measure(&mut self, samples: &[f32], sample_rate: u32) {
+        let peak = samples.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+        self.duration_sec = Some(samples.len() as f64 / sample_rate as f64);
+        self.loudness_lufs = integrated_loudness(samples, sample_rate);
+        self.peak_db = Some(20.0 * peak.max(1e-10).log10());
+    }
+}
+
+/// 读取文件头部与标签，再由 `measure` 补充需要样本的字段
+///
+/// 读取失败不会中断整个扫描，错误记录在 `error` 字段中。
+fn inspect(path: &Path, measure: impl FnOnce(&mut FileInfo) -> Result<()>) -> FileInfo {
+    let mut info = FileInfo {
+        path: path.to_string_lossy().into_owned(),
+        size_bytes: std::fs::metadata(path).map_or(0, |metadata| metadata.len()),
+        ..Default::default()
+    };
+    let result = (|| -> Result<()> {
+        let probed = probe_audio(path)?;
+        info.codec = probed.codec;
+        info.sample_rate = probed.sample_rate;
+        info.channels = probed.channels;
+        info.bits_per_sample = probed.bits_per_sample;
+        info.duration_sec = probed.duration_sec;
+        info.tags = probed.tags;
+        measure(&mut info)
+    })();
+    if let Err(error) = result {
+        info.error = Some(error.to_string());
+    }
+    info
+}
+
+/// 读取单个文件的元数据，`decode` 为真时完整解码以测量时长、响度与峰值
+fn inspect_file(path: &Path, decode: bool) -> FileInfo {
+    inspect(path, |info| {
+        if decode {
+            let decoded = decode_audio_until(path, None)?;
+            info.measure(&decoded.downmix(), decoded.sample_rate);
+        }
+        Ok(())
+    })
+}
+
+/// 与 `scan` 相同的元数据，响度与峰值由调用方已解码的声道平均测得，不再重新解码
+pub fn inspect_decoded(path: &Path, samples: &[f32], sample_rate: u32) -> FileInfo {
+    inspect(path, |info| {
+        info.measure(samples, sample_rate);
+        Ok(())
+    })
+}
+
+/// 按 `format` (未指定时按扩展名判断) 写出元数据目录
+pub fn write_file_catalog(
+    output: &Path,
+    format: Option<ScanFormat>,
+    files: &[FileInfo],
+) -> Result<()> {
+    let format = format.unwrap_or_else(|| ScanFormat::from_path(output));
+    write_atomic(output, |writer| match format {
+        ScanFormat::Csv => write_csv(writer, files),
+        ScanFormat::Json => Ok(serde_json::to_writer_pretty(writer, files)?),
+    })
+}
+
+fn write_csv(writer: &mut impl Write, files: &[FileInfo]) -> Result<()> {
+    let tag_keys: BTreeSet<&str> = files
+        .iter()
+        .flat_map(|file| file.tags.keys().map(String::as_str))
+        .collect();
+    let optional = |value: Option<String>| value.unwrap_or_default();
+
+    write!(
+        writer,
+        "path,size_bytes,codec,sample_rate,channels,bits_per_sample,duration_sec,loudness_lufs,peak_db,error"
+    )?;
+    for key in &tag_keys {
+        write!(writer, ",{}", csv_field(&format!("tag:{key}")))?;
+    }
+    writeln!(writer)?;
+
+    for file in files {
+        write!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&file.path),
+            file.size_bytes,
+            csv_field(&file.codec),
+            optional(file.sample_rate.map(|v| v.to_string())),
+            optional(file.channels.map(|v| v.to_string())),
+            optional(file.bits_per_sample.map(|v| v.to_string())),
+            optional(file.duration_sec.map(|v| format!("{v:.3}"))),
+            optional(file.loudness_lufs.map(|v| format!("{v:.2}"))),
+            optional(file.peak_db.map(|v| format!("{v:.2}"))),
+            csv_field(file.error.as_deref().unwrap_or_default()),
+        )?;
+        for key in &tag_keys {
+            let value = file.tags.get(*key).map(String::as_str).unwrap_or_default();
+            write!(writer, ",{}", csv_field(value))?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+pub fn run_scan(args: ScanArgs) -> Result<()> {
+    let threads = args.threads.unwrap_or_else(num_cpus::get).max(1);
+
+    let found = Mutex::new(Vec::new());
+    let stats = scan_audio_files(
+        &args.input,
+        threads,
+        false,
+        &|path: PathBuf| found.lock().unwrap().push(path),
+        &|_, _| {},
+    )?;
+    let mut paths = found.into_inner().unwrap();
+    paths.sort();
+    println!("📂 找到音频文件: {}个", paths.len());
+    for (dir, error) in &stats.unreadable {
+        println!("   ⚠️ 无法读取的目录: {}: {}", dir.display(), error);
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()?;
+    let files: Vec<FileInfo> = pool.install(|| {
+        paths
+            .par_iter()
+            .map(|path| inspect_file(path, !args.headers_only))
+            .collect()
+    });
+
+    write_file_catalog(&args.output, args.format, &files)?;
+
+    let failed: Vec<&FileInfo> = files.iter().filter(|file| file.error.is_some()).collect();
+    for file in &failed {
+        println!(
+            "   ❌ {}: {}",
+            file.path,
+            file.error.as_deref().unwrap_or_default()
+        );
+    }
+    let total_duration: f64 = files.iter().filter_map(|file| file.duration_sec).sum();
+    println!("📊 元数据目录:");
+    println!("   - 文件: {}个 (读取失败 {}个)", files.len(), failed.len());
+    println!(
+        "   - 总时长: {}",
+        NumberFormat::default().duration(total_duration)
+    );
+    println!("   - 输出: {}", args.output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileInfo, ScanFormat, write_csv};
+    use std::path::Path;
+
+    #[test]
+    fn csv_has_a_column_per_tag() {
+        let files = vec![
+            FileInfo {
+                path: "a.wav".to_string(),
+                codec: "pcm_s16le".to_string(),
+                sample_rate: Some(16000),
+                duration_sec: Some(1.5),
+                tags: [("Artist".to_string(), "x, y".to_string())].into(),
+                ..Default::default()
+            },
+            FileInfo {
+                path: "b.mp3".to_string(),
+                error: Some("bad".to_string()),
+                tags: [("TrackTitle".to_string(), "t".to_string())].into(),
+                ..Default::default()
+            },
+        ];
+        let mut out = Vec::new();
+        write_csv(&mut out, &files).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].ends_with(",error,tag:Artist,tag:TrackTitle"));
+        assert_eq!(lines[1], "a.wav,0,pcm_s16le,16000,,,1.500,,,,\"x, y\",");
+        assert_eq!(lines[2], "b.mp3,0,,,,,,,,bad,,t");
+
+        assert_eq!(
+            ScanFormat::from_path(Path::new("meta.JSON")),
+            ScanFormat::Json
+        );
+        assert_eq!(
+            ScanFormat::from_path(Path::new("meta.txt")),
+            ScanFormat::Csv
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::dedup::path_hash;
+
+/// 输出目录布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Layout {
+    /// 保持输入目录结构: `<输出>/<相对目录>/<文件名>/`
+    #[default]
+    Mirror,
+    /// 所有文件的切片目录直接放在输出目录下: `<输出>/<文件名>/`
+    Flat,
+}
+
+/// 平铺布局中文件名冲突时的消歧方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CollisionNaming {
+    /// 以相对目录为前缀: `speaker1__take1`
+    #[default]
+    Path,
+    /// 以相对路径的哈希为后缀: `take1-3f2a9c1e`
+    Hash,
+}
+
+/// 一组冲突的文件及其消歧后的目录名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    /// 冲突的默认目录名
+    pub name: String,
+    pub files: Vec<(PathBuf, String)>,
+}
+
+/// 输入文件到切片输出目录 (相对于输出根目录) 的映射
+pub struct OutputNames {
+    input_base: PathBuf,
+    layout: Layout,
+    /// 发生冲突的文件 -> 消歧后的目录名
+    renamed: HashMap<PathBuf, String>,
+    collisions: Vec<Collision>,
+}
+
+impl OutputNames {
+    /// 保持目录结构的映射，不需要预先知道所有文件
+    pub fn mirror(input_base: &Path) -> Self {
+        Self {
+            input_base: input_base.to_path_buf(),
+            layout: Layout::Mirror,
+            renamed: HashMap::new(),
+            collisions: Vec::new(),
+        }
+    }
+
+    /// 平铺布局的映射: 文件名 (不含扩展名) 相同的文件全部消歧，与处理顺序无关
+    pub fn flat(input_base: &Path, naming: CollisionNaming, files: &[PathBuf]) -> Self {
+        let mut groups: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+        for file in files {
+            groups.entry(stem(file)).or_default().push(file);
+        }
+        // 已分配的名字: 先是不冲突的文件的默认名，之后加入各冲突组生成的名字
+        let mut assigned: HashSet<String> = groups
+            .iter()
+            .filter(|(_, group)| group.len() == 1)
+            .map(|(name, _)| name.clone())
+            .collect();
+        let relative = |file: &Path| file.strip_prefix(input_base).unwrap_or(file).to_path_buf();
+        let preferred: Vec<(String, Vec<(PathBuf, String)>)> = groups
+            .into_iter()
+            .filter(|(_, group)| group.len() > 1)
+            .map(|(name, mut group)| {
+                group.sort();
+                let files = group
+                    .into_iter()
+                    .map(|file| {
+                        let name = match naming {
+                            CollisionNaming::Path => path_name(&relative(file)),
+                            CollisionNaming::Hash => hash_name(&relative(file)),
+                        };
+                        (file.clone(), name)
+                    })
+                    .collect();
+                (name, files)
+            })
+            .collect();
+        // 生成的名字在所有冲突组中重复 (仅扩展名不同，或不同组的路径连接后相同，
+        // 如 `x/a__b` 与 `x__a/b`) 时全部退回到带哈希的名字，与组的处理顺序无关
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_, files) in &preferred {
+            for (_, name) in files {
+                *counts.entry(name).or_default() += 1;
+            }
+        }
+        let repeated: HashSet<String> = counts
+            .into_iter()
+            .filter(|&(_, count)| count > 1)
+            .map(|(name, _)| name.to_string())
+            .collect();
+
+        let mut renamed = HashMap::new();
+        let mut collisions = Vec::new();
+        for (name, files) in preferred {
+            let files: Vec<(PathBuf, String)> = files
+                .into_iter()
+                .map(|(file, name)| {
+                    let name = if repeated.contains(&name) || assigned.contains(&name) {
+                        hash_name(&relative(&file))
+                    } else {
+                        name
+                    };
+                    assigned.insert(name.clone());
+                    (file, name)
+                })
+                .collect();
+            renamed.extend(files.iter().cloned());
+            collisions.push(Collision { name, files });
+        }
+
+        Self {
+            input_base: input_base.to_path_buf(),
+            layout: Layout::Flat,
+            renamed,
+            collisions,
+        }
+    }
+
+    /// 文件的切片输出目录
+    pub fn dir(&self, input_file: &Path, output_base: &Path) -> Result<PathBuf> {
+        let relative_path = input_file.strip_prefix(&self.input_base)?;
+        let name = match self.renamed.get(input_file) {
+            Some(name) => name.clone(),
+            None => stem(input_file),
+        };
+        Ok(match (self.layout, relative_path.parent()) {
+            (Layout::Mirror, Some(parent)) => output_base.join(parent).join(name),
+            _ => output_base.join(name),
+        })
+    }
+
+    /// 平铺布局中发生的文件名冲突
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+}
+
+/// 第 `index` 个切片在切片目录中的相对路径: 按 `shard_size` 分到 `000/`、`001/`…
+/// 分片目录 (路径分隔符总是 `/`)，不分片时为文件名本身
+pub fn shard_path(file_name: &str, index: usize, shard_size: Option<usize>) -> String {
+    match shard_size {
+        Some(size) => format!("{:03}/{file_name}", index / size.max(1)),
+        None => file_name.to_string(),
+    }
+}
+
+/// 目录名是否为分片目录 (全部为数字)
+pub fn is_shard_dir(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn stem(file: &Path) -> String {
+    file.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 相对目录各级与文件名以 `__` 连接
+fn path_name(relative: &Path) -> String {
+    let mut parts: Vec<String> = relative
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    parts.push(stem(relative));
+    parts.join("__")
+}
+
+fn hash_name(relative: &Path) -> String {
+    format!(
+        "{}-{}",
+        stem(relative),
+        &path_hash(&relative.to_string_lossy())[..8]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CollisionNaming, OutputNames, is_shard_dir, shard_path};
+    use std::path::{Path, PathBuf};
+
+    fn files() -> Vec<PathBuf> {
+        [
+            "in/a/take1.wav",
+            "in/b/take1.wav",
+            "in/b/take1.mp3",
+            "in/c/solo.wav",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+
+    #[test]
+    fn shards_slices_by_index() {
+        assert_eq!(shard_path("slice_000.wav", 0, None), "slice_000.wav");
+        assert_eq!(
+            shard_path("slice_009.wav", 9, Some(10)),
+            "000/slice_009.wav"
+        );
+        assert_eq!(
+            shard_path("slice_010.wav", 10, Some(10)),
+            "001/slice_010.wav"
+        );
+        assert_eq!(shard_path("x.wav", 12345, Some(10)), "1234/x.wav");
+        assert!(is_shard_dir("001") && !is_shard_dir("a1") && !is_shard_dir(""));
+    }
+
+    #[test]
+    fn flat_names_are_disambiguated() {
+        let names = OutputNames::flat(Path::new("in"), CollisionNaming::Path, &files());
+        let out = Path::new("out");
+        let dir = |file: &str| names.dir(Path::new(file), out).unwrap();
+        assert_eq!(dir("in/c/solo.wav"), out.join("solo"));
+        assert_eq!(dir("in/a/take1.wav"), out.join("a__take1"));
+        // 同一目录下仅扩展名不同，退回到哈希
+        let b_wav = dir("in/b/take1.wav");
+        let b_mp3 = dir("in/b/take1.mp3");
+        assert_ne!(b_wav, b_mp3);
+        assert!(b_wav.to_string_lossy().starts_with("out/take1-"));
+        assert_eq!(names.collisions().len(), 1);
+        assert_eq!(names.collisions()[0].files.len(), 3);
+
+        // 结果与文件的发现顺序无关
+        let mut reversed = files();
+        reversed.reverse();
+        let again = OutputNames::flat(Path::new("in"), CollisionNaming::Path, &reversed);
+        assert_eq!(again.dir(Path::new("in/b/take1.mp3"), out).unwrap(), b_mp3);
+    }
+
+    #[test]
+    fn path_names_do_not_clash_across_groups() {
+        let files: Vec<PathBuf> = [
+            "in/x/a__b.wav",
+            "in/y/a__b.wav",
+            "in/x__a/b.wav",
+            "in/z/b.wav",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        let names = OutputNames::flat(Path::new("in"), CollisionNaming::Path, &files);
+        let out = Path::new("out");
+        let dir = |file: &str| names.dir(Path::new(file), out).unwrap();
+        // `x/a__b` 与 `x__a/b` 按路径连接都是 `x__a__b`
+        let (first, second) = (dir("in/x/a__b.wav"), dir("in/x__a/b.wav"));
+        assert_ne!(first, second);
+        assert!(first.to_string_lossy().starts_with("out/a__b-"));
+        assert!(second.to_string_lossy().starts_with("out/b-"));
+        assert_eq!(dir("in/y/a__b.wav"), out.join("y__a__b"));
+        assert_eq!(dir("in/z/b.wav"), out.join("z__b"));
+    }
+
+    #[test]
+    fn hash_naming_and_mirror() {
+        let names = OutputNames::flat(Path::new("in"), CollisionNaming::Hash, &files());
+        let a = names
+            .dir(Path::new("in/a/take1.wav"), Path::new("out"))
+            .unwrap();
+        assert_eq!(a.to_string_lossy().len(), "out/take1-".len() + 8);
+
+        let mirror = OutputNames::mirror(Path::new("in"));
+        assert_eq!(
+            mirror
+                .dir(Path::new("in/a/take1.wav"), Path::new("out"))
+                .unwrap(),
+            Path::new("out/a/take1")
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! 音频切片核心库
+//!
+//! 命令行程序 `audio-learning` 基于本库实现；在其他数据处理流程中可以直接调用，
+//! 无需启动子进程。
+//!
+//! ```no_run
+//! use audio_learning::{BoundaryConfig, Slicer, SlicerConfig, load_audio};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let (samples, sample_rate) = load_audio("input.wav")?;
+//! let slicer = Slicer::new(SlicerConfig {
+//!     sample_rate,
+//!     threshold_db: -40.0,
+//!     min_length_ms: 5000,
+//!     ..Default::default()
+//! })?;
+//! for (range, stats) in slicer.segment_ranges(&samples, &BoundaryConfig::default()) {
+//!     println!("{} - {}: {:.1} dBFS", range.start, range.end, stats.rms_db);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! 切片后的静音过滤见 [`SilenceFilter::is_silence`]，检测后的短片段合并见
+//! [`merge_short_chunks`]。需要在保存前逐片否决、改名或附加元数据时，用
+//! [`Slicer::segments_with`] 传入切片钩子 ([`SegmentHook`])。
+//!
+//! 样本逐块到达时 (实时采集、网络流) 用 [`Slicer::feed`] 推送，切片一结束即返回:
+//!
+//! ```no_run
+//! # fn capture() -> Option<Vec<f32>> { None }
+//! # fn run(mut slicer: audio_learning::Slicer) {
+//! while let Some(block) = capture() {
+//!     for chunk in slicer.feed(&block) {
+//!         println!("{} - {}", chunk.start_sample, chunk.end_sample);
+//!     }
+//! }
+//! let tail = slicer.finish();
+//! # }
+//! ```
+
+pub mod audio;
+pub mod hook;
+pub mod pitch;
+pub mod slicer;
+
+pub use audio::{DecodedAudio, Sample, decode_audio_until, load_audio};
+pub use hook::{HookedSegment, Segment, SegmentDecision, SegmentHook};
+pub use slicer::{
+    BoundaryConfig, Chunk, DetectionMetric, MaxDurationMode, MergeMode, RatioMode, SegmentStats,
+    SilenceFilter, Slicer, SlicerConfig, merge_short_chunks,
+};
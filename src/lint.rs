@@ -0,0 +1,154 @@
+use crate::SliceArgs;
+use crate::audio::DetectOn;
+
+/// 参数检查时考虑的常见采样率
+const COMMON_SAMPLE_RATES: [u32; 4] = [16000, 22050, 44100, 48000];
+/// 取整后有效时长与设定值的最大相对偏差
+const MAX_ROUNDING_ERROR: f32 = 0.2;
+/// 典型语音 RMS 电平的上限 (dBFS)，阈值高于此值会把正常语音判为静音
+const TYPICAL_SPEECH_RMS_DB: f32 = -20.0;
+
+/// 一条参数检查警告
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintWarning {
+    pub message: String,
+    pub suggestion: String,
+}
+
+/// 检查会导致静默异常行为的参数组合
+pub fn lint_params(args: &SliceArgs) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+
+    if args.enable_merge && args.min_length_ms * 2 > args.max_merge_duration_ms {
+        warnings.push(LintWarning {
+            message: format!(
+                "min_length_ms ({}) 接近 max_merge_duration_ms ({})，两个最短切片合并后即超过上限，合并几乎不会发生",
+                args.min_length_ms, args.max_merge_duration_ms
+            ),
+            suggestion: format!(
+                "将 --max-merge-duration-ms 提高到至少 {}",
+                args.min_length_ms * 2
+            ),
+        });
+    }
+
+    for sample_rate in COMMON_SAMPLE_RATES {
+        let hop_samples = (sample_rate as f32 * args.hop_size_ms as f32 / 1000.0).round();
+        if hop_samples == 0.0 {
+            continue;
+        }
+        let interval_samples = (sample_rate as f32 * args.min_interval_ms as f32 / 1000.0).round();
+        let interval_frames = (interval_samples / hop_samples).round();
+        let effective_ms = interval_frames * hop_samples * 1000.0 / sample_rate as f32;
+        let requested_ms = args.min_interval_ms as f32;
+        if (effective_ms - requested_ms).abs() > requested_ms * MAX_ROUNDING_ERROR {
+            warnings.push(LintWarning {
+                message: format!(
+                    "{sample_rate}Hz 下 min_interval_ms ({}) 按 hop ({}ms) 取整后实际为 {effective_ms:.1}ms",
+                    args.min_interval_ms, args.hop_size_ms
+                ),
+                suggestion: "将 --min-interval-ms 设为 --hop-size-ms 的整数倍".to_string(),
+            });
+            break;
+        }
+    }
+
+    if args.threshold_db > TYPICAL_SPEECH_RMS_DB {
+        warnings.push(LintWarning {
+            message: format!(
+                "threshold_db ({}dB) 高于典型语音 RMS 电平 ({TYPICAL_SPEECH_RMS_DB}dB)，正常语音会被判为静音",
+                args.threshold_db
+            ),
+            suggestion: "将 --threshold-db 降低到 -40 左右".to_string(),
+        });
+    }
+
+    let (max_duration_ms, _) = args.max_duration();
+    if max_duration_ms > 0 && max_duration_ms < args.min_length_ms {
+        warnings.push(LintWarning {
+            message: format!(
+                "max_duration_ms ({}) 小于 min_length_ms ({})，所有切片都会被硬切成短于最小长度的块",
+                max_duration_ms, args.min_length_ms
+            ),
+            suggestion: format!(
+                "将 --max-duration-ms 设为 0 或至少 {}",
+                args.min_length_ms
+            ),
+        });
+    }
+
+    if args.relax_length > 0 && max_duration_ms > 0 && args.relax_length >= max_duration_ms {
+        warnings.push(LintWarning {
+            message: format!(
+                "relax_length ({}) 不小于 max_duration_ms ({})，介于两者之间的片段会直接被硬切，放宽检测不起作用",
+                args.relax_length, max_duration_ms
+            ),
+            suggestion: format!(
+                "将 --relax-length 设为小于 {} 的值",
+                max_duration_ms
+            ),
+        });
+    }
+
+    if args.detect_on != DetectOn::Mix
+        && let Some(detect) = args
+            .channel_map
+            .as_ref()
+            .and_then(|map| map.detect.as_ref())
+    {
+        warnings.push(LintWarning {
+            message: format!(
+                "--channel-map 指定了检测声道 ({detect})，--detect-on {} 不起作用",
+                args.detect_on
+            ),
+            suggestion: "去掉 --detect-on，或去掉 --channel-map 中的 detect 部分".to_string(),
+        });
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lint_params;
+    use crate::{Cli, Commands};
+    use clap::Parser;
+
+    fn lint(extra: &[&str]) -> usize {
+        let mut argv = vec!["audio-slicer", "slice", "-i", "in", "-o", "out"];
+        argv.extend_from_slice(extra);
+        let Commands::Slice(args) = Cli::parse_from(argv).command else {
+            unreachable!()
+        };
+        lint_params(&args).len()
+    }
+
+    #[test]
+    fn defaults_are_clean() {
+        assert_eq!(lint(&[]), 0);
+        assert_eq!(lint(&["--enable-merge"]), 0);
+    }
+
+    #[test]
+    fn flags_degenerate_combinations() {
+        assert_eq!(
+            lint(&["--enable-merge", "--max-merge-duration-ms", "1500"]),
+            1
+        );
+        assert_eq!(lint(&["--min-interval-ms", "7"]), 1);
+        assert_eq!(lint(&["--threshold-db", "-10"]), 1);
+        assert_eq!(lint(&["--max-duration-ms", "500"]), 1);
+        assert_eq!(
+            lint(&["--max-duration-ms", "8000", "--relax-length", "10000"]),
+            1
+        );
+        assert_eq!(
+            lint(&["--detect-on", "max", "--channel-map", "detect=2"]),
+            1
+        );
+        assert_eq!(
+            lint(&["--detect-on", "max", "--channel-map", "output=0,1"]),
+            0
+        );
+    }
+}
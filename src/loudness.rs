@@ -0,0 +1,178 @@
+use std::f64::consts::PI;
+
+use crate::dsp::Biquad;
+
+/// 门限块长度 (ms)
+const BLOCK_MS: u32 = 400;
+/// 门限块步长 (ms)，对应 75% 重叠
+const STEP_MS: u32 = 100;
+/// 绝对门限 (LUFS)
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// 相对门限 (LU)
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// ReplayGain 2.0 参考响度 (LUFS)
+pub const REPLAYGAIN_REFERENCE_LUFS: f32 = -18.0;
+/// EBU R128 参考响度 (LUFS)
+pub const R128_REFERENCE_LUFS: f32 = -23.0;
+
+/// ITU-R BS.1770 K 加权滤波器 (高架 + 高通)，按采样率重新计算系数
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    // 第一级: 模拟头部声学效应的高架滤波器
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    // 第二级: RLB 高通滤波器
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    [shelf, highpass]
+}
+
+/// 计算单声道信号的积分响度 (LUFS, ITU-R BS.1770-4)
+///
+/// 使用 400ms 门限块 (75% 重叠)、-70 LUFS 绝对门限与 -10 LU 相对门限。
+/// 信号短于一个门限块或全部低于绝对门限时返回 `None`。
+pub fn integrated_loudness(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    gated_loudness(&[samples], sample_rate)
+}
+
+/// 计算多声道信号的积分响度 (LUFS)，各声道的门限块功率以权重 1 相加
+///
+/// 单声道信号与 [`integrated_loudness`] 相同；环绕声道的 1.41 权重不区分。
+pub fn channel_loudness(channels: &[Vec<f32>], sample_rate: u32) -> Option<f32> {
+    let channels: Vec<&[f32]> = channels.iter().map(Vec::as_slice).collect();
+    gated_loudness(&channels, sample_rate)
+}
+
+/// 对各声道施加同一增益，使积分响度达到 `target_lufs`，返回施加的增益 (dB)
+///
+/// 无法测得响度 (短于 400ms 或全部低于绝对门限) 时不做处理并返回 `None`。
+pub fn normalize_loudness(
+    channels: &mut [Vec<f32>],
+    sample_rate: u32,
+    target_lufs: f32,
+) -> Option<f32> {
+    let gain_db = target_lufs - channel_loudness(channels, sample_rate)?;
+    let gain = 10f32.powf(gain_db / 20.0);
+    for sample in channels.iter_mut().flatten() {
+        *sample *= gain;
+    }
+    Some(gain_db)
+}
+
+fn gated_loudness(channels: &[&[f32]], sample_rate: u32) -> Option<f32> {
+    let block_len = (sample_rate * BLOCK_MS / 1000) as usize;
+    let step = (sample_rate * STEP_MS / 1000) as usize;
+    let length = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    if block_len == 0 || length < block_len {
+        return None;
+    }
+
+    let mut block_powers = vec![0.0; (length - block_len) / step + 1];
+    for samples in channels {
+        let [mut shelf, mut highpass] = k_weighting(sample_rate);
+        let weighted: Vec<f64> = samples[..length]
+            .iter()
+            .map(|&x| highpass.process(shelf.process(x as f64)))
+            .collect();
+        for (i, power) in block_powers.iter_mut().enumerate() {
+            let block = &weighted[i * step..i * step + block_len];
+            *power += block.iter().map(|x| x * x).sum::<f64>() / block_len as f64;
+        }
+    }
+
+    let gated_mean = |threshold_lufs: f64| {
+        let gated: Vec<f64> = block_powers
+            .iter()
+            .copied()
+            .filter(|&power| power_to_lufs(power) > threshold_lufs)
+            .collect();
+        if gated.is_empty() {
+            None
+        } else {
+            Some(gated.iter().sum::<f64>() / gated.len() as f64)
+        }
+    };
+
+    let absolute_mean = gated_mean(ABSOLUTE_GATE_LUFS)?;
+    let relative_gate = power_to_lufs(absolute_mean) + RELATIVE_GATE_LU;
+    let integrated = gated_mean(relative_gate.max(ABSOLUTE_GATE_LUFS))?;
+    Some(power_to_lufs(integrated) as f32)
+}
+
+fn power_to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.max(1e-20).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel_loudness, integrated_loudness, normalize_loudness};
+
+    fn sine(freq: f32, amplitude: f32, sample_rate: u32, secs: f32) -> Vec<f32> {
+        (0..(sample_rate as f32 * secs) as usize)
+            .map(|i| {
+                amplitude
+                    * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_1khz_sine_reads_minus_3_lufs() {
+        for sample_rate in [16000, 44100, 48000] {
+            let loudness =
+                integrated_loudness(&sine(1000.0, 1.0, sample_rate, 2.0), sample_rate).unwrap();
+            assert!((loudness + 3.01).abs() < 0.1, "{sample_rate}Hz: {loudness}");
+        }
+    }
+
+    #[test]
+    fn halving_amplitude_drops_6_db() {
+        let full = integrated_loudness(&sine(500.0, 0.5, 48000, 2.0), 48000).unwrap();
+        let half = integrated_loudness(&sine(500.0, 0.25, 48000, 2.0), 48000).unwrap();
+        assert!((full - half - 6.02).abs() < 0.05);
+    }
+
+    #[test]
+    fn normalizes_stereo_to_target() {
+        let left = sine(1000.0, 0.1, 48000, 2.0);
+        let mut channels = vec![left.clone(), left];
+        // 两个相同的声道比单声道响 3dB
+        let mono = integrated_loudness(&channels[0], 48000).unwrap();
+        let stereo = channel_loudness(&channels, 48000).unwrap();
+        assert!((stereo - mono - 3.01).abs() < 0.05);
+
+        let gain = normalize_loudness(&mut channels, 48000, -23.0).unwrap();
+        assert!((gain - (-23.0 - stereo)).abs() < 1e-4);
+        let normalized = channel_loudness(&channels, 48000).unwrap();
+        assert!((normalized + 23.0).abs() < 0.05);
+        assert_eq!(
+            normalize_loudness(&mut [vec![0.0; 100]], 48000, -23.0),
+            None
+        );
+    }
+
+    #[test]
+    fn silence_and_short_input_have_no_loudness() {
+        assert_eq!(integrated_loudness(&vec![0.0; 48000], 48000), None);
+        assert_eq!(integrated_loudness(&[0.5; 100], 48000), None);
+    }
+}
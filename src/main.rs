@@ -1,17 +1,142 @@
-mod audio;
-mod slicer;
+mod analysis;
+mod announce;
+mod archive;
+mod atomic;
+mod audit;
+mod catalog;
+mod chain;
+mod clean;
+mod config;
+mod dataset;
+mod dedup;
+mod dsp;
+mod encoder;
+mod events;
+mod export;
+#[cfg(feature = "gui")]
+mod gui;
+mod inspect;
+mod layout;
+mod lint;
+mod loudness;
+mod manifest;
+mod migrate;
+mod normalize;
+mod notify;
+mod precheck;
+mod refilter;
+mod report;
+mod rerun;
+mod scan;
+mod spectral;
+mod staging;
+mod summary;
+mod tags;
+mod trace;
+mod tree;
+mod vad;
+mod verify;
+mod viz;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use audio_learning::{audio, slicer};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use hound::{WavSpec, WavWriter};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::BufWriter;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
-use walkdir::WalkDir;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use analysis::{applause_score, estimate_rt60, extract_noise_profile};
+use announce::Announcer;
+use archive::{decode_input, decode_input_head, decode_input_i16, decode_input_streaming};
+use atomic::{
+    IndexLock, OnLocked, RunLock, check_overwrite, claim_dir, create_file, enable_safe_mode,
+};
+use audio::{
+    ChannelMap, ContainerTimeline, DetectOn, KaraokeMode, NanPolicy, OutputChannels, PhaseCheck,
+    SampleStorage, SourceSamples, apply_nan_policy, check_deadline, detect_karaoke,
+    detection_signal, downmix_cancellation_db, mix_channels, read_float_mono_wav,
+    replace_non_finite,
+};
+use audit::{AuditCandidate, write_audit_sample};
+use catalog::{Catalog, RunInfo, run_query};
+use chain::DspChain;
+use clean::run_clean;
+use dataset::{DATASET_CARD_FILE_NAME, write_dataset_card};
+use dedup::{ContentIndex, content_hash};
+use dsp::{
+    AugmentSpec, ClipMode, Dither, FadeShape, PadMode, Quantizer, Resampler, ResamplerBackend,
+    ResamplerQuality, fade_edges, normalize_peak, pad_slice, speed_perturb, true_peak_limit,
+};
+use encoder::{Bitrate, FileFormat, check_encoder, write_flac, write_lossy};
+use events::{Event, EventFormat, JsonLinesSink, NullSink, ProgressSink, RunSummary};
+use export::{
+    LABELS_FILE_NAME, MASK_FILE_NAME, PEAKS_FILE_NAME, auto_label, slice_peaks_name,
+    write_audacity_labels, write_frame_mask, write_label_studio, write_peaks,
+};
+use inspect::{FileInfo, ScanFormat, inspect_decoded, run_scan, write_file_catalog};
+use layout::{CollisionNaming, Layout, OutputNames, shard_path};
+use lint::lint_params;
+use loudness::normalize_loudness;
+use manifest::{
+    AugmentedRecord, FileManifest, MANIFEST_FILE_NAME, MANIFEST_SCHEMA_VERSION,
+    NOISE_PROFILE_FILE_NAME, SliceRecord, StageTimings, read_manifest, write_manifest,
+    write_run_manifest,
+};
+use migrate::run_migrate;
+use normalize::{NormalizeScope, normalize_slices};
+use notify::run_notify_cmd;
+use precheck::precheck;
+use refilter::run_refilter;
+use report::{DurationStyle, Locale, NumberFormat};
+use rerun::{ERRORS_FILE_NAME, FileSelection, read_failed, write_errors};
+use scan::scan_audio_files;
+use slicer::{
+    AUTO_THRESHOLD_MAX_DB, AUTO_THRESHOLD_MIN_DB, BoundaryConfig, Chunk, DetectionMetric, Detector,
+    MaxDurationMode, MergeMode, RatioMode, ShortOutputPolicy, SilenceFilter, SliceMode, Slicer,
+    SlicerConfig, ThresholdMode, auto_threshold_db, extend_to_min_length, fixed_windows,
+    ms_to_frames, noise_floor_db,
+};
+use staging::Mover;
+use summary::{FileStatsRow, FileStatus, PER_FILE_STATS_FILE_NAME, write_per_file_stats};
+use tags::{Tags, append_wav_tags, fingerprint, loudness_tags, provenance_tags};
+use trace::{DetectionTrace, TRACE_FILE_NAME};
+use tree::preserve_tree_metadata;
+use vad::{VadConfig, check_model};
+use verify::{FixAction, run_verify};
+use viz::{Timeline, VizFormat, VizSegment};
+
+/// 面向用户的文本输出是否改写到标准错误 (标准输出留给机器可读事件)
+static HUMAN_OUTPUT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// 打印面向用户的文本
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if HUMAN_OUTPUT_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
-use audio::load_audio;
-use slicer::{Slicer, SlicerConfig, enforce_max_duration, is_silence, merge_short_chunks};
+/// 按 `SourceSamples` 的实际样本类型展开 `$body`，`$s` 绑定为该类型的样本切片
+macro_rules! with_samples {
+    ($samples:expr, |$s:ident| $body:expr) => {
+        match $samples {
+            SourceSamples::F32($s) => $body,
+            SourceSamples::I16($s) => $body,
+        }
+    };
+}
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -23,63 +148,806 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// 音频切片处理
-    Slice {
-        /// 输入音频文件或目录路径
-        #[arg(short, long)]
-        input: PathBuf,
-
-        /// 输出目录
-        #[arg(short, long)]
-        output: PathBuf,
-
-        /// 并行处理线程数 (默认为CPU核心数)
-        #[arg(short, long)]
-        threads: Option<usize>,
-
-        /// 静音阈值 (dB)
-        #[arg(long, default_value = "-55.0")]
-        threshold_db: f32,
-
-        /// 最小片段长度 (ms)
-        #[arg(long, default_value = "1000")]
-        min_length_ms: u32,
-
-        /// 最小间隔 (ms)
-        #[arg(long, default_value = "100")]
-        min_interval_ms: u32,
-
-        /// 跳跃大小 (ms)
-        #[arg(long, default_value = "5")]
-        hop_size_ms: u32,
-
-        /// 最大静音长度 (ms)
-        #[arg(long, default_value = "800")]
-        max_silence_ms: u32,
-
-        /// 启用切片合并
-        #[arg(long, default_value = "false")]
-        enable_merge: bool,
-
-        /// 最大合并时长 (ms)
-        #[arg(long, default_value = "8000")]
-        max_merge_duration_ms: u32,
-
-        /// 最大切片时长 (ms)，超过则硬切成多块；0 表示禁用
-        #[arg(long, default_value = "0")]
-        max_duration_ms: u32,
-
-        /// 静音检测阈值
-        #[arg(long, default_value = "0.001")]
-        silence_threshold: f32,
-
-        /// 最小有效音频占比
-        #[arg(long, default_value = "0.1")]
-        min_audio_ratio: f32,
+    #[command(args_override_self = true)]
+    Slice(SliceArgs),
+
+    /// 按新的筛选条件重新筛选已有输出中的切片，无需重新切片
+    Refilter(RefilterArgs),
+
+    /// 查询语料库目录数据库中的切片
+    Query(QueryArgs),
+
+    /// 比对输出目录中的清单 (及目录数据库) 与磁盘文件，检查缺失与孤立文件
+    Verify(VerifyArgs),
+
+    /// 不切片，批量提取音频文件的技术元数据、标签与响度，写出 CSV/JSON 目录
+    Scan(ScanArgs),
+
+    /// 把输出目录中旧版本生成的清单升级到当前结构版本
+    Migrate(MigrateArgs),
+
+    /// 按清单或目录数据库中的运行编号删除某次运行写出的文件
+    Clean(CleanArgs),
+
+    /// 启动桌面界面
+    #[cfg(feature = "gui")]
+    Gui,
+
+    /// 在子进程中处理单个文件 (供 --isolate 内部使用)
+    #[command(hide = true, args_override_self = true)]
+    Worker {
+        #[command(flatten)]
+        args: SliceArgs,
+
+        /// 要处理的音频文件
+        #[arg(long)]
+        worker_file: PathBuf,
+
+        /// 该文件的切片输出目录
+        #[arg(long)]
+        worker_output_dir: PathBuf,
     },
 }
 
+/// `refilter` 子命令参数
+#[derive(Args, Clone)]
+struct RefilterArgs {
+    /// 已有的切片输出目录 (包含各文件的 manifest.json)
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// 最短切片时长 (毫秒)
+    #[arg(long)]
+    min_duration_ms: Option<u32>,
+
+    /// 最长切片时长 (毫秒)
+    #[arg(long)]
+    max_duration_ms: Option<u32>,
+
+    /// 最低 RMS 电平 (dBFS)
+    #[arg(long, allow_negative_numbers = true)]
+    min_rms_db: Option<f32>,
+
+    /// 最高峰值电平 (dBFS)
+    #[arg(long, allow_negative_numbers = true)]
+    max_peak_db: Option<f32>,
+
+    /// 最低有声帧占比 (0.0-1.0)
+    #[arg(long)]
+    min_voiced_ratio: Option<f32>,
+
+    /// 最大 RT60 (秒)，仅对清单中带有混响估计的切片生效
+    #[arg(long)]
+    max_rt60: Option<f32>,
+
+    /// 最大掌声/笑声可能性 (0~1)，仅对清单中带有分类结果的切片生效
+    #[arg(long)]
+    max_applause_score: Option<f32>,
+
+    /// 最小掌声/笑声可能性 (0~1)，用于只保留掌声/笑声切片；没有分类结果的切片被剔除
+    #[arg(long)]
+    min_applause_score: Option<f32>,
+
+    /// 最大限幅衰减 (dB)，仅对清单中带有限幅记录的切片生效
+    #[arg(long)]
+    max_limiter_db: Option<f32>,
+
+    /// 把剔除的切片移动到该目录 (保持相对路径) 并更新清单；不指定时只写出列表
+    #[arg(long)]
+    move_rejected: Option<PathBuf>,
+}
+
+/// `query` 子命令参数
+#[derive(Args, Clone)]
+struct QueryArgs {
+    /// 目录数据库路径 (由 slice --catalog 生成)
+    #[arg(long)]
+    catalog: PathBuf,
+
+    /// 只查询源文件路径包含该字符串的切片
+    #[arg(long)]
+    source: Option<String>,
+
+    /// 只查询指定运行 ID 的切片 (不论之后是否重新处理过同一源文件)
+    #[arg(long)]
+    run: Option<i64>,
+
+    /// 包含历史运行的切片 (默认只返回每个源文件最近一次运行的切片，指定 --run 时不限制)
+    #[arg(long, default_value = "false")]
+    all_runs: bool,
+
+    /// 最短切片时长 (毫秒)
+    #[arg(long)]
+    min_duration_ms: Option<u32>,
+
+    /// 最长切片时长 (毫秒)
+    #[arg(long)]
+    max_duration_ms: Option<u32>,
+
+    /// 最低 RMS 电平 (dBFS)
+    #[arg(long, allow_negative_numbers = true)]
+    min_rms_db: Option<f32>,
+
+    /// 最高峰值电平 (dBFS)
+    #[arg(long, allow_negative_numbers = true)]
+    max_peak_db: Option<f32>,
+
+    /// 最低有声帧占比 (0.0-1.0)
+    #[arg(long)]
+    min_voiced_ratio: Option<f32>,
+
+    /// 最多返回的切片数
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// 只输出切片路径，每行一个
+    #[arg(long, default_value = "false")]
+    paths_only: bool,
+}
+
+/// `verify` 子命令参数
+#[derive(Args, Clone)]
+struct VerifyArgs {
+    /// 切片输出目录 (包含各文件的 manifest.json)
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// 同时检查目录数据库中位于输出目录下的切片是否存在
+    #[arg(long)]
+    catalog: Option<PathBuf>,
+
+    /// 修复方式，可重复指定 (prune-orphans: 删除孤立切片, mark-stale: 标记缺失文件的清单记录)
+    #[arg(long, value_enum)]
+    fix: Vec<FixAction>,
+}
+
+/// `migrate` 子命令参数
+#[derive(Args, Clone)]
+struct MigrateArgs {
+    /// 切片输出目录 (包含各文件的 manifest.json)
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// 只列出需要升级的清单，不写回
+    #[arg(long, default_value = "false")]
+    dry_run: bool,
+}
+
+/// `clean` 子命令参数
+#[derive(Args, Clone)]
+struct CleanArgs {
+    /// 切片清单 (manifest.json) 或包含清单的目录，可重复指定
+    #[arg(long, required_unless_present = "run", conflicts_with = "run")]
+    manifest: Vec<PathBuf>,
+
+    /// 目录数据库中的运行编号 (见 query --run)，删除该运行记录的切片
+    #[arg(long, requires = "catalog")]
+    run: Option<i64>,
+
+    /// 目录数据库 (SQLite) 路径，与 --run 一起使用
+    #[arg(long)]
+    catalog: Option<PathBuf>,
+
+    /// 只列出将删除的文件，不删除
+    #[arg(long, default_value = "false")]
+    dry_run: bool,
+}
+
+/// `scan` 子命令参数
+#[derive(Args, Clone)]
+struct ScanArgs {
+    /// 输入音频文件或目录路径
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// 输出的元数据目录文件 (.csv 或 .json)
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// 输出格式 (默认按输出文件扩展名判断，无法判断时为 csv)
+    #[arg(long, value_enum)]
+    format: Option<ScanFormat>,
+
+    /// 并行线程数 (默认为CPU核心数)
+    #[arg(short, long)]
+    threads: Option<usize>,
+
+    /// 只读取文件头部与标签，不解码音频 (不测量响度与峰值，时长取容器声明值)
+    #[arg(long, default_value = "false")]
+    headers_only: bool,
+}
+
+/// 解析正的有限秒数
+fn positive_secs(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .ok_or_else(|| format!("应为大于 0 的秒数: {s}"))
+}
+
+/// `slice` 子命令参数
+#[derive(Args, Clone)]
+struct SliceArgs {
+    /// 从 TOML/YAML 配置文件读取参数，键名与长参数名相同；命令行参数覆盖文件中的值
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// 输入音频文件或目录路径
+    #[arg(short, long)]
+    input: PathBuf,
+
+    /// 输出目录
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// 并行处理线程数 (默认为CPU核心数)
+    #[arg(short, long)]
+    threads: Option<usize>,
+
+    /// 静音阈值 (dB)
+    #[arg(long, default_value = "-55.0", allow_negative_numbers = true)]
+    threshold_db: f32,
+
+    /// 静音阈值的确定方式: fixed 所有文件使用 --threshold-db；auto 按每个文件的噪声底
+    /// (帧 RMS 的第 10 百分位) 加 --threshold-offset-db 确定，限制在 -80 ~ -25 dB
+    #[arg(long, value_enum, default_value_t = ThresholdMode::Fixed)]
+    threshold: ThresholdMode,
+
+    /// --threshold auto 中阈值高出文件噪声底多少 dB
+    #[arg(long, default_value = "10.0")]
+    threshold_offset_db: f32,
+
+    /// 静音检测指标
+    #[arg(long, value_enum, default_value_t = DetectionMetric::Rms)]
+    metric: DetectionMetric,
+
+    /// 峰值检测阈值 (dB)，用于 peak/hybrid 指标
+    #[arg(long, default_value = "-45.0", allow_negative_numbers = true)]
+    peak_threshold_db: f32,
+
+    /// 有声/静音的判定方式: rms 按能量阈值 (指标见 --metric)，vad 用语音活动检测模型
+    /// (--vad-model，需要 vad 特性，模型或 ONNX Runtime 不可用时回退到 rms)，spectral 按频带与噪声底比较
+    #[arg(long, value_enum, default_value_t = Detector::Rms)]
+    detector: Detector,
+
+    /// Silero VAD 的 ONNX 模型文件，用于 --detector vad
+    #[arg(long)]
+    vad_model: Option<PathBuf>,
+
+    /// --detector vad 的语音概率阈值，不低于该值的窗口判为有声
+    #[arg(long, default_value = "0.5")]
+    vad_threshold: f32,
+
+    /// --detector spectral 中频带能量高出该频带噪声底多少 dB 才算有声
+    #[arg(long, default_value = "6.0")]
+    spectral_margin_db: f32,
+
+    /// 多分辨率分析的粗扫描步长 (hop 的倍数)，只在有声/静音交界处逐帧分析；1 表示禁用。
+    /// 步长限制在最大静音长度的一半以内
+    #[arg(long, default_value = "8")]
+    coarse_hop_factor: u32,
+
+    /// 切点细化: 把切点移到所在静音段中 RMS 最低的帧，而不是固定在静音开始后 --min-interval-ms 处
+    #[arg(long, default_value = "false")]
+    refine_cuts: bool,
+
+    /// NaN/inf 样本的处理策略
+    #[arg(long, value_enum, default_value_t = NanPolicy::Zero)]
+    nan_policy: NanPolicy,
+
+    /// 源文件在内存中的样本格式: i16 在解码时直接混音为单声道 16 位整数，
+    /// 加载阶段的内存峰值减半以上 (适合大批量的电话录音等低采样率语料)，不支持按声道的功能
+    #[arg(long, value_enum, default_value_t = SampleStorage::F32)]
+    sample_storage: SampleStorage,
+
+    /// 静音检测使用的信号 (mix: 声道平均, mid: 中置, side: 侧信号, ch:N: 第 N 个声道,
+    /// max: 逐样本取最响的声道)，不影响输出的声道 (见 --channels)
+    #[arg(long, default_value_t = DetectOn::Mix)]
+    detect_on: DetectOn,
+
+    /// 多声道输入的声道映射，如 `detect=2;output=0,1,2,4,5`: 检测信号与输出各自使用的
+    /// 声道序号 (从 0 开始)，可写成 `N:权重`，权重归一化；指定 detect 时忽略 --detect-on
+    #[arg(long)]
+    channel_map: Option<ChannelMap>,
+
+    /// 按声道平均检测时检查混音的相位抵消 (off: 不检查, warn: 警告, auto: 警告并改为按 max 检测)
+    #[arg(long, value_enum, default_value_t = PhaseCheck::Warn)]
+    phase_check: PhaseCheck,
+
+    /// 切片的声道 (mono: 声道平均, keep: 保留源文件的声道布局, left/right: 只取左/右声道)，
+    /// 静音检测仍按 --detect-on 进行；不能与 --channel-map 的 output 同时使用
+    #[arg(long, value_enum, default_value_t = OutputChannels::Mono)]
+    channels: OutputChannels,
+
+    /// 一侧人声、一侧伴奏的双声道音频 (off: 不判断, auto: 按声道相关性与能量分布自动判断,
+    /// left/right: 人声固定在左/右声道)，人声声道替代默认的声道平均用于检测与输出
+    #[arg(long, value_enum, default_value_t = KaraokeMode::Auto)]
+    karaoke: KaraokeMode,
+
+    /// 混音能量比各声道平均能量低多少 dB 时视为相位抵消
+    #[arg(long, default_value = "12.0")]
+    phase_cancel_db: f32,
+
+    /// 最小片段长度 (ms)
+    #[arg(long, default_value = "1000")]
+    min_length_ms: u32,
+
+    /// 最小间隔 (ms)
+    #[arg(long, default_value = "100")]
+    min_interval_ms: u32,
+
+    /// 跳跃大小 (ms)
+    #[arg(long, default_value = "5")]
+    hop_size_ms: u32,
+
+    /// 最大静音长度 (ms)
+    #[arg(long, default_value = "800")]
+    max_silence_ms: u32,
+
+    /// 响亮事件模式: 提取高于 --event-threshold-db 的片段 (关门声、警报、狗叫等)，而非语音
+    #[arg(long, default_value = "false")]
+    loud_events: bool,
+
+    /// 切片方式: silence 按静音检测切分，fixed 按 --window-ms 的固定时长窗口切分
+    /// (相邻窗口按 --overlap-ms 重叠)
+    #[arg(long, value_enum, default_value_t = SliceMode::Silence, conflicts_with = "loud_events")]
+    mode: SliceMode,
+
+    /// 固定窗口模式的窗口时长 (ms)
+    #[arg(long, default_value = "10000")]
+    window_ms: u32,
+
+    /// 事件模式的阈值 (dB)，代替 --threshold-db
+    #[arg(long, default_value = "-20.0", allow_negative_numbers = true)]
+    event_threshold_db: f32,
+
+    /// 事件的最短持续时间 (ms)，更短的瞬态被丢弃
+    #[arg(long, default_value = "50")]
+    event_min_ms: u32,
+
+    /// 事件前后各保留的上下文时长 (ms)
+    #[arg(long, default_value = "500")]
+    event_context_ms: u32,
+
+    /// 启用切片合并
+    #[arg(long, default_value = "false")]
+    enable_merge: bool,
+
+    /// 最大合并时长 (ms)
+    #[arg(long, default_value = "8000")]
+    max_merge_duration_ms: u32,
+
+    /// 合并策略
+    #[arg(long, value_enum, default_value_t = MergeMode::Greedy)]
+    merge_mode: MergeMode,
+
+    /// 句间停顿长度 (ms)，sentence 合并策略不会跨越不短于该值的停顿
+    #[arg(long, default_value = "500")]
+    sentence_pause_ms: u32,
+
+    /// 目标切片时长 (ms)，用于 target/target-optimal 合并策略
+    #[arg(long, default_value = "5000")]
+    target_duration_ms: u32,
+
+    /// 起点回退上限 (ms)：从切片中的起音沿能量下降方向向前回退，早于检测起点时取代之，避免切掉起始辅音；0 表示禁用
+    #[arg(long, default_value = "0")]
+    onset_backoff_ms: u32,
+
+    /// 相邻切片在原始时间轴上的最小间隔 (ms)；间隔不足时重新放置边界或合并，0 表示禁用
+    #[arg(long, default_value = "0")]
+    min_gap_ms: u32,
+
+    /// 最大切片时长 (ms)，超过则硬切成多块；0 表示禁用
+    #[arg(long, default_value = "0")]
+    max_duration_ms: u32,
+
+    /// 超长切片的断开方式: hard 等长硬切，phrase 在能量低且不处于长音中的乐句边界处断开 (歌声)，
+    /// quietest 在能量最低处断开
+    #[arg(long, value_enum, default_value_t = MaxDurationMode::Hard)]
+    max_duration_mode: MaxDurationMode,
+
+    /// 切片时长上限 (ms)，超过则在窗口内能量最低的帧强制断开；
+    /// 等同于 --max-duration-ms <MS> --max-duration-mode quietest
+    #[arg(long, conflicts_with_all = ["max_duration_ms", "max_duration_mode"])]
+    max_length_ms: Option<u32>,
+
+    /// 检测到的片段超过该时长 (ms) 时，在片段内部逐步提高阈值重新检测，优先在自然停顿处切开；0 表示禁用
+    #[arg(long, default_value = "0")]
+    relax_length: u32,
+
+    /// 放宽检测每次提高的阈值 (dB)
+    #[arg(long, default_value = "3.0")]
+    relax_step_db: f32,
+
+    /// 放宽检测的最大迭代次数
+    #[arg(long, default_value = "4")]
+    relax_max_iterations: u32,
+
+    /// 相邻切片的重叠时长 (ms)，每个切片的起点向前延伸该时长；固定窗口模式下为相邻窗口的重叠时长
+    #[arg(long, default_value = "0")]
+    overlap_ms: u32,
+
+    /// 不把切片边界移到最近的过零点 (默认在 ±10ms 内对齐过零点，避免切口处的爆音)
+    #[arg(long, default_value = "false")]
+    no_zero_cross: bool,
+
+    /// 二次静音过滤阈值 (dBFS)，默认 -60
+    #[arg(
+        long,
+        allow_negative_numbers = true,
+        conflicts_with = "silence_threshold"
+    )]
+    silence_threshold_db: Option<f32>,
+
+    /// 二次静音过滤阈值 (线性幅度，已弃用，请使用 --silence-threshold-db)
+    #[arg(long)]
+    silence_threshold: Option<f32>,
+
+    /// 最小有效音频占比
+    #[arg(long, default_value = "0.1")]
+    min_audio_ratio: f32,
+
+    /// 有效音频占比的统计方式 (voiced: 检测器判定为有声的帧, samples: 样本, frames: 20ms 帧)
+    #[arg(long, value_enum, default_value_t = RatioMode::Voiced)]
+    ratio_mode: RatioMode,
+
+    /// 将所有切片重采样到统一的输出采样率 (Hz)
+    #[arg(long, conflicts_with = "preserve_sr", value_parser = clap::value_parser!(u32).range(1..))]
+    unify_sr: Option<u32>,
+
+    /// 保持每个源文件的原始采样率输出 (默认行为)
+    #[arg(long, default_value = "false")]
+    preserve_sr: bool,
+
+    /// 重采样质量: sinc-high 质量最好，sinc-fast 更快，linear 最快 (无抗混叠)
+    #[arg(long, value_enum, default_value_t = ResamplerQuality::SincHigh)]
+    resampler: ResamplerQuality,
+
+    /// 重采样的实现: builtin 为内置 sinc 插值，rubato 在长文件上更快
+    #[arg(long, value_enum, default_value_t = ResamplerBackend::Builtin)]
+    resampler_backend: ResamplerBackend,
+
+    /// 保存前按顺序应用的处理链，如 "dc,hpf:80,gain:3,fade:10"
+    /// (可用阶段: dc, hpf:<Hz>, lpf:<Hz>, gain:<dB>, fade:<ms>)
+    #[arg(long, allow_hyphen_values = true)]
+    chain: Option<DspChain>,
+
+    /// 切片的文件格式 (wav, flac: 无损压缩的整数样本, mp3/opus: 有损压缩，需要 lame/opusenc)
+    #[arg(long, value_enum, default_value_t = FileFormat::Wav)]
+    output_format: FileFormat,
+
+    /// mp3/opus 输出的码率，如 64k、128k
+    #[arg(long, default_value = "128k")]
+    bitrate: Bitrate,
+
+    /// 输出 WAV 采样格式；FLAC 输出时 i16 为 16 位，其余为 24 位
+    #[arg(long, value_enum, default_value_t = WavFormat::F32)]
+    wav_format: WavFormat,
+
+    /// 整数格式下超出满幅样本的处理方式 (hard: 截断, soft: tanh 软饱和)
+    #[arg(long, value_enum, default_value_t = ClipMode::Hard)]
+    clip_mode: ClipMode,
+
+    /// 整数格式量化时的抖动方式 (none: 直接舍入, tpdf: TPDF 抖动 + 噪声整形)
+    #[arg(long, value_enum, default_value_t = Dither::None)]
+    dither: Dither,
+
+    /// 计算每个切片的 ReplayGain 2.0 / R128 增益并写入输出文件标签与清单
+    #[arg(long, default_value = "false")]
+    replaygain: bool,
+
+    /// 在输出文件标签中写入源文件、起止时间、版本与参数指纹，便于追溯切片来源
+    #[arg(long, default_value = "false")]
+    provenance_tags: bool,
+
+    /// 真峰值限幅上限 (dBTP)，设置后对每个切片做 4 倍过采样真峰值限幅，保证不削波
+    #[arg(long, allow_negative_numbers = true)]
+    true_peak_limit_db: Option<f32>,
+
+    /// 把每个切片的积分响度 (EBU R128 / BS.1770) 归一化到指定值 (LUFS，如 -23)，
+    /// 在处理链之后、真峰值限幅之前施加增益
+    #[arg(long, allow_negative_numbers = true, conflicts_with = "normalize_peak")]
+    normalize_lufs: Option<f32>,
+
+    /// 把每个切片缩放到样本峰值 (绝对值) 达到指定的线性幅度 (0~1，如 0.95)，
+    /// 比 --normalize-lufs 简单快速，在处理链之后、真峰值限幅之前施加
+    #[arg(long)]
+    normalize_peak: Option<f32>,
+
+    /// 在每个切片首尾施加的淡入淡出时长 (ms)，消除在波形中间切断造成的爆音，
+    /// 在处理链之后、归一化之前施加
+    #[arg(long)]
+    fade_ms: Option<f32>,
+
+    /// --fade-ms 的淡变曲线
+    #[arg(long, value_enum, default_value_t = FadeShape::Linear, requires = "fade_ms")]
+    fade_shape: FadeShape,
+
+    /// 在每个保存的切片首尾各补指定时长 (ms)，避免切片恰好从语音起点开始；
+    /// 清单中的起止时间仍为切片内容本身
+    #[arg(long)]
+    pad_silence_ms: Option<u32>,
+
+    /// --pad-silence-ms 的补白来源: silence 为数字静音，context 取源文件中切片前后的真实音频
+    #[arg(long, value_enum, default_value_t = PadMode::Silence, requires = "pad_silence_ms")]
+    pad_mode: PadMode,
+
+    /// 最短输出时长 (ms)，取整后短于该值的切片不会写出为几乎为空的文件，设为 0 关闭检查
+    #[arg(long, default_value = "10")]
+    min_output_ms: u32,
+
+    /// 短于 --min-output-ms 的切片的处理方式: drop 丢弃，extend 向两侧扩展到最短时长
+    #[arg(long, value_enum, default_value_t = ShortOutputPolicy::Drop)]
+    short_output: ShortOutputPolicy,
+
+    /// 数据增强：为每个切片额外输出变速版本，如 speed=0.9,1.0,1.1 (因子 0.1 ~ 10)
+    #[arg(long)]
+    augment: Option<AugmentSpec>,
+
+    /// 估计每个切片的混响时间 (RT60) 并写入清单
+    #[arg(long, default_value = "false")]
+    estimate_reverb: bool,
+
+    /// 最大允许 RT60 (秒)，估计值超过则丢弃该切片 (隐含 --estimate-reverb)
+    #[arg(long)]
+    max_rt60: Option<f32>,
+
+    /// 估计每个切片为掌声/笑声 (宽带噪声爆发) 的可能性 (0~1) 并写入清单
+    #[arg(long, default_value = "false")]
+    classify_applause: bool,
+
+    /// 掌声/笑声可能性超过该值 (0~1) 的切片被丢弃 (隐含 --classify-applause)
+    #[arg(long)]
+    max_applause_score: Option<f32>,
+
+    /// 拼接检测到的静音区间，为每个文件导出噪声样本 (noise_profile.wav)
+    #[arg(long, default_value = "false")]
+    export_noise_profile: bool,
+
+    /// 在独立子进程中处理每个文件，解码器崩溃只影响该文件
+    #[arg(long, default_value = "false")]
+    isolate: bool,
+
+    /// 单个文件的处理时间上限 (秒)，超时的文件中止并记为失败。
+    /// 超时在解码的数据包之间与各处理阶段之间检查，卡死在单次解码调用中的文件
+    /// 只有配合 --isolate (超时后终止子进程) 才能中止
+    #[arg(long, value_parser = positive_secs)]
+    per_file_timeout: Option<f64>,
+
+    /// 流式处理: 边解码边检测，切片的终点一经确定即写出，不把整个文件读入内存
+    /// (用于数小时的高采样率录音)；只支持静音检测与逐切片的输出处理，
+    /// --max-duration-ms 作为连续有声时的切片时长上限；切片一经确定即写出，
+    /// 边界不对齐过零点 (相当于 --no-zero-cross)
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = [
+            "refine_cuts", "sample_storage", "detect_on", "channel_map", "channels", "karaoke",
+            "loud_events", "mode", "detector", "threshold", "enable_merge", "onset_backoff_ms", "min_gap_ms",
+            "max_duration_mode", "max_length_ms", "relax_length", "overlap_ms", "pad_silence_ms",
+            "short_output", "augment", "estimate_reverb", "classify_applause",
+            "export_noise_profile", "isolate", "export_mask", "export_labels", "labels_only",
+            "dry_run", "export_peaks", "trace_detection", "viz", "dedup_content", "scan_output",
+        ]
+    )]
+    stream: bool,
+
+    /// 流式处理时的最大输出延迟 (ms): 连续有声达到该时长时不等停顿直接输出切片，
+    /// 同时限制缓冲的样本数；与 --max-duration-ms 同时指定时取较小者
+    #[arg(long, requires = "stream", value_parser = clap::value_parser!(u32).range(1..))]
+    max_latency_ms: Option<u32>,
+
+    /// 为每个文件导出逐帧语音/静音判定与RMS能量 (mask.csv)
+    #[arg(long, default_value = "false")]
+    export_mask: bool,
+
+    /// 为每个文件导出 Audacity 标签轨 (labels.txt)，每个切片一个标签，与源音频对齐
+    #[arg(long, default_value = "false")]
+    export_labels: bool,
+
+    /// 在导出的标签 (Audacity 标签轨、Label Studio 区域备注) 中附加由切片指标得到的描述:
+    /// speech/sparse、quiet/normal/loud 与时长区间
+    #[arg(long, default_value = "false")]
+    auto_labels: bool,
+
+    /// 只导出 Audacity 标签轨，不写出切片音频与清单 (隐含 --export-labels)，
+    /// 用于在切片前先检查、调整切点
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = [
+            "audit_sample", "catalog", "run_manifest", "label_studio", "normalize_corpus",
+        ]
+    )]
+    labels_only: bool,
+
+    /// 试运行: 完成加载、检测、合并与静音过滤，打印每个文件的切片数与时长，
+    /// 不向磁盘写入任何内容 (不能与写出附加文件的选项同时使用)
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = [
+            "isolate", "scratch_dir", "on_locked", "catalog", "audit_sample", "run_manifest",
+            "label_studio", "scan_output", "labels_only", "export_labels", "normalize_corpus", "export_mask", "export_peaks",
+            "export_noise_profile", "trace_detection", "viz", "preserve_tree_metadata", "announce",
+        ]
+    )]
+    dry_run: bool,
+
+    /// 为每个文件及每个切片导出 audiowaveform 格式的波形峰值 (peaks.dat / slice_000.dat)，供网页波形组件直接绘制
+    #[arg(long, default_value = "false")]
+    export_peaks: bool,
+
+    /// 波形峰值的分辨率: 每对最小值/最大值覆盖的样本数
+    #[arg(long, default_value = "256", value_parser = clap::value_parser!(u32).range(1..))]
+    peaks_samples_per_pixel: u32,
+
+    /// 为每个文件写出检测跟踪日志 (detection_trace.log): 阈值穿越、静音长度与每个候选边界的取舍原因
+    #[arg(long, default_value = "false")]
+    trace_detection: bool,
+
+    /// 为每个文件导出 RMS 包络与检测片段的时间线图 (png: timeline.png, html: timeline.html)
+    #[arg(long, value_enum)]
+    viz: Option<VizFormat>,
+
+    /// 运行结束后从所有文件中随机抽取 N 个切片复制到 audit/ 目录，并写出其指标 (audit.csv)
+    #[arg(long)]
+    audit_sample: Option<usize>,
+
+    /// 抽检随机种子，相同种子与输出得到相同的抽检集
+    #[arg(long, default_value = "0")]
+    audit_seed: u64,
+
+    /// 把本次运行、源文件、切片及其指标追加记录到 SQLite 目录数据库，可用 query 子命令查询
+    #[arg(long)]
+    catalog: Option<PathBuf>,
+
+    /// 运行结束后把所有文件的切片记录合并写入一个运行级清单 (JSON 数组)，
+    /// 每项含源文件、源采样率、相对于输出目录的切片路径与清单中的全部字段
+    #[arg(long)]
+    run_manifest: Option<PathBuf>,
+
+    /// 运行结束后把所有文件的切片写成 Label Studio 预标注任务 (JSON)，标注员从检测结果开始修正
+    #[arg(long)]
+    label_studio: Option<PathBuf>,
+
+    /// Label Studio 任务中音频地址的前缀，后接源文件相对于输入目录的路径
+    /// (如 /data/local-files/?d=corpus/)；不指定时为源文件路径
+    #[arg(long, requires = "label_studio")]
+    label_studio_audio_prefix: Option<String>,
+
+    /// 同时写出与 scan 子命令相同的源文件元数据目录 (.csv 或 .json)，
+    /// 时长、响度与峰值取自切片时已解码的音频，不再单独解码
+    #[arg(long, conflicts_with = "isolate")]
+    scan_output: Option<PathBuf>,
+
+    /// 全部文件切完后按切片的响度分布统一调整电平 (corpus: 所有切片同一增益,
+    /// source: 每个源文件一个增益)，使响度中位数达到 --normalize-target-lufs，
+    /// 保留切片之间的相对动态；只支持无损输出格式
+    #[arg(
+        long,
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "corpus",
+        conflicts_with_all = ["announce", "normalize_lufs", "normalize_peak"]
+    )]
+    normalize_corpus: Option<NormalizeScope>,
+
+    /// 批量响度归一化的目标响度 (LUFS)
+    #[arg(long, default_value = "-23.0", allow_negative_numbers = true)]
+    normalize_target_lufs: f32,
+
+    /// 按音频内容哈希 (开头 30 秒 PCM + 时长) 去重: 跳过本次运行中已处理过、
+    /// 或已记录在 --catalog 目录数据库中的相同内容
+    #[arg(long, default_value = "false", conflicts_with = "isolate")]
+    dedup_content: bool,
+
+    /// 完整加载前先解码每个文件开头的若干秒，检查是否全为数字静音或有无信号的声道；
+    /// 发现问题时记为处理警告
+    #[arg(long)]
+    precheck_secs: Option<f64>,
+
+    /// 预检发现问题的文件直接跳过，不再完整解码 (原因列在跳过报告中)
+    #[arg(long, default_value = "false", requires = "precheck_secs")]
+    precheck_skip: bool,
+
+    /// 只处理上次失败的文件: 指定失败列表 (输出目录下的 errors.json) 或目录数据库
+    #[arg(long)]
+    only_failed: Option<PathBuf>,
+
+    /// 只处理尚未处理过的文件 (指定 --catalog 时按目录数据库判断，否则按输出目录中是否已有清单判断)；
+    /// 与 --only-failed 同时指定时处理两者的并集
+    #[arg(long, default_value = "false")]
+    only_new: bool,
+
+    /// 运行结束后把输入目录树的修改时间与权限复制到输出目录树 (切片目录沿用源文件的时间)，
+    /// 避免基于 rsync 的下游同步每次重新传输
+    #[arg(long, default_value = "false")]
+    preserve_tree_metadata: bool,
+
+    /// 暂存目录 (快速本地存储)：切片先写到这里，再由后台线程移动到输出目录，
+    /// 使编码吞吐不受网络存储延迟影响
+    #[arg(long)]
+    scratch_dir: Option<PathBuf>,
+
+    /// 输出目录布局: mirror 保持输入目录结构，flat 把所有文件的切片目录直接放在输出目录下
+    #[arg(long, value_enum, default_value_t = Layout::Mirror)]
+    layout: Layout,
+
+    /// 把输入中的 zip/tar/tar.gz 压缩包当作目录读取: 直接从压缩包中解码音频成员 (不解压到磁盘)，
+    /// 压缩包内的路径作为输出镜像的相对结构
+    #[arg(long, default_value = "false")]
+    read_archives: bool,
+
+    /// 平铺布局中同名文件 (来自不同目录) 的消歧方式: path 以相对目录为前缀，hash 以相对路径的哈希为后缀
+    #[arg(long, value_enum, default_value_t = CollisionNaming::Path)]
+    collision_naming: CollisionNaming,
+
+    /// 按切片序号把每个文件的切片分到编号子目录 (000/、001/…)，每个子目录最多 N 个切片，
+    /// 避免单个目录中的文件过多
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    shard_output: Option<u32>,
+
+    /// 输出目录正被另一运行使用时的处理方式: fail 立即报错，wait 等待其结束，
+    /// append 与其他 append 模式的运行共同写入 (每个文件由先认领的运行处理)
+    #[arg(long, value_enum, default_value_t = OnLocked::Fail)]
+    on_locked: OnLocked,
+
+    /// 安全模式: 本次运行只创建新文件，任何覆盖或删除已有文件的操作 (切片、清单、统计表、
+    /// 旧的失败列表等) 都报错，用于输出目录中同时存放不可替代的原始文件的场景
+    #[arg(
+        long,
+        default_value = "false",
+        conflicts_with_all = ["normalize_corpus", "scratch_dir", "catalog"]
+    )]
+    safe: bool,
+
+    /// 报告中数字的区域格式 (千位分隔符与小数点)，auto 按 LC_ALL/LC_NUMERIC/LANG 选择
+    #[arg(long, value_enum, default_value_t = Locale::Zh)]
+    locale: Locale,
+
+    /// 报告中时长的显示方式: human 自动选择单位，seconds 输出不带单位的秒数便于粘贴到表格
+    #[arg(long, value_enum, default_value_t = DurationStyle::Human)]
+    duration_format: DurationStyle,
+
+    /// 向标准输出逐行输出机器可读事件 (此时文本输出改写到标准错误)
+    #[arg(long, value_enum)]
+    events: Option<EventFormat>,
+
+    /// 把每个完成的切片 (路径与指标) 以一行 JSON 写到该 Unix 套接字或 Windows 命名管道
+    #[arg(long)]
+    announce: Option<PathBuf>,
+
+    /// 运行结束 (成功或失败) 后执行的命令，运行结果通过 SLICER_* 环境变量传入
+    #[arg(long)]
+    notify_cmd: Option<String>,
+
+    /// 参数检查发现问题时拒绝运行
+    #[arg(long, default_value = "false")]
+    strict: bool,
+}
+
+impl SliceArgs {
+    /// 生效的最大切片时长与断开方式，`--max-length-ms` 优先
+    fn max_duration(&self) -> (u32, MaxDurationMode) {
+        match self.max_length_ms {
+            Some(ms) => (ms, MaxDurationMode::Quietest),
+            None => (self.max_duration_ms, self.max_duration_mode),
+        }
+    }
+}
+
+/// 默认二次静音过滤阈值 (dBFS)
+const DEFAULT_SILENCE_THRESHOLD_DB: f32 = -60.0;
+
+/// 噪声样本最大时长 (秒)
+const NOISE_PROFILE_MAX_SECS: usize = 10;
+
+/// 切片边界对齐过零点的最大移动距离 (ms)
+const ZERO_CROSS_SEARCH_MS: u32 = 10;
+
 /// 性能统计结构
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 struct PerformanceStats {
     total_files: usize,
     processed_files: usize,
@@ -93,6 +961,19 @@ struct PerformanceStats {
     total_chunks_merged: usize,
     total_slices_saved: usize,
     total_saved_duration: f64,
+    total_reverb_rejected: usize,
+    /// 判定为掌声/笑声而丢弃的切片数
+    #[serde(default)]
+    total_applause_rejected: usize,
+    total_non_finite_samples: usize,
+    total_slices_limited: usize,
+    total_augmented_saved: usize,
+    /// 样本原样复制的文件数 (32 位浮点单声道 WAV)
+    #[serde(default)]
+    total_bit_exact_files: usize,
+    /// 短于 --min-output-ms 的切片数 (按策略丢弃或扩展)
+    #[serde(default)]
+    total_short_outputs: usize,
 }
 
 impl PerformanceStats {
@@ -108,34 +989,169 @@ impl PerformanceStats {
         self.total_chunks_merged += other.total_chunks_merged;
         self.total_slices_saved += other.total_slices_saved;
         self.total_saved_duration += other.total_saved_duration;
+        self.total_reverb_rejected += other.total_reverb_rejected;
+        self.total_applause_rejected += other.total_applause_rejected;
+        self.total_non_finite_samples += other.total_non_finite_samples;
+        self.total_slices_limited += other.total_slices_limited;
+        self.total_augmented_saved += other.total_augmented_saved;
+        self.total_bit_exact_files += other.total_bit_exact_files;
+        self.total_short_outputs += other.total_short_outputs;
     }
 }
 
+/// 子进程通过标准输出返回的处理结果
+#[derive(Serialize, Deserialize)]
+struct WorkerOutput {
+    stats: PerformanceStats,
+    error: Option<String>,
+    #[serde(default)]
+    warnings: Vec<String>,
+}
+
 /// 单个文件的处理结果
 struct FileProcessResult {
     file_path: PathBuf,
     stats: PerformanceStats,
     success: bool,
     error: Option<String>,
+    /// 跳过时的原因 (内容重复或已由另一运行认领，此时 `success` 为 true 但没有输出)
+    skipped: Option<String>,
+    /// 处理成功但需要提醒的问题 (如相位抵消)
+    warnings: Vec<String>,
+    /// 由已解码音频得到的源文件元数据 (--scan-output)
+    scan_info: Option<FileInfo>,
+}
+
+impl FileProcessResult {
+    /// 没有经过处理的结果: 跳过 (`skipped`) 或处理前即失败 (`error`)
+    fn unprocessed(file: &Path, skipped: Option<String>, error: Option<String>) -> Self {
+        Self {
+            file_path: file.to_path_buf(),
+            stats: PerformanceStats::default(),
+            success: error.is_none(),
+            error,
+            skipped,
+            warnings: Vec::new(),
+            scan_info: None,
+        }
+    }
 }
 
-/// 保存音频切片
-fn save_slice(samples: &[f32], sample_rate: u32, output_path: &Path) -> Result<()> {
+/// WAV 输出采样格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+enum WavFormat {
+    /// 32 位浮点
+    #[default]
+    F32,
+    /// 16 位整数
+    I16,
+    /// 24 位整数
+    I24,
+}
+
+/// 输出文件的格式与采样格式设置
+#[derive(Debug, Clone, Copy, Serialize)]
+struct OutputFormat {
+    file_format: FileFormat,
+    bitrate: Bitrate,
+    wav_format: WavFormat,
+    clip_mode: ClipMode,
+    dither: Dither,
+}
+
+/// 保存音频切片 (每个声道一个数组)，整数格式下超出满幅的样本按 `clip_mode` 处理，
+/// 并按 `dither` 逐声道量化
+///
+/// `tags` 非空时以元数据标签形式写入输出文件。安全模式下以独占方式创建目标，
+/// 目标已存在 (包括检查之后才被其他进程创建) 时报错。
+fn save_slice(
+    channels: &[Vec<f32>],
+    sample_rate: u32,
+    output: &OutputFormat,
+    tags: &[(String, String)],
+    output_path: &Path,
+) -> Result<()> {
+    if output.file_format == FileFormat::Flac {
+        let (bits_per_sample, full_scale) = match output.wav_format {
+            WavFormat::I16 => (16, 32767.0),
+            WavFormat::I24 | WavFormat::F32 => (24, 8388607.0),
+        };
+        let quantized: Vec<Vec<i32>> = channels
+            .iter()
+            .map(|samples| {
+                let mut quantizer = Quantizer::new(output.dither);
+                samples
+                    .iter()
+                    .map(|&sample| quantizer.quantize(output.clip_mode.apply(sample), full_scale))
+                    .collect()
+            })
+            .collect();
+        return write_flac(output_path, &quantized, sample_rate, bits_per_sample, tags);
+    }
+    if matches!(output.file_format, FileFormat::Mp3 | FileFormat::Opus) {
+        let quantized: Vec<Vec<i16>> = channels
+            .iter()
+            .map(|samples| {
+                let mut quantizer = Quantizer::new(output.dither);
+                samples
+                    .iter()
+                    .map(|&sample| {
+                        quantizer.quantize(output.clip_mode.apply(sample), 32767.0) as i16
+                    })
+                    .collect()
+            })
+            .collect();
+        return write_lossy(
+            output_path,
+            &quantized,
+            sample_rate,
+            output.file_format,
+            output.bitrate,
+            tags,
+        );
+    }
+
+    let format = output.wav_format;
+    let (bits_per_sample, sample_format) = match format {
+        WavFormat::F32 => (32, hound::SampleFormat::Float),
+        WavFormat::I16 => (16, hound::SampleFormat::Int),
+        WavFormat::I24 => (24, hound::SampleFormat::Int),
+    };
     let spec = WavSpec {
-        channels: 1,
+        channels: channels.len() as u16,
         sample_rate,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
+        bits_per_sample,
+        sample_format,
     };
 
-    let mut writer = WavWriter::create(output_path, spec)?;
-    for &sample in samples {
-        writer.write_sample(sample)?;
+    let mut writer = WavWriter::new(BufWriter::new(create_file(output_path)?), spec)?;
+    let mut quantizers: Vec<Quantizer> = channels
+        .iter()
+        .map(|_| Quantizer::new(output.dither))
+        .collect();
+    for i in 0..channels[0].len() {
+        for (samples, quantizer) in channels.iter().zip(&mut quantizers) {
+            let sample = samples[i];
+            match format {
+                WavFormat::F32 => writer.write_sample(sample)?,
+                WavFormat::I16 => writer.write_sample(
+                    quantizer.quantize(output.clip_mode.apply(sample), 32767.0) as i16,
+                )?,
+                WavFormat::I24 => writer
+                    .write_sample(quantizer.quantize(output.clip_mode.apply(sample), 8388607.0))?,
+            }
+        }
     }
     writer.finalize()?;
+    append_wav_tags(output_path, tags)?;
     Ok(())
 }
 
+/// dB 换算为线性幅度
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
 /// 计算RTF (Real Time Factor)
 fn calculate_rtf(audio_duration_secs: f64, processing_time_secs: f64) -> f64 {
     if audio_duration_secs > 0.0 {
@@ -145,110 +1161,654 @@ fn calculate_rtf(audio_duration_secs: f64, processing_time_secs: f64) -> f64 {
     }
 }
 
-/// 格式化时间显示
-fn format_duration(duration_secs: f64) -> String {
-    if duration_secs < 1.0 {
-        format!("{:.1}ms", duration_secs * 1000.0)
-    } else if duration_secs < 60.0 {
-        format!("{duration_secs:.2}s")
-    } else {
-        let minutes = (duration_secs / 60.0) as u32;
-        let seconds = duration_secs % 60.0;
-        format!("{minutes}m{seconds:.1}s")
+/// 计算样本的均方根
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
     }
+    (samples.iter().map(|&x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
 }
 
-/// 检查文件是否为支持的音频格式
-fn is_audio_file(path: &Path) -> bool {
-    if let Some(ext) = path.extension() {
-        let ext_str = ext.to_string_lossy().to_lowercase();
-        matches!(
-            ext_str.as_str(),
-            "wav" | "mp3" | "flac" | "m4a" | "aac" | "ogg"
-        )
-    } else {
-        false
-    }
+/// 处理配置参数结构体
+#[derive(Debug, Clone)]
+struct ProcessingConfig {
+    config: SlicerConfig,
+    nan_policy: NanPolicy,
+    sample_storage: SampleStorage,
+    detect_on: DetectOn,
+    channel_map: ChannelMap,
+    phase_check: PhaseCheck,
+    phase_cancel_db: f32,
+    channels: OutputChannels,
+    karaoke: KaraokeMode,
+    silence_filter: SilenceFilter,
+    boundaries: BoundaryConfig,
+    unify_sr: Option<u32>,
+    resampler: Resampler,
+    chain: DspChain,
+    output_format: OutputFormat,
+    true_peak_limit_db: Option<f32>,
+    normalize_lufs: Option<f32>,
+    normalize_peak: Option<f32>,
+    fade: Option<(f32, FadeShape)>,
+    padding: Option<(u32, PadMode)>,
+    /// 最短输出时长 (ms) 与更短切片的处理方式
+    min_output_ms: u32,
+    short_output: ShortOutputPolicy,
+    shard_output: Option<usize>,
+    replaygain: bool,
+    provenance_tags: bool,
+    augment_speeds: Vec<f32>,
+    estimate_reverb: bool,
+    max_rt60: Option<f32>,
+    classify_applause: bool,
+    max_applause_score: Option<f32>,
+    export_noise_profile: bool,
+    export_mask: bool,
+    export_labels: bool,
+    /// 标签附加由切片指标得到的描述
+    auto_labels: bool,
+    /// 只导出标签轨，不写出切片音频与清单
+    labels_only: bool,
+    /// 试运行，不写入任何文件
+    dry_run: bool,
+    /// 波形峰值的分辨率 (每像素样本数)，不导出时为 `None`
+    export_peaks: Option<usize>,
+    trace_detection: bool,
+    viz: Option<VizFormat>,
+    per_file_timeout: Option<f64>,
+    /// 预检解码的开头时长 (秒)，不预检时为 `None`
+    precheck_secs: Option<f64>,
+    precheck_skip: bool,
+    /// 为元数据目录测量每个源文件 (--scan-output)
+    scan_output: bool,
+    /// 运行结束后批量响度归一化，切片文件会被改写 (--normalize-corpus)
+    normalize_corpus: bool,
+    stream: bool,
+    /// 流式处理的最大输出延迟 (ms)，见 `--max-latency-ms`
+    max_latency_ms: Option<u32>,
+    /// 响亮事件模式的参数，语音切片时为 `None`
+    loud_events: Option<EventMode>,
+    mode: SliceMode,
+    /// 固定窗口模式的窗口时长 (ms)
+    window_ms: u32,
+    /// 语音活动检测的参数，按能量阈值检测时为 `None`
+    vad: Option<VadConfig>,
+    /// 频谱门限检测的频带判定余量 (dB)，不使用频谱门限时为 `None`
+    spectral_margin_db: Option<f32>,
+    /// 自动阈值高出噪声底的量 (dB)，使用固定阈值时为 `None`
+    auto_threshold_offset_db: Option<f32>,
+}
+
+/// 响亮事件模式的参数
+#[derive(Debug, Clone, Copy, Serialize)]
+struct EventMode {
+    threshold_db: f32,
+    min_event_ms: u32,
+    context_ms: u32,
 }
 
-/// 收集音频文件
-fn collect_audio_files(input_path: &Path) -> Result<Vec<PathBuf>> {
-    let mut audio_files = Vec::new();
+/// 参数指纹覆盖的参数: 只包含影响切片划分与输出文件内容的参数，
+/// 不含超时、试运行、逐文件附加导出等只影响运行方式的参数
+#[derive(Serialize)]
+struct FingerprintParams<'a> {
+    config: &'a SlicerConfig,
+    nan_policy: &'a NanPolicy,
+    detect_on: &'a DetectOn,
+    channel_map: &'a ChannelMap,
+    phase_check: &'a PhaseCheck,
+    phase_cancel_db: &'a f32,
+    channels: &'a OutputChannels,
+    karaoke: &'a KaraokeMode,
+    silence_filter: &'a SilenceFilter,
+    boundaries: &'a BoundaryConfig,
+    unify_sr: &'a Option<u32>,
+    resampler: &'a Resampler,
+    chain: &'a DspChain,
+    output_format: &'a OutputFormat,
+    true_peak_limit_db: &'a Option<f32>,
+    normalize_lufs: &'a Option<f32>,
+    normalize_peak: &'a Option<f32>,
+    fade: &'a Option<(f32, FadeShape)>,
+    padding: &'a Option<(u32, PadMode)>,
+    min_output_ms: &'a u32,
+    short_output: &'a ShortOutputPolicy,
+    shard_output: &'a Option<usize>,
+    replaygain: &'a bool,
+    provenance_tags: &'a bool,
+    augment_speeds: &'a Vec<f32>,
+    estimate_reverb: &'a bool,
+    max_rt60: &'a Option<f32>,
+    classify_applause: &'a bool,
+    max_applause_score: &'a Option<f32>,
+    stream: &'a bool,
+    max_latency_ms: &'a Option<u32>,
+    loud_events: &'a Option<EventMode>,
+    mode: &'a SliceMode,
+    window_ms: &'a u32,
+    vad: &'a Option<VadConfig>,
+    spectral_margin_db: &'a Option<f32>,
+    auto_threshold_offset_db: &'a Option<f32>,
+}
 
-    if input_path.is_file() {
-        if is_audio_file(input_path) {
-            audio_files.push(input_path.to_path_buf());
-        } else {
-            return Err(anyhow::anyhow!("输入文件不是支持的音频格式"));
+impl<'a> From<&'a ProcessingConfig> for FingerprintParams<'a> {
+    fn from(config: &'a ProcessingConfig) -> Self {
+        Self {
+            config: &config.config,
+            nan_policy: &config.nan_policy,
+            detect_on: &config.detect_on,
+            channel_map: &config.channel_map,
+            phase_check: &config.phase_check,
+            phase_cancel_db: &config.phase_cancel_db,
+            channels: &config.channels,
+            karaoke: &config.karaoke,
+            silence_filter: &config.silence_filter,
+            boundaries: &config.boundaries,
+            unify_sr: &config.unify_sr,
+            resampler: &config.resampler,
+            chain: &config.chain,
+            output_format: &config.output_format,
+            true_peak_limit_db: &config.true_peak_limit_db,
+            normalize_lufs: &config.normalize_lufs,
+            normalize_peak: &config.normalize_peak,
+            fade: &config.fade,
+            padding: &config.padding,
+            min_output_ms: &config.min_output_ms,
+            short_output: &config.short_output,
+            shard_output: &config.shard_output,
+            replaygain: &config.replaygain,
+            provenance_tags: &config.provenance_tags,
+            augment_speeds: &config.augment_speeds,
+            estimate_reverb: &config.estimate_reverb,
+            max_rt60: &config.max_rt60,
+            classify_applause: &config.classify_applause,
+            max_applause_score: &config.max_applause_score,
+            stream: &config.stream,
+            max_latency_ms: &config.max_latency_ms,
+            loud_events: &config.loud_events,
+            mode: &config.mode,
+            window_ms: &config.window_ms,
+            vad: &config.vad,
+            spectral_margin_db: &config.spectral_margin_db,
+            auto_threshold_offset_db: &config.auto_threshold_offset_db,
         }
-    } else if input_path.is_dir() {
-        for entry in WalkDir::new(input_path) {
-            let entry = entry?;
-            if entry.file_type().is_file() && is_audio_file(entry.path()) {
-                audio_files.push(entry.path().to_path_buf());
-            }
+    }
+}
+
+impl ProcessingConfig {
+    /// 影响输出内容的参数指纹，按字段名序列化为 JSON 后计算
+    fn fingerprint(&self) -> String {
+        let params =
+            serde_json::to_vec(&FingerprintParams::from(self)).expect("处理参数总能序列化为 JSON");
+        fingerprint(&params)
+    }
+
+    /// 输出不对样本做任何处理 (32 位浮点、无处理链与限幅)，
+    /// 与输出采样率相同的 32 位浮点单声道 WAV 输入可以原样复制样本
+    fn allows_passthrough(&self) -> bool {
+        self.output_format.file_format == FileFormat::Wav
+            && self.output_format.wav_format == WavFormat::F32
+            && self.channel_map.output.is_none()
+            && self.channels == OutputChannels::Mono
+            && self.chain.is_empty()
+            && self.true_peak_limit_db.is_none()
+            && self.normalize_lufs.is_none()
+            && self.normalize_peak.is_none()
+            && self.fade.is_none()
+            && self.padding.is_none()
+            && !self.normalize_corpus
+    }
+
+    fn from_args(args: &SliceArgs) -> Self {
+        Self {
+            config: SlicerConfig {
+                sample_rate: 44100, // 临时值，会在处理时更新
+                threshold_db: args.threshold_db,
+                min_length_ms: args.min_length_ms,
+                min_interval_ms: args.min_interval_ms,
+                hop_size_ms: args.hop_size_ms,
+                max_silence_ms: args.max_silence_ms,
+                metric: args.metric,
+                peak_threshold_db: args.peak_threshold_db,
+                coarse_hop_factor: args.coarse_hop_factor,
+                refine_cuts: args.refine_cuts,
+            },
+            nan_policy: args.nan_policy,
+            sample_storage: args.sample_storage,
+            detect_on: args.detect_on,
+            channel_map: args.channel_map.clone().unwrap_or_default(),
+            phase_check: args.phase_check,
+            phase_cancel_db: args.phase_cancel_db,
+            channels: args.channels,
+            karaoke: args.karaoke,
+            silence_filter: SilenceFilter {
+                threshold: match (args.silence_threshold_db, args.silence_threshold) {
+                    (Some(db), _) => db_to_amplitude(db),
+                    (None, Some(linear)) => linear,
+                    (None, None) => db_to_amplitude(DEFAULT_SILENCE_THRESHOLD_DB),
+                },
+                min_audio_ratio: args.min_audio_ratio,
+                ratio_mode: args.ratio_mode,
+            },
+            boundaries: BoundaryConfig {
+                relax_length: args.relax_length,
+                relax_step_db: args.relax_step_db,
+                relax_max_iterations: args.relax_max_iterations,
+                enable_merge: args.enable_merge,
+                max_merge_duration_ms: args.max_merge_duration_ms,
+                merge_mode: args.merge_mode,
+                sentence_pause_ms: args.sentence_pause_ms,
+                target_duration_ms: args.target_duration_ms,
+                onset_backoff_ms: args.onset_backoff_ms,
+                min_gap_ms: args.min_gap_ms,
+                max_duration_ms: args.max_duration().0,
+                max_duration_mode: args.max_duration().1,
+                overlap_ms: args.overlap_ms,
+                // 流式处理的切片确定后立即写出，无法在切口两侧搜索过零点
+                zero_cross_max_shift_ms: if args.no_zero_cross || args.stream {
+                    0
+                } else {
+                    ZERO_CROSS_SEARCH_MS
+                },
+            },
+            unify_sr: args.unify_sr,
+            resampler: Resampler {
+                backend: args.resampler_backend,
+                quality: args.resampler,
+            },
+            chain: args.chain.clone().unwrap_or_default(),
+            output_format: OutputFormat {
+                file_format: args.output_format,
+                bitrate: args.bitrate,
+                wav_format: args.wav_format,
+                clip_mode: args.clip_mode,
+                dither: args.dither,
+            },
+            true_peak_limit_db: args.true_peak_limit_db,
+            normalize_lufs: args.normalize_lufs,
+            normalize_peak: args.normalize_peak,
+            fade: args.fade_ms.map(|ms| (ms, args.fade_shape)),
+            padding: args
+                .pad_silence_ms
+                .filter(|&ms| ms > 0)
+                .map(|ms| (ms, args.pad_mode)),
+            min_output_ms: args.min_output_ms,
+            short_output: args.short_output,
+            shard_output: args.shard_output.map(|size| size as usize),
+            replaygain: args.replaygain,
+            provenance_tags: args.provenance_tags,
+            augment_speeds: args
+                .augment
+                .as_ref()
+                .map(|spec| spec.speeds.clone())
+                .unwrap_or_default(),
+            estimate_reverb: args.estimate_reverb || args.max_rt60.is_some(),
+            max_rt60: args.max_rt60,
+            classify_applause: args.classify_applause || args.max_applause_score.is_some(),
+            max_applause_score: args.max_applause_score,
+            export_noise_profile: args.export_noise_profile,
+            export_mask: args.export_mask,
+            export_labels: args.export_labels || args.labels_only,
+            auto_labels: args.auto_labels,
+            labels_only: args.labels_only,
+            dry_run: args.dry_run,
+            export_peaks: args
+                .export_peaks
+                .then_some(args.peaks_samples_per_pixel as usize),
+            trace_detection: args.trace_detection,
+            viz: args.viz,
+            per_file_timeout: args.per_file_timeout,
+            precheck_secs: args.precheck_secs,
+            precheck_skip: args.precheck_skip,
+            scan_output: args.scan_output.is_some(),
+            normalize_corpus: args.normalize_corpus.is_some(),
+            stream: args.stream,
+            max_latency_ms: args.max_latency_ms,
+            loud_events: args.loud_events.then_some(EventMode {
+                threshold_db: args.event_threshold_db,
+                min_event_ms: args.event_min_ms,
+                context_ms: args.event_context_ms,
+            }),
+            mode: args.mode,
+            window_ms: args.window_ms,
+            vad: match (args.detector, &args.vad_model) {
+                (Detector::Vad, Some(model)) => Some(VadConfig {
+                    model: model.clone(),
+                    threshold: args.vad_threshold,
+                }),
+                _ => None,
+            },
+            spectral_margin_db: (args.detector == Detector::Spectral)
+                .then_some(args.spectral_margin_db),
+            auto_threshold_offset_db: (args.threshold == ThresholdMode::Auto)
+                .then_some(args.threshold_offset_db),
         }
+    }
+}
 
-        if audio_files.is_empty() {
-            return Err(anyhow::anyhow!("在输入目录中未找到支持的音频文件"));
+/// 加载后的源文件
+struct LoadedSource {
+    sample_rate: u32,
+    /// 与样本位置不一一对应的容器时间戳
+    timeline: Option<ContainerTimeline>,
+    /// 输出信号 (单声道)
+    samples: SourceSamples,
+    /// 与输出信号不同的检测信号
+    detection: Option<SourceSamples>,
+    /// 保留声道布局时的各声道
+    kept_channels: Option<Vec<Vec<f32>>>,
+    non_finite: usize,
+    /// 切片样本逐位等于源文件中的对应区间
+    bit_exact: bool,
+    /// 启用 `--scan-output` 时与 `scan` 相同的元数据 (按源文件的声道平均测量)
+    scan_info: Option<FileInfo>,
+}
+
+/// 解码源文件并按配置得到输出信号、检测信号与保留的声道，按策略处理非有限样本
+fn load_source(
+    input_file: &Path,
+    processing_config: &ProcessingConfig,
+    deadline: Option<Instant>,
+    warnings: &mut Vec<String>,
+) -> Result<LoadedSource> {
+    if processing_config.sample_storage == SampleStorage::I16 {
+        // 解码时已混音为单声道，非有限样本按 0 计入
+        let decoded = decode_input_i16(input_file, deadline)?;
+        if decoded.non_finite > 0 && processing_config.nan_policy == NanPolicy::Fail {
+            return Err(anyhow::anyhow!(
+                "音频包含 {} 个 NaN/inf 样本",
+                decoded.non_finite
+            ));
         }
+        let samples = SourceSamples::I16(decoded.samples);
+        let scan_info = processing_config
+            .scan_output
+            .then(|| inspect_decoded(input_file, &samples.to_f32(), decoded.sample_rate));
+        return Ok(LoadedSource {
+            sample_rate: decoded.sample_rate,
+            timeline: decoded.timeline.filter(|timeline| !timeline.is_identity()),
+            samples,
+            detection: None,
+            kept_channels: None,
+            non_finite: decoded.non_finite,
+            bit_exact: false,
+            scan_info,
+        });
+    }
+
+    // 32 位浮点单声道 WAV 直接读取样本，不经过解码器；采样率无需转换且没有
+    // NaN/inf 时，切片样本逐位等于源文件中的对应区间
+    let raw = if processing_config.allows_passthrough() {
+        read_float_mono_wav(input_file)?
     } else {
-        return Err(anyhow::anyhow!("输入路径不存在或无法访问"));
+        None
+    };
+    let bit_exact = raw.as_ref().is_some_and(|raw| {
+        processing_config
+            .unify_sr
+            .is_none_or(|rate| rate == raw.sample_rate)
+            && raw.channels[0].iter().all(|x| x.is_finite())
+    });
+    let decoded = match raw {
+        Some(raw) => raw,
+        None => decode_input(input_file, deadline)?,
+    };
+    let sample_rate = decoded.sample_rate;
+    let timeline = decoded
+        .timeline
+        .clone()
+        .filter(|timeline| !timeline.is_identity());
+    // 元数据目录与 `scan` 一致，按源文件的声道平均测量，不受输出声道选择的影响
+    let scan_info = processing_config
+        .scan_output
+        .then(|| inspect_decoded(input_file, &decoded.downmix(), sample_rate));
+    let channel_map = &processing_config.channel_map;
+    let selected = decoded.select_channel(processing_config.channels)?;
+    // 卡拉OK音轨的人声声道只替代默认的声道平均 (输出与检测各自未另行指定时)
+    let default_output = channel_map.output.is_none() && selected.is_none();
+    let default_detect =
+        channel_map.detect.is_none() && processing_config.detect_on == DetectOn::Mix;
+    let vocal_channel = match processing_config.karaoke {
+        _ if decoded.channels.len() != 2 || !(default_output || default_detect) => None,
+        KaraokeMode::Off => None,
+        KaraokeMode::Left => Some(0),
+        KaraokeMode::Right => Some(1),
+        KaraokeMode::Auto => detect_karaoke(&decoded).map(|split| {
+            warnings.push(format!(
+                "疑似卡拉OK音轨 (声道相关系数 {:.2})，已改用人声声道 {}",
+                split.correlation, split.vocal_channel
+            ));
+            split.vocal_channel
+        }),
+    };
+    let mut samples = match (&channel_map.output, &selected, vocal_channel) {
+        (Some(mix), _, _) => mix.apply(&decoded)?,
+        (None, Some(channel), _) => channel.clone(),
+        (None, None, Some(vocal)) => decoded.channels[vocal].clone(),
+        (None, None, None) => decoded.downmix(),
+    };
+    let mut detect_on = processing_config.detect_on;
+    if let Some(vocal) = vocal_channel
+        && default_detect
+    {
+        detect_on = DetectOn::Channel(vocal);
+    }
+    if detect_on == DetectOn::Mix
+        && channel_map.detect.is_none()
+        && processing_config.phase_check != PhaseCheck::Off
+        && let Some(cancelled_db) = downmix_cancellation_db(&decoded, &samples)
+        && cancelled_db >= processing_config.phase_cancel_db
+    {
+        let mut warning = format!("声道平均比各声道能量低 {cancelled_db:.1}dB，疑似反相");
+        if processing_config.phase_check == PhaseCheck::Auto {
+            detect_on = DetectOn::Max;
+            warning.push_str("，已改为按 max 检测");
+        }
+        warnings.push(warning);
+    }
+    let mut detection = match &channel_map.detect {
+        Some(mix) => Some(mix.apply(&decoded)?),
+        // 只输出单个声道时仍按声道平均检测
+        None if detect_on == DetectOn::Mix && selected.is_some() => Some(decoded.downmix()),
+        None => detection_signal(&decoded, detect_on)?,
+    };
+    // 保留声道布局时切片从各声道切出，`samples` 仍为声道平均 (用于统计与分析)
+    let mut kept_channels = (processing_config.channels == OutputChannels::Keep
+        && decoded.channels.len() > 1)
+        .then_some(decoded.channels);
+    let non_finite = apply_nan_policy(&mut samples, processing_config.nan_policy)?;
+    if let Some(detection) = &mut detection {
+        apply_nan_policy(detection, processing_config.nan_policy)?;
     }
+    for channel in kept_channels.iter_mut().flatten() {
+        apply_nan_policy(channel, processing_config.nan_policy)?;
+    }
+    Ok(LoadedSource {
+        sample_rate,
+        timeline,
+        samples: SourceSamples::F32(samples),
+        detection: detection.map(SourceSamples::F32),
+        kept_channels,
+        non_finite,
+        bit_exact,
+        scan_info,
+    })
+}
 
-    Ok(audio_files)
+/// 写出的切片: 处理后的各声道与施加的电平调整
+struct RenderedSlice {
+    channels: Vec<Vec<f32>>,
+    /// 声道平均 (响度标签与波形峰值按此计算)
+    mixed: Vec<f32>,
+    normalization_gain_db: Option<f32>,
+    limiter_reduction_db: Option<f32>,
+    replaygain_db: Option<f32>,
 }
 
-/// 处理配置参数结构体
-#[derive(Clone)]
-struct ProcessingConfig {
-    config: SlicerConfig,
-    silence_threshold: f32,
-    min_audio_ratio: f32,
-    enable_merge: bool,
-    max_merge_duration_ms: u32,
-    max_duration_ms: u32,
+/// 对切片的各声道依次重采样到输出采样率、施加效果链与淡入淡出、归一化电平并限幅，
+/// 再连同元数据标签按输出格式写到 `path`
+fn render_slice(
+    source_channels: &[Cow<[f32]>],
+    sample_rate: u32,
+    processing_config: &ProcessingConfig,
+    provenance: &Tags,
+    path: &Path,
+) -> Result<RenderedSlice> {
+    let output_sample_rate = processing_config.unify_sr.unwrap_or(sample_rate);
+    let mut channels: Vec<Vec<f32>> = source_channels
+        .iter()
+        .map(|channel| {
+            let mut output =
+                processing_config
+                    .resampler
+                    .process(channel, sample_rate, output_sample_rate);
+            processing_config
+                .chain
+                .apply(&mut output, output_sample_rate)?;
+            if let Some((fade_ms, shape)) = processing_config.fade {
+                fade_edges(&mut output, output_sample_rate, fade_ms, shape);
+            }
+            Ok(output)
+        })
+        .collect::<Result<_>>()?;
+    let normalization_gain_db = match (
+        processing_config.normalize_lufs,
+        processing_config.normalize_peak,
+    ) {
+        (Some(target), _) => normalize_loudness(&mut channels, output_sample_rate, target),
+        (None, Some(target)) => normalize_peak(&mut channels, target),
+        (None, None) => None,
+    };
+    let limiter_reduction_db = processing_config.true_peak_limit_db.map(|ceiling_db| {
+        true_peak_limit(&mut channels, output_sample_rate, ceiling_db).max_reduction_db
+    });
+    let mixed = mix_channels(&channels);
+
+    let (mut tags, replaygain_db) = if processing_config.replaygain {
+        loudness_tags(&mixed, output_sample_rate)
+    } else {
+        (Tags::new(), None)
+    };
+    tags.extend(provenance.iter().cloned());
+    save_slice(
+        &channels,
+        output_sample_rate,
+        &processing_config.output_format,
+        &tags,
+        path,
+    )?;
+    Ok(RenderedSlice {
+        channels,
+        mixed,
+        normalization_gain_db,
+        limiter_reduction_db,
+        replaygain_db,
+    })
 }
 
 /// 处理单个音频文件 (线程安全版本)
 #[allow(clippy::too_many_arguments)]
 fn process_single_file_threaded(
     input_file: &Path,
-    input_base: &Path,
     output_base: &Path,
+    output_file_dir: &Path,
     processing_config: &ProcessingConfig,
+    content_index: Option<&ContentIndex>,
     progress_bar: &ProgressBar,
 ) -> FileProcessResult {
     let start_time = Instant::now();
+    let deadline = processing_config
+        .per_file_timeout
+        .map(|secs| start_time + Duration::from_secs_f64(secs));
     let mut result = FileProcessResult {
         file_path: input_file.to_path_buf(),
         stats: PerformanceStats::default(),
         success: false,
         error: None,
+        skipped: None,
+        warnings: Vec::new(),
+        scan_info: None,
     };
 
     let process_result = (|| -> Result<()> {
-        // 构建输出路径，保持目录结构
-        let relative_path = input_file.strip_prefix(input_base)?;
-        let output_dir = if let Some(parent) = relative_path.parent() {
-            output_base.join(parent)
-        } else {
-            output_base.to_path_buf()
-        };
-
-        let file_stem = input_file.file_stem().unwrap().to_string_lossy();
-        let output_file_dir = output_dir.join(&*file_stem);
-
         progress_bar.set_message(format!(
             "处理: {}",
             input_file.file_name().unwrap().to_string_lossy()
         ));
 
+        // 0. 预检: 只解码开头部分，排除全为数字静音或有无信号声道的输入
+        if let Some(secs) = processing_config.precheck_secs {
+            let precheck_start = Instant::now();
+            let head = decode_input_head(input_file, secs)?;
+            result.stats.total_load_time += precheck_start.elapsed().as_secs_f64();
+            let issues = precheck(&head);
+            if !issues.is_empty() {
+                let reason = format!(
+                    "预检: {}",
+                    issues
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("，")
+                );
+                if processing_config.precheck_skip {
+                    progress_bar.set_message(format!(
+                        "跳过: {} ({reason})",
+                        input_file.file_name().unwrap().to_string_lossy()
+                    ));
+                    result.skipped = Some(reason);
+                    return Ok(());
+                }
+                result.warnings.push(reason);
+            }
+        }
+
+        if processing_config.stream {
+            return process_streaming(
+                input_file,
+                output_base,
+                output_file_dir,
+                processing_config,
+                deadline,
+                progress_bar,
+                &mut result,
+            );
+        }
+
         // 1. 加载音频
         let load_start = Instant::now();
-        let (samples, sample_rate) = load_audio(input_file)?;
+        let LoadedSource {
+            sample_rate,
+            timeline,
+            mut samples,
+            mut detection,
+            mut kept_channels,
+            non_finite,
+            bit_exact,
+            scan_info,
+        } = load_source(
+            input_file,
+            processing_config,
+            deadline,
+            &mut result.warnings,
+        )?;
+        result.stats.total_non_finite_samples += non_finite;
         let load_duration = load_start.elapsed().as_secs_f64();
         result.stats.total_load_time += load_duration;
+        if bit_exact {
+            result.stats.total_bit_exact_files += 1;
+        }
+        result.scan_info = scan_info;
+
+        let hash = with_samples!(&samples, |s| content_hash(s, sample_rate));
+        if let Some(reason) = content_index.and_then(|index| index.claim(&hash, input_file)) {
+            progress_bar.set_message(format!(
+                "跳过: {} ({reason})",
+                input_file.file_name().unwrap().to_string_lossy()
+            ));
+            result.skipped = Some(reason);
+            return Ok(());
+        }
 
         let audio_duration = samples.len() as f64 / sample_rate as f64;
         result.stats.total_audio_duration += audio_duration;
@@ -256,75 +1816,504 @@ fn process_single_file_threaded(
         // 2. 配置切片器
         let mut slicer_cfg = processing_config.config.clone();
         slicer_cfg.sample_rate = sample_rate;
-        let slicer = Slicer::new(slicer_cfg)?;
+        if let Some(events) = &processing_config.loud_events {
+            slicer_cfg.threshold_db = events.threshold_db;
+        }
+        if let Some(offset_db) = processing_config.auto_threshold_offset_db {
+            // 按该文件的噪声底确定阈值，峰值阈值随之平移
+            let probe = Slicer::new(slicer_cfg.clone())?;
+            let rms = with_samples!(detection.as_ref().unwrap_or(&samples), |s| probe
+                .frame_rms(s));
+            if let Some(floor_db) = noise_floor_db(&rms) {
+                let threshold_db = auto_threshold_db(floor_db, offset_db);
+                slicer_cfg.peak_threshold_db += threshold_db - slicer_cfg.threshold_db;
+                slicer_cfg.threshold_db = threshold_db;
+            }
+        }
+        let slicer = Slicer::new(slicer_cfg.clone())?;
+        let mut trace = processing_config.trace_detection.then(|| {
+            DetectionTrace::new(
+                &input_file.to_string_lossy(),
+                &slicer_cfg,
+                slicer.hop_size(),
+            )
+        });
 
-        // 3. 执行切片
+        // 3. 执行切片 (含超长切片的放宽重新检测)；固定窗口模式不做检测
+        let fixed = processing_config.mode == SliceMode::Fixed;
         let slice_start = Instant::now();
-        let mut chunks = slicer.slice(&samples);
+        // 语音活动检测模型或频谱门限给出逐帧判定，模型推理失败时回退到能量阈值检测
+        let detect_source = detection.as_ref().unwrap_or(&samples);
+        let masked_silence = if let Some(vad) = &processing_config.vad {
+            let detect = detect_source.to_f32();
+            let frame_count = slicer.frame_count(detect.len());
+            vad::frame_silence(vad, &detect, sample_rate, slicer.hop_size(), frame_count)
+                .map_err(|e| {
+                    let warning = format!("语音活动检测失败，改用能量阈值检测: {e}");
+                    result.warnings.push(warning);
+                })
+                .ok()
+        } else {
+            let margin_db = processing_config.spectral_margin_db;
+            margin_db.map(|margin_db| {
+                with_samples!(detect_source, |s| {
+                    let level = slicer.frame_silence(s);
+                    let gated = spectral::frame_silence(
+                        s,
+                        sample_rate,
+                        slicer.hop_size(),
+                        level.len(),
+                        margin_db,
+                    );
+                    // 低于能量阈值的帧 (如数字静音) 仍判为静音
+                    level
+                        .into_iter()
+                        .zip(gated)
+                        .map(|(quiet, gated)| quiet || gated)
+                        .collect::<Vec<bool>>()
+                })
+            })
+        };
+        let chunks = if fixed {
+            Vec::new()
+        } else if let Some(silence) = &masked_silence {
+            slicer.detect_chunks_masked(silence, &mut trace)
+        } else {
+            with_samples!(detection.as_ref().unwrap_or(&samples), |s| {
+                match &processing_config.loud_events {
+                    Some(events) => slicer.loud_events(
+                        s,
+                        ms_to_frames(events.min_event_ms, sample_rate, slicer.hop_size()),
+                        ms_to_frames(events.context_ms, sample_rate, slicer.hop_size()),
+                        &mut trace,
+                    ),
+                    None => {
+                        slicer.detect_chunks_observed(s, &processing_config.boundaries, &mut trace)
+                    }
+                }
+            })
+        };
         let slice_duration = slice_start.elapsed().as_secs_f64();
         result.stats.total_slice_time += slice_duration;
+        check_deadline(deadline)?;
+        if non_finite > 0 && processing_config.nan_policy == NanPolicy::SkipFrame {
+            // 16 位整数存储在解码时已把非有限样本按 0 计入
+            if let SourceSamples::F32(samples) = &mut samples {
+                replace_non_finite(samples);
+            }
+            if let Some(SourceSamples::F32(detection)) = &mut detection {
+                replace_non_finite(detection);
+            }
+            for channel in kept_channels.iter_mut().flatten() {
+                replace_non_finite(channel);
+            }
+        }
+        let detect_samples = detection.as_ref().unwrap_or(&samples);
         result.stats.total_chunks_detected += chunks.len();
 
         // 4. 合并短片段（可选）+ 硬切超长切片
         let merge_start = Instant::now();
-        if processing_config.enable_merge {
-            chunks = merge_short_chunks(
-                &chunks,
-                processing_config.max_merge_duration_ms,
-                sample_rate,
-                slicer.hop_size(),
-            );
-        }
-        if processing_config.max_duration_ms > 0 {
-            chunks = enforce_max_duration(
-                &chunks,
-                processing_config.max_duration_ms,
-                sample_rate,
-                slicer.hop_size(),
-            );
-        }
+        let chunks = if fixed {
+            chunks
+        } else {
+            with_samples!(detect_samples, |s| {
+                slicer.refine_chunks_observed(s, &chunks, &processing_config.boundaries, &mut trace)
+            })
+        };
         let merge_duration = merge_start.elapsed().as_secs_f64();
         result.stats.total_merge_time += merge_duration;
         result.stats.total_chunks_merged += chunks.len();
 
         // 5. 保存切片
         let save_start = Instant::now();
-        std::fs::create_dir_all(&output_file_dir)?;
+        if !processing_config.dry_run {
+            std::fs::create_dir_all(output_file_dir)?;
+        }
         let mut saved_count = 0;
         let mut file_saved_duration = 0.0;
+        let output_sample_rate = processing_config.unify_sr.unwrap_or(sample_rate);
+        let params_hash = processing_config.fingerprint();
+        let mut manifest = FileManifest {
+            schema_version: MANIFEST_SCHEMA_VERSION,
+            source: input_file.to_string_lossy().into_owned(),
+            sample_rate,
+            output_sample_rate,
+            time_base: timeline.as_ref().map(|timeline| timeline.time_base),
+            params_hash: params_hash.clone(),
+            content_hash: Some(hash),
+            silence_threshold: processing_config.silence_filter.threshold,
+            silence_threshold_db: processing_config.silence_filter.threshold_db(),
+            noise_profile: None,
+            peaks: None,
+            bit_exact,
+            shard_size: processing_config.shard_output,
+            padding_ms: processing_config.padding.map(|(ms, _)| ms),
+            resampler: (output_sample_rate != sample_rate)
+                .then(|| processing_config.resampler.name()),
+            threshold_db: processing_config
+                .auto_threshold_offset_db
+                .map(|_| slicer_cfg.threshold_db),
+            timings: None,
+            slices: Vec::new(),
+        };
 
-        for &(start_frame, end_frame) in chunks.iter() {
-            let start_sample = start_frame * slicer.hop_size();
-            let end_sample = end_frame * slicer.hop_size();
-            let slice_samples = &samples[start_sample..end_sample.min(samples.len())];
-
-            if !is_silence(
-                slice_samples,
-                processing_config.silence_threshold,
-                processing_config.min_audio_ratio,
-            ) {
-                let slice_duration = slice_samples.len() as f64 / sample_rate as f64;
-                file_saved_duration += slice_duration;
+        if processing_config.export_mask {
+            write_frame_mask(
+                &output_file_dir.join(MASK_FILE_NAME),
+                &with_samples!(detect_samples, |s| slicer.frame_rms(s)),
+                &with_samples!(detect_samples, |s| slicer.frame_silence(s)),
+                slicer.hop_size(),
+                sample_rate,
+            )?;
+        }
 
+        if processing_config.export_noise_profile {
+            let hop_size = slicer.hop_size();
+            let ranges: Vec<(usize, usize)> = with_samples!(detect_samples, |s| slicer
+                .silent_ranges(s))
+            .into_iter()
+            .map(|(start, end)| (start * hop_size, end * hop_size))
+            .collect();
+            let profile = extract_noise_profile(
+                &samples.to_f32(),
+                &ranges,
+                NOISE_PROFILE_MAX_SECS * sample_rate as usize,
+            );
+            if !profile.is_empty() {
+                // 降噪工具通常只接受 WAV，噪声样本不随 --output-format 改变
                 save_slice(
-                    slice_samples,
-                    sample_rate,
-                    &output_file_dir.join(format!("slice_{saved_count:03}.wav")),
+                    &[processing_config.resampler.process(
+                        &profile,
+                        sample_rate,
+                        output_sample_rate,
+                    )],
+                    output_sample_rate,
+                    &OutputFormat {
+                        file_format: FileFormat::Wav,
+                        ..processing_config.output_format
+                    },
+                    &[],
+                    &output_file_dir.join(NOISE_PROFILE_FILE_NAME),
                 )?;
-                saved_count += 1;
+                manifest.noise_profile = Some(NOISE_PROFILE_FILE_NAME.to_string());
             }
         }
 
-        let save_duration = save_start.elapsed().as_secs_f64();
-        result.stats.total_save_time += save_duration;
-        result.stats.total_slices_saved += saved_count;
-        result.stats.total_saved_duration += file_saved_duration;
+        if let Some(samples_per_pixel) = processing_config.export_peaks {
+            write_peaks(
+                &output_file_dir.join(PEAKS_FILE_NAME),
+                &samples.to_f32(),
+                sample_rate,
+                samples_per_pixel,
+            )?;
+            manifest.peaks = Some(PEAKS_FILE_NAME.to_string());
+        }
 
-        let file_processing_time = start_time.elapsed().as_secs_f64();
-        result.stats.total_processing_time += file_processing_time;
-        result.stats.processed_files += 1;
+        // 固定窗口保持精确的窗口时长，不对齐过零点
+        let ranges = if fixed {
+            let to_samples = |ms: u32| (ms as u64 * sample_rate as u64 / 1000) as usize;
+            fixed_windows(
+                samples.len(),
+                to_samples(processing_config.window_ms),
+                to_samples(processing_config.boundaries.overlap_ms),
+            )
+        } else {
+            with_samples!(&samples, |s| {
+                slicer.chunk_sample_ranges(s, &chunks, &processing_config.boundaries)
+            })
+        };
+        let mut prev_end = 0usize;
+        let mut labels = Vec::new();
+
+        let min_output_samples =
+            (processing_config.min_output_ms as u64 * sample_rate as u64 / 1000) as usize;
+        for &(start_sample, end_sample) in &ranges {
+            check_deadline(deadline)?;
+            let (start_sample, end_sample) = if end_sample - start_sample < min_output_samples {
+                result.stats.total_short_outputs += 1;
+                let extended = match processing_config.short_output {
+                    ShortOutputPolicy::Drop => None,
+                    ShortOutputPolicy::Extend => extend_to_min_length(
+                        start_sample,
+                        end_sample,
+                        min_output_samples,
+                        samples.len(),
+                    ),
+                };
+                let Some(range) = extended else {
+                    if let Some(trace) = &mut trace {
+                        trace.reject(start_sample..end_sample, "短于最短输出时长");
+                    }
+                    continue;
+                };
+                range
+            } else {
+                (start_sample, end_sample)
+            };
+            // 与上一个实际保存的切片的重叠；被过滤掉的切片不计
+            let overlap_samples = prev_end.saturating_sub(start_sample);
+            let slice_range = samples.range(start_sample..end_sample);
+            let slice_samples: &[f32] = &slice_range;
+            let stats = with_samples!(detect_samples, |s| {
+                slicer.segment_stats(s, start_sample..end_sample)
+            });
+
+            if processing_config
+                .silence_filter
+                .is_silence(slice_samples, &stats, sample_rate)
+            {
+                if let Some(trace) = &mut trace {
+                    trace.reject(start_sample..end_sample, "二次静音过滤判定为静音");
+                }
+                continue;
+            }
 
-        progress_bar.set_message(format!(
+            let rt60 = if processing_config.estimate_reverb {
+                estimate_rt60(slice_samples, sample_rate)
+            } else {
+                None
+            };
+            if let (Some(max_rt60), Some(rt60)) = (processing_config.max_rt60, rt60)
+                && rt60 > max_rt60
+            {
+                if let Some(trace) = &mut trace {
+                    trace.reject(
+                        start_sample..end_sample,
+                        &format!("RT60 {rt60:.2}s 超过上限 {max_rt60}s"),
+                    );
+                }
+                result.stats.total_reverb_rejected += 1;
+                continue;
+            }
+
+            let applause_score = if processing_config.classify_applause {
+                applause_score(slice_samples, sample_rate)
+            } else {
+                None
+            };
+            if let (Some(max_score), Some(score)) =
+                (processing_config.max_applause_score, applause_score)
+                && score > max_score
+            {
+                if let Some(trace) = &mut trace {
+                    trace.reject(
+                        start_sample..end_sample,
+                        &format!("掌声/笑声可能性 {score:.2} 超过上限 {max_score}"),
+                    );
+                }
+                result.stats.total_applause_rejected += 1;
+                continue;
+            }
+
+            let extension = processing_config.output_format.file_format.extension();
+            let file_name = format!("slice_{saved_count:03}.{extension}");
+
+            let slice_duration = slice_samples.len() as f64 / sample_rate as f64;
+            file_saved_duration += slice_duration;
+            let start_sec = start_sample as f64 / sample_rate as f64;
+            let end_sec = end_sample as f64 / sample_rate as f64;
+            if processing_config.export_labels {
+                let mut label = Path::new(&file_name)
+                    .with_extension("")
+                    .to_string_lossy()
+                    .into_owned();
+                if processing_config.auto_labels {
+                    label.push(' ');
+                    label.push_str(&auto_label(
+                        slice_duration,
+                        stats.rms_db,
+                        stats.voiced_ratio,
+                    ));
+                }
+                labels.push((start_sec, end_sec, label));
+            }
+            if processing_config.labels_only || processing_config.dry_run {
+                saved_count += 1;
+                prev_end = end_sample;
+                continue;
+            }
+            let file_name = shard_path(&file_name, saved_count, processing_config.shard_output);
+            if let Some(shard_dir) = Path::new(&file_name).parent()
+                && !shard_dir.as_os_str().is_empty()
+            {
+                std::fs::create_dir_all(output_file_dir.join(shard_dir))?;
+            }
+
+            let range = start_sample..end_sample;
+            let source_channels: Vec<Cow<[f32]>> = match (&kept_channels, processing_config.padding)
+            {
+                (Some(channels), None) => channels
+                    .iter()
+                    .map(|channel| Cow::Borrowed(&channel[range.clone()]))
+                    .collect(),
+                (Some(channels), Some((pad_ms, mode))) => channels
+                    .iter()
+                    .map(|channel| {
+                        let pad = (sample_rate as u64 * pad_ms as u64 / 1000) as usize;
+                        Cow::Owned(pad_slice(channel, range.clone(), pad, mode))
+                    })
+                    .collect(),
+                (None, None) => vec![Cow::Borrowed(slice_samples)],
+                (None, Some((pad_ms, mode))) => {
+                    let pad = (sample_rate as u64 * pad_ms as u64 / 1000) as usize;
+                    let padded = with_samples!(&samples, |s| pad_slice(s, range, pad, mode));
+                    vec![Cow::Owned(padded)]
+                }
+            };
+            let provenance = if processing_config.provenance_tags {
+                provenance_tags(&manifest.source, start_sec, end_sec, &params_hash)
+            } else {
+                Tags::new()
+            };
+            let RenderedSlice {
+                channels: output_channels,
+                mixed: output_samples,
+                normalization_gain_db,
+                limiter_reduction_db,
+                replaygain_db,
+            } = render_slice(
+                &source_channels,
+                sample_rate,
+                processing_config,
+                &provenance,
+                &output_file_dir.join(&file_name),
+            )?;
+            if limiter_reduction_db.is_some_and(|db| db > 0.0) {
+                result.stats.total_slices_limited += 1;
+            }
+            let peaks = match processing_config.export_peaks {
+                Some(samples_per_pixel) => {
+                    let peaks_name = slice_peaks_name(&file_name);
+                    write_peaks(
+                        &output_file_dir.join(&peaks_name),
+                        &output_samples,
+                        output_sample_rate,
+                        samples_per_pixel,
+                    )?;
+                    Some(peaks_name)
+                }
+                None => None,
+            };
+
+            let mut augmented = Vec::new();
+            for &speed in &processing_config.augment_speeds {
+                if speed == 1.0 {
+                    continue;
+                }
+                let variant_name = shard_path(
+                    &format!("slice_{saved_count:03}_speed{speed}.{extension}"),
+                    saved_count,
+                    processing_config.shard_output,
+                );
+                let variant: Vec<Vec<f32>> = output_channels
+                    .iter()
+                    .map(|channel| speed_perturb(channel, output_sample_rate, speed))
+                    .collect();
+                let mut variant_tags = if processing_config.replaygain {
+                    loudness_tags(&mix_channels(&variant), output_sample_rate).0
+                } else {
+                    Tags::new()
+                };
+                variant_tags.extend(provenance.iter().cloned());
+                save_slice(
+                    &variant,
+                    output_sample_rate,
+                    &processing_config.output_format,
+                    &variant_tags,
+                    &output_file_dir.join(&variant_name),
+                )?;
+                augmented.push(AugmentedRecord {
+                    file: variant_name,
+                    speed,
+                });
+            }
+            result.stats.total_augmented_saved += augmented.len();
+
+            manifest.slices.push(SliceRecord {
+                file: file_name,
+                start_sample,
+                end_sample,
+                start_sec,
+                end_sec,
+                duration_sec: slice_duration,
+                container_start_ts: timeline.as_ref().map(|t| t.timestamp(start_sample)),
+                container_end_ts: timeline.as_ref().map(|t| t.timestamp(end_sample)),
+                rms: rms(slice_samples),
+                rms_db: stats.rms_db,
+                peak_db: stats.peak_db,
+                voiced_ratio: stats.voiced_ratio,
+                overlap_samples,
+                rt60,
+                applause_score,
+                limiter_reduction_db,
+                replaygain_db,
+                normalization_gain_db,
+                augmented,
+                peaks,
+                metadata: BTreeMap::new(),
+                stale: false,
+            });
+            saved_count += 1;
+            prev_end = end_sample;
+        }
+
+        if processing_config.export_labels {
+            write_audacity_labels(&output_file_dir.join(LABELS_FILE_NAME), &labels)?;
+        }
+        if !processing_config.labels_only && !processing_config.dry_run {
+            let total_sec = start_time.elapsed().as_secs_f64();
+            manifest.timings = Some(StageTimings {
+                load_sec: load_duration,
+                slice_sec: slice_duration,
+                merge_sec: merge_duration,
+                save_sec: save_start.elapsed().as_secs_f64(),
+                total_sec,
+                rtf: calculate_rtf(audio_duration, total_sec),
+            });
+            // 原子重命名只保证清单不被写坏；refilter / verify / migrate 在锁内读取-修改-写回
+            // 清单，不加锁时本次写出的清单会被它们用旧内容覆盖
+            let _lock = IndexLock::acquire(output_base)?;
+            write_manifest(&output_file_dir.join(MANIFEST_FILE_NAME), &manifest)?;
+        }
+        if let Some(trace) = &trace {
+            trace.write(&output_file_dir.join(TRACE_FILE_NAME))?;
+        }
+
+        if let Some(format) = processing_config.viz {
+            let segments: Vec<VizSegment> = ranges
+                .iter()
+                .map(|&(start_sample, end_sample)| VizSegment {
+                    start_sample,
+                    end_sample,
+                    file: manifest
+                        .slices
+                        .iter()
+                        .find(|record| {
+                            record.start_sample == start_sample && record.end_sample == end_sample
+                        })
+                        .map(|record| record.file.clone()),
+                })
+                .collect();
+            Timeline {
+                rms: &with_samples!(detect_samples, |s| slicer.frame_rms(s)),
+                hop_size: slicer.hop_size(),
+                sample_rate,
+                threshold_db: slicer_cfg.threshold_db,
+                segments: &segments,
+            }
+            .write(format, output_file_dir, &manifest.source)?;
+        }
+
+        let save_duration = save_start.elapsed().as_secs_f64();
+        result.stats.total_save_time += save_duration;
+        result.stats.total_slices_saved += saved_count;
+        result.stats.total_saved_duration += file_saved_duration;
+
+        let file_processing_time = start_time.elapsed().as_secs_f64();
+        result.stats.total_processing_time += file_processing_time;
+        result.stats.processed_files += 1;
+
+        progress_bar.set_message(format!(
             "完成: {} ({}个切片, RTF: {:.3}x)",
             input_file.file_name().unwrap().to_string_lossy(),
             saved_count,
@@ -351,76 +2340,1010 @@ fn process_single_file_threaded(
     result
 }
 
-#[allow(clippy::too_many_arguments)]
-fn process_slice_command(
-    input: PathBuf,
-    output: PathBuf,
-    threads: Option<usize>,
-    threshold_db: f32,
-    min_length_ms: u32,
-    min_interval_ms: u32,
-    hop_size_ms: u32,
-    max_silence_ms: u32,
-    enable_merge: bool,
-    max_merge_duration_ms: u32,
-    max_duration_ms: u32,
-    silence_threshold: f32,
-    min_audio_ratio: f32,
+/// 流式处理单个文件 (--stream): 边解码边检测，切片的终点一经确定即过滤并写出
+///
+/// 内存占用取决于最长的切片而不是文件时长。解码、检测与写出交错进行，
+/// 检测与写出之外的时间计入加载时间。
+fn process_streaming(
+    input_file: &Path,
+    output_base: &Path,
+    output_file_dir: &Path,
+    processing_config: &ProcessingConfig,
+    deadline: Option<Instant>,
+    progress_bar: &ProgressBar,
+    result: &mut FileProcessResult,
 ) -> Result<()> {
+    let start_time = Instant::now();
+    std::fs::create_dir_all(output_file_dir)?;
+    let source = input_file.to_string_lossy().into_owned();
+    let params_hash = processing_config.fingerprint();
+    let extension = processing_config.output_format.file_format.extension();
+    let stats = &mut result.stats;
+    let mut stream: Option<Slicer> = None;
+    let mut slices: Vec<SliceRecord> = Vec::new();
+    let mut audio_samples = 0usize;
+    let (mut slice_sec, mut save_sec) = (0.0, 0.0);
+
+    let mut save = |chunk: Chunk,
+                    slicer: &Slicer,
+                    sample_rate: u32,
+                    stats: &mut PerformanceStats|
+     -> Result<()> {
+        let save_start = Instant::now();
+        let Chunk {
+            start_sample,
+            end_sample,
+            samples,
+        } = chunk;
+        stats.total_chunks_detected += 1;
+        stats.total_chunks_merged += 1;
+        let min_output_samples =
+            (processing_config.min_output_ms as u64 * sample_rate as u64 / 1000) as usize;
+        if samples.len() < min_output_samples {
+            stats.total_short_outputs += 1;
+            return Ok(());
+        }
+        let segment = slicer.segment_stats(&samples, 0..samples.len());
+        if processing_config
+            .silence_filter
+            .is_silence(&samples, &segment, sample_rate)
+        {
+            return Ok(());
+        }
+
+        let index = slices.len();
+        let file_name = shard_path(
+            &format!("slice_{index:03}.{extension}"),
+            index,
+            processing_config.shard_output,
+        );
+        if let Some(shard_dir) = Path::new(&file_name).parent()
+            && !shard_dir.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(output_file_dir.join(shard_dir))?;
+        }
+        let start_sec = start_sample as f64 / sample_rate as f64;
+        let end_sec = end_sample as f64 / sample_rate as f64;
+        let provenance = if processing_config.provenance_tags {
+            provenance_tags(&source, start_sec, end_sec, &params_hash)
+        } else {
+            Tags::new()
+        };
+        let rendered = render_slice(
+            &[Cow::Borrowed(&samples)],
+            sample_rate,
+            processing_config,
+            &provenance,
+            &output_file_dir.join(&file_name),
+        )?;
+        if rendered.limiter_reduction_db.is_some_and(|db| db > 0.0) {
+            stats.total_slices_limited += 1;
+        }
+        let duration_sec = samples.len() as f64 / sample_rate as f64;
+        stats.total_saved_duration += duration_sec;
+        slices.push(SliceRecord {
+            file: file_name,
+            start_sample,
+            end_sample,
+            start_sec,
+            end_sec,
+            duration_sec,
+            container_start_ts: None,
+            container_end_ts: None,
+            rms: rms(&samples),
+            rms_db: segment.rms_db,
+            peak_db: segment.peak_db,
+            voiced_ratio: segment.voiced_ratio,
+            overlap_samples: 0,
+            rt60: None,
+            applause_score: None,
+            limiter_reduction_db: rendered.limiter_reduction_db,
+            replaygain_db: rendered.replaygain_db,
+            normalization_gain_db: rendered.normalization_gain_db,
+            augmented: Vec::new(),
+            peaks: None,
+            metadata: BTreeMap::new(),
+            stale: false,
+        });
+        save_sec += save_start.elapsed().as_secs_f64();
+        Ok(())
+    };
+
+    let (sample_rate, timeline) =
+        decode_input_streaming(input_file, deadline, |samples, sample_rate| {
+            let slicer = match &mut stream {
+                Some(slicer) => slicer,
+                None => {
+                    let mut slicer = Slicer::new(SlicerConfig {
+                        sample_rate,
+                        ..processing_config.config.clone()
+                    })?;
+                    // 连续有声超过最大时长或最大延迟时不等停顿直接输出，限制缓冲的样本数
+                    let max_duration_ms = processing_config.boundaries.max_duration_ms;
+                    let max_latency_ms = [
+                        (max_duration_ms > 0).then_some(max_duration_ms),
+                        processing_config.max_latency_ms,
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .min();
+                    if let Some(ms) = max_latency_ms {
+                        slicer = slicer.with_max_latency_ms(ms);
+                    }
+                    stream.insert(slicer)
+                }
+            };
+            audio_samples += samples.len();
+            let slice_start = Instant::now();
+            let chunks = slicer.feed(samples);
+            slice_sec += slice_start.elapsed().as_secs_f64();
+            for chunk in chunks {
+                save(chunk, slicer, sample_rate, stats)?;
+            }
+            Ok(())
+        })?;
+    if let Some(mut slicer) = stream {
+        let slice_start = Instant::now();
+        let chunks = slicer.finish();
+        slice_sec += slice_start.elapsed().as_secs_f64();
+        for chunk in chunks {
+            save(chunk, &slicer, sample_rate, stats)?;
+        }
+    }
+
+    let timeline = timeline.filter(|timeline| !timeline.is_identity());
+    if let Some(timeline) = &timeline {
+        for record in &mut slices {
+            record.container_start_ts = Some(timeline.timestamp(record.start_sample));
+            record.container_end_ts = Some(timeline.timestamp(record.end_sample));
+        }
+    }
+    let audio_duration = audio_samples as f64 / sample_rate as f64;
+    let total_sec = start_time.elapsed().as_secs_f64();
+    let load_sec = (total_sec - slice_sec - save_sec).max(0.0);
+    let output_sample_rate = processing_config.unify_sr.unwrap_or(sample_rate);
+    let saved_count = slices.len();
+    let manifest = FileManifest {
+        schema_version: MANIFEST_SCHEMA_VERSION,
+        source,
+        sample_rate,
+        output_sample_rate,
+        time_base: timeline.as_ref().map(|timeline| timeline.time_base),
+        params_hash,
+        content_hash: None,
+        silence_threshold: processing_config.silence_filter.threshold,
+        silence_threshold_db: processing_config.silence_filter.threshold_db(),
+        noise_profile: None,
+        peaks: None,
+        bit_exact: false,
+        shard_size: processing_config.shard_output,
+        padding_ms: None,
+        resampler: (output_sample_rate != sample_rate).then(|| processing_config.resampler.name()),
+        threshold_db: None,
+        timings: Some(StageTimings {
+            load_sec,
+            slice_sec,
+            merge_sec: 0.0,
+            save_sec,
+            total_sec,
+            rtf: calculate_rtf(audio_duration, total_sec),
+        }),
+        slices,
+    };
+    {
+        // 与 refilter / verify / migrate 的读取-修改-写回互斥，见 process_single_file_threaded
+        let _lock = IndexLock::acquire(output_base)?;
+        write_manifest(&output_file_dir.join(MANIFEST_FILE_NAME), &manifest)?;
+    }
+
+    stats.total_audio_duration += audio_duration;
+    stats.total_load_time += load_sec;
+    stats.total_slice_time += slice_sec;
+    stats.total_save_time += save_sec;
+    stats.total_slices_saved += saved_count;
+    let file_processing_time = start_time.elapsed().as_secs_f64();
+    stats.total_processing_time += file_processing_time;
+    stats.processed_files += 1;
+    progress_bar.set_message(format!(
+        "完成: {} ({}个切片, RTF: {:.3}x)",
+        input_file.file_name().unwrap().to_string_lossy(),
+        saved_count,
+        calculate_rtf(audio_duration, file_processing_time)
+    ));
+    Ok(())
+}
+
+/// `--isolate` 中子进程超过 `--per-file-timeout` 多久后由主进程终止 (子进程自身的超时检查优先)
+const ISOLATE_KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// 运行子进程并收集输出；超过 `timeout` 时终止子进程并返回 `Ok(None)`
+fn run_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<std::process::Output>> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // 在后台读取输出，避免管道写满使子进程阻塞
+    let read = |pipe: Option<Box<dyn std::io::Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
+            }
+            buffer
+        })
+    };
+    let stdout = read(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = read(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            child.kill()?;
+            child.wait()?;
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    Ok(Some(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+/// 在子进程中处理单个文件，子进程崩溃或超时 (超时后终止) 时记为该文件失败
+fn process_single_file_isolated(
+    input_file: &Path,
+    output_file_dir: &Path,
+    timeout: Option<Duration>,
+    progress_bar: &ProgressBar,
+) -> FileProcessResult {
+    let mut result = FileProcessResult {
+        file_path: input_file.to_path_buf(),
+        stats: PerformanceStats::default(),
+        success: false,
+        error: None,
+        skipped: None,
+        warnings: Vec::new(),
+        scan_info: None,
+    };
+
+    progress_bar.set_message(format!(
+        "处理: {}",
+        input_file.file_name().unwrap().to_string_lossy()
+    ));
+
+    // 子进程沿用本次 slice 命令的全部参数
+    let child_output = std::env::current_exe().and_then(|exe| {
+        run_with_timeout(
+            Command::new(exe)
+                .arg("worker")
+                .args(std::env::args_os().skip(2))
+                .arg("--worker-file")
+                .arg(input_file)
+                .arg("--worker-output-dir")
+                .arg(output_file_dir)
+                .stdin(Stdio::null()),
+            timeout.map(|timeout| timeout + ISOLATE_KILL_GRACE),
+        )
+    });
+
+    let worker_output = match child_output {
+        Ok(None) => Err(format!(
+            "处理超时 ({}s)，已终止子进程",
+            timeout.unwrap_or_default().as_secs_f64()
+        )),
+        Ok(Some(output)) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .last()
+            .and_then(|line| serde_json::from_str::<WorkerOutput>(line).ok())
+            .ok_or_else(|| {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                format!(
+                    "子进程异常退出 ({}): {}",
+                    output.status,
+                    stderr.lines().last().unwrap_or("").trim()
+                )
+            }),
+        Err(e) => Err(format!("无法启动子进程: {e}")),
+    };
+
+    match worker_output {
+        Ok(WorkerOutput {
+            stats,
+            error,
+            warnings,
+        }) => {
+            result.stats = stats;
+            result.warnings = warnings;
+            result.success = error.is_none();
+            result.error = error;
+        }
+        Err(e) => result.error = Some(e),
+    }
+
+    progress_bar.set_message(format!(
+        "{}: {}",
+        if result.success { "完成" } else { "失败" },
+        input_file.file_name().unwrap().to_string_lossy()
+    ));
+    progress_bar.inc(1);
+    result
+}
+
+/// `worker` 子命令: 处理单个文件并将结果以 JSON 写到标准输出
+fn process_worker_command(args: SliceArgs, file: PathBuf, output_dir: PathBuf) -> Result<()> {
+    if args.safe {
+        enable_safe_mode();
+    }
+    let mut processing_config = ProcessingConfig::from_args(&args);
+    // 主进程已检查过模型并给出提示，这里只需同样回退；须在开始处理之前检查 (见 `check_model`)
+    if processing_config
+        .vad
+        .as_ref()
+        .is_some_and(|vad| check_model(vad).is_err())
+    {
+        processing_config.vad = None;
+    }
+    let result = process_single_file_threaded(
+        &file,
+        args.scratch_dir.as_deref().unwrap_or(&args.output),
+        &output_dir,
+        &processing_config,
+        None,
+        &ProgressBar::hidden(),
+    );
+    println!(
+        "{}",
+        serde_json::to_string(&WorkerOutput {
+            stats: result.stats,
+            error: result.error,
+            warnings: result.warnings,
+        })?
+    );
+    Ok(())
+}
+
+fn process_slice_command(args: SliceArgs) -> Result<()> {
+    let notify_cmd = args.notify_cmd.clone();
+    let output = args.output.clone();
+    let sink: Box<dyn ProgressSink> = match args.events {
+        Some(EventFormat::Jsonl) => {
+            HUMAN_OUTPUT_TO_STDERR.store(true, Ordering::Relaxed);
+            Box::new(JsonLinesSink)
+        }
+        None => Box::new(NullSink),
+    };
+    let outcome = run_slice_command(args, sink.as_ref());
+
+    if let Some(command) = notify_cmd {
+        match run_notify_cmd(&command, &output, outcome.as_ref()) {
+            Ok(status) if !status.success() => say!("⚠️ 通知命令退出状态: {status}"),
+            Err(e) => say!("⚠️ 通知命令执行失败: {e}"),
+            Ok(_) => {}
+        }
+    }
+
+    outcome.map(|_| ())
+}
+
+/// 执行批量切片，进度与结果事件发送到 `sink`，返回运行汇总
+fn run_slice_command(args: SliceArgs, sink: &dyn ProgressSink) -> Result<RunSummary> {
+    let mut processing_config = ProcessingConfig::from_args(&args);
+    if args.sample_storage == SampleStorage::I16
+        && (args.channel_map.is_some()
+            || args.channels != OutputChannels::Mono
+            || args.detect_on != DetectOn::Mix
+            || matches!(args.karaoke, KaraokeMode::Left | KaraokeMode::Right))
+    {
+        return Err(anyhow::anyhow!(
+            "--sample-storage i16 在解码时即混音为单声道，不能与 --channel-map、--channels、--detect-on、--karaoke left/right 同时使用"
+        ));
+    }
+    if args.channels != OutputChannels::Mono
+        && args
+            .channel_map
+            .as_ref()
+            .is_some_and(|map| map.output.is_some())
+    {
+        return Err(anyhow::anyhow!(
+            "--channels 不能与 --channel-map 的 output 同时使用"
+        ));
+    }
+    if args
+        .normalize_peak
+        .is_some_and(|target| !(target > 0.0 && target <= 1.0))
+    {
+        return Err(anyhow::anyhow!("--normalize-peak 应在 (0, 1] 之间"));
+    }
+    if args.fade_ms.is_some_and(|ms| ms <= 0.0 || !ms.is_finite()) {
+        return Err(anyhow::anyhow!("--fade-ms 应大于 0"));
+    }
+    if args
+        .precheck_secs
+        .is_some_and(|secs| secs <= 0.0 || !secs.is_finite())
+    {
+        return Err(anyhow::anyhow!("--precheck-secs 应大于 0"));
+    }
+    if args.mode == SliceMode::Fixed && args.overlap_ms >= args.window_ms {
+        return Err(anyhow::anyhow!("--overlap-ms 应小于 --window-ms"));
+    }
+    if args.detector != Detector::Rms && (args.loud_events || args.mode == SliceMode::Fixed) {
+        return Err(anyhow::anyhow!(
+            "--detector vad/spectral 不能与 --loud-events、--mode fixed 同时使用"
+        ));
+    }
+    if args.threshold == ThresholdMode::Auto && (args.loud_events || args.mode == SliceMode::Fixed)
+    {
+        return Err(anyhow::anyhow!(
+            "--threshold auto 不能与 --loud-events、--mode fixed 同时使用"
+        ));
+    }
+    if args.threshold_offset_db <= 0.0 || !args.threshold_offset_db.is_finite() {
+        return Err(anyhow::anyhow!("--threshold-offset-db 应大于 0"));
+    }
+    if args.spectral_margin_db <= 0.0 || !args.spectral_margin_db.is_finite() {
+        return Err(anyhow::anyhow!("--spectral-margin-db 应大于 0"));
+    }
+    if !(args.vad_threshold > 0.0 && args.vad_threshold < 1.0) {
+        return Err(anyhow::anyhow!("--vad-threshold 应在 (0, 1) 之间"));
+    }
+    if args.normalize_corpus.is_some()
+        && matches!(args.output_format, FileFormat::Mp3 | FileFormat::Opus)
+    {
+        return Err(anyhow::anyhow!(
+            "--normalize-corpus 需要重新写出切片，只支持无损输出格式 (wav、flac)"
+        ));
+    }
+
+    let warnings = lint_params(&args);
+    if !warnings.is_empty() {
+        say!("⚠️ 参数检查发现 {} 个问题:", warnings.len());
+        for warning in &warnings {
+            say!("   - {}", warning.message);
+            say!("     建议: {}", warning.suggestion);
+        }
+        if args.strict {
+            return Err(anyhow::anyhow!("参数检查未通过 (--strict)"));
+        }
+    }
+    check_encoder(args.output_format, args.bitrate)?;
+
+    let (max_duration_ms, max_duration_mode) = args.max_duration();
+    let SliceArgs {
+        input,
+        output,
+        threads,
+        threshold_db,
+        metric,
+        detector,
+        vad_model,
+        vad_threshold,
+        spectral_margin_db,
+        threshold,
+        threshold_offset_db,
+        peak_threshold_db,
+        coarse_hop_factor,
+        refine_cuts,
+        nan_policy,
+        sample_storage,
+        detect_on,
+        channel_map,
+        phase_check,
+        channels,
+        karaoke,
+        phase_cancel_db,
+        min_length_ms,
+        min_interval_ms,
+        hop_size_ms,
+        max_silence_ms,
+        loud_events,
+        mode,
+        window_ms,
+        event_threshold_db,
+        event_min_ms,
+        event_context_ms,
+        enable_merge,
+        max_merge_duration_ms,
+        merge_mode,
+        sentence_pause_ms,
+        target_duration_ms,
+        onset_backoff_ms,
+        min_gap_ms,
+        max_duration_ms: _,
+        max_duration_mode: _,
+        max_length_ms: _,
+        relax_length,
+        relax_step_db,
+        relax_max_iterations,
+        overlap_ms,
+        no_zero_cross,
+        unify_sr,
+        resampler,
+        resampler_backend,
+        chain,
+        output_format,
+        bitrate,
+        wav_format,
+        clip_mode,
+        dither,
+        true_peak_limit_db,
+        normalize_lufs,
+        normalize_peak,
+        fade_ms,
+        fade_shape,
+        pad_silence_ms,
+        pad_mode,
+        min_output_ms,
+        short_output,
+        replaygain,
+        provenance_tags,
+        silence_threshold,
+        min_audio_ratio,
+        ratio_mode,
+        augment,
+        max_rt60,
+        max_applause_score,
+        export_noise_profile,
+        isolate,
+        per_file_timeout,
+        stream,
+        max_latency_ms,
+        export_mask,
+        export_labels,
+        auto_labels,
+        labels_only,
+        dry_run,
+        export_peaks,
+        peaks_samples_per_pixel,
+        trace_detection,
+        viz,
+        audit_sample,
+        audit_seed,
+        catalog,
+        run_manifest,
+        label_studio,
+        label_studio_audio_prefix,
+        scan_output,
+        normalize_corpus,
+        normalize_target_lufs,
+        dedup_content,
+        precheck_secs,
+        precheck_skip,
+        only_failed,
+        only_new,
+        preserve_tree_metadata: preserve_metadata,
+        scratch_dir,
+        announce,
+        locale,
+        duration_format,
+        on_locked,
+        safe,
+        layout,
+        read_archives,
+        shard_output,
+        collision_naming,
+        config,
+        ..
+    } = args;
+    let numbers = NumberFormat::new(locale, duration_format);
+    let estimate_reverb = processing_config.estimate_reverb;
+    let classify_applause = processing_config.classify_applause;
     let total_start_time = Instant::now();
 
+    // 检查模型时可能首次加载 ONNX Runtime (会临时替换全局 panic hook)，须在创建线程池之前
+    let vad_unavailable = (detector == Detector::Vad).then(|| match &processing_config.vad {
+        _ if !cfg!(feature = "vad") => Some(vad::UNSUPPORTED.to_string()),
+        None => Some("未指定 --vad-model".to_string()),
+        Some(vad) => check_model(vad).err().map(|e| e.to_string()),
+    });
+
     // 设置线程池
     let thread_count = threads.unwrap_or_else(num_cpus::get);
-    rayon::ThreadPoolBuilder::new()
+    let thread_pool = rayon::ThreadPoolBuilder::new()
         .num_threads(thread_count)
-        .build_global()
-        .unwrap();
-
-    println!("🎵 音频切片处理器启动");
-    println!("🧵 使用 {thread_count} 个线程进行并行处理");
+        .build()?;
 
-    // 收集音频文件
-    let collect_start = Instant::now();
-    let audio_files = collect_audio_files(&input)?;
-    let collect_duration = collect_start.elapsed().as_secs_f64();
-
-    println!("📂 文件扫描完成:");
-    println!("   - 输入路径: {}", input.display());
-    println!("   - 找到音频文件: {}个", audio_files.len());
-    println!("   - 扫描用时: {}", format_duration(collect_duration));
+    say!("🎵 音频切片处理器启动");
+    say!("🧵 使用 {thread_count} 个线程进行并行处理");
 
     // 显示配置
-    println!("\n⚙️  切片器配置:");
-    println!("   - 静音阈值: {threshold_db}dB");
-    println!("   - 最小片段长度: {min_length_ms}ms");
-    println!("   - 最小间隔: {min_interval_ms}ms");
-    println!("   - 跳跃大小: {hop_size_ms}ms");
-    println!("   - 最大静音长度: {max_silence_ms}ms");
-    println!(
+    say!("⚙️  切片器配置:");
+    if let Some(config) = &config {
+        say!("   - 配置文件: {}", config.display());
+    }
+    match threshold {
+        ThresholdMode::Fixed => say!("   - 静音阈值: {threshold_db}dB"),
+        ThresholdMode::Auto => say!(
+            "   - 静音阈值: 自动 (每个文件的噪声底 + {threshold_offset_db}dB，限制在 {AUTO_THRESHOLD_MIN_DB} ~ {AUTO_THRESHOLD_MAX_DB}dB)"
+        ),
+    }
+    say!("   - 检测指标: {metric:?}");
+    if metric != DetectionMetric::Rms {
+        say!("   - 峰值阈值: {peak_threshold_db}dB");
+    }
+    say!("   - 最小片段长度: {min_length_ms}ms");
+    say!("   - 最小间隔: {min_interval_ms}ms");
+    say!("   - 跳跃大小: {hop_size_ms}ms");
+    if coarse_hop_factor > 1 {
+        say!("   - 粗扫描步长: {coarse_hop_factor}x hop");
+    }
+    if refine_cuts {
+        say!("   - 切点细化: 移到静音段中能量最低的帧");
+    }
+    say!("   - NaN/inf 处理策略: {nan_policy:?}");
+    if sample_storage == SampleStorage::I16 {
+        say!("   - 样本存储: 16 位整数单声道");
+    }
+    if let Some(channel_map) = &channel_map {
+        say!("   - 声道映射: {channel_map}");
+    }
+    if channels != OutputChannels::Mono {
+        say!("   - 输出声道: {channels:?}");
+    }
+    if karaoke != KaraokeMode::Auto {
+        say!("   - 卡拉OK人声声道: {karaoke:?}");
+    }
+    // 声道映射指定检测声道时 --detect-on 与相位检查不起作用
+    if channel_map.as_ref().is_none_or(|map| map.detect.is_none()) {
+        if detect_on != DetectOn::Mix {
+            say!("   - 检测信号: {detect_on}");
+        } else if phase_check != PhaseCheck::Warn {
+            say!("   - 相位抵消检查: {phase_check:?} (阈值 {phase_cancel_db}dB)");
+        }
+    }
+    say!("   - 最大静音长度: {max_silence_ms}ms");
+    if mode == SliceMode::Fixed {
+        say!("   - 切片方式: 固定窗口 {window_ms}ms，重叠 {overlap_ms}ms");
+    }
+    if detector == Detector::Spectral {
+        say!("   - 检测方式: 频谱门限 (高出频带噪声底 {spectral_margin_db}dB 为有声)");
+    }
+    if let Some(unavailable) = vad_unavailable {
+        match (unavailable, &vad_model) {
+            (None, Some(model)) => say!(
+                "   - 检测方式: 语音活动检测 (模型 {}，概率阈值 {vad_threshold})",
+                model.display()
+            ),
+            (reason, _) => {
+                let reason = reason.unwrap_or_default();
+                say!("   ⚠️ 语音活动检测不可用 ({reason})，回退到能量阈值检测");
+                processing_config.vad = None;
+            }
+        }
+    }
+    if loud_events {
+        say!(
+            "   - 响亮事件模式: 阈值 {event_threshold_db}dB, 最短 {event_min_ms}ms, 上下文 {event_context_ms}ms"
+        );
+    }
+    say!(
         "   - 切片合并: {}",
         if enable_merge { "启用" } else { "禁用" }
     );
     if enable_merge {
-        println!("   - 最大合并时长: {max_merge_duration_ms}ms");
+        say!("   - 最大合并时长: {max_merge_duration_ms}ms");
+        say!("   - 合并策略: {merge_mode:?}");
+        if merge_mode == MergeMode::Sentence {
+            say!("   - 句间停顿长度: {sentence_pause_ms}ms");
+        }
+        if matches!(merge_mode, MergeMode::Target | MergeMode::TargetOptimal) {
+            say!("   - 目标切片时长: {target_duration_ms}ms");
+        }
+    }
+    if onset_backoff_ms > 0 {
+        say!("   - 起点回退上限: {onset_backoff_ms}ms");
+    }
+    if min_gap_ms > 0 {
+        say!("   - 最小切片间隔: {min_gap_ms}ms");
+    }
+    if relax_length > 0 {
+        say!(
+            "   - 超长片段放宽检测: >{relax_length}ms 时阈值每次提高 {relax_step_db}dB，最多 {relax_max_iterations} 次"
+        );
     }
     if max_duration_ms > 0 {
-        println!("   - 最大切片时长: {max_duration_ms}ms (硬切)");
+        match max_duration_mode {
+            MaxDurationMode::Hard => say!("   - 最大切片时长: {max_duration_ms}ms (硬切)"),
+            MaxDurationMode::Phrase => {
+                say!("   - 最大切片时长: {max_duration_ms}ms (在乐句边界断开)")
+            }
+            MaxDurationMode::Quietest => {
+                say!("   - 最大切片时长: {max_duration_ms}ms (在能量最低处断开)")
+            }
+        }
+    }
+    if overlap_ms > 0 {
+        say!("   - 切片重叠: {overlap_ms}ms");
+    }
+    if no_zero_cross || stream {
+        say!("   - 过零点对齐: 关闭");
+    }
+    match unify_sr {
+        Some(rate) => say!(
+            "   - 输出采样率: {rate}Hz (统一重采样，{})",
+            Resampler {
+                backend: resampler_backend,
+                quality: resampler,
+            }
+            .name()
+        ),
+        None => say!("   - 输出采样率: 保持源文件采样率"),
+    }
+    if let Some(chain) = chain.as_ref().filter(|chain| !chain.is_empty()) {
+        say!("   - 处理链: {chain}");
+    }
+    match output_format {
+        FileFormat::Wav => say!("   - 输出格式: {wav_format:?}"),
+        FileFormat::Flac => say!(
+            "   - 输出格式: FLAC ({} 位)",
+            if wav_format == WavFormat::I16 { 16 } else { 24 }
+        ),
+        FileFormat::Mp3 | FileFormat::Opus => {
+            say!("   - 输出格式: {output_format:?} ({bitrate}bps)")
+        }
+    }
+    if wav_format != WavFormat::F32 || output_format != FileFormat::Wav {
+        say!("   - 削波方式: {clip_mode:?}");
+        say!("   - 抖动: {dither:?}");
+    }
+    if let Some(target) = normalize_lufs {
+        say!("   - 响度归一化: {target} LUFS");
+    }
+    if let Some(target) = normalize_peak {
+        say!("   - 峰值归一化: {target}");
+    }
+    if let Some(fade_ms) = fade_ms {
+        say!("   - 首尾淡入淡出: {fade_ms}ms ({fade_shape:?})");
+    }
+    if let Some(pad_ms) = pad_silence_ms.filter(|&ms| ms > 0) {
+        say!("   - 首尾补白: {pad_ms}ms ({pad_mode:?})");
+    }
+    if min_output_ms > 0 {
+        say!("   - 最短输出时长: {min_output_ms}ms ({short_output:?})");
+    }
+    if let Some(ceiling_db) = true_peak_limit_db {
+        say!("   - 真峰值限幅: {ceiling_db}dBTP");
+    }
+    if replaygain {
+        say!("   - ReplayGain 标签: 启用");
+    }
+    if provenance_tags {
+        say!(
+            "   - 溯源标签: 启用 (参数指纹 {})",
+            processing_config.fingerprint()
+        );
+    }
+    let silence_filter = processing_config.silence_filter;
+    say!(
+        "   - 静音检测阈值: {:.1}dBFS (线性 {:.6})",
+        silence_filter.threshold_db(),
+        silence_filter.threshold
+    );
+    if silence_threshold.is_some() {
+        say!("   ⚠️ --silence-threshold 已弃用，请改用 --silence-threshold-db");
+    }
+    say!(
+        "   - 最小有效音频占比: {} (按{})",
+        numbers.percent(min_audio_ratio as f64),
+        match ratio_mode {
+            RatioMode::Voiced => "检测有声帧",
+            RatioMode::Samples => "样本",
+            RatioMode::Frames => "20ms 帧",
+        }
+    );
+    if let Some(spec) = &augment {
+        say!("   - 变速增强: {:?}", spec.speeds);
+    }
+    if estimate_reverb {
+        say!("   - 混响估计: 启用");
+    }
+    if let Some(max_rt60) = max_rt60 {
+        say!("   - 最大 RT60: {max_rt60}s");
+    }
+    if classify_applause {
+        say!("   - 掌声/笑声分类: 启用");
+    }
+    if let Some(max_score) = max_applause_score {
+        say!("   - 最大掌声/笑声可能性: {max_score}");
+    }
+    if export_noise_profile {
+        say!("   - 导出噪声样本: 启用");
+    }
+    if export_mask {
+        say!("   - 导出帧级检测结果: 启用");
+    }
+    if dry_run {
+        say!("   - 试运行: 不写入任何文件");
+    }
+    if labels_only {
+        say!("   - 只导出 Audacity 标签轨: 不写出切片音频");
+    } else if export_labels {
+        say!("   - 导出 Audacity 标签轨: 启用");
+    }
+    if auto_labels {
+        say!("   - 自动标签: 启用");
+    }
+    if export_peaks {
+        say!("   - 导出波形峰值: 每 {peaks_samples_per_pixel} 个样本一对");
+    }
+    if trace_detection {
+        say!("   - 检测跟踪日志: 启用");
+    }
+    if let Some(format) = viz {
+        say!("   - 导出时间线图: {}", format.file_name());
+    }
+    if let Some(secs) = per_file_timeout {
+        say!("   - 单文件超时: {secs}s");
+    }
+    if stream {
+        say!("   - 流式处理: 启用");
+    }
+    if let Some(ms) = max_latency_ms {
+        say!("   - 最大输出延迟: {ms}ms");
+    }
+    if let Some(secs) = precheck_secs {
+        let action = if precheck_skip { "跳过" } else { "警告" };
+        say!("   - 输入预检: 开头 {secs}s，发现数字静音/无信号声道时{action}");
+    }
+    if safe {
+        enable_safe_mode();
+        if !dry_run {
+            // 运行级输出在所有文件处理完后才写出，提前检查以免处理结束才报错
+            let mut targets = vec![
+                output.join(PER_FILE_STATS_FILE_NAME),
+                output.join(ERRORS_FILE_NAME),
+            ];
+            if !labels_only {
+                targets.push(output.join(DATASET_CARD_FILE_NAME));
+            }
+            if audit_sample.is_some() {
+                targets.push(
+                    output
+                        .join(audit::AUDIT_DIR_NAME)
+                        .join(audit::AUDIT_CSV_FILE_NAME),
+                );
+            }
+            targets.extend(
+                [&run_manifest, &scan_output, &label_studio]
+                    .into_iter()
+                    .flatten()
+                    .cloned(),
+            );
+            for target in &targets {
+                check_overwrite(target)?;
+            }
+        }
+        say!("   - 安全模式: 只创建新文件，拒绝覆盖或删除已有文件");
     }
-    println!("   - 静音检测阈值: {silence_threshold}");
-    println!("   - 最小有效音频占比: {:.1}%", min_audio_ratio * 100.0);
 
-    let config = SlicerConfig {
-        sample_rate: 44100, // 临时值，会在处理时更新
-        threshold_db,
-        min_length_ms,
-        min_interval_ms,
-        hop_size_ms,
-        max_silence_ms,
+    if isolate {
+        say!("   - 子进程隔离: 启用");
+    }
+    if let Some(count) = audit_sample {
+        say!("   - 随机抽检: {count} 个切片 (种子 {audit_seed})");
+    }
+    if let Some(path) = &catalog {
+        say!("   - 目录数据库: {}", path.display());
+    }
+    if let Some(path) = &run_manifest {
+        say!("   - 运行级清单: {}", path.display());
+    }
+    if let Some(path) = &label_studio {
+        say!("   - Label Studio 预标注: {}", path.display());
+    }
+    if let Some(path) = &scan_output {
+        say!("   - 源文件元数据目录: {}", path.display());
+    }
+    if preserve_metadata {
+        say!("   - 保留目录时间戳与权限: 启用");
+    }
+    if layout == Layout::Flat {
+        say!("   - 输出布局: 平铺 (文件名冲突时按 {collision_naming:?} 消歧)");
+    }
+    if read_archives {
+        say!("   - 读取压缩包: 启用");
+    }
+    if let Some(size) = shard_output {
+        say!("   - 分片子目录: 每个 {size} 个切片");
+    }
+    if let Some(dir) = &scratch_dir {
+        if dir == &output {
+            return Err(anyhow::anyhow!("暂存目录不能与输出目录相同"));
+        }
+        std::fs::create_dir_all(dir)?;
+        say!("   - 暂存目录: {} (后台移动到输出目录)", dir.display());
+    }
+    let announcer = match &announce {
+        Some(path) => {
+            say!("   - 切片通告: {}", path.display());
+            Some(Arc::new(Announcer::connect(path)?))
+        }
+        None => None,
+    };
+    // 单写入者保护，运行结束 (函数返回) 时释放
+    let _run_lock = if dry_run {
+        None
+    } else {
+        Some(RunLock::acquire(&output, on_locked, |holder| {
+            say!("⏳ 输出目录正被另一运行使用{holder}，等待其结束...")
+        })?)
+    };
+    // 处理线程写入的位置: 指定暂存目录时先写到暂存目录
+    let write_base = scratch_dir.as_deref().unwrap_or(&output);
+    // 指定暂存目录时，切片在移动到输出目录后才通告
+    let mover = scratch_dir.as_deref().map(|dir| {
+        let announcer = announcer.clone();
+        Mover::spawn(dir, move |target| {
+            if let Some(announcer) = &announcer {
+                let _ = announcer.announce_dir(target);
+            }
+        })
+    });
+    let content_index = if dedup_content {
+        let known = match &catalog {
+            Some(path) => Catalog::open(path)?.content_hashes()?,
+            None => HashMap::new(),
+        };
+        say!(
+            "   - 按内容去重: 启用 (目录数据库中已有 {} 个源文件)",
+            known.len()
+        );
+        Some(ContentIndex::new(known))
+    } else {
+        None
+    };
+    let selection = if only_failed.is_some() || only_new {
+        let failed = match &only_failed {
+            Some(path) => {
+                let failed = read_failed(path)?;
+                say!(
+                    "   - 只处理失败文件: {} 个 (来自 {})",
+                    failed.len(),
+                    path.display()
+                );
+                Some(failed)
+            }
+            None => None,
+        };
+        let only_new = if only_new {
+            match &catalog {
+                Some(path) => {
+                    say!("   - 只处理新文件: 按目录数据库判断");
+                    Some(Some(
+                        Catalog::open(path)?.source_paths()?.into_iter().collect(),
+                    ))
+                }
+                None => {
+                    say!("   - 只处理新文件: 按输出目录中的切片清单判断");
+                    Some(None)
+                }
+            }
+        } else {
+            None
+        };
+        Some(FileSelection { failed, only_new })
+    } else {
+        None
     };
 
-    // 创建多进度条管理器
+    // 创建多进度条管理器: 扫描进度 + 总体处理进度 (总数随扫描增长)
     let multi_progress = MultiProgress::new();
-    let overall_progress = multi_progress.add(ProgressBar::new(audio_files.len() as u64));
+    let scan_progress = multi_progress.add(ProgressBar::new_spinner());
+    scan_progress.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.yellow} [{elapsed_precise}] {msg}")
+            .unwrap(),
+    );
+    scan_progress.enable_steady_tick(Duration::from_millis(100));
+    scan_progress.set_message("扫描输入目录...");
+    let overall_progress = multi_progress.add(ProgressBar::new(0));
     overall_progress.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({percent}%) {msg}")
@@ -428,8 +3351,12 @@ fn process_slice_command(
             .progress_chars("#>-")
     );
 
-    println!("\n🔄 开始并行处理...\n");
-    overall_progress.set_message("准备开始并行处理...");
+    if layout == Layout::Flat {
+        say!("\n🔄 开始扫描并并行处理 (平铺布局: 扫描完成、确定输出名称后开始处理)...\n");
+    } else {
+        say!("\n🔄 开始扫描并并行处理 (边扫描边处理)...\n");
+    }
+    overall_progress.set_message("等待扫描结果...");
 
     // 处理每个文件 (并行)
     let input_base = if input.is_file() {
@@ -439,41 +3366,301 @@ fn process_slice_command(
     };
 
     let processing_start = Instant::now();
-    let results: Vec<FileProcessResult> = audio_files
-        .par_iter()
-        .map(|audio_file| {
-            process_single_file_threaded(
-                audio_file,
-                input_base,
-                &output,
-                &ProcessingConfig {
-                    config: config.clone(),
-                    silence_threshold,
-                    min_audio_ratio,
-                    enable_merge,
-                    max_merge_duration_ms,
-                    max_duration_ms,
+    let (file_sender, file_receiver) = std::sync::mpsc::channel::<PathBuf>();
+    let excluded = AtomicUsize::new(0);
+    // 平铺布局需要所有文件名才能确定冲突，扫描结束后才设置；文件在设置之后才进入处理队列
+    let names = OnceLock::new();
+    if layout == Layout::Mirror {
+        let _ = names.set(OutputNames::mirror(input_base));
+    }
+    let pending = std::sync::Mutex::new(Vec::new());
+    let (scan_result, scan_duration, mut results) = std::thread::scope(|scope| {
+        // 扫描在独立线程 (及其自己的线程池) 中进行，发现的文件立即送入处理队列
+        let scanner = scope.spawn(|| {
+            // 移入线程，扫描结束时释放发送端以结束处理队列
+            let file_sender = file_sender;
+            let admit = |path: PathBuf, names: &OutputNames| {
+                if let Some(selection) = &selection {
+                    let accepted = names
+                        .dir(&path, &output)
+                        .is_ok_and(|dir| selection.accepts(&path, &dir));
+                    if !accepted {
+                        excluded.fetch_add(1, Ordering::Relaxed);
+                        return;
+                    }
+                }
+                overall_progress.inc_length(1);
+                let _ = file_sender.send(path);
+            };
+            let scan_start = Instant::now();
+            let result = scan_audio_files(
+                &input,
+                thread_count,
+                read_archives,
+                &|path| match names.get() {
+                    Some(names) => admit(path, names),
+                    None => pending.lock().unwrap().push(path),
                 },
-                &overall_progress,
-            )
-        })
-        .collect();
+                &|dirs, files| {
+                    scan_progress.set_message(format!(
+                        "扫描中: 已遍历 {dirs} 个目录，发现 {files} 个音频文件"
+                    ));
+                },
+            );
+            let scan_duration = scan_start.elapsed().as_secs_f64();
+            if names.get().is_none() {
+                let mut files = std::mem::take(&mut *pending.lock().unwrap());
+                files.sort();
+                let flat =
+                    names.get_or_init(|| OutputNames::flat(input_base, collision_naming, &files));
+                for path in files {
+                    admit(path, flat);
+                }
+            }
+            match &result {
+                Ok(stats) => {
+                    scan_progress.finish_with_message(format!(
+                        "扫描完成: {} 个目录，{} 个音频文件，用时 {}",
+                        numbers.count(stats.dirs),
+                        numbers.count(stats.files),
+                        numbers.duration(scan_duration)
+                    ));
+                    sink.emit(Event::ScanDone {
+                        input: input.to_string_lossy().into_owned(),
+                        files: stats.files - excluded.load(Ordering::Relaxed),
+                        duration_sec: scan_duration,
+                    });
+                }
+                Err(_) => scan_progress.finish_with_message("扫描失败"),
+            }
+            (result, scan_duration)
+        });
+
+        let params_hash = processing_config.fingerprint();
+        let results: Vec<FileProcessResult> = thread_pool.install(|| {
+            file_receiver
+                .into_iter()
+                .par_bridge()
+                .map(|audio_file| {
+                    let audio_file = &audio_file;
+                    sink.emit(Event::FileStart {
+                        file: audio_file.to_string_lossy().into_owned(),
+                    });
+                    let names = names.get().expect("文件在输出名称确定后才进入处理队列");
+                    let prepared = names.dir(audio_file, &output).and_then(|target| {
+                        let write_dir = names.dir(audio_file, write_base)?;
+                        // append 模式下与其他运行共享输出目录，认领锁在该文件处理完成前一直持有
+                        let claim = if on_locked == OnLocked::Append {
+                            Some(claim_dir(&target)?)
+                        } else {
+                            None
+                        };
+                        // 认领到的目录中已有参数指纹相同的清单: 先认领的运行已处理完成
+                        let done = matches!(claim, Some(Some(_)))
+                            && read_manifest(&target.join(MANIFEST_FILE_NAME))
+                                .is_ok_and(|manifest| manifest.params_hash == params_hash);
+                        Ok((write_dir, target, claim, done))
+                    });
+                    let result = match &prepared {
+                        Err(e) => {
+                            overall_progress.inc(1);
+                            FileProcessResult::unprocessed(audio_file, None, Some(e.to_string()))
+                        }
+                        Ok((_, _, Some(None), _)) => {
+                            overall_progress.inc(1);
+                            let reason = "已由另一运行认领处理".to_string();
+                            FileProcessResult::unprocessed(audio_file, Some(reason), None)
+                        }
+                        Ok((_, _, _, true)) => {
+                            overall_progress.inc(1);
+                            let reason = "已由另一运行处理完成".to_string();
+                            FileProcessResult::unprocessed(audio_file, Some(reason), None)
+                        }
+                        Ok((write_dir, _, _, _)) if isolate => {
+                            let timeout = processing_config
+                                .per_file_timeout
+                                .map(Duration::from_secs_f64);
+                            process_single_file_isolated(
+                                audio_file,
+                                write_dir,
+                                timeout,
+                                &overall_progress,
+                            )
+                        }
+                        Ok((write_dir, _, _, _)) => process_single_file_threaded(
+                            audio_file,
+                            write_base,
+                            write_dir,
+                            &processing_config,
+                            content_index.as_ref(),
+                            &overall_progress,
+                        ),
+                    };
+                    if mover.is_none()
+                        && result.success
+                        && result.skipped.is_none()
+                        && let Some(announcer) = &announcer
+                        && let Ok((_, target, _, false)) = &prepared
+                    {
+                        let _ = announcer.announce_dir(target);
+                    }
+                    if let Some(mover) = &mover
+                        && let Ok((staged, target, claim, false)) = prepared
+                        && staged.is_dir()
+                    {
+                        mover.enqueue(staged, target, claim.flatten());
+                    }
+                    sink.emit(Event::FileDone {
+                        file: audio_file.to_string_lossy().into_owned(),
+                        success: result.success,
+                        error: result.error.clone(),
+                        skipped: result.skipped.clone(),
+                        slices: result.stats.total_slices_saved,
+                        audio_duration_sec: result.stats.total_audio_duration,
+                        saved_duration_sec: result.stats.total_saved_duration,
+                        processing_time_sec: result.stats.total_processing_time,
+                    });
+                    result
+                })
+                .collect()
+        });
+        let (scan_result, scan_duration) = scanner.join().expect("扫描线程异常退出");
+        (scan_result, scan_duration, results)
+    });
 
-    let processing_duration = processing_start.elapsed().as_secs_f64();
     overall_progress.finish_with_message("所有文件处理完成!");
+    if let Some(mover) = mover {
+        say!("\n📦 等待后台移动到输出目录...");
+        let move_start = Instant::now();
+        let report = mover.finish();
+        say!(
+            "   - 已移动: {} 个目录，{} 个文件，{}MB (等待 {})",
+            numbers.count(report.dirs),
+            numbers.count(report.files),
+            numbers.decimal(report.bytes as f64 / 1024.0 / 1024.0, 1),
+            numbers.duration(move_start.elapsed().as_secs_f64())
+        );
+        if !report.errors.is_empty() {
+            for error in &report.errors {
+                say!("   ❌ 移动失败: {error}");
+            }
+            return Err(anyhow::anyhow!(
+                "{} 个目录未能移动到输出目录，文件保留在暂存目录中",
+                report.errors.len()
+            ));
+        }
+    }
+    let processing_duration = processing_start.elapsed().as_secs_f64();
+
+    let scan_stats = scan_result?;
+    let names = names
+        .into_inner()
+        .unwrap_or_else(|| OutputNames::mirror(input_base));
+    say!("\n📂 文件扫描完成:");
+    say!("   - 输入路径: {}", input.display());
+    say!("   - 找到音频文件: {}个", numbers.count(scan_stats.files));
+    say!("   - 扫描用时: {}", numbers.duration(scan_duration));
+    let excluded = excluded.into_inner();
+    if excluded > 0 {
+        say!(
+            "   - 按 --only-failed/--only-new 排除: {}个",
+            numbers.count(excluded)
+        );
+    }
+    if !scan_stats.unreadable.is_empty() {
+        say!("   ⚠️ 无法读取的目录: {} 个", scan_stats.unreadable.len());
+        for (dir, error) in &scan_stats.unreadable {
+            say!("      - {}: {}", dir.display(), error);
+        }
+    }
+    if !names.collisions().is_empty() {
+        say!(
+            "   ⚠️ 平铺布局中的文件名冲突: {} 组 (已按 {:?} 消歧)",
+            names.collisions().len(),
+            collision_naming
+        );
+        for collision in names.collisions() {
+            say!("      - {}:", collision.name);
+            for (file, name) in &collision.files {
+                say!("         {} -> {}", file.display(), name);
+            }
+        }
+    }
+    if scan_stats.files == 0 {
+        return Err(anyhow::anyhow!("在输入目录中未找到支持的音频文件"));
+    }
+    // 处理顺序取决于扫描顺序，按路径排序使汇总与抽检结果可复现
+    results.sort_by(|a, b| a.file_path.cmp(&b.file_path));
 
     // 汇总统计结果
     let mut final_stats = PerformanceStats {
-        total_files: audio_files.len(),
+        total_files: results.len(),
         ..Default::default()
     };
     let mut successful_files = 0;
     let mut failed_files = Vec::new();
+    let mut skipped_files = Vec::new();
+    let mut file_manifests = Vec::new();
+    let mut processed_files = Vec::new();
+    let mut file_rows = Vec::new();
+    let mut warned_files = Vec::new();
+    let mut scan_rows = Vec::new();
 
     for result in results {
-        if result.success {
+        let status = if result.skipped.is_some() {
+            FileStatus::Skipped
+        } else if result.success {
+            FileStatus::Ok
+        } else {
+            FileStatus::Failed
+        };
+        file_rows.push(FileStatsRow {
+            path: result.file_path.to_string_lossy().into_owned(),
+            status,
+            message: result
+                .skipped
+                .clone()
+                .or_else(|| result.error.clone())
+                .or_else(|| (!result.warnings.is_empty()).then(|| result.warnings.join("; "))),
+            audio_duration: result.stats.total_audio_duration,
+            slices: result.stats.total_slices_saved,
+            kept_duration: result.stats.total_saved_duration,
+            processing_time: result.stats.total_processing_time,
+            load_time: result.stats.total_load_time,
+            slice_time: result.stats.total_slice_time,
+            merge_time: result.stats.total_merge_time,
+            save_time: result.stats.total_save_time,
+        });
+        for warning in &result.warnings {
+            warned_files.push((result.file_path.clone(), warning.clone()));
+        }
+        match (result.scan_info, &result.error) {
+            (Some(info), _) => scan_rows.push(info),
+            (None, Some(error)) if scan_output.is_some() => {
+                scan_rows.push(FileInfo::failed(&result.file_path, error));
+            }
+            _ => {}
+        }
+        if let Some(reason) = result.skipped {
+            final_stats.total_load_time += result.stats.total_load_time;
+            skipped_files.push((result.file_path, reason));
+        } else if result.success {
             final_stats.add(&result.stats);
             successful_files += 1;
+            if preserve_metadata {
+                let dir = names.dir(&result.file_path, &output)?;
+                processed_files.push((result.file_path.clone(), dir));
+            }
+            if audit_sample.is_some()
+                || normalize_corpus.is_some()
+                || catalog.is_some()
+                || run_manifest.is_some()
+                || label_studio.is_some()
+            {
+                let dir = names.dir(&result.file_path, &output)?;
+                let manifest = read_manifest(&dir.join(MANIFEST_FILE_NAME))?;
+                file_manifests.push((dir, manifest));
+            }
         } else {
             failed_files.push((
                 result.file_path,
@@ -482,12 +3669,50 @@ fn process_slice_command(
         }
     }
 
+    if !warned_files.is_empty() {
+        say!("\n⚠️ 处理警告:");
+        for (file_path, warning) in &warned_files {
+            say!("   - {}: {}", file_path.display(), warning);
+        }
+    }
+
+    // 显示跳过的重复文件
+    if !skipped_files.is_empty() {
+        say!("\n⏭️ 跳过的文件:");
+        for (file_path, reason) in &skipped_files {
+            say!("   - {}: {}", file_path.display(), reason);
+        }
+    }
+
     // 显示失败的文件
     if !failed_files.is_empty() {
-        println!("\n❌ 处理失败的文件:");
+        say!("\n❌ 处理失败的文件:");
         for (file_path, error) in &failed_files {
-            println!("   - {}: {}", file_path.display(), error);
+            say!("   - {}: {}", file_path.display(), error);
+        }
+    }
+
+    if dry_run {
+        say!("\n🧪 试运行结果 (未写入任何文件):");
+        for row in file_rows.iter().filter(|row| row.status == FileStatus::Ok) {
+            say!(
+                "   - {}: {} 个切片, 保留 {} / {}",
+                row.path,
+                numbers.count(row.slices),
+                numbers.duration(row.kept_duration),
+                numbers.duration(row.audio_duration)
+            );
         }
+    } else {
+        // 跳过的文件没有新的结果，保留失败列表中的旧记录
+        let processed: HashSet<String> = file_rows
+            .iter()
+            .filter(|row| row.status != FileStatus::Skipped)
+            .map(|row| row.path.clone())
+            .collect();
+        let _lock = IndexLock::acquire(&output)?;
+        write_errors(&output, &processed, &failed_files)?;
+        write_per_file_stats(&output, &file_rows)?;
     }
 
     // 最终性能统计
@@ -497,124 +3722,362 @@ fn process_slice_command(
         final_stats.total_processing_time,
     );
 
-    println!("\n📊 最终性能统计:");
-    println!(
+    say!("\n📊 最终性能统计:");
+    say!(
         "   - 处理文件: {}/{} 个",
-        successful_files, final_stats.total_files
+        numbers.count(successful_files),
+        numbers.count(final_stats.total_files)
     );
     if !failed_files.is_empty() {
-        println!("   - 失败文件: {} 个", failed_files.len());
+        say!("   - 失败文件: {} 个", numbers.count(failed_files.len()));
     }
-    println!(
+    if !skipped_files.is_empty() {
+        say!("   - 跳过文件: {} 个", numbers.count(skipped_files.len()));
+    }
+    say!(
         "   - 总音频时长: {}",
-        format_duration(final_stats.total_audio_duration)
+        numbers.duration(final_stats.total_audio_duration)
     );
-    println!("   - 有效切片总数: {} 个", final_stats.total_slices_saved);
-    println!(
+    say!(
+        "   - 有效切片总数: {} 个",
+        numbers.count(final_stats.total_slices_saved)
+    );
+    if final_stats.total_non_finite_samples > 0 {
+        say!(
+            "   - NaN/inf 样本: {} 个",
+            numbers.count(final_stats.total_non_finite_samples)
+        );
+    }
+    if final_stats.total_slices_limited > 0 {
+        say!(
+            "   - 触发限幅的切片: {} 个",
+            numbers.count(final_stats.total_slices_limited)
+        );
+    }
+    if final_stats.total_short_outputs > 0 {
+        say!(
+            "   - 短于最短输出时长的切片: {} 个",
+            numbers.count(final_stats.total_short_outputs)
+        );
+    }
+    if final_stats.total_bit_exact_files > 0 {
+        say!(
+            "   - 样本原样复制: {} 个文件 (32 位浮点单声道 WAV)",
+            numbers.count(final_stats.total_bit_exact_files)
+        );
+    }
+    if final_stats.total_augmented_saved > 0 {
+        say!(
+            "   - 增强版本: {} 个",
+            numbers.count(final_stats.total_augmented_saved)
+        );
+    }
+    if final_stats.total_reverb_rejected > 0 {
+        say!(
+            "   - 混响过重丢弃: {} 个",
+            numbers.count(final_stats.total_reverb_rejected)
+        );
+    }
+    if final_stats.total_applause_rejected > 0 {
+        say!(
+            "   - 掌声/笑声丢弃: {} 个",
+            numbers.count(final_stats.total_applause_rejected)
+        );
+    }
+    say!(
         "   - 有效音频时长: {}",
-        format_duration(final_stats.total_saved_duration)
+        numbers.duration(final_stats.total_saved_duration)
     );
-    println!(
-        "   - 音频保留率: {:.1}%",
-        (final_stats.total_saved_duration / final_stats.total_audio_duration) * 100.0
+    say!(
+        "   - 音频保留率: {}",
+        numbers.percent(final_stats.total_saved_duration / final_stats.total_audio_duration)
     );
 
-    println!("\n⏱️  各阶段用时:");
-    println!("   - 文件扫描: {}", format_duration(collect_duration));
-    println!(
+    say!("\n⏱️  各阶段用时:");
+    say!("   - 文件扫描: {}", numbers.duration(scan_duration));
+    say!(
         "   - 音频加载: {}",
-        format_duration(final_stats.total_load_time)
+        numbers.duration(final_stats.total_load_time)
     );
-    println!(
+    say!(
         "   - 切片分析: {}",
-        format_duration(final_stats.total_slice_time)
+        numbers.duration(final_stats.total_slice_time)
     );
-    println!(
+    say!(
         "   - 片段合并: {}",
-        format_duration(final_stats.total_merge_time)
+        numbers.duration(final_stats.total_merge_time)
     );
-    println!(
+    say!(
         "   - 文件保存: {}",
-        format_duration(final_stats.total_save_time)
+        numbers.duration(final_stats.total_save_time)
     );
-    println!(
+    say!(
         "   - 总处理时间: {}",
-        format_duration(final_stats.total_processing_time)
+        numbers.duration(final_stats.total_processing_time)
     );
-    println!(
+    say!(
         "   - 实际并行用时: {}",
-        format_duration(processing_duration)
+        numbers.duration(processing_duration)
     );
-    println!("   - 程序总用时: {}", format_duration(total_duration));
+    say!("   - 程序总用时: {}", numbers.duration(total_duration));
 
-    println!("\n🚀 性能指标:");
-    println!("   - 整体RTF: {overall_rtf:.3}x");
+    say!("\n🚀 性能指标:");
+    say!("   - 整体RTF: {}x", numbers.decimal(overall_rtf, 3));
     if overall_rtf < 1.0 {
-        println!("   - 处理速度比实时播放快 {:.1}倍", 1.0 / overall_rtf);
+        say!(
+            "   - 处理速度比实时播放快 {}倍",
+            numbers.decimal(1.0 / overall_rtf, 1)
+        );
     } else {
-        println!("   - 处理速度比实时播放慢 {overall_rtf:.1}倍");
+        say!(
+            "   - 处理速度比实时播放慢 {}倍",
+            numbers.decimal(overall_rtf, 1)
+        );
     }
     if successful_files > 0 {
-        println!(
+        say!(
             "   - 平均每个文件处理时间: {}",
-            format_duration(final_stats.total_processing_time / successful_files as f64)
+            numbers.duration(final_stats.total_processing_time / successful_files as f64)
         );
     }
 
     // 计算并行加速比
     let theoretical_sequential_time = final_stats.total_processing_time;
     let speedup = theoretical_sequential_time / processing_duration;
-    println!("   - 并行加速比: {speedup:.2}x (使用{thread_count}线程)");
-    println!(
-        "   - 并行效率: {:.1}%",
-        (speedup / thread_count as f64) * 100.0
+    say!(
+        "   - 并行加速比: {}x (使用{thread_count}线程)",
+        numbers.decimal(speedup, 2)
+    );
+    say!(
+        "   - 并行效率: {}",
+        numbers.percent(speedup / thread_count as f64)
     );
 
-    println!("\n💾 输出信息:");
-    println!("   - 输出目录: {}", output.display());
+    say!("\n💾 输出信息:");
+    say!("   - 输出目录: {}", output.display());
+    if announcer
+        .as_ref()
+        .is_some_and(|announcer| announcer.is_closed())
+    {
+        say!("   ⚠️ 切片通告通道已断开，之后完成的切片未通告");
+    }
+    if !dry_run {
+        say!(
+            "   - 逐文件统计: {}",
+            output.join(PER_FILE_STATS_FILE_NAME).display()
+        );
+    }
+    if let Some(scope) = normalize_corpus {
+        let report = normalize_slices(
+            &mut file_manifests,
+            scope,
+            normalize_target_lufs,
+            &processing_config,
+        )?;
+        match report.distribution {
+            Some([p10, median, p90]) => say!(
+                "   - 批量响度归一化: 调整 {} 个切片，其中 {} 个测得响度 (P10/中位数/P90: {p10:.1}/{median:.1}/{p90:.1} LUFS → 目标 {normalize_target_lufs} LUFS)",
+                report.adjusted,
+                report.measured
+            ),
+            None => say!("   - 批量响度归一化: 没有可测量响度的切片，未调整"),
+        }
+        if report.limited > 0 {
+            say!("     经限幅: {} 个切片", report.limited);
+        }
+        if report.clipped > 0 {
+            say!("   ⚠️ 归一化后超过满幅: {} 个切片", report.clipped);
+        }
+    }
+    if let Some(count) = audit_sample {
+        let candidates: Vec<AuditCandidate> = file_manifests
+            .iter()
+            .flat_map(|(dir, manifest)| {
+                manifest.slices.iter().map(|record| AuditCandidate {
+                    source: manifest.source.clone(),
+                    dir: dir.clone(),
+                    record: record.clone(),
+                })
+            })
+            .collect();
+        let picked = write_audit_sample(&output, &candidates, count, audit_seed)?;
+        say!(
+            "   - 抽检切片: {picked} 个 ({})",
+            output.join(audit::AUDIT_DIR_NAME).display()
+        );
+    }
 
-    println!("\n✨ 批量处理完成！");
+    if let Some(path) = &run_manifest {
+        let slices = write_run_manifest(path, &output, &file_manifests)?;
+        say!("   - 运行级清单: {slices} 个切片 ({})", path.display());
+    }
+    if let Some(path) = &scan_output {
+        write_file_catalog(path, None, &scan_rows)?;
+        say!(
+            "   - 源文件元数据目录: {} 个文件 ({})",
+            scan_rows.len(),
+            path.display()
+        );
+    }
+    if let Some(path) = &label_studio {
+        let tasks = write_label_studio(
+            path,
+            &file_manifests,
+            input_base,
+            label_studio_audio_prefix.as_deref(),
+            auto_labels,
+        )?;
+        say!(
+            "   - Label Studio 预标注: {tasks} 个任务 ({})",
+            path.display()
+        );
+    }
 
-    Ok(())
+    if !dry_run && !labels_only {
+        let (card, unreadable) = {
+            let _lock = IndexLock::acquire(&output)?;
+            write_dataset_card(&output)?
+        };
+        say!(
+            "   - 数据集描述: {} 个源文件、{} 个切片、{:.2} 小时 ({})",
+            card.source_count,
+            card.slice_count,
+            card.total_hours,
+            output.join(DATASET_CARD_FILE_NAME).display()
+        );
+        for path in &unreadable {
+            say!("   ⚠️ 无法读取清单，未计入数据集描述: {}", path.display());
+        }
+    }
+    if preserve_metadata {
+        let (copied, errors) = preserve_tree_metadata(input_base, &output, &processed_files);
+        say!("   - 已复制目录时间戳与权限: {copied} 个目录");
+        for error in &errors {
+            say!("   ⚠️ 无法复制目录元数据: {error}");
+        }
+    }
+
+    say!("\n✨ 批量处理完成！");
+    let summary = RunSummary {
+        files: final_stats.total_files,
+        succeeded: successful_files,
+        failed: failed_files.len(),
+        skipped: skipped_files.len(),
+        slices: final_stats.total_slices_saved,
+        audio_duration_sec: final_stats.total_audio_duration,
+        saved_duration_sec: final_stats.total_saved_duration,
+        elapsed_sec: total_duration,
+    };
+    if let Some(path) = &catalog {
+        let run_id = Catalog::open(path)?.record_run(
+            &RunInfo {
+                input: &input,
+                output: &output,
+                params_hash: &processing_config.fingerprint(),
+                summary: &summary,
+                failures: &failed_files,
+            },
+            &file_manifests,
+        )?;
+        say!(
+            "🗂️  已记录到目录数据库 (运行 ID {run_id}): {}",
+            path.display()
+        );
+    }
+    sink.emit(Event::RunSummary(summary.clone()));
+
+    Ok(summary)
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let args = config::expand_config_args(std::env::args_os().collect(), &Cli::command())?;
+    let cli = Cli::parse_from(args);
 
     match cli.command {
-        Commands::Slice {
-            input,
-            output,
-            threads,
-            threshold_db,
-            min_length_ms,
-            min_interval_ms,
-            hop_size_ms,
-            max_silence_ms,
-            enable_merge,
-            max_merge_duration_ms,
-            max_duration_ms,
-            silence_threshold,
-            min_audio_ratio,
-        } => {
-            process_slice_command(
-                input,
-                output,
-                threads,
-                threshold_db,
-                min_length_ms,
-                min_interval_ms,
-                hop_size_ms,
-                max_silence_ms,
-                enable_merge,
-                max_merge_duration_ms,
-                max_duration_ms,
-                silence_threshold,
-                min_audio_ratio,
-            )?;
-        }
+        Commands::Slice(args) => process_slice_command(args)?,
+        Commands::Refilter(args) => run_refilter(args)?,
+        Commands::Query(args) => run_query(args)?,
+        Commands::Verify(args) => run_verify(args)?,
+        Commands::Scan(args) => run_scan(args)?,
+        Commands::Migrate(args) => run_migrate(args)?,
+        Commands::Clean(args) => run_clean(args)?,
+        #[cfg(feature = "gui")]
+        Commands::Gui => gui::run_gui()?,
+        Commands::Worker {
+            args,
+            worker_file,
+            worker_output_dir,
+        } => process_worker_command(args, worker_file, worker_output_dir)?,
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Commands, ProcessingConfig};
+    use audio_learning::SlicerConfig;
+    use clap::Parser;
+
+    #[test]
+    fn negative_thresholds_parse_as_values() {
+        let argv = [
+            "audio-slicer",
+            "slice",
+            "-i",
+            "in",
+            "-o",
+            "out",
+            "--threshold-db",
+            "-40",
+            "--peak-threshold-db",
+            "-30.5",
+        ];
+        let Commands::Slice(args) = Cli::parse_from(argv).command else {
+            unreachable!()
+        };
+        assert_eq!((args.threshold_db, args.peak_threshold_db), (-40.0, -30.5));
+    }
+
+    #[test]
+    fn slicer_config_default_matches_cli_defaults() {
+        let argv = ["audio-slicer", "slice", "-i", "in", "-o", "out"];
+        let Commands::Slice(args) = Cli::parse_from(argv).command else {
+            unreachable!()
+        };
+        assert_eq!(
+            ProcessingConfig::from_args(&args).config,
+            SlicerConfig::default()
+        );
+    }
+
+    #[test]
+    fn output_file_names_are_reserved_for_hooks() {
+        use audio_learning::hook::{RESERVED_FILE_NAMES, validate_file_name};
+        let names = [
+            crate::manifest::MANIFEST_FILE_NAME,
+            crate::manifest::NOISE_PROFILE_FILE_NAME,
+            crate::export::MASK_FILE_NAME,
+            crate::export::PEAKS_FILE_NAME,
+            crate::export::LABELS_FILE_NAME,
+            crate::trace::TRACE_FILE_NAME,
+            crate::viz::VizFormat::Png.file_name(),
+            crate::viz::VizFormat::Html.file_name(),
+            crate::rerun::ERRORS_FILE_NAME,
+            crate::summary::PER_FILE_STATS_FILE_NAME,
+            crate::dataset::DATASET_CARD_FILE_NAME,
+            crate::audit::AUDIT_DIR_NAME,
+            crate::audit::AUDIT_CSV_FILE_NAME,
+            crate::refilter::KEEP_LIST_FILE_NAME,
+            crate::refilter::DROP_LIST_FILE_NAME,
+            crate::atomic::LOCK_FILE_NAME,
+            crate::atomic::RUN_LOCK_FILE_NAME,
+            crate::atomic::CLAIM_FILE_NAME,
+        ];
+        for name in names {
+            assert!(RESERVED_FILE_NAMES.contains(&name), "{name}");
+        }
+        assert!(validate_file_name(&crate::export::slice_peaks_name("slice_000.wav")).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::atomic::write_atomic;
+use crate::migrate;
+
+/// 清单文件名
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+/// 当前的清单结构版本，旧版本的清单在读取时升级 (见 `migrate`)
+pub const MANIFEST_SCHEMA_VERSION: u32 = 2;
+/// 噪声样本文件名
+pub const NOISE_PROFILE_FILE_NAME: &str = "noise_profile.wav";
+
+/// 单个切片的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SliceRecord {
+    /// 切片文件名 (相对于清单所在目录)
+    pub file: String,
+    pub start_sample: usize,
+    pub end_sample: usize,
+    pub start_sec: f64,
+    pub end_sec: f64,
+    pub duration_sec: f64,
+    /// 起止位置的容器时间戳 (单位见 `FileManifest::time_base`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_start_ts: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_end_ts: Option<u64>,
+    pub rms: f32,
+    /// 检测信号上的帧级 RMS (dBFS)
+    pub rms_db: f32,
+    /// 检测信号上的峰值 (dBFS)
+    pub peak_db: f32,
+    /// 检测器判定为有声的帧占比
+    pub voiced_ratio: f32,
+    /// 与前一切片重叠的样本数
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub overlap_samples: usize,
+    /// 估计的混响时间 RT60 (秒)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rt60: Option<f32>,
+    /// 掌声/笑声可能性 (0~1)，启用 `--classify-applause` 时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applause_score: Option<f32>,
+    /// 真峰值限幅器的最大增益衰减 (dB)，启用限幅时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limiter_reduction_db: Option<f32>,
+    /// ReplayGain 2.0 音轨增益 (dB，参考 -18 LUFS)，启用 `--replaygain` 且可测得响度时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaygain_db: Option<f32>,
+    /// 电平归一化 (`--normalize-lufs`、`--normalize-peak` 或 `--normalize-corpus`) 施加的增益 (dB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalization_gain_db: Option<f32>,
+    /// 该切片的增强版本
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub augmented: Vec<AugmentedRecord>,
+    /// 波形峰值文件名 (启用 `--export-peaks` 时)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peaks: Option<String>,
+    /// 切片钩子 (见库的 `Slicer::segments_with`) 附加的自定义元数据，命令行本身不写入
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// 切片文件已不存在 (由 `verify --fix mark-stale` 标记)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub stale: bool,
+}
+
+/// 切片的数据增强版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AugmentedRecord {
+    pub file: String,
+    /// 变速因子
+    pub speed: f32,
+}
+
+/// 运行级清单中的一个切片: 切片记录加上来源信息
+#[derive(Debug, Serialize)]
+pub struct RunSliceEntry<'a> {
+    /// 源音频文件路径
+    pub source: &'a str,
+    /// 源采样率，`start_sample`/`end_sample` 以此为准
+    pub sample_rate: u32,
+    /// 切片文件路径 (相对于输出根目录)
+    pub path: String,
+    #[serde(flatten)]
+    pub record: &'a SliceRecord,
+}
+
+/// 单个输入文件的切片清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileManifest {
+    /// 清单结构版本，早期版本没有该字段 (视为版本 1)
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
+    /// 源音频文件路径
+    pub source: String,
+    pub sample_rate: u32,
+    /// 输出切片的采样率；切片记录中的样本位置仍以源采样率为准
+    pub output_sample_rate: u32,
+    /// 容器时间基 `[分子, 分母]`，仅在容器时间戳与样本位置不一致时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_base: Option<[u32; 2]>,
+    /// 影响输出内容的切片参数指纹
+    pub params_hash: String,
+    /// 源音频的内容哈希 (见 `dedup::content_hash`)，用于按内容去重
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// 二次静音过滤阈值 (线性幅度)
+    pub silence_threshold: f32,
+    /// 二次静音过滤阈值 (dBFS)
+    pub silence_threshold_db: f32,
+    /// 噪声样本文件名 (启用 `--export-noise-profile` 时)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub noise_profile: Option<String>,
+    /// 整个源文件的波形峰值文件名 (启用 `--export-peaks` 时)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peaks: Option<String>,
+    /// 切片样本逐位等于源文件中的对应区间 (32 位浮点单声道 WAV 输入，输出未经任何处理)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub bit_exact: bool,
+    /// 每个分片目录的切片数 (`--shard-output`)，切片文件名带有分片目录前缀 (如 `001/slice_1000.wav`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard_size: Option<usize>,
+    /// 切片文件首尾各补的时长 (`--pad-silence-ms`)，切片的起止时间不含补白
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding_ms: Option<u32>,
+    /// 重采样器 (`实现/质量`，如 `builtin/sinc-high`)，仅在输出采样率与源不同时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resampler: Option<String>,
+    /// 该文件实际使用的静音阈值 (dB)，仅在按噪声底自动确定阈值 (`--threshold auto`) 时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold_db: Option<f32>,
+    /// 处理该文件各阶段的耗时，用于事后找出解码或处理异常缓慢的文件
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<StageTimings>,
+    pub slices: Vec<SliceRecord>,
+}
+
+/// 单个源文件各处理阶段的耗时 (秒)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct StageTimings {
+    /// 解码与预处理
+    pub load_sec: f64,
+    /// 静音检测 (含放宽重新检测)
+    pub slice_sec: f64,
+    /// 合并、起点回退、最小间隔与超长硬切
+    pub merge_sec: f64,
+    /// 写出切片文件，不含清单本身
+    pub save_sec: f64,
+    /// 从开始处理到写出清单的总耗时
+    pub total_sec: f64,
+    /// 实时率: 总耗时 / 音频时长
+    pub rtf: f64,
+}
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// 读取清单文件，旧版本的清单在内存中升级到当前结构版本
+pub fn read_manifest(path: &Path) -> Result<FileManifest> {
+    let file = std::fs::File::open(path)?;
+    let mut value: serde_json::Value = serde_json::from_reader(std::io::BufReader::new(file))?;
+    if migrate::schema_version(&value) != MANIFEST_SCHEMA_VERSION {
+        migrate::upgrade_manifest(&mut value, path.parent().unwrap_or(Path::new(".")))?;
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// 把各文件的清单合并为一个运行级清单 (切片数组)，切片路径相对于 `output`
+///
+/// 下游对齐工具只需读取一个文件即可把所有切片映射回源音频。
+pub fn write_run_manifest(
+    path: &Path,
+    output: &Path,
+    manifests: &[(PathBuf, FileManifest)],
+) -> Result<usize> {
+    let entries: Vec<RunSliceEntry> = manifests
+        .iter()
+        .flat_map(|(dir, manifest)| {
+            let dir = dir.strip_prefix(output).unwrap_or(dir);
+            manifest.slices.iter().map(move |record| RunSliceEntry {
+                source: &manifest.source,
+                sample_rate: manifest.sample_rate,
+                path: dir.join(&record.file).to_string_lossy().into_owned(),
+                record,
+            })
+        })
+        .collect();
+    write_atomic(path, |writer| {
+        Ok(serde_json::to_writer_pretty(writer, &entries)?)
+    })?;
+    Ok(entries.len())
+}
+
+/// 写入清单文件 (原子替换，读取方不会看到写了一半的清单)
+pub fn write_manifest(path: &Path, manifest: &FileManifest) -> Result<()> {
+    write_atomic(path, |writer| {
+        Ok(serde_json::to_writer_pretty(writer, manifest)?)
+    })
+}
+
+/// 测试用的清单: 源文件 `a.wav` (16 kHz)，每个文件名一个各项指标为 0 的切片，
+/// 测试按需修改字段
+#[cfg(test)]
+pub fn test_manifest(files: &[&str]) -> FileManifest {
+    let slices: Vec<serde_json::Value> = files
+        .iter()
+        .map(|file| {
+            serde_json::json!({
+                "file": file,
+                "start_sample": 0, "end_sample": 0,
+                "start_sec": 0.0, "end_sec": 0.0, "duration_sec": 0.0,
+                "rms": 0.0, "rms_db": 0.0, "peak_db": 0.0, "voiced_ratio": 0.0,
+            })
+        })
+        .collect();
+    serde_json::from_value(serde_json::json!({
+        "source": "a.wav",
+        "sample_rate": 16000,
+        "output_sample_rate": 16000,
+        "params_hash": "0",
+        "silence_threshold": 0.001,
+        "silence_threshold_db": -60.0,
+        "slices": slices,
+    }))
+    .unwrap()
+}
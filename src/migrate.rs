@@ -0,0 +1,209 @@
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+use std::path::Path;
+
+use crate::MigrateArgs;
+use crate::atomic::IndexLock;
+use crate::audio::decode_audio_until;
+use crate::manifest::{MANIFEST_SCHEMA_VERSION, read_manifest, write_manifest};
+use crate::refilter::find_manifests;
+use crate::{DEFAULT_SILENCE_THRESHOLD_DB, db_to_amplitude};
+
+/// 清单的结构版本，没有 `schema_version` 字段的清单为版本 1
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(1, |version| version as u32)
+}
+
+/// 把清单 JSON 从其结构版本逐级升级到当前版本，`dir` 为清单所在目录
+///
+/// 只补齐缺失的字段，已有的值保持不变，因此对已经完整的旧清单没有副作用。
+pub fn upgrade_manifest(value: &mut Value, dir: &Path) -> Result<()> {
+    let version = schema_version(value);
+    if version > MANIFEST_SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "清单结构版本 {version} 高于本程序支持的版本 {MANIFEST_SCHEMA_VERSION}，请升级程序"
+        ));
+    }
+    let Some(manifest) = value.as_object_mut() else {
+        return Err(anyhow::anyhow!("清单不是 JSON 对象"));
+    };
+    if version < 2 {
+        upgrade_v1(manifest, dir);
+    }
+    manifest.insert("schema_version".to_string(), json!(MANIFEST_SCHEMA_VERSION));
+    Ok(())
+}
+
+/// 版本 1 → 2: 早期版本的清单缺少输出采样率、参数指纹、静音阈值与切片的
+/// 分贝指标，按已有信息推导
+///
+/// - 输出采样率取源采样率，参数指纹为空 (视为未知参数)
+/// - 静音阈值由线性值与分贝值互相换算，都缺失时取默认阈值
+/// - `rms_db` 由 `rms` 换算；`peak_db` 读取切片文件测量，无法读取时取 `rms_db`；
+///   有声帧占比无从得知，记为 1
+fn upgrade_v1(manifest: &mut Map<String, Value>, dir: &Path) {
+    let sample_rate = manifest.get("sample_rate").cloned().unwrap_or(json!(0));
+    manifest.entry("output_sample_rate").or_insert(sample_rate);
+    manifest.entry("params_hash").or_insert(json!(""));
+
+    let linear = manifest.get("silence_threshold").and_then(Value::as_f64);
+    let db = manifest.get("silence_threshold_db").and_then(Value::as_f64);
+    let (linear, db) = match (linear, db) {
+        (Some(linear), Some(db)) => (linear, db),
+        (Some(linear), None) => (linear, to_db(linear)),
+        (None, Some(db)) => (db_to_amplitude(db as f32) as f64, db),
+        (None, None) => (
+            db_to_amplitude(DEFAULT_SILENCE_THRESHOLD_DB) as f64,
+            DEFAULT_SILENCE_THRESHOLD_DB as f64,
+        ),
+    };
+    manifest.insert("silence_threshold".to_string(), json!(linear));
+    manifest.insert("silence_threshold_db".to_string(), json!(db));
+
+    let slices = manifest
+        .get_mut("slices")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut);
+    for slice in slices {
+        let rms_db = match slice.get("rms_db").and_then(Value::as_f64) {
+            Some(rms_db) => rms_db,
+            None => to_db(slice.get("rms").and_then(Value::as_f64).unwrap_or(0.0)),
+        };
+        slice.insert("rms_db".to_string(), json!(rms_db));
+        if !slice.contains_key("peak_db") {
+            let peak_db = slice
+                .get("file")
+                .and_then(Value::as_str)
+                .and_then(|file| measure_peak_db(&dir.join(file)))
+                .unwrap_or(rms_db);
+            slice.insert("peak_db".to_string(), json!(peak_db));
+        }
+        slice.entry("voiced_ratio").or_insert(json!(1.0));
+    }
+}
+
+fn to_db(amplitude: f64) -> f64 {
+    20.0 * amplitude.max(1e-10).log10()
+}
+
+/// 切片文件的样本峰值 (dBFS)
+fn measure_peak_db(path: &Path) -> Option<f64> {
+    let decoded = decode_audio_until(path, None).ok()?;
+    let peak = decoded
+        .downmix()
+        .iter()
+        .fold(0.0f32, |peak, x| peak.max(x.abs()));
+    Some(to_db(peak as f64))
+}
+
+pub fn run_migrate(args: MigrateArgs) -> Result<()> {
+    let output = &args.output;
+    if !output.is_dir() {
+        return Err(anyhow::anyhow!("输出目录不存在: {}", output.display()));
+    }
+    let _lock = IndexLock::acquire(output)?;
+
+    let manifests = find_manifests(output);
+    println!("📂 检查切片清单: {}个", manifests.len());
+    let mut upgraded = 0;
+    let mut current = 0;
+    let mut failed = 0;
+    for path in &manifests {
+        let version = std::fs::read(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(schema_version(&serde_json::from_slice(&bytes)?)));
+        let result = version.and_then(|version| {
+            if version == MANIFEST_SCHEMA_VERSION {
+                return Ok(false);
+            }
+            // 读取时即按版本升级
+            let manifest = read_manifest(path)?;
+            if !args.dry_run {
+                write_manifest(path, &manifest)?;
+            }
+            println!(
+                "   ⬆️ {}: 版本 {version} → {MANIFEST_SCHEMA_VERSION}",
+                path.display()
+            );
+            Ok(true)
+        });
+        match result {
+            Ok(true) => upgraded += 1,
+            Ok(false) => current += 1,
+            Err(error) => {
+                println!("   ❌ {}: {error}", path.display());
+                failed += 1;
+            }
+        }
+    }
+
+    println!("📊 迁移结果:");
+    let verb = if args.dry_run {
+        "需要升级"
+    } else {
+        "已升级"
+    };
+    println!("   - {verb}: {upgraded} 个");
+    println!("   - 已是当前版本: {current} 个");
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{failed} 个清单无法迁移"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{schema_version, upgrade_manifest};
+    use crate::manifest::{FileManifest, MANIFEST_SCHEMA_VERSION};
+    use std::path::Path;
+
+    #[test]
+    fn upgrades_first_release_manifest() {
+        // 最早版本的清单: 只有源文件、采样率与切片的时间和 RMS
+        let mut value = serde_json::json!({
+            "source": "a.wav",
+            "sample_rate": 16000,
+            "slices": [{
+                "file": "missing.wav",
+                "start_sample": 0, "end_sample": 16000,
+                "start_sec": 0.0, "end_sec": 1.0, "duration_sec": 1.0,
+                "rms": 0.1,
+            }],
+        });
+        assert_eq!(schema_version(&value), 1);
+        upgrade_manifest(&mut value, Path::new("/nonexistent")).unwrap();
+        let manifest: FileManifest = serde_json::from_value(value).unwrap();
+        assert_eq!(manifest.schema_version, MANIFEST_SCHEMA_VERSION);
+        assert_eq!(manifest.output_sample_rate, 16000);
+        assert_eq!(manifest.silence_threshold_db, -60.0);
+        let slice = &manifest.slices[0];
+        assert!((slice.rms_db + 20.0).abs() < 1e-4);
+        // 切片文件不存在时峰值取 RMS
+        assert_eq!(slice.peak_db, slice.rms_db);
+        assert_eq!(slice.voiced_ratio, 1.0);
+    }
+
+    #[test]
+    fn keeps_existing_values_and_rejects_newer_versions() {
+        let mut value = serde_json::json!({
+            "source": "a.wav",
+            "sample_rate": 44100,
+            "output_sample_rate": 16000,
+            "params_hash": "abc",
+            "silence_threshold": 0.01,
+            "slices": [],
+        });
+        upgrade_manifest(&mut value, Path::new(".")).unwrap();
+        assert_eq!(value["output_sample_rate"], 16000);
+        assert_eq!(value["params_hash"], "abc");
+        assert!((value["silence_threshold_db"].as_f64().unwrap() + 40.0).abs() < 1e-4);
+
+        let mut newer = serde_json::json!({ "schema_version": MANIFEST_SCHEMA_VERSION + 1 });
+        assert!(upgrade_manifest(&mut newer, Path::new(".")).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::audio::{decode_audio_until, mix_channels};
+use crate::dsp::true_peak_limit;
+use crate::export::write_peaks;
+use crate::loudness::channel_loudness;
+use crate::manifest::{FileManifest, MANIFEST_FILE_NAME, write_manifest};
+use crate::tags::{Tags, loudness_tags, provenance_tags};
+use crate::{ProcessingConfig, db_to_amplitude, save_slice};
+
+/// 批量响度归一化的增益范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NormalizeScope {
+    /// 所有切片使用同一个增益
+    Corpus,
+    /// 同一源文件的切片使用同一个增益
+    Source,
+}
+
+/// 批量响度归一化的结果
+#[derive(Debug, Default)]
+pub struct NormalizeReport {
+    /// 测得响度的切片数 (短于 400ms 或全静音的切片无法测量)
+    pub measured: usize,
+    /// 切片响度的 10/50/90 百分位 (LUFS)
+    pub distribution: Option<[f32; 3]>,
+    /// 调整了电平的切片数
+    pub adjusted: usize,
+    /// 调整后样本峰值超过满幅的切片数
+    pub clipped: usize,
+    /// 调整后经 --true-peak-limit 限幅的切片数
+    pub limited: usize,
+}
+
+/// 单个切片文件改写后的结果
+struct Rewritten {
+    clipped: bool,
+    limiter_reduction_db: Option<f32>,
+    replaygain_db: Option<f32>,
+}
+
+/// 两遍处理: 先测量本次运行所有切片的积分响度，再按 `scope` 计算增益
+/// (使响度的中位数达到 `target_lufs`)，改写切片文件并更新清单
+///
+/// 同一增益范围内的切片乘以相同的增益，切片之间的相对动态保持不变。切片文件按原格式
+/// 重新写出，增强版本与波形峰值一并更新；元数据标签按改写后的样本重新生成。
+/// 施加了非零增益的清单不再是逐位复制 (`bit_exact` 置为 `false`)。
+pub fn normalize_slices(
+    manifests: &mut [(PathBuf, FileManifest)],
+    scope: NormalizeScope,
+    target_lufs: f32,
+    config: &ProcessingConfig,
+) -> Result<NormalizeReport> {
+    // 第一遍: 测量
+    let loudness: Vec<Vec<Option<f32>>> = manifests
+        .par_iter()
+        .map(|(dir, manifest)| {
+            manifest
+                .slices
+                .iter()
+                .map(|record| measure_loudness(&dir.join(&record.file)))
+                .collect()
+        })
+        .collect::<Result<_>>()?;
+    let all: Vec<f32> = loudness.iter().flatten().flatten().copied().collect();
+    let mut report = NormalizeReport {
+        measured: all.len(),
+        distribution: percentiles(all),
+        ..Default::default()
+    };
+    let gains: Vec<Option<f32>> = match scope {
+        NormalizeScope::Corpus => {
+            let gain = report
+                .distribution
+                .map(|[_, median, _]| target_lufs - median);
+            vec![gain; manifests.len()]
+        }
+        NormalizeScope::Source => loudness
+            .iter()
+            .map(|slices| {
+                let values = slices.iter().flatten().copied().collect();
+                percentiles(values).map(|[_, median, _]| target_lufs - median)
+            })
+            .collect(),
+    };
+
+    // 第二遍: 改写
+    let results: Vec<Vec<Rewritten>> = manifests
+        .par_iter_mut()
+        .zip(gains)
+        .map(|((dir, manifest), gain)| {
+            let Some(gain) = gain else {
+                return Ok(Vec::new());
+            };
+            if gain != 0.0 {
+                manifest.bit_exact = false;
+            }
+            let mut rewritten = Vec::new();
+            for index in 0..manifest.slices.len() {
+                let slice = rewrite_slice(dir, manifest, index, gain, config)?;
+                let record = &mut manifest.slices[index];
+                record.normalization_gain_db = Some(gain);
+                if config.replaygain {
+                    record.replaygain_db = slice.replaygain_db;
+                }
+                if let Some(reduction) = slice.limiter_reduction_db {
+                    let previous = record.limiter_reduction_db.unwrap_or(0.0);
+                    record.limiter_reduction_db = Some(previous.max(reduction));
+                }
+                rewritten.push(slice);
+            }
+            write_manifest(&dir.join(MANIFEST_FILE_NAME), manifest)?;
+            Ok(rewritten)
+        })
+        .collect::<Result<_>>()?;
+
+    for slice in results.iter().flatten() {
+        report.adjusted += 1;
+        report.clipped += usize::from(slice.clipped);
+        report.limited += usize::from(slice.limiter_reduction_db.is_some_and(|db| db > 0.0));
+    }
+    Ok(report)
+}
+
+/// 切片文件的积分响度
+fn measure_loudness(path: &Path) -> Result<Option<f32>> {
+    let decoded = decode_audio_until(path, None)
+        .map_err(|e| anyhow::anyhow!("无法读取切片 {}: {e}", path.display()))?;
+    Ok(channel_loudness(&decoded.channels, decoded.sample_rate))
+}
+
+/// 升序排列后的 10/50/90 百分位，没有值时返回 `None`
+fn percentiles(mut values: Vec<f32>) -> Option<[f32; 3]> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f32::total_cmp);
+    let at = |p: usize| values[(values.len() - 1) * p / 100];
+    Some([at(10), at(50), at(90)])
+}
+
+/// 对第 `index` 个切片及其增强版本施加增益并按原格式重新写出
+fn rewrite_slice(
+    dir: &Path,
+    manifest: &FileManifest,
+    index: usize,
+    gain_db: f32,
+    config: &ProcessingConfig,
+) -> Result<Rewritten> {
+    let record = &manifest.slices[index];
+    let provenance = if config.provenance_tags {
+        provenance_tags(
+            &manifest.source,
+            record.start_sec,
+            record.end_sec,
+            &manifest.params_hash,
+        )
+    } else {
+        Tags::new()
+    };
+    let slice = rewrite_file(&dir.join(&record.file), gain_db, &provenance, config)?;
+    if let (Some(peaks), Some(samples_per_pixel)) = (&record.peaks, config.export_peaks) {
+        let decoded = decode_audio_until(dir.join(&record.file), None)?;
+        write_peaks(
+            &dir.join(peaks),
+            &decoded.downmix(),
+            decoded.sample_rate,
+            samples_per_pixel,
+        )?;
+    }
+    for variant in &record.augmented {
+        rewrite_file(&dir.join(&variant.file), gain_db, &provenance, config)?;
+    }
+    Ok(slice)
+}
+
+/// 读取切片文件、乘以增益 (按配置限幅) 后写到临时文件再替换原文件
+fn rewrite_file(
+    path: &Path,
+    gain_db: f32,
+    provenance: &Tags,
+    config: &ProcessingConfig,
+) -> Result<Rewritten> {
+    let decoded = decode_audio_until(path, None)
+        .map_err(|e| anyhow::anyhow!("无法读取切片 {}: {e}", path.display()))?;
+    let sample_rate = decoded.sample_rate;
+    let gain = db_to_amplitude(gain_db);
+    let mut channels = decoded.channels;
+    for sample in channels.iter_mut().flatten() {
+        *sample *= gain;
+    }
+    let limiter_reduction_db = config
+        .true_peak_limit_db
+        .map(|ceiling_db| true_peak_limit(&mut channels, sample_rate, ceiling_db).max_reduction_db);
+    let clipped = channels.iter().flatten().any(|x| x.abs() > 1.0);
+
+    let (mut tags, replaygain_db) = if config.replaygain {
+        loudness_tags(&mix_channels(&channels), sample_rate)
+    } else {
+        (Tags::new(), None)
+    };
+    tags.extend(provenance.iter().cloned());
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{file_name}.normalize"));
+    save_slice(&channels, sample_rate, &config.output_format, &tags, &temp)?;
+    std::fs::rename(&temp, path)?;
+    Ok(Rewritten {
+        clipped,
+        limiter_reduction_db,
+        replaygain_db,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NormalizeScope, normalize_slices, percentiles};
+    use crate::manifest::{read_manifest, test_manifest, write_manifest};
+    use crate::{Cli, Commands, ProcessingConfig};
+    use clap::Parser;
+
+    #[test]
+    fn percentiles_of_slice_loudness() {
+        assert_eq!(percentiles(Vec::new()), None);
+        assert_eq!(percentiles(vec![-20.0]), Some([-20.0; 3]));
+        let values: Vec<f32> = (0..=100).rev().map(|i| -(i as f32)).collect();
+        assert_eq!(percentiles(values), Some([-90.0, -50.0, -10.0]));
+    }
+
+    #[test]
+    fn gain_clears_bit_exact() {
+        let dir = std::env::temp_dir().join(format!("slicer_normalize_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(dir.join("slice_000.wav"), spec).unwrap();
+        for i in 0..16000 {
+            writer.write_sample(0.1 * (i as f32 * 0.05).sin()).unwrap();
+        }
+        writer.finalize().unwrap();
+        let mut manifest = test_manifest(&["slice_000.wav"]);
+        manifest.bit_exact = true;
+        let manifest_path = dir.join(super::MANIFEST_FILE_NAME);
+        write_manifest(&manifest_path, &manifest).unwrap();
+
+        let argv = ["audio-slicer", "slice", "-i", "in", "-o", "out"];
+        let Commands::Slice(args) = Cli::parse_from(argv).command else {
+            unreachable!()
+        };
+        let config = ProcessingConfig::from_args(&args);
+        let mut manifests = vec![(dir.clone(), manifest)];
+        let report =
+            normalize_slices(&mut manifests, NormalizeScope::Corpus, -23.0, &config).unwrap();
+        assert_eq!(report.adjusted, 1);
+        assert!(!manifests[0].1.bit_exact);
+        assert!(!read_manifest(&manifest_path).unwrap().bit_exact);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+use crate::events::RunSummary;
+
+/// 运行结束后执行通知命令，运行结果通过 `SLICER_*` 环境变量传入
+///
+/// - `SLICER_STATUS`: `success` (全部成功)、`partial` (部分文件失败) 或 `failure` (运行中止)
+/// - `SLICER_ERROR`: 运行中止时的错误信息
+/// - `SLICER_OUTPUT`: 输出目录
+/// - `SLICER_FILES`/`SLICER_SUCCEEDED`/`SLICER_FAILED`/`SLICER_SLICES`: 文件与切片计数
+/// - `SLICER_AUDIO_SEC`/`SLICER_SAVED_SEC`/`SLICER_ELAPSED_SEC`: 时长统计 (秒)
+pub fn run_notify_cmd(
+    command: &str,
+    output: &Path,
+    outcome: Result<&RunSummary, &anyhow::Error>,
+) -> Result<ExitStatus> {
+    let mut cmd = shell_command(command);
+    cmd.env("SLICER_OUTPUT", output);
+
+    match outcome {
+        Ok(summary) => {
+            let status = if summary.failed == 0 {
+                "success"
+            } else {
+                "partial"
+            };
+            cmd.env("SLICER_STATUS", status)
+                .env("SLICER_FILES", summary.files.to_string())
+                .env("SLICER_SUCCEEDED", summary.succeeded.to_string())
+                .env("SLICER_FAILED", summary.failed.to_string())
+                .env("SLICER_SLICES", summary.slices.to_string())
+                .env(
+                    "SLICER_AUDIO_SEC",
+                    format!("{:.3}", summary.audio_duration_sec),
+                )
+                .env(
+                    "SLICER_SAVED_SEC",
+                    format!("{:.3}", summary.saved_duration_sec),
+                )
+                .env("SLICER_ELAPSED_SEC", format!("{:.3}", summary.elapsed_sec));
+        }
+        Err(error) => {
+            cmd.env("SLICER_STATUS", "failure")
+                .env("SLICER_ERROR", error.to_string());
+        }
+    }
+
+    Ok(cmd.status()?)
+}
+
+#[cfg(unix)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::run_notify_cmd;
+    use crate::events::RunSummary;
+    use std::path::Path;
+
+    fn summary(failed: usize) -> RunSummary {
+        RunSummary {
+            files: 3,
+            succeeded: 3 - failed,
+            failed,
+            skipped: 0,
+            slices: 12,
+            audio_duration_sec: 60.0,
+            saved_duration_sec: 50.0,
+            elapsed_sec: 2.0,
+        }
+    }
+
+    #[test]
+    fn passes_summary_environment() {
+        let status = run_notify_cmd(
+            r#"test "$SLICER_STATUS" = partial && test "$SLICER_SLICES" = 12 && test "$SLICER_OUTPUT" = out"#,
+            Path::new("out"),
+            Ok(&summary(1)),
+        )
+        .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn reports_failure() {
+        let error = anyhow::anyhow!("输入路径不存在");
+        let status = run_notify_cmd(
+            r#"test "$SLICER_STATUS" = failure && test -n "$SLICER_ERROR""#,
+            Path::new("out"),
+            Err(&error),
+        )
+        .unwrap();
+        assert!(status.success());
+    }
+}
@@ -0,0 +1,135 @@
+//! 逐帧基频估计
+//!
+//! 在降采样到约 8 kHz 的信号上用 YIN 的累积均值归一化差分函数估计基频，
+//! 精度足以判断音高是否连续 (如歌声中的长音)，不适合作为精确的音高标注。
+
+use std::ops::Range;
+
+use crate::audio::Sample;
+
+/// 可检测的最低基频 (Hz)
+pub const PITCH_MIN_HZ: f32 = 60.0;
+/// 可检测的最高基频 (Hz)
+pub const PITCH_MAX_HZ: f32 = 1000.0;
+/// 分析采样率的下限，降采样倍数取使分析采样率不低于该值的最大整数
+const ANALYSIS_MIN_RATE: u32 = 8000;
+/// YIN 判定为有声的归一化差分阈值
+const YIN_THRESHOLD: f32 = 0.15;
+/// 低于该 RMS 的帧不估计基频
+const SILENT_RMS: f32 = 1e-4;
+
+/// 估计 `frames` 中每帧的基频 (Hz)，无声或无法判定的帧为 `None`
+///
+/// 第 `i` 帧从样本 `i * hop_size` 开始，分析窗口覆盖两个最低基频周期。
+pub fn frame_pitch<S: Sample>(
+    samples: &[S],
+    sample_rate: u32,
+    hop_size: usize,
+    frames: Range<usize>,
+) -> Vec<Option<f32>> {
+    let factor = (sample_rate / ANALYSIS_MIN_RATE).max(1) as usize;
+    let rate = sample_rate as f32 / factor as f32;
+    let min_lag = (rate / PITCH_MAX_HZ).floor().max(2.0) as usize;
+    let max_lag = (rate / PITCH_MIN_HZ).ceil() as usize;
+    let window = 2 * max_lag;
+
+    frames
+        .map(|frame| {
+            // 按盒式滤波降采样，只取该帧需要的部分
+            let start = frame * hop_size;
+            let decimated: Vec<f32> = samples
+                .get(start..)
+                .unwrap_or_default()
+                .chunks(factor)
+                .take(window)
+                .map(|block| block.iter().map(|x| x.to_f32()).sum::<f32>() / block.len() as f32)
+                .collect();
+            if decimated.len() < window {
+                return None;
+            }
+            let rms = (decimated.iter().map(|x| x * x).sum::<f32>() / window as f32).sqrt();
+            if rms < SILENT_RMS {
+                return None;
+            }
+            yin(&decimated[..window], min_lag, max_lag).map(|lag| rate / lag)
+        })
+        .collect()
+}
+
+/// YIN: 返回第一个低于阈值的累积均值归一化差分的局部极小处的延迟 (含抛物线插值)
+fn yin(window: &[f32], min_lag: usize, max_lag: usize) -> Option<f32> {
+    let length = window.len() - max_lag;
+    let difference: Vec<f32> = (0..=max_lag)
+        .map(|lag| {
+            (0..length)
+                .map(|i| {
+                    let d = window[i] - window[i + lag];
+                    d * d
+                })
+                .sum()
+        })
+        .collect();
+
+    let mut running_sum = 0.0;
+    let mut normalized = vec![1.0; max_lag + 1];
+    for lag in 1..=max_lag {
+        running_sum += difference[lag];
+        normalized[lag] = if running_sum > 0.0 {
+            difference[lag] * lag as f32 / running_sum
+        } else {
+            1.0
+        };
+    }
+
+    let mut lag = min_lag;
+    while lag < max_lag {
+        if normalized[lag] < YIN_THRESHOLD {
+            while lag + 1 < max_lag && normalized[lag + 1] < normalized[lag] {
+                lag += 1;
+            }
+            let (a, b, c) = (normalized[lag - 1], normalized[lag], normalized[lag + 1]);
+            let denominator = a - 2.0 * b + c;
+            let offset = if denominator.abs() > f32::EPSILON {
+                (0.5 * (a - c) / denominator).clamp(-0.5, 0.5)
+            } else {
+                0.0
+            };
+            return Some(lag as f32 + offset);
+        }
+        lag += 1;
+    }
+    None
+}
+
+/// 两个基频之间的音程 (半音)
+pub fn semitones(a: f32, b: f32) -> f32 {
+    12.0 * (a / b).log2().abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{frame_pitch, semitones};
+    use std::f32::consts::PI;
+
+    #[test]
+    fn tracks_a_sung_note_and_ignores_silence() {
+        let sample_rate = 44100;
+        // 220 Hz 带泛音的音符 0.5 秒，之后 0.2 秒静音
+        let mut samples: Vec<f32> = (0..sample_rate / 2)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                0.5 * (2.0 * PI * 220.0 * t).sin() + 0.2 * (2.0 * PI * 440.0 * t).sin()
+            })
+            .collect();
+        samples.extend(vec![0.0; sample_rate as usize / 5]);
+
+        let hop = 441;
+        let pitch = frame_pitch(&samples, sample_rate, hop, 0..samples.len() / hop);
+        let voiced: Vec<f32> = pitch[..40].iter().map(|p| p.unwrap()).collect();
+        assert!(
+            voiced.iter().all(|&f0| semitones(f0, 220.0) < 0.3),
+            "{voiced:?}"
+        );
+        assert!(pitch[55..65].iter().all(Option::is_none));
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+
+use crate::audio::DecodedAudio;
+
+/// 峰值低于 16 位整数的一个最低有效位时视为数字静音
+const DIGITAL_SILENCE_PEAK: f32 = 1.0 / 32768.0;
+
+/// 预检在文件开头发现的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrecheckIssue {
+    /// 所有声道均为数字静音
+    DigitalSilence,
+    /// 多声道文件中某个声道 (从 0 开始) 为数字静音，其余声道有信号
+    DeadChannel { channel: usize, channels: usize },
+}
+
+impl fmt::Display for PrecheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DigitalSilence => write!(f, "全为数字静音"),
+            Self::DeadChannel { channel, channels } => {
+                write!(f, "{channels} 个声道中的声道 {channel} 无信号")
+            }
+        }
+    }
+}
+
+/// 检查解码出的开头部分: 全部声道为数字静音时只报告 `DigitalSilence`，
+/// 否则报告每个无信号的声道
+pub fn precheck(audio: &DecodedAudio) -> Vec<PrecheckIssue> {
+    let silent: Vec<bool> = audio
+        .channels
+        .iter()
+        .map(|channel| {
+            channel
+                .iter()
+                .all(|x| !x.is_finite() || x.abs() < DIGITAL_SILENCE_PEAK)
+        })
+        .collect();
+    if silent.iter().all(|&silent| silent) {
+        return vec![PrecheckIssue::DigitalSilence];
+    }
+    silent
+        .iter()
+        .enumerate()
+        .filter(|&(_, &silent)| silent)
+        .map(|(channel, _)| PrecheckIssue::DeadChannel {
+            channel,
+            channels: silent.len(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PrecheckIssue, precheck};
+    use crate::audio::DecodedAudio;
+
+    fn audio(channels: Vec<Vec<f32>>) -> DecodedAudio {
+        DecodedAudio {
+            channels,
+            sample_rate: 16000,
+            timeline: None,
+        }
+    }
+
+    #[test]
+    fn flags_silence_and_dead_channels() {
+        let tone: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.1).sin() * 0.3).collect();
+        let dither = vec![1e-6; 1600];
+        assert_eq!(precheck(&audio(vec![tone.clone()])), []);
+        assert_eq!(precheck(&audio(vec![tone.clone(), tone.clone()])), []);
+        assert_eq!(
+            precheck(&audio(vec![dither.clone(), vec![0.0; 1600]])),
+            [PrecheckIssue::DigitalSilence]
+        );
+        assert_eq!(
+            precheck(&audio(Vec::new())),
+            [PrecheckIssue::DigitalSilence]
+        );
+        let issues = precheck(&audio(vec![tone, dither]));
+        assert_eq!(
+            issues,
+            [PrecheckIssue::DeadChannel {
+                channel: 1,
+                channels: 2
+            }]
+        );
+        assert_eq!(issues[0].to_string(), "2 个声道中的声道 1 无信号");
+    }
+}
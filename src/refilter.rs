@@ -0,0 +1,274 @@
+use anyhow::Result;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::RefilterArgs;
+use crate::atomic::{IndexLock, check_overwrite};
+use crate::manifest::{MANIFEST_FILE_NAME, SliceRecord, read_manifest, write_manifest};
+
+/// 保留列表文件名
+pub const KEEP_LIST_FILE_NAME: &str = "refilter_keep.txt";
+/// 剔除列表文件名
+pub const DROP_LIST_FILE_NAME: &str = "refilter_drop.txt";
+
+/// 对已有切片的筛选条件，未设置的条件不参与判断
+#[derive(Debug, Clone, Default)]
+pub struct SliceCriteria {
+    pub min_duration_sec: Option<f64>,
+    pub max_duration_sec: Option<f64>,
+    pub min_rms_db: Option<f32>,
+    pub max_peak_db: Option<f32>,
+    pub min_voiced_ratio: Option<f32>,
+    pub max_rt60: Option<f32>,
+    pub max_applause_score: Option<f32>,
+    pub min_applause_score: Option<f32>,
+    pub max_limiter_db: Option<f32>,
+}
+
+impl SliceCriteria {
+    pub fn from_args(args: &RefilterArgs) -> Self {
+        Self {
+            min_duration_sec: args.min_duration_ms.map(|ms| ms as f64 / 1000.0),
+            max_duration_sec: args.max_duration_ms.map(|ms| ms as f64 / 1000.0),
+            min_rms_db: args.min_rms_db,
+            max_peak_db: args.max_peak_db,
+            min_voiced_ratio: args.min_voiced_ratio,
+            max_rt60: args.max_rt60,
+            max_applause_score: args.max_applause_score,
+            min_applause_score: args.min_applause_score,
+            max_limiter_db: args.max_limiter_db,
+        }
+    }
+
+    /// 切片不满足条件时返回原因
+    ///
+    /// 清单中缺少的可选指标 (如未启用混响估计时的 RT60) 视为满足条件。
+    pub fn rejection_reason(&self, record: &SliceRecord) -> Option<String> {
+        if let Some(min) = self.min_duration_sec
+            && record.duration_sec < min
+        {
+            return Some(format!("时长 {:.3}s < {min}s", record.duration_sec));
+        }
+        if let Some(max) = self.max_duration_sec
+            && record.duration_sec > max
+        {
+            return Some(format!("时长 {:.3}s > {max}s", record.duration_sec));
+        }
+        if let Some(min) = self.min_rms_db
+            && record.rms_db < min
+        {
+            return Some(format!("RMS {:.1}dB < {min}dB", record.rms_db));
+        }
+        if let Some(max) = self.max_peak_db
+            && record.peak_db > max
+        {
+            return Some(format!("峰值 {:.1}dB > {max}dB", record.peak_db));
+        }
+        if let Some(min) = self.min_voiced_ratio
+            && record.voiced_ratio < min
+        {
+            return Some(format!("有声占比 {:.3} < {min}", record.voiced_ratio));
+        }
+        if let (Some(max), Some(rt60)) = (self.max_rt60, record.rt60)
+            && rt60 > max
+        {
+            return Some(format!("RT60 {rt60:.2}s > {max}s"));
+        }
+        if let (Some(max), Some(score)) = (self.max_applause_score, record.applause_score)
+            && score > max
+        {
+            return Some(format!("掌声/笑声可能性 {score:.2} > {max}"));
+        }
+        if let Some(min) = self.min_applause_score {
+            match record.applause_score {
+                Some(score) if score < min => {
+                    return Some(format!("掌声/笑声可能性 {score:.2} < {min}"));
+                }
+                None => return Some("清单中没有掌声/笑声分类结果".to_string()),
+                Some(_) => {}
+            }
+        }
+        if let (Some(max), Some(reduction)) = (self.max_limiter_db, record.limiter_reduction_db)
+            && reduction > max
+        {
+            return Some(format!("限幅衰减 {reduction:.1}dB > {max}dB"));
+        }
+        None
+    }
+}
+
+/// 查找输出目录下的所有切片清单
+pub fn find_manifests(output: &Path) -> Vec<PathBuf> {
+    let mut manifests: Vec<PathBuf> = WalkDir::new(output)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.file_name() == MANIFEST_FILE_NAME)
+        .map(|entry| entry.into_path())
+        .collect();
+    manifests.sort();
+    manifests
+}
+
+/// 移动文件，跨文件系统时退回到复制后删除
+pub fn move_file(from: &Path, to: &Path) -> Result<()> {
+    check_overwrite(to)?;
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::rename(from, to).is_err() {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// `refilter` 子命令: 按新条件重新筛选已有输出中的切片，无需重新解码与切片
+///
+/// 在输出目录写出保留/剔除列表；指定 `--move-rejected` 时把剔除的切片 (含增强版本)
+/// 移到该目录下的相同相对位置，并从清单中删除对应记录。
+pub fn run_refilter(args: RefilterArgs) -> Result<()> {
+    let criteria = SliceCriteria::from_args(&args);
+    let output = &args.output;
+    if !output.is_dir() {
+        return Err(anyhow::anyhow!("输出目录不存在: {}", output.display()));
+    }
+    // 读取-修改-写回清单期间阻止其他运行更新同一数据集
+    let _lock = IndexLock::acquire(output)?;
+
+    let manifests = find_manifests(output);
+    println!("📂 找到切片清单: {}个", manifests.len());
+
+    let mut keep_list = BufWriter::new(std::fs::File::create(output.join(KEEP_LIST_FILE_NAME))?);
+    let mut drop_list = BufWriter::new(std::fs::File::create(output.join(DROP_LIST_FILE_NAME))?);
+    let mut kept = 0;
+    let mut dropped = 0;
+
+    for manifest_path in &manifests {
+        let mut manifest = read_manifest(manifest_path)?;
+        let dir = manifest_path.parent().unwrap_or(output);
+        let relative_dir = dir.strip_prefix(output)?;
+
+        let mut retained = Vec::with_capacity(manifest.slices.len());
+        for record in std::mem::take(&mut manifest.slices) {
+            let relative = relative_dir.join(&record.file);
+            match criteria.rejection_reason(&record) {
+                None => {
+                    writeln!(keep_list, "{}", relative.display())?;
+                    kept += 1;
+                    retained.push(record);
+                }
+                Some(reason) => {
+                    writeln!(drop_list, "{}\t{reason}", relative.display())?;
+                    dropped += 1;
+                    if let Some(rejected_dir) = &args.move_rejected {
+                        let files = std::iter::once(&record.file)
+                            .chain(record.augmented.iter().map(|augmented| &augmented.file))
+                            .chain(&record.peaks);
+                        for file in files {
+                            let from = dir.join(file);
+                            if from.exists() {
+                                move_file(&from, &rejected_dir.join(relative_dir).join(file))?;
+                            }
+                        }
+                    } else {
+                        retained.push(record);
+                    }
+                }
+            }
+        }
+
+        manifest.slices = retained;
+        if args.move_rejected.is_some() {
+            write_manifest(manifest_path, &manifest)?;
+        }
+    }
+    keep_list.flush()?;
+    drop_list.flush()?;
+
+    println!("📊 重新筛选结果:");
+    println!("   - 保留切片: {kept} 个");
+    println!("   - 剔除切片: {dropped} 个");
+    println!(
+        "   - 列表: {}, {}",
+        output.join(KEEP_LIST_FILE_NAME).display(),
+        output.join(DROP_LIST_FILE_NAME).display()
+    );
+    if let Some(rejected_dir) = &args.move_rejected {
+        println!("   - 剔除的切片已移动到: {}", rejected_dir.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SliceCriteria;
+    use crate::manifest::{SliceRecord, test_manifest};
+
+    fn record(duration_sec: f64, rms_db: f32, rt60: Option<f32>) -> SliceRecord {
+        SliceRecord {
+            duration_sec,
+            rms_db,
+            rt60,
+            ..test_manifest(&["x.wav"]).slices.remove(0)
+        }
+    }
+
+    #[test]
+    fn applies_each_bound() {
+        let criteria = SliceCriteria {
+            min_duration_sec: Some(1.0),
+            max_duration_sec: Some(10.0),
+            min_rms_db: Some(-40.0),
+            ..Default::default()
+        };
+        assert!(
+            criteria
+                .rejection_reason(&record(3.0, -20.0, None))
+                .is_none()
+        );
+        assert!(
+            criteria
+                .rejection_reason(&record(0.5, -20.0, None))
+                .is_some()
+        );
+        assert!(
+            criteria
+                .rejection_reason(&record(12.0, -20.0, None))
+                .is_some()
+        );
+        assert!(
+            criteria
+                .rejection_reason(&record(3.0, -50.0, None))
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn missing_metrics_pass() {
+        let criteria = SliceCriteria {
+            max_rt60: Some(0.5),
+            ..Default::default()
+        };
+        assert!(
+            criteria
+                .rejection_reason(&record(3.0, -20.0, None))
+                .is_none()
+        );
+        assert!(
+            criteria
+                .rejection_reason(&record(3.0, -20.0, Some(0.8)))
+                .is_some()
+        );
+
+        // 只保留掌声时，没有分类结果的切片被剔除
+        let applause_only = SliceCriteria {
+            min_applause_score: Some(0.5),
+            ..Default::default()
+        };
+        let mut applause = record(3.0, -20.0, None);
+        assert!(applause_only.rejection_reason(&applause).is_some());
+        applause.applause_score = Some(0.9);
+        assert!(applause_only.rejection_reason(&applause).is_none());
+    }
+}
@@ -0,0 +1,174 @@
+use clap::ValueEnum;
+
+/// 报告中数字的区域格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Locale {
+    /// 中文: 1,234,567.8
+    #[default]
+    Zh,
+    /// 英文: 1,234,567.8
+    En,
+    /// 德文: 1.234.567,8
+    De,
+    /// 法文: 1 234 567,8
+    Fr,
+    /// 根据 LC_ALL / LC_NUMERIC / LANG 环境变量选择
+    Auto,
+}
+
+impl Locale {
+    /// 解析 `Auto`，无法识别的环境变量回退为中文
+    fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        let value = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        match value.get(..2) {
+            Some("en") => Self::En,
+            Some("de") => Self::De,
+            Some("fr") => Self::Fr,
+            _ => Self::Zh,
+        }
+    }
+
+    /// (千位分隔符, 小数点)
+    fn separators(self) -> (&'static str, char) {
+        match self {
+            Self::De => (".", ','),
+            // 法文使用窄不换行空格分组
+            Self::Fr => ("\u{202f}", ','),
+            Self::Zh | Self::En | Self::Auto => (",", '.'),
+        }
+    }
+}
+
+/// 时长的显示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DurationStyle {
+    /// 按大小选择单位: 12.5ms / 3.20s / 2m5.0s
+    #[default]
+    Human,
+    /// 不带单位、不分组的秒数 (3 位小数)，便于粘贴到表格
+    Seconds,
+}
+
+/// 报告中时长、百分比与计数的格式化
+#[derive(Debug, Clone, Copy)]
+pub struct NumberFormat {
+    locale: Locale,
+    durations: DurationStyle,
+}
+
+impl NumberFormat {
+    pub fn new(locale: Locale, durations: DurationStyle) -> Self {
+        Self {
+            locale: locale.resolve(),
+            durations,
+        }
+    }
+
+    /// 固定小数位数的数字，整数部分按区域分组
+    pub fn decimal(&self, value: f64, decimals: usize) -> String {
+        self.format_number(value, decimals, true)
+    }
+
+    fn format_number(&self, value: f64, decimals: usize, grouped: bool) -> String {
+        let (group, point) = self.locale.separators();
+        let text = format!("{:.decimals$}", value.abs());
+        let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let mut out = String::new();
+        if value.is_sign_negative() && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            out.push('-');
+        }
+        for (i, digit) in integer.chars().enumerate() {
+            if grouped && i > 0 && (integer.len() - i) % 3 == 0 {
+                out.push_str(group);
+            }
+            out.push(digit);
+        }
+        if !fraction.is_empty() {
+            out.push(point);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// 计数
+    pub fn count(&self, value: usize) -> String {
+        self.decimal(value as f64, 0)
+    }
+
+    /// 百分比，`ratio` 为 0~1 的比例；德文与法文在数字与 % 之间加空格
+    pub fn percent(&self, ratio: f64) -> String {
+        let number = self.decimal(ratio * 100.0, 1);
+        match self.locale {
+            Locale::De | Locale::Fr => format!("{number}\u{a0}%"),
+            _ => format!("{number}%"),
+        }
+    }
+
+    /// 时长
+    pub fn duration(&self, secs: f64) -> String {
+        if self.durations == DurationStyle::Seconds {
+            return self.format_number(secs, 3, false);
+        }
+        if secs < 1.0 {
+            format!("{}ms", self.decimal(secs * 1000.0, 1))
+        } else if secs < 60.0 {
+            format!("{}s", self.decimal(secs, 2))
+        } else {
+            let minutes = (secs / 60.0) as u64;
+            format!(
+                "{}m{}s",
+                self.count(minutes as usize),
+                self.decimal(secs % 60.0, 1)
+            )
+        }
+    }
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::new(Locale::Zh, DurationStyle::Human)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DurationStyle, Locale, NumberFormat};
+
+    #[test]
+    fn formats_by_locale() {
+        let zh = NumberFormat::default();
+        assert_eq!(zh.count(1234567), "1,234,567");
+        assert_eq!(zh.decimal(-1234.5, 2), "-1,234.50");
+        assert_eq!(zh.decimal(-0.01, 1), "0.0");
+        assert_eq!(zh.percent(0.125), "12.5%");
+
+        let de = NumberFormat::new(Locale::De, DurationStyle::Human);
+        assert_eq!(de.count(1234567), "1.234.567");
+        assert_eq!(de.percent(0.125), "12,5\u{a0}%");
+        assert_eq!(de.duration(3.2), "3,20s");
+
+        let fr = NumberFormat::new(Locale::Fr, DurationStyle::Human);
+        assert_eq!(fr.decimal(1234.5, 1), "1\u{202f}234,5");
+    }
+
+    #[test]
+    fn durations() {
+        let human = NumberFormat::default();
+        assert_eq!(human.duration(0.0125), "12.5ms");
+        assert_eq!(human.duration(3.2), "3.20s");
+        assert_eq!(human.duration(125.0), "2m5.0s");
+
+        let seconds = NumberFormat::new(Locale::De, DurationStyle::Seconds);
+        assert_eq!(seconds.duration(4125.5), "4125,500");
+        let seconds = NumberFormat::new(Locale::En, DurationStyle::Seconds);
+        assert_eq!(seconds.duration(0.0126), "0.013");
+    }
+}
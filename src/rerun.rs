@@ -0,0 +1,145 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::atomic::{check_removal, write_atomic};
+use crate::catalog::Catalog;
+use crate::manifest::MANIFEST_FILE_NAME;
+
+/// 失败文件列表的文件名 (写在输出目录下)
+pub const ERRORS_FILE_NAME: &str = "errors.json";
+
+/// 失败文件列表中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedFile {
+    pub file: String,
+    pub error: String,
+}
+
+/// 把本次运行的失败文件合并进失败文件列表；合并后为空时删除列表 (安全模式下报错)
+///
+/// `processed` 为本次运行处理过 (成功或失败) 的文件：列表中这些文件的旧记录被本次
+/// 结果取代，其他文件的记录 (例如同时追加写入同一输出目录的其他运行) 保留。
+/// 调用方应持有输出目录的索引锁。
+pub fn write_errors(
+    output: &Path,
+    processed: &HashSet<String>,
+    failures: &[(PathBuf, String)],
+) -> Result<()> {
+    let path = output.join(ERRORS_FILE_NAME);
+    let mut entries: Vec<FailedFile> = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| anyhow::anyhow!("无法读取失败文件列表 {}: {e}", path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    entries.retain(|entry| !processed.contains(&entry.file));
+    entries.extend(failures.iter().map(|(file, error)| FailedFile {
+        file: file.to_string_lossy().into_owned(),
+        error: error.clone(),
+    }));
+    if entries.is_empty() {
+        if path.exists() {
+            check_removal(&path)?;
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    std::fs::create_dir_all(output)?;
+    write_atomic(&path, |writer| {
+        Ok(serde_json::to_writer_pretty(writer, &entries)?)
+    })
+}
+
+/// 读取需要重试的文件: `.json` 视为失败文件列表，其他文件视为目录数据库
+pub fn read_failed(source: &Path) -> Result<HashSet<String>> {
+    if !source.is_file() {
+        return Err(anyhow::anyhow!("失败列表不存在: {}", source.display()));
+    }
+    let is_json = source
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        let entries: Vec<FailedFile> = serde_json::from_slice(&std::fs::read(source)?)?;
+        Ok(entries.into_iter().map(|entry| entry.file).collect())
+    } else {
+        Ok(Catalog::open(source)?
+            .failed_sources()?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// `--only-failed` / `--only-new` 的文件选择，两者同时指定时取并集
+pub struct FileSelection {
+    /// 需要重试的失败文件
+    pub failed: Option<HashSet<String>>,
+    /// 只处理新文件；`Some(known)` 时按目录数据库中已记录的源文件判断，
+    /// `None` 时按输出目录中是否已有切片清单判断
+    pub only_new: Option<Option<HashSet<String>>>,
+}
+
+impl FileSelection {
+    /// 文件是否需要处理，`output_dir` 为该文件的切片输出目录
+    pub fn accepts(&self, file: &Path, output_dir: &Path) -> bool {
+        let key = file.to_string_lossy();
+        let failed = self
+            .failed
+            .as_ref()
+            .is_some_and(|failed| failed.contains(key.as_ref()));
+        let new = match &self.only_new {
+            Some(Some(known)) => !known.contains(key.as_ref()),
+            Some(None) => !output_dir.join(MANIFEST_FILE_NAME).is_file(),
+            None => false,
+        };
+        failed || new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileSelection, read_failed, write_errors};
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn errors_round_trip() {
+        let dir = std::env::temp_dir().join(format!("slicer_rerun_{}", std::process::id()));
+        let processed = HashSet::from(["in/a.m4a".to_string()]);
+        let failures = [(PathBuf::from("in/a.m4a"), "解码失败".to_string())];
+        write_errors(&dir, &processed, &failures).unwrap();
+        let failed = read_failed(&dir.join(super::ERRORS_FILE_NAME)).unwrap();
+        assert_eq!(failed, HashSet::from(["in/a.m4a".to_string()]));
+
+        // 另一运行只处理了 in/b.m4a: 保留 in/a.m4a 的记录
+        let processed_b = HashSet::from(["in/b.m4a".to_string()]);
+        let failures_b = [(PathBuf::from("in/b.m4a"), "文件损坏".to_string())];
+        write_errors(&dir, &processed_b, &failures_b).unwrap();
+        let failed = read_failed(&dir.join(super::ERRORS_FILE_NAME)).unwrap();
+        assert_eq!(
+            failed,
+            HashSet::from(["in/a.m4a".to_string(), "in/b.m4a".to_string()])
+        );
+
+        write_errors(&dir, &processed, &[]).unwrap();
+        write_errors(&dir, &processed_b, &[]).unwrap();
+        assert!(!dir.join(super::ERRORS_FILE_NAME).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn selection_is_union() {
+        let selection = FileSelection {
+            failed: Some(HashSet::from(["in/a.wav".to_string()])),
+            only_new: Some(Some(HashSet::from([
+                "in/a.wav".to_string(),
+                "in/b.wav".to_string(),
+            ]))),
+        };
+        let out = Path::new("out");
+        assert!(selection.accepts(Path::new("in/a.wav"), out));
+        assert!(!selection.accepts(Path::new("in/b.wav"), out));
+        assert!(selection.accepts(Path::new("in/c.wav"), out));
+    }
+}
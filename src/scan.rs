@@ -0,0 +1,192 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::archive::{is_archive_file, list_audio_members};
+
+/// 检查文件是否为支持的音频格式
+pub fn is_audio_file(path: &Path) -> bool {
+    if let Some(ext) = path.extension() {
+        let ext_str = ext.to_string_lossy().to_lowercase();
+        matches!(
+            ext_str.as_str(),
+            "wav" | "mp3" | "flac" | "m4a" | "aac" | "ogg"
+        )
+    } else {
+        false
+    }
+}
+
+/// 扫描结果统计
+#[derive(Debug, Default)]
+pub struct ScanStats {
+    /// 找到的音频文件数
+    pub files: usize,
+    /// 遍历的目录数
+    pub dirs: usize,
+    /// 无法读取的目录及错误信息
+    pub unreadable: Vec<(PathBuf, String)>,
+}
+
+/// 并行遍历时各任务共享的状态
+struct Walker<'a> {
+    on_file: &'a (dyn Fn(PathBuf) + Sync),
+    on_progress: &'a (dyn Fn(usize, usize) + Sync),
+    read_archives: bool,
+    files: AtomicUsize,
+    dirs: AtomicUsize,
+    unreadable: Mutex<Vec<(PathBuf, String)>>,
+}
+
+impl Walker<'_> {
+    fn visit<'s>(&'s self, scope: &rayon::Scope<'s>, dir: PathBuf) {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.unreadable.lock().unwrap().push((dir, e.to_string()));
+                return;
+            }
+        };
+        let dirs = self.dirs.fetch_add(1, Ordering::Relaxed) + 1;
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.unreadable
+                        .lock()
+                        .unwrap()
+                        .push((dir.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            // 与 walkdir 默认行为一致: 不跟随符号链接
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                scope.spawn(move |scope| self.visit(scope, path));
+            } else if file_type.is_file() && is_audio_file(&path) {
+                self.found(path, dirs);
+            } else if file_type.is_file() && self.read_archives && is_archive_file(&path) {
+                match list_audio_members(&path) {
+                    Ok(members) => members.into_iter().for_each(|m| self.found(m, dirs)),
+                    Err(e) => self.unreadable.lock().unwrap().push((path, e.to_string())),
+                }
+            }
+        }
+    }
+
+    fn found(&self, path: PathBuf, dirs: usize) {
+        let files = self.files.fetch_add(1, Ordering::Relaxed) + 1;
+        (self.on_file)(path);
+        (self.on_progress)(dirs, files);
+    }
+}
+
+/// 收集音频文件: 输入为目录时用 `threads` 个线程并行递归遍历子目录
+///
+/// `read_archives` 时压缩包 (zip/tar) 中的音频成员也作为输入，以 `压缩包路径/成员路径`
+/// 的虚拟路径给出，用 [`crate::archive::decode_input`] 解码。每发现一个音频文件立即调用 `on_file`，调用方可以边扫描边处理；`on_progress`
+/// 收到已遍历的目录数与已找到的文件数。文件的发现顺序不固定。无法读取的子目录
+/// 不会中断扫描，记录在返回的统计中。
+pub fn scan_audio_files(
+    input_path: &Path,
+    threads: usize,
+    read_archives: bool,
+    on_file: &(dyn Fn(PathBuf) + Sync),
+    on_progress: &(dyn Fn(usize, usize) + Sync),
+) -> Result<ScanStats> {
+    if input_path.is_file() && read_archives && is_archive_file(input_path) {
+        let members = list_audio_members(input_path)?;
+        for member in &members {
+            on_file(member.clone());
+        }
+        on_progress(0, members.len());
+        return Ok(ScanStats {
+            files: members.len(),
+            ..Default::default()
+        });
+    }
+    if input_path.is_file() {
+        if !is_audio_file(input_path) {
+            return Err(anyhow::anyhow!("输入文件不是支持的音频格式"));
+        }
+        on_file(input_path.to_path_buf());
+        on_progress(0, 1);
+        return Ok(ScanStats {
+            files: 1,
+            ..Default::default()
+        });
+    }
+    if !input_path.is_dir() {
+        return Err(anyhow::anyhow!("输入路径不存在或无法访问"));
+    }
+    // 根目录不可读时直接报错
+    std::fs::read_dir(input_path)?;
+
+    let walker = Walker {
+        on_file,
+        on_progress,
+        read_archives,
+        files: AtomicUsize::new(0),
+        dirs: AtomicUsize::new(0),
+        unreadable: Mutex::new(Vec::new()),
+    };
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()?;
+    pool.scope(|scope| walker.visit(scope, input_path.to_path_buf()));
+
+    let mut unreadable = walker.unreadable.into_inner().unwrap();
+    unreadable.sort();
+    Ok(ScanStats {
+        files: walker.files.into_inner(),
+        dirs: walker.dirs.into_inner(),
+        unreadable,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scan_audio_files;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    #[test]
+    fn finds_nested_audio_files() {
+        let root = std::env::temp_dir().join(format!("slicer_scan_{}", std::process::id()));
+        for dir in ["a/b/c", "d"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["x.wav", "a/y.FLAC", "a/b/c/z.mp3", "d/notes.txt"] {
+            std::fs::write(root.join(file), b"").unwrap();
+        }
+
+        let found = Mutex::new(Vec::new());
+        let stats = scan_audio_files(
+            &root,
+            4,
+            false,
+            &|path: PathBuf| found.lock().unwrap().push(path),
+            &|_, _| {},
+        )
+        .unwrap();
+        let mut found = found.into_inner().unwrap();
+        found.sort();
+
+        assert_eq!(stats.files, 3);
+        assert_eq!(stats.dirs, 5);
+        assert!(stats.unreadable.is_empty());
+        assert_eq!(
+            found,
+            vec![
+                root.join("a/b/c/z.mp3"),
+                root.join("a/y.FLAC"),
+                root.join("x.wav")
+            ]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,7 +1,109 @@
 use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::path::Path;
+
+use crate::audio::Sample;
+use crate::hook::{HookedSegment, Segment, SegmentHook};
+use crate::pitch::{frame_pitch, semitones};
+
+/// 静音检测指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum DetectionMetric {
+    /// 窗口均方根能量
+    #[default]
+    Rms,
+    /// 窗口峰值幅度，能捕捉 RMS 容易漏掉的短促瞬态
+    Peak,
+    /// RMS 与峰值任一超过各自阈值即视为有声
+    Hybrid,
+}
+
+/// 切片合并策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum MergeMode {
+    /// 贪心合并，直到达到最大合并时长
+    #[default]
+    Greedy,
+    /// 按停顿结构合并：不跨越长停顿，超出时长时在最长的停顿处断开
+    Sentence,
+    /// 贪心打包，使每个切片尽量接近目标时长
+    Target,
+    /// 动态规划打包，使所有切片与目标时长的偏差平方和最小
+    TargetOptimal,
+}
+
+/// 超长切片的断开方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum MaxDurationMode {
+    /// 按最大时长等长硬切
+    #[default]
+    Hard,
+    /// 在后半段窗口内选择能量最低、且不处于持续音高 (长音) 中的帧断开，适合歌声
+    Phrase,
+    /// 在后半段窗口内选择能量最低的帧断开，适合没有停顿的连续语音
+    Quietest,
+}
+
+/// 切片方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum SliceMode {
+    /// 按静音检测切分
+    #[default]
+    Silence,
+    /// 不做静音检测，按固定时长的 (可重叠) 窗口切分，适合分类器训练数据
+    Fixed,
+}
+
+/// 有声/静音的判定方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Detector {
+    /// 能量阈值 (指标见 --metric)
+    #[default]
+    Rms,
+    /// 语音活动检测模型 (Silero VAD)，适合底噪较高的录音
+    Vad,
+    /// 频谱门限: 逐频带与估计的噪声底比较，适合带有恒定宽带底噪 (嘶声) 的录音
+    Spectral,
+}
+
+/// 静音阈值的确定方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ThresholdMode {
+    /// 所有文件使用同一个阈值 (--threshold-db)
+    #[default]
+    Fixed,
+    /// 按每个文件的噪声底确定阈值，适合录音条件差异大的语料 (录音棚与手机录音混合)
+    Auto,
+}
+
+/// 自动阈值以帧 RMS 的该百分位作为噪声底
+pub const NOISE_FLOOR_PERCENTILE: usize = 10;
+/// 自动阈值的下限 (dB)，数字静音的噪声底不会把阈值拉得过低
+pub const AUTO_THRESHOLD_MIN_DB: f32 = -80.0;
+/// 自动阈值的上限 (dB)，几乎没有停顿的录音中噪声底估计偏高时不会把语音判为静音
+pub const AUTO_THRESHOLD_MAX_DB: f32 = -25.0;
+
+/// 由逐帧 RMS (见 `Slicer::frame_rms`) 估计噪声底 (dB)，没有帧时返回 `None`
+pub fn noise_floor_db(frame_rms: &[f32]) -> Option<f32> {
+    let mut sorted = frame_rms.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let index = sorted.len().checked_sub(1)? * NOISE_FLOOR_PERCENTILE / 100;
+    Some(power_db(sorted[index] * sorted[index]))
+}
+
+/// 自动阈值: 噪声底加上 `offset_db`，限制在 [`AUTO_THRESHOLD_MIN_DB`, `AUTO_THRESHOLD_MAX_DB`] 内
+pub fn auto_threshold_db(noise_floor_db: f32, offset_db: f32) -> f32 {
+    (noise_floor_db + offset_db).clamp(AUTO_THRESHOLD_MIN_DB, AUTO_THRESHOLD_MAX_DB)
+}
 
 /// 音频切片器配置参数
-#[derive(Debug, Clone)]
+///
+/// 默认值与命令行 `slice` 子命令的默认参数一致，采样率默认为 44100 Hz；
+/// 构造时只需写出要改动的字段，其余用 `..Default::default()` 补足。
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct SlicerConfig {
     pub sample_rate: u32,
     pub threshold_db: f32,
@@ -9,16 +111,74 @@ pub struct SlicerConfig {
     pub min_interval_ms: u32,
     pub hop_size_ms: u32,
     pub max_silence_ms: u32,
+    pub metric: DetectionMetric,
+    pub peak_threshold_db: f32,
+    /// 粗扫描步长相对于 hop 的倍数，1 表示禁用多分辨率分析 (见 `Slicer::frame_silence`)
+    pub coarse_hop_factor: u32,
+    /// 把切点移到所在静音段中能量最低的帧
+    pub refine_cuts: bool,
+}
+
+impl Default for SlicerConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 44100,
+            threshold_db: -55.0,
+            min_length_ms: 1000,
+            min_interval_ms: 100,
+            hop_size_ms: 5,
+            max_silence_ms: 800,
+            metric: DetectionMetric::Rms,
+            peak_threshold_db: -45.0,
+            coarse_hop_factor: 8,
+            refine_cuts: false,
+        }
+    }
+}
+
+/// 检测之后的切片边界处理参数，0 表示禁用对应步骤
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BoundaryConfig {
+    /// 超过该时长 (ms) 的切片用更严格的阈值重新检测
+    pub relax_length: u32,
+    pub relax_step_db: f32,
+    pub relax_max_iterations: u32,
+    pub enable_merge: bool,
+    pub max_merge_duration_ms: u32,
+    pub merge_mode: MergeMode,
+    pub sentence_pause_ms: u32,
+    pub target_duration_ms: u32,
+    pub onset_backoff_ms: u32,
+    pub min_gap_ms: u32,
+    pub max_duration_ms: u32,
+    pub max_duration_mode: MaxDurationMode,
+    /// 相邻切片的重叠时长 (ms)
+    pub overlap_ms: u32,
+    /// 切片边界对齐过零点的最大移动距离 (ms)，见 `snap_to_zero_crossings`
+    pub zero_cross_max_shift_ms: u32,
+}
+
+/// 将毫秒换算为以 `hop_size` 为单位的帧数
+pub fn ms_to_frames(ms: u32, sample_rate: u32, hop_size: usize) -> usize {
+    (sample_rate as f32 * ms as f32 / 1000.0 / hop_size as f32).round() as usize
 }
 
 /// 音频切片器
+#[derive(Debug, Clone)]
 pub struct Slicer {
+    sample_rate: u32,
     hop_size: usize,
     win_size: usize,
     min_length: usize,
     min_interval: usize,
     max_silence: usize,
-    threshold: f32,
+    threshold_db: f32,
+    metric: DetectionMetric,
+    peak_threshold_db: f32,
+    coarse_hop_factor: usize,
+    refine_cuts: bool,
+    /// `feed` 在调用之间延续的流式状态
+    stream: StreamBuffer,
 }
 
 impl Slicer {
@@ -38,8 +198,12 @@ impl Slicer {
         let min_interval =
             (cfg.sample_rate as f32 * cfg.min_interval_ms as f32 / 1000.0).round() as usize;
         let win_size = min_interval.min(4 * hop_size);
+        let max_silence =
+            (cfg.sample_rate as f32 * cfg.max_silence_ms as f32 / 1000.0 / hop_size as f32).round()
+                as usize;
 
         Ok(Self {
+            sample_rate: cfg.sample_rate,
             hop_size,
             win_size,
             min_length: (cfg.sample_rate as f32 * cfg.min_length_ms as f32
@@ -47,61 +211,1081 @@ impl Slicer {
                 / hop_size as f32)
                 .round() as usize,
             min_interval: (min_interval as f32 / hop_size as f32).round() as usize,
-            max_silence: (cfg.sample_rate as f32 * cfg.max_silence_ms as f32
-                / 1000.0
-                / hop_size as f32)
-                .round() as usize,
-            threshold: 10f32.powf(cfg.threshold_db / 20.0), // dB转线性值
+            max_silence,
+            threshold_db: cfg.threshold_db,
+            metric: cfg.metric,
+            peak_threshold_db: cfg.peak_threshold_db,
+            // 有声块内部的短停顿不逐帧分析，限制步长使其不会长到产生切点
+            coarse_hop_factor: (cfg.coarse_hop_factor as usize).min(max_silence / 2).max(1),
+            refine_cuts: cfg.refine_cuts,
+            stream: StreamBuffer::default(),
         })
     }
 
+    /// 为 `feed` 设置输出延迟上限: 连续有声达到 `ms` 时不再等待停顿，直接输出切片
+    /// (见 `SliceState::with_max_latency`)，同时限制了缓冲的样本数
+    pub fn with_max_latency_ms(mut self, ms: u32) -> Self {
+        self.stream.state = SliceState::with_max_latency(self.ms_to_frames(ms));
+        self
+    }
+
     pub fn hop_size(&self) -> usize {
         self.hop_size
     }
 
-    /// 执行音频切片
-    pub fn slice(&self, samples: &[f32]) -> Vec<(usize, usize)> {
+    /// 计算每帧分析窗口的RMS能量
+    pub fn frame_rms<S: Sample>(&self, samples: &[S]) -> Vec<f32> {
         let frame_count = samples.len().div_ceil(self.hop_size);
-        let mut chunks = vec![];
-
-        // 计算RMS能量
-        let rms: Vec<f32> = (0..frame_count)
+        (0..frame_count)
             .map(|i| {
                 let start = i * self.hop_size;
                 let end = (start + self.win_size).min(samples.len());
-                let slice = &samples[start..end];
-                (slice.iter().map(|&x| x * x).sum::<f32>() / slice.len() as f32).sqrt()
+                window_stats(&samples[start..end])
+                    .map_or(0.0, |(mean_square, _)| mean_square.sqrt())
             })
-            .collect();
+            .collect()
+    }
+
+    /// 判断每帧是否为静音
+    ///
+    /// 启用多分辨率分析时，以 `coarse_hop_factor` 帧为一块，由逐 hop 的能量前缀和以 O(1)
+    /// 得到整块能量，把整块判为静音 (块内每个窗口都保证低于阈值)、有声 (块内至少一个窗口
+    /// 高于阈值) 或不确定。只有不确定的块，以及与非有声块相邻的有声块 (有声/静音交界处)
+    /// 才逐帧精细分析，其余块整块标记。
+    ///
+    /// 有声块内部的停顿不会被精细分析，这样的停顿短于两块；粗扫描步长限制在最大静音长度的
+    /// 一半以内，它们不会产生切点，切片结果与逐帧分析一致。
+    pub fn frame_silence<S: Sample>(&self, samples: &[S]) -> Vec<bool> {
+        self.frame_silence_range(samples, 0..self.frame_count(samples.len()))
+    }
+
+    /// 仅对 `frames` 范围内的帧做静音判定，用于分块分析
+    ///
+    /// 粗扫描的块按全局帧号对齐，结果与范围如何划分无关。
+    pub fn frame_silence_range<S: Sample>(&self, samples: &[S], frames: Range<usize>) -> Vec<bool> {
+        let fine = |i: usize| {
+            let start = i * self.hop_size;
+            let end = (start + self.win_size).min(samples.len());
+            self.is_silent_window(&samples[start..end])
+        };
 
-        // 检测静音段并切片
+        let factor = self.coarse_hop_factor;
+        if factor <= 1 || frames.is_empty() {
+            return frames.map(fine).collect();
+        }
+
+        let first_block = frames.start / factor;
+        let last_block = (frames.end - 1) / factor;
+        // 判断是否处于交界处还需要前后相邻块的分类
+        let block_count = self.frame_count(samples.len()).div_ceil(factor);
+        let blocks = first_block.saturating_sub(1)..(last_block + 2).min(block_count);
+        let classes = self.classify_blocks(samples, blocks.clone());
+        let is_voiced = |block: Option<usize>| {
+            block.is_some_and(|b| blocks.contains(&b) && classes[b - blocks.start] == Block::Voiced)
+        };
+
+        let mut silence = Vec::with_capacity(frames.len());
+        for b in first_block..=last_block {
+            let block_frames = (b * factor).max(frames.start)..((b + 1) * factor).min(frames.end);
+            match classes[b - blocks.start] {
+                Block::Silent => silence.extend(std::iter::repeat_n(true, block_frames.len())),
+                Block::Voiced if is_voiced(b.checked_sub(1)) && is_voiced(Some(b + 1)) => {
+                    silence.extend(std::iter::repeat_n(false, block_frames.len()))
+                }
+                _ => silence.extend(block_frames.map(fine)),
+            }
+        }
+        silence
+    }
+
+    /// 粗扫描: 对 `blocks` 中的每块 (`coarse_hop_factor` 帧) 做整块判定
+    ///
+    /// 先计算每个 hop 长度样本段的平方和与峰值，并对平方和求前缀和；每块的能量为前缀和之差
+    /// 加上末尾不足一个 hop 的剩余样本。含 NaN/inf 或末尾窗口被截断的块判为不确定。
+    fn classify_blocks<S: Sample>(&self, samples: &[S], blocks: Range<usize>) -> Vec<Block> {
+        let factor = self.coarse_hop_factor;
+        let hop = self.hop_size;
+        let frame_count = self.frame_count(samples.len());
+        let first_piece = blocks.start * factor;
+        let end_piece = (blocks.end * factor + self.win_size / hop).min(frame_count);
+
+        // 平方和与峰值，含 NaN/inf 时为 `None`
+        let span_stats = |span: &[S]| {
+            window_stats(span).map(|(mean_square, peak)| (mean_square * span.len() as f32, peak))
+        };
+        let mut energy = vec![0.0f64];
+        let mut non_finite = vec![0usize];
+        let mut peaks = Vec::with_capacity(end_piece - first_piece);
+        for piece in first_piece..end_piece {
+            let start = piece * hop;
+            let stats = span_stats(&samples[start..(start + hop).min(samples.len())]);
+            let (sum, peak) = stats.unwrap_or_default();
+            energy.push(energy[energy.len() - 1] + sum as f64);
+            non_finite.push(non_finite[non_finite.len() - 1] + usize::from(stats.is_none()));
+            peaks.push(peak);
+        }
+
+        blocks
+            .map(|b| {
+                let first = b * factor;
+                let last = ((b + 1) * factor).min(frame_count);
+                let span_end = (last - 1) * hop + self.win_size;
+                // 末尾被截断的窗口长度不足，上界不成立
+                if span_end > samples.len() {
+                    return Block::Uncertain;
+                }
+                let pieces = first - first_piece..span_end / hop - first_piece;
+                let rest = &samples[span_end / hop * hop..span_end];
+                let rest_stats = if rest.is_empty() {
+                    Some((0.0, 0.0))
+                } else {
+                    span_stats(rest)
+                };
+                let Some((rest_sum, rest_peak)) = rest_stats else {
+                    return Block::Uncertain;
+                };
+                if non_finite[pieces.end] > non_finite[pieces.start] {
+                    return Block::Uncertain;
+                }
+                let sum = (energy[pieces.end] - energy[pieces.start] + rest_sum as f64) as f32;
+                let peak = peaks[pieces].iter().fold(rest_peak, |acc, &p| acc.max(p));
+
+                // 任一窗口的能量不超过整块能量。块内每隔 `stride` 帧取一个窗口 (加上最后一帧
+                // 的窗口) 即可覆盖整块，其中能量最大的窗口不低于整块能量的 1/`windows`；
+                // 峰值所在窗口的峰值即整块峰值
+                let stride = self.win_size / hop * hop;
+                let windows = (span_end - first * hop - self.win_size).div_ceil(stride) + 1;
+                if self.is_below_threshold(sum / self.win_size as f32, peak) {
+                    Block::Silent
+                } else if !self.is_below_threshold(sum / (windows * self.win_size) as f32, peak) {
+                    Block::Voiced
+                } else {
+                    Block::Uncertain
+                }
+            })
+            .collect()
+    }
+
+    /// 按检测指标判断单个分析窗口是否为静音
+    ///
+    /// 含 NaN/inf 样本的窗口视为静音 (跳过该帧)，避免非有限值污染阈值判断。
+    fn is_silent_window<S: Sample>(&self, window: &[S]) -> bool {
+        window_stats(window)
+            .is_none_or(|(mean_square, peak)| self.is_below_threshold(mean_square, peak))
+    }
+
+    /// 在 dB 域比较均方值与峰值是否低于阈值
+    fn is_below_threshold(&self, mean_square: f32, peak: f32) -> bool {
+        let rms_ok = || power_db(mean_square) < self.threshold_db;
+        let peak_ok = || power_db(peak * peak) < self.peak_threshold_db;
+
+        match self.metric {
+            DetectionMetric::Rms => rms_ok(),
+            DetectionMetric::Peak => peak_ok(),
+            DetectionMetric::Hybrid => rms_ok() && peak_ok(),
+        }
+    }
+
+    /// 返回所有低于阈值的连续静音帧区间 `(start_frame, end_frame)`
+    pub fn silent_ranges<S: Sample>(&self, samples: &[S]) -> Vec<(usize, usize)> {
+        let mut ranges = vec![];
         let mut silence_start = None;
-        let mut clip_start = 0;
 
-        for (i, &rms_val) in rms.iter().enumerate() {
-            if rms_val < self.threshold {
-                if silence_start.is_none() {
-                    silence_start = Some(i);
+        for (i, &silent) in self.frame_silence(samples).iter().enumerate() {
+            if silent {
+                silence_start.get_or_insert(i);
+            } else if let Some(start) = silence_start.take() {
+                ranges.push((start, i));
+            }
+        }
+        if let Some(start) = silence_start {
+            ranges.push((start, samples.len().div_ceil(self.hop_size)));
+        }
+
+        ranges
+    }
+
+    /// 起点回退：从切片中第一个有声帧 (起音) 出发，沿能量持续下降的方向最多向前回退
+    /// `max_frames` 帧，回退到的位置早于原起点时取代原起点
+    ///
+    /// 检测得到的起点通常落在起音之前的静音中，但低于阈值、能量逐渐上升的起始辅音可能
+    /// 比保留的静音更长；回退保证这段渐强完整地留在切片内。回退后的起点可能与前一切片
+    /// 的尾部重叠。
+    pub fn apply_onset_backoff<S: Sample>(
+        &self,
+        samples: &[S],
+        chunks: &[(usize, usize)],
+        max_frames: usize,
+    ) -> Vec<(usize, usize)> {
+        if max_frames == 0 {
+            return chunks.to_vec();
+        }
+
+        let silence = self.frame_silence(samples);
+        let rms = self.frame_rms(samples);
+
+        chunks
+            .iter()
+            .map(|&(start, end)| {
+                let Some(onset) = (start..end.min(silence.len())).find(|&i| !silence[i]) else {
+                    return (start, end);
+                };
+                let mut new_start = onset;
+                while new_start > 0
+                    && onset - new_start < max_frames
+                    && rms[new_start - 1] < rms[new_start]
+                {
+                    new_start -= 1;
+                }
+                (new_start.min(start), end)
+            })
+            .collect()
+    }
+
+    /// 用与检测相同的帧分析计算一段样本区间的能量统计
+    ///
+    /// 以 hop 为步长在区间内逐帧计算均方值与峰值，`voiced_ratio` 为按检测指标判定为有声的帧占比。
+    /// 含 NaN/inf 的帧按静音计入。
+    pub fn segment_stats<S: Sample>(&self, samples: &[S], range: Range<usize>) -> SegmentStats {
+        let end = range.end.min(samples.len());
+        let mut energy_sum = 0.0;
+        let mut peak = 0.0f32;
+        let mut frames = 0;
+        let mut voiced_frames = 0;
+
+        for start in (range.start..end).step_by(self.hop_size) {
+            let window = &samples[start..(start + self.win_size).min(end)];
+            frames += 1;
+            if let Some((mean_square, window_peak)) = window_stats(window) {
+                energy_sum += mean_square;
+                peak = peak.max(window_peak);
+                if !self.is_below_threshold(mean_square, window_peak) {
+                    voiced_frames += 1;
+                }
+            }
+        }
+
+        if frames == 0 {
+            return SegmentStats {
+                rms_db: power_db(0.0),
+                peak_db: power_db(0.0),
+                voiced_ratio: 0.0,
+            };
+        }
+        SegmentStats {
+            rms_db: power_db(energy_sum / frames as f32),
+            peak_db: power_db(peak * peak),
+            voiced_ratio: voiced_frames as f32 / frames as f32,
+        }
+    }
+
+    /// 样本数对应的分析帧数
+    pub fn frame_count(&self, sample_count: usize) -> usize {
+        sample_count.div_ceil(self.hop_size)
+    }
+
+    /// 执行音频切片，返回每个切片的帧区间及其能量统计 (见 `segment_stats`)
+    ///
+    /// 按 `ANALYSIS_BLOCK_FRAMES` 帧分块计算静音判定并推进状态机，
+    /// 中间结果的内存占用与输入时长无关。
+    pub fn slice<S: Sample>(&self, samples: &[S]) -> Vec<((usize, usize), SegmentStats)> {
+        self.slice_frames(samples)
+            .into_iter()
+            .map(|(start, end)| {
+                let range = start * self.hop_size..(end * self.hop_size).min(samples.len());
+                ((start, end), self.segment_stats(samples, range))
+            })
+            .collect()
+    }
+
+    /// 同 `slice`，只返回帧区间
+    fn slice_frames<S: Sample>(&self, samples: &[S]) -> Vec<(usize, usize)> {
+        self.slice_in_blocks(samples, ANALYSIS_BLOCK_FRAMES, &mut ())
+    }
+
+    fn slice_in_blocks<S: Sample>(
+        &self,
+        samples: &[S],
+        block_frames: usize,
+        observer: &mut impl DetectionObserver,
+    ) -> Vec<(usize, usize)> {
+        let frame_count = self.frame_count(samples.len());
+        let mut state = SliceState::default();
+        let mut chunks = vec![];
+
+        for first in (0..frame_count).step_by(block_frames) {
+            let last = (first + block_frames).min(frame_count);
+            let silence = self.frame_silence_range(samples, first..last);
+            chunks.extend(self.advance(&mut state, &silence, observer));
+        }
+
+        chunks.extend(self.finish_state(state, observer));
+        if self.refine_cuts {
+            self.refine_cut_points(samples, &mut chunks);
+        }
+        chunks
+    }
+
+    /// 切点细化: 状态机在静音开始后 `min_interval` 帧处切分，该处可能仍是低电平的尾音。
+    /// 对每个由静音产生的切点，在静音段开头的 `max_silence` 帧内搜索 RMS 最低的帧
+    /// (能量相同时取离原切点最近的帧)，两侧切片的边界一起移到该帧。
+    ///
+    /// 切点之前 `min_interval` 帧不全是静音的边界 (文件首尾、最大延迟输出) 保持不变。
+    fn refine_cut_points<S: Sample>(&self, samples: &[S], chunks: &mut [(usize, usize)]) {
+        let frame_count = self.frame_count(samples.len());
+        let frame_stats = |i: usize| {
+            let start = i * self.hop_size;
+            window_stats(&samples[start..(start + self.win_size).min(samples.len())])
+        };
+
+        let mut moves = BTreeMap::new();
+        for &(start, end) in chunks.iter() {
+            for cut in [start, end] {
+                if cut == 0 || cut >= frame_count || cut < self.min_interval {
+                    continue;
+                }
+                let silence_start = cut - self.min_interval;
+                let search_end = (silence_start + self.max_silence).min(frame_count);
+                let mut best: Option<(f32, usize)> = None;
+                let mut frame = silence_start;
+                while frame < search_end {
+                    let stats = frame_stats(frame);
+                    if stats.is_some_and(|(mean_square, peak)| {
+                        !self.is_below_threshold(mean_square, peak)
+                    }) {
+                        break;
+                    }
+                    if let Some((mean_square, _)) = stats {
+                        let is_better = best.is_none_or(|(energy, best_frame)| {
+                            mean_square < energy
+                                || (mean_square == energy
+                                    && frame.abs_diff(cut) < best_frame.abs_diff(cut))
+                        });
+                        if is_better {
+                            best = Some((mean_square, frame));
+                        }
+                    }
+                    frame += 1;
+                }
+                if frame >= cut
+                    && let Some((_, refined)) = best
+                {
+                    moves.insert(cut, refined);
                 }
+            }
+        }
+
+        for (start, end) in chunks.iter_mut() {
+            *start = moves.get(start).copied().unwrap_or(*start);
+            *end = moves.get(end).copied().unwrap_or(*end);
+        }
+    }
+
+    /// 向状态机喂入一批连续帧的静音判定，返回其间完成的切片
+    ///
+    /// 状态在多次调用之间延续，可用于分块或流式分析。每个判定交给 `observer`，
+    /// 不需要时传入 `&mut ()`。
+    pub fn advance(
+        &self,
+        state: &mut SliceState,
+        silence: &[bool],
+        observer: &mut impl DetectionObserver,
+    ) -> Vec<(usize, usize)> {
+        let mut chunks = vec![];
+
+        for &silent in silence {
+            let i = state.frame;
+            state.frame += 1;
+
+            if silent {
+                if state.silence_start.is_none() {
+                    state.silence_start = Some(i);
+                    observer.event(TraceEvent::EnterSilence { frame: i });
+                }
+                continue;
+            }
+
+            let Some(sil_start) = state.silence_start.take() else {
+                if let Some(max_latency) = state.max_latency
+                    && state.frame - state.clip_start >= max_latency
+                {
+                    let clip = (state.clip_start, state.frame);
+                    state.clip_start = clip.1;
+                    chunks.push(clip);
+                    observer.event(TraceEvent::LatencyFlush { clip });
+                }
+                continue;
+            };
+            let decision = if i - sil_start > self.max_silence {
+                let clip = (state.clip_start, sil_start + self.min_interval);
+                state.clip_start = clip.1;
+                if clip.1 - clip.0 >= self.min_length {
+                    chunks.push(clip);
+                    BoundaryDecision::Cut { clip }
+                } else {
+                    BoundaryDecision::ClipTooShort { clip }
+                }
+            } else {
+                BoundaryDecision::PauseTooShort
+            };
+            observer.event(TraceEvent::SilenceRun {
+                start: sil_start,
+                end: i,
+                decision,
+            });
+        }
+
+        chunks
+    }
+
+    /// 响亮事件检测 (反转的静音判定): 返回高于阈值的连续帧区间，用于提取关门声、
+    /// 警报、狗叫等声音事件而非语音
+    ///
+    /// 间隔不长于最小间隔的相邻事件合并为一个，短于 `min_event` 帧的事件丢弃，
+    /// 其余事件向两侧各扩展 `context` 帧的上下文，扩展后重叠的事件合并。
+    pub fn loud_events<S: Sample>(
+        &self,
+        samples: &[S],
+        min_event: usize,
+        context: usize,
+        observer: &mut impl DetectionObserver,
+    ) -> Vec<(usize, usize)> {
+        let frame_count = self.frame_count(samples.len());
+        let mut events: Vec<(usize, usize)> = Vec::new();
+        let mut loud_start = None;
+        // 末尾补一个静音帧以结束最后一个事件
+        let silence = self.frame_silence(samples).into_iter().chain([true]);
+        for (i, silent) in silence.enumerate() {
+            match (silent, loud_start) {
+                (false, None) => loud_start = Some(i),
+                (true, Some(start)) => {
+                    loud_start = None;
+                    match events.last_mut() {
+                        Some(last) if start - last.1 <= self.min_interval => last.1 = i,
+                        _ => events.push((start, i)),
+                    }
+                }
+                _ => {}
+            }
+        }
+        observer.stage("事件检测", &events);
+
+        let mut padded: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in events.into_iter().filter(|(s, e)| e - s >= min_event) {
+            let start = start.saturating_sub(context);
+            let end = (end + context).min(frame_count);
+            match padded.last_mut() {
+                Some(last) if start <= last.1 => last.1 = end,
+                _ => padded.push((start, end)),
+            }
+        }
+        observer.stage("事件上下文", &padded);
+        padded
+    }
+
+    /// 超长片段的放宽检测：长于 `max_frames` 帧的切片在其内部以逐步提高的阈值
+    /// (每次提高 `step_db`，最多 `max_iterations` 次) 重新检测
+    ///
+    /// 子片段仍然超长时继续提高阈值；提高阈值后整段都被判为静音时保留原切片。
+    /// 用于在硬切之前优先利用片段内部较浅的自然停顿。
+    pub fn relax_long_chunks<S: Sample>(
+        &self,
+        samples: &[S],
+        chunks: &[(usize, usize)],
+        max_frames: usize,
+        step_db: f32,
+        max_iterations: u32,
+    ) -> Vec<(usize, usize)> {
+        if max_frames == 0 || max_iterations == 0 {
+            return chunks.to_vec();
+        }
+        chunks
+            .iter()
+            .flat_map(|&chunk| {
+                self.relax_chunk(samples, chunk, max_frames, step_db, max_iterations)
+            })
+            .collect()
+    }
+
+    fn relax_chunk<S: Sample>(
+        &self,
+        samples: &[S],
+        (start, end): (usize, usize),
+        max_frames: usize,
+        step_db: f32,
+        iterations_left: u32,
+    ) -> Vec<(usize, usize)> {
+        if end - start <= max_frames || iterations_left == 0 {
+            return vec![(start, end)];
+        }
+        let stricter = Slicer {
+            threshold_db: self.threshold_db + step_db,
+            peak_threshold_db: self.peak_threshold_db + step_db,
+            ..self.clone()
+        };
+        let first_sample = (start * self.hop_size).min(samples.len());
+        let last_sample = (end * self.hop_size).min(samples.len());
+        // 子区间从帧边界开始，子切片的帧号加上 `start` 即为原时间轴上的帧号
+        let sub_chunks = stricter.slice_frames(&samples[first_sample..last_sample]);
+        if sub_chunks.is_empty() {
+            return vec![(start, end)];
+        }
+        sub_chunks
+            .into_iter()
+            .flat_map(|(sub_start, sub_end)| {
+                stricter.relax_chunk(
+                    samples,
+                    (start + sub_start, start + sub_end),
+                    max_frames,
+                    step_db,
+                    iterations_left - 1,
+                )
+            })
+            .collect()
+    }
+
+    /// 检测切片，并对超长切片按 `relax_length` 放宽重新检测，返回帧区间
+    pub fn detect_chunks<S: Sample>(
+        &self,
+        samples: &[S],
+        boundaries: &BoundaryConfig,
+    ) -> Vec<(usize, usize)> {
+        self.detect_chunks_observed(samples, boundaries, &mut ())
+    }
+
+    /// 同 `detect_chunks`，并把每个判定与阶段结果交给 `observer`
+    pub fn detect_chunks_observed<S: Sample>(
+        &self,
+        samples: &[S],
+        boundaries: &BoundaryConfig,
+        observer: &mut impl DetectionObserver,
+    ) -> Vec<(usize, usize)> {
+        let chunks = self.slice_in_blocks(samples, ANALYSIS_BLOCK_FRAMES, observer);
+        observer.stage("检测", &chunks);
+        if boundaries.relax_length == 0 {
+            return chunks;
+        }
+        let chunks = self.relax_long_chunks(
+            samples,
+            &chunks,
+            self.ms_to_frames(boundaries.relax_length),
+            boundaries.relax_step_db,
+            boundaries.relax_max_iterations,
+        );
+        observer.stage("放宽检测", &chunks);
+        chunks
+    }
+
+    /// 按外部给出的逐帧静音判定 (如语音活动检测模型的结果) 检测切片，返回帧区间
+    ///
+    /// `silence` 的第 `i` 项对应第 `i` 个分析帧。切点细化与放宽重新检测都依赖能量阈值，这里不做。
+    pub fn detect_chunks_masked(
+        &self,
+        silence: &[bool],
+        observer: &mut impl DetectionObserver,
+    ) -> Vec<(usize, usize)> {
+        let mut state = SliceState::default();
+        let mut chunks = self.advance(&mut state, silence, observer);
+        chunks.extend(self.finish_state(state, observer));
+        observer.stage("检测", &chunks);
+        chunks
+    }
+
+    /// 依次执行合并、起点回退、最小间隔与超长硬切
+    pub fn refine_chunks<S: Sample>(
+        &self,
+        samples: &[S],
+        chunks: &[(usize, usize)],
+        boundaries: &BoundaryConfig,
+    ) -> Vec<(usize, usize)> {
+        self.refine_chunks_observed(samples, chunks, boundaries, &mut ())
+    }
+
+    /// 同 `refine_chunks`，每个启用的步骤之后把结果交给 `observer`
+    pub fn refine_chunks_observed<S: Sample>(
+        &self,
+        samples: &[S],
+        chunks: &[(usize, usize)],
+        boundaries: &BoundaryConfig,
+        observer: &mut impl DetectionObserver,
+    ) -> Vec<(usize, usize)> {
+        let sample_rate = self.sample_rate;
+        let mut chunks = chunks.to_vec();
+        if boundaries.enable_merge {
+            chunks = match boundaries.merge_mode {
+                MergeMode::Greedy => merge_short_chunks(
+                    &chunks,
+                    boundaries.max_merge_duration_ms,
+                    sample_rate,
+                    self.hop_size,
+                ),
+                MergeMode::Sentence => {
+                    let pauses = pause_lengths(&chunks, &self.silent_ranges(samples));
+                    merge_by_pauses(
+                        &chunks,
+                        &pauses,
+                        boundaries.max_merge_duration_ms,
+                        self.ms_to_frames(boundaries.sentence_pause_ms),
+                        sample_rate,
+                        self.hop_size,
+                    )
+                }
+                MergeMode::Target => pack_to_target(
+                    &chunks,
+                    boundaries.target_duration_ms,
+                    boundaries.max_merge_duration_ms,
+                    sample_rate,
+                    self.hop_size,
+                ),
+                MergeMode::TargetOptimal => pack_to_target_optimal(
+                    &chunks,
+                    boundaries.target_duration_ms,
+                    boundaries.max_merge_duration_ms,
+                    sample_rate,
+                    self.hop_size,
+                ),
+            };
+            observer.stage("合并", &chunks);
+        }
+        if boundaries.onset_backoff_ms > 0 {
+            let backoff_frames = self.ms_to_frames(boundaries.onset_backoff_ms);
+            chunks = self.apply_onset_backoff(samples, &chunks, backoff_frames);
+            observer.stage("起点回退", &chunks);
+        }
+        if boundaries.min_gap_ms > 0 {
+            chunks = enforce_min_gap(&chunks, self.ms_to_frames(boundaries.min_gap_ms));
+            observer.stage("最小间隔", &chunks);
+        }
+        if boundaries.max_duration_ms > 0 {
+            chunks = match boundaries.max_duration_mode {
+                MaxDurationMode::Hard => enforce_max_duration(
+                    &chunks,
+                    boundaries.max_duration_ms,
+                    sample_rate,
+                    self.hop_size,
+                ),
+                MaxDurationMode::Phrase => {
+                    self.split_at_low_energy(samples, &chunks, boundaries.max_duration_ms, true)
+                }
+                MaxDurationMode::Quietest => {
+                    self.split_at_low_energy(samples, &chunks, boundaries.max_duration_ms, false)
+                }
+            };
+            observer.stage("超长硬切", &chunks);
+        }
+        chunks
+    }
+
+    /// 超长切片在能量低处断开，保证每块时长严格小于 `max_duration_ms`
+    ///
+    /// 每次在 `[最大长度的一半, 最大长度]` 的窗口内选择断点，代价为该帧 RMS 相对切片峰值
+    /// 的比例；`avoid_held_notes` 时断点两侧的基频相差不到一个半音 (处于同一长音中)
+    /// 再加 1，使断点落在乐句边界。代价相同时取更靠后的断点。
+    fn split_at_low_energy<S: Sample>(
+        &self,
+        samples: &[S],
+        chunks: &[(usize, usize)],
+        max_duration_ms: u32,
+        avoid_held_notes: bool,
+    ) -> Vec<(usize, usize)> {
+        let max_frames = max_chunk_frames(max_duration_ms, self.sample_rate, self.hop_size);
+        if chunks.iter().all(|&(start, end)| end - start <= max_frames) {
+            return chunks.to_vec();
+        }
+        let rms = self.frame_rms(samples);
+        let rms_at = |frame: usize| rms.get(frame).copied().unwrap_or(0.0);
+
+        let mut result = Vec::with_capacity(chunks.len());
+        for &(start, end) in chunks {
+            if end - start <= max_frames {
+                result.push((start, end));
                 continue;
             }
+            let pitch = if avoid_held_notes {
+                frame_pitch(samples, self.sample_rate, self.hop_size, start..end)
+            } else {
+                vec![None; end - start]
+            };
+            let peak = (start..end).map(rms_at).fold(f32::EPSILON, f32::max);
+            let cost = |frame: usize| {
+                let held = match (pitch[frame - start - 1], pitch[frame - start]) {
+                    (Some(before), Some(after)) => semitones(before, after) < 1.0,
+                    _ => false,
+                };
+                rms_at(frame) / peak + if held { 1.0 } else { 0.0 }
+            };
+
+            let mut cur = start;
+            while end - cur > max_frames {
+                let earliest = cur + (max_frames / 2).max(1);
+                let latest = cur + max_frames;
+                let (cut, _) = (earliest..=latest).map(|frame| (frame, cost(frame))).fold(
+                    (latest, f32::INFINITY),
+                    |best, candidate| {
+                        if candidate.1 <= best.1 {
+                            candidate
+                        } else {
+                            best
+                        }
+                    },
+                );
+                result.push((cur, cut));
+                cur = cut;
+            }
+            result.push((cur, end));
+        }
+        result
+    }
+
+    /// 帧区间换算为 `samples` 中的样本区间，按 `overlap_ms` 向前延伸，
+    /// 再按 `zero_cross_max_shift_ms` 把边界对齐到最近的过零点
+    pub fn chunk_sample_ranges<S: Sample>(
+        &self,
+        samples: &[S],
+        chunks: &[(usize, usize)],
+        boundaries: &BoundaryConfig,
+    ) -> Vec<(usize, usize)> {
+        let to_samples = |ms: u32| (self.sample_rate as u64 * ms as u64 / 1000) as usize;
+        let ranges = to_sample_ranges(
+            chunks,
+            self.hop_size,
+            samples.len(),
+            to_samples(boundaries.overlap_ms),
+        );
+        if boundaries.zero_cross_max_shift_ms == 0 {
+            return ranges;
+        }
+        snap_to_zero_crossings(
+            samples,
+            &ranges,
+            to_samples(boundaries.zero_cross_max_shift_ms),
+        )
+    }
 
-            if let Some(sil_start) = silence_start.take()
-                && i - sil_start > self.max_silence
-            {
-                let clip_end = sil_start + self.min_interval;
-                if clip_end - clip_start >= self.min_length {
-                    chunks.push((clip_start, clip_end));
+    /// 完整的切片流程，返回每个切片在 `samples` 中的样本区间及其能量统计
+    ///
+    /// 与命令行的切片结果一致 (不含逐片的静音过滤，过滤可用统计调用 `SilenceFilter::is_silence`)，
+    /// 供已持有解码缓冲区的库调用方直接索引原缓冲区，避免复制。命令行需要分步计时，
+    /// 因此分步调用上面的方法。
+    pub fn segment_ranges<S: Sample>(
+        &self,
+        samples: &[S],
+        boundaries: &BoundaryConfig,
+    ) -> Vec<(Range<usize>, SegmentStats)> {
+        let chunks = self.detect_chunks(samples, boundaries);
+        let chunks = self.refine_chunks(samples, &chunks, boundaries);
+        self.chunk_sample_ranges(samples, &chunks, boundaries)
+            .into_iter()
+            .map(|(start, end)| (start..end, self.segment_stats(samples, start..end)))
+            .collect()
+    }
+
+    /// 与 `segment_ranges` 相同，直接返回借用原缓冲区的子切片
+    pub fn segments<'a, S: Sample>(
+        &self,
+        samples: &'a [S],
+        boundaries: &BoundaryConfig,
+    ) -> impl Iterator<Item = (&'a [S], SegmentStats)> + use<'a, S> {
+        self.segment_ranges(samples, boundaries)
+            .into_iter()
+            .map(move |(range, stats)| (&samples[range], stats))
+    }
+
+    /// 完整的切片流程加上逐片的静音过滤与切片钩子，返回钩子保留的切片
+    ///
+    /// 通过 `filter` 的切片按顺序交给 `hook`，默认文件名为 `slice_{序号:03}.wav`
+    /// (序号只计保留的切片)，钩子可以否决切片、改名或附加元数据。`source` 原样传给钩子。
+    /// 钩子给出的文件名无效 (见 `validate_file_name`) 或与之前保留的切片重名时返回错误。
+    pub fn segments_with(
+        &self,
+        samples: &[f32],
+        boundaries: &BoundaryConfig,
+        filter: &SilenceFilter,
+        source: &Path,
+        hook: &SegmentHook,
+    ) -> Result<Vec<HookedSegment>> {
+        let mut kept = Vec::new();
+        let mut used_names = HashSet::new();
+        for (range, stats) in self.segment_ranges(samples, boundaries) {
+            let segment_samples = &samples[range.clone()];
+            if filter.is_silence(segment_samples, &stats, self.sample_rate) {
+                continue;
+            }
+            let file_name = format!("slice_{:03}.wav", kept.len());
+            let segment = Segment {
+                source,
+                index: kept.len(),
+                sample_rate: self.sample_rate,
+                start_sample: range.start,
+                end_sample: range.end,
+                stats: &stats,
+                file_name: &file_name,
+            };
+            let decision = hook(&segment, segment_samples);
+            if let Some((file_name, metadata)) = decision.apply(file_name)? {
+                if !used_names.insert(file_name.clone()) {
+                    return Err(anyhow::anyhow!("切片钩子给出的文件名重复: {file_name:?}"));
                 }
-                clip_start = clip_end;
+                kept.push(HookedSegment {
+                    range,
+                    stats,
+                    file_name,
+                    metadata,
+                });
             }
         }
+        Ok(kept)
+    }
+
+    fn ms_to_frames(&self, ms: u32) -> usize {
+        ms_to_frames(ms, self.sample_rate, self.hop_size)
+    }
+
+    /// 结束状态机，返回剩余音频构成的切片 (若足够长)
+    pub fn finish_state(
+        &self,
+        state: SliceState,
+        observer: &mut impl DetectionObserver,
+    ) -> Option<(usize, usize)> {
+        let clip = (state.clip_start, state.frame);
+        let kept = clip.1 - clip.0 >= self.min_length;
+        observer.event(TraceEvent::Tail { clip, kept });
+        kept.then_some(clip)
+    }
+}
+
+/// dB 计算的能量下限 (-100 dB)，避免 log(0) 得到 -inf
+const ENERGY_EPSILON: f32 = 1e-10;
+
+/// 均方值换算为 dB，带下限
+fn power_db(mean_square: f32) -> f32 {
+    10.0 * mean_square.max(ENERGY_EPSILON).log10()
+}
+
+/// 计算窗口的均方值与峰值；窗口为空或包含 NaN/inf 时返回 `None`
+fn window_stats<S: Sample>(window: &[S]) -> Option<(f32, f32)> {
+    if window.is_empty() || window.iter().any(|x| !x.to_f32().is_finite()) {
+        return None;
+    }
+    let mean_square =
+        window.iter().map(|x| x.to_f32() * x.to_f32()).sum::<f32>() / window.len() as f32;
+    let peak = window
+        .iter()
+        .fold(0.0f32, |acc, x| acc.max(x.to_f32().abs()));
+    Some((mean_square, peak))
+}
+
+/// 切片的帧级能量统计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentStats {
+    /// 各帧均方值平均后的 RMS (dBFS)
+    pub rms_db: f32,
+    /// 峰值 (dBFS)
+    pub peak_db: f32,
+    /// 检测器判定为有声的帧占比
+    pub voiced_ratio: f32,
+}
+
+/// 分块分析时每块的帧数
+const ANALYSIS_BLOCK_FRAMES: usize = 65536;
+
+/// 粗扫描对一块帧的判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    /// 块内每帧都是静音
+    Silent,
+    /// 块内至少一帧有声
+    Voiced,
+    /// 需要逐帧判断
+    Uncertain,
+}
+
+/// 切片状态机的一次判定，帧号为检测信号上的分析帧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// 在该帧进入静音
+    EnterSilence { frame: usize },
+    /// 静音 `[start, end)` 结束 (在 `end` 帧恢复有声) 时对候选边界的判定
+    SilenceRun {
+        start: usize,
+        end: usize,
+        decision: BoundaryDecision,
+    },
+    /// 文件结尾剩余的片段，`kept` 为假表示短于最小长度被丢弃
+    Tail { clip: (usize, usize), kept: bool },
+    /// 连续有声达到最大延迟，未等到停顿即输出切片 `clip`
+    LatencyFlush { clip: (usize, usize) },
+}
+
+/// 候选边界 (一段静音) 的判定结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundaryDecision {
+    /// 静音不长于最大静音长度，不切分
+    PauseTooShort,
+    /// 切分，静音前的片段 `clip` 成为切片
+    Cut { clip: (usize, usize) },
+    /// 切分，但静音前的片段 `clip` 短于最小长度而被丢弃
+    ClipTooShort { clip: (usize, usize) },
+}
+
+/// 检测过程的观察者，用于调试时记录每个判定；默认实现忽略所有回调
+pub trait DetectionObserver {
+    fn event(&mut self, _event: TraceEvent) {}
+
+    /// 一个处理阶段结束后的切片 (帧区间)
+    fn stage(&mut self, _name: &'static str, _chunks: &[(usize, usize)]) {}
+}
+
+impl DetectionObserver for () {}
 
-        // 处理剩余音频
-        if frame_count - clip_start >= self.min_length {
-            chunks.push((clip_start, frame_count));
+impl<T: DetectionObserver> DetectionObserver for Option<T> {
+    fn event(&mut self, event: TraceEvent) {
+        if let Some(observer) = self {
+            observer.event(event);
         }
+    }
+
+    fn stage(&mut self, name: &'static str, chunks: &[(usize, usize)]) {
+        if let Some(observer) = self {
+            observer.stage(name, chunks);
+        }
+    }
+}
+
+/// 切片状态机在分块之间延续的状态
+#[derive(Debug, Clone, Default)]
+pub struct SliceState {
+    /// 已处理的帧数
+    frame: usize,
+    silence_start: Option<usize>,
+    clip_start: usize,
+    /// 最大输出延迟 (帧)，见 `with_max_latency`
+    max_latency: Option<usize>,
+}
+
+impl SliceState {
+    /// 带输出延迟上限的状态: 当前片段连续有声达到 `frames` 帧时不再等待停顿，
+    /// 直接输出切片并从下一帧开始新片段
+    ///
+    /// 供边采集边切片的实时路径 (如实时字幕) 使用，保证下游按时收到切片；
+    /// 离线处理应使用 `max_duration_ms` 在检测后硬切。
+    pub fn with_max_latency(frames: usize) -> Self {
+        Self {
+            max_latency: Some(frames.max(1)),
+            ..Default::default()
+        }
+    }
+}
 
+/// `Slicer::feed` 输出的一个切片
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub start_sample: usize,
+    pub end_sample: usize,
+    pub samples: Vec<f32>,
+}
+
+/// 流式切片的缓冲: 尚未结束的切片与分析窗口所需的样本
+#[derive(Debug, Clone, Default)]
+struct StreamBuffer {
+    state: SliceState,
+    buffer: Vec<f32>,
+    /// `buffer[0]` 在整个流中的样本位置
+    offset: usize,
+    /// 终点样本尚未全部到达的切片 (帧区间)
+    pending: VecDeque<(usize, usize)>,
+}
+
+impl Slicer {
+    /// 推送式流式切片: 逐块喂入单声道样本 (如实时采集或网络流)，返回其间完成的切片
+    ///
+    /// 切片的终点一经确定即返回。只缓冲尚未结束的切片与分析窗口所需的样本，内存占用
+    /// 取决于最长的切片而不是输入时长；检测结果与对整个信号调用 `slice` 相同 (不做切点细化)。
+    /// 输入结束后调用 `finish` 取出剩余的切片。
+    pub fn feed(&mut self, samples: &[f32]) -> Vec<Chunk> {
+        let mut stream = std::mem::take(&mut self.stream);
+        stream.buffer.extend_from_slice(samples);
+        let available = stream.offset + stream.buffer.len();
+        // 只分析窗口已完整到达的帧
+        let ready = match available.checked_sub(self.win_size) {
+            Some(last_start) => last_start / self.hop_size + 1,
+            None => 0,
+        };
+        self.analyze_stream(&mut stream, ready, available);
+        let chunks = self.emit_stream(&mut stream, available);
+        self.stream = stream;
+        chunks
+    }
+
+    /// 结束输入: 分析剩余的帧 (末尾窗口截断)，返回所有剩余的切片
+    ///
+    /// 之后切片器回到初始状态 (保留延迟上限)，可以开始下一个流。
+    pub fn finish(&mut self) -> Vec<Chunk> {
+        let mut stream = std::mem::take(&mut self.stream);
+        let total = stream.offset + stream.buffer.len();
+        self.analyze_stream(&mut stream, self.frame_count(total), total);
+        self.stream.state = SliceState {
+            max_latency: stream.state.max_latency,
+            ..Default::default()
+        };
+        let state = std::mem::take(&mut stream.state);
+        stream.pending.extend(self.finish_state(state, &mut ()));
+        stream
+            .pending
+            .iter()
+            .map(|&(start, end)| {
+                let start = (start * self.hop_size).min(total);
+                let end = (end * self.hop_size).min(total);
+                Chunk {
+                    start_sample: start,
+                    end_sample: end,
+                    samples: stream.buffer[start - stream.offset..end - stream.offset].to_vec(),
+                }
+            })
+            .collect()
+    }
+
+    /// 对 `frames` 之前尚未分析的帧做静音判定并推进状态机
+    fn analyze_stream(&self, stream: &mut StreamBuffer, frames: usize, available: usize) {
+        if frames <= stream.state.frame {
+            return;
+        }
+        let silence: Vec<bool> = (stream.state.frame..frames)
+            .map(|i| {
+                let start = i * self.hop_size;
+                let end = (start + self.win_size).min(available);
+                self.is_silent_window(&stream.buffer[start - stream.offset..end - stream.offset])
+            })
+            .collect();
+        let chunks = self.advance(&mut stream.state, &silence, &mut ());
+        stream.pending.extend(chunks);
+    }
+
+    /// 取出终点样本已到达的切片，并丢弃之后不再需要的样本
+    fn emit_stream(&self, stream: &mut StreamBuffer, available: usize) -> Vec<Chunk> {
+        let mut chunks = Vec::new();
+        while let Some(&(start, end)) = stream.pending.front() {
+            if end * self.hop_size > available {
+                break;
+            }
+            stream.pending.pop_front();
+            let (start, end) = (start * self.hop_size, end * self.hop_size);
+            chunks.push(Chunk {
+                start_sample: start,
+                end_sample: end,
+                samples: stream.buffer[start - stream.offset..end - stream.offset].to_vec(),
+            });
+        }
+        // 之后的切片最早从下一个待输出切片或当前片段的起点开始，未分析的帧都在其后
+        let keep_from = stream
+            .pending
+            .front()
+            .map_or(stream.state.clip_start, |&(start, _)| start)
+            * self.hop_size;
+        if keep_from > stream.offset {
+            stream.buffer.drain(..keep_from - stream.offset);
+            stream.offset = keep_from;
+        }
         chunks
     }
 }
@@ -117,25 +1301,218 @@ pub fn merge_short_chunks(
         return vec![];
     }
 
-    let max_frames = (max_duration_ms as f32 * sample_rate as f32 / 1000.0) as usize;
-    let mut merged = vec![];
-    let (mut current_start, mut current_end) = chunks[0];
+    let max_frames = (max_duration_ms as f32 * sample_rate as f32 / 1000.0) as usize;
+    let mut merged = vec![];
+    let (mut current_start, mut current_end) = chunks[0];
+
+    for &(start, end) in &chunks[1..] {
+        let current_duration = (current_end - current_start) * hop_size;
+        let next_duration = (end - start) * hop_size;
+
+        if current_duration + next_duration <= max_frames {
+            current_end = end;
+        } else {
+            merged.push((current_start, current_end));
+            current_start = start;
+            current_end = end;
+        }
+    }
+
+    merged.push((current_start, current_end));
+    merged
+}
+
+/// 贪心打包相邻切片，使合并后的时长尽量接近 `target_duration_ms`
+///
+/// 仅当并入下一个切片能让当前时长更接近目标、且不超过 `max_duration_ms` 时才合并。
+pub fn pack_to_target(
+    chunks: &[(usize, usize)],
+    target_duration_ms: u32,
+    max_duration_ms: u32,
+    sample_rate: u32,
+    hop_size: usize,
+) -> Vec<(usize, usize)> {
+    if chunks.is_empty() {
+        return vec![];
+    }
+
+    let target = target_duration_ms as f64 * sample_rate as f64 / 1000.0;
+    let max_samples = max_duration_ms as f64 * sample_rate as f64 / 1000.0;
+    let mut merged = vec![];
+    let (mut current_start, mut current_end) = chunks[0];
+
+    for &(start, end) in &chunks[1..] {
+        let current = ((current_end - current_start) * hop_size) as f64;
+        let combined = ((end - current_start) * hop_size) as f64;
+
+        if combined <= max_samples && (combined - target).abs() <= (current - target).abs() {
+            current_end = end;
+        } else {
+            merged.push((current_start, current_end));
+            current_start = start;
+            current_end = end;
+        }
+    }
+
+    merged.push((current_start, current_end));
+    merged
+}
+
+/// 动态规划打包相邻切片，使各切片时长与 `target_duration_ms` 的偏差平方和最小
+///
+/// 合并后的跨度不超过 `max_duration_ms`（单个原始切片总是允许的）。
+pub fn pack_to_target_optimal(
+    chunks: &[(usize, usize)],
+    target_duration_ms: u32,
+    max_duration_ms: u32,
+    sample_rate: u32,
+    hop_size: usize,
+) -> Vec<(usize, usize)> {
+    let n = chunks.len();
+    if n == 0 {
+        return vec![];
+    }
+
+    let target = target_duration_ms as f64 / 1000.0;
+    let max_secs = max_duration_ms as f64 / 1000.0;
+    let secs = |first: usize, last: usize| {
+        ((chunks[last].1 - chunks[first].0) * hop_size) as f64 / sample_rate as f64
+    };
+
+    // cost[i]: 前 i 个切片的最小代价；prev[i]: 最优方案中最后一组的起点
+    let mut cost = vec![f64::INFINITY; n + 1];
+    let mut prev = vec![0; n + 1];
+    cost[0] = 0.0;
+
+    for i in 1..=n {
+        for j in (0..i).rev() {
+            let duration = secs(j, i - 1);
+            if duration > max_secs && j + 1 < i {
+                break;
+            }
+            let candidate = cost[j] + (duration - target).powi(2);
+            if candidate < cost[i] {
+                cost[i] = candidate;
+                prev[i] = j;
+            }
+        }
+    }
+
+    let mut merged = vec![];
+    let mut i = n;
+    while i > 0 {
+        let j = prev[i];
+        merged.push((chunks[j].0, chunks[i - 1].1));
+        i = j;
+    }
+    merged.reverse();
+    merged
+}
+
+/// 计算相邻切片之间的停顿长度 (帧)
+///
+/// 对每对相邻切片，取与边界区间 `[end - 1, next_start]` 相交的最长静音区间长度；
+/// 返回值长度为 `chunks.len() - 1`。
+pub fn pause_lengths(chunks: &[(usize, usize)], silent_ranges: &[(usize, usize)]) -> Vec<usize> {
+    chunks
+        .windows(2)
+        .map(|pair| {
+            let lo = pair[0].1.saturating_sub(1);
+            let hi = pair[1].0;
+            silent_ranges
+                .iter()
+                .filter(|&&(start, end)| start <= hi && end > lo)
+                .map(|&(start, end)| end - start)
+                .max()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// 按停顿结构合并切片
+///
+/// 停顿不短于 `sentence_pause_frames` 的边界永远不会被合并；合并后跨度超过
+/// `max_duration_ms` 时，在当前候选范围内停顿最长的边界处断开。
+/// `pauses[i]` 为 `chunks[i]` 与 `chunks[i + 1]` 之间的停顿帧数。
+pub fn merge_by_pauses(
+    chunks: &[(usize, usize)],
+    pauses: &[usize],
+    max_duration_ms: u32,
+    sentence_pause_frames: usize,
+    sample_rate: u32,
+    hop_size: usize,
+) -> Vec<(usize, usize)> {
+    if chunks.is_empty() {
+        return vec![];
+    }
+
+    let max_samples = (max_duration_ms as f32 * sample_rate as f32 / 1000.0) as usize;
+    let span = |first: usize, last: usize| (chunks[last].1 - chunks[first].0) * hop_size;
+    let mut merged = vec![];
+    let mut group_start = 0;
+    let mut j = 1;
+
+    while j < chunks.len() {
+        // 长停顿是硬边界
+        if pauses[j - 1] >= sentence_pause_frames {
+            merged.push((chunks[group_start].0, chunks[j - 1].1));
+            group_start = j;
+            j += 1;
+            continue;
+        }
+
+        if span(group_start, j) <= max_samples {
+            j += 1;
+            continue;
+        }
+
+        // 超出时长预算: 在 (group_start, j] 中停顿最长的边界处断开 (并列时取最靠后者)
+        let split = (group_start + 1..=j)
+            .max_by_key(|&k| (pauses[k - 1], k))
+            .unwrap();
+        merged.push((chunks[group_start].0, chunks[split - 1].1));
+        group_start = split;
+        if group_start == j {
+            j += 1;
+        }
+    }
+
+    merged.push((chunks[group_start].0, chunks[chunks.len() - 1].1));
+    merged
+}
 
-    for &(start, end) in &chunks[1..] {
-        let current_duration = (current_end - current_start) * hop_size;
-        let next_duration = (end - start) * hop_size;
+/// 保证相邻切片在原始时间轴上至少间隔 `min_gap_frames` 帧
+///
+/// 间隔不足时以两者边界中点为中心重新放置边界（各自裁掉一半差额）；
+/// 若裁剪会使任一切片变为空，则将两者合并。
+pub fn enforce_min_gap(chunks: &[(usize, usize)], min_gap_frames: usize) -> Vec<(usize, usize)> {
+    if min_gap_frames == 0 || chunks.is_empty() {
+        return chunks.to_vec();
+    }
 
-        if current_duration + next_duration <= max_frames {
-            current_end = end;
+    let mut result: Vec<(usize, usize)> = Vec::with_capacity(chunks.len());
+    for &(start, end) in chunks {
+        let Some(last) = result.last_mut() else {
+            result.push((start, end));
+            continue;
+        };
+        if start >= last.1 + min_gap_frames {
+            result.push((start, end));
+            continue;
+        }
+
+        // 以边界中点为中心留出 min_gap_frames 的间隔
+        let mid = (last.1 + start) / 2;
+        let new_end = mid.saturating_sub(min_gap_frames / 2);
+        let new_start = new_end + min_gap_frames;
+        if new_end > last.0 && new_start < end {
+            last.1 = new_end;
+            result.push((new_start, end));
         } else {
-            merged.push((current_start, current_end));
-            current_start = start;
-            current_end = end;
+            last.1 = last.1.max(end);
         }
     }
-
-    merged.push((current_start, current_end));
-    merged
+    result
 }
 
 /// 当切片时长超过 `max_duration_ms` 时硬切成多块，保证每块时长严格小于 `max_duration_ms`
@@ -151,17 +1528,7 @@ pub fn enforce_max_duration(
     if max_duration_ms == 0 || chunks.is_empty() || hop_size == 0 {
         return chunks.to_vec();
     }
-
-    // 计算满足 duration < max_duration_ms 的最大样本数。
-    // 要求 samples * 1000 < max_duration_ms * sample_rate，取整后:
-    //   max_samples = (max_duration_ms * sample_rate - 1) / 1000
-    let product = (max_duration_ms as u64)
-        .saturating_mul(sample_rate as u64)
-        .saturating_sub(1);
-    let max_samples = (product / 1000) as usize;
-
-    // 每块的最大帧数: (end - start) * hop_size <= max_samples
-    let max_frames = (max_samples / hop_size).max(1);
+    let max_frames = max_chunk_frames(max_duration_ms, sample_rate, hop_size);
 
     let mut result = Vec::with_capacity(chunks.len());
     for &(start, end) in chunks {
@@ -177,35 +1544,265 @@ pub fn enforce_max_duration(
     result
 }
 
-/// 检测音频切片是否主要是静音
-pub fn is_silence(samples: &[f32], threshold: f32, min_audio_ratio: f32) -> bool {
-    if samples.is_empty() {
-        return true;
+/// 时长严格小于 `max_duration_ms` 的切片最多包含的帧数 (至少为 1)
+fn max_chunk_frames(max_duration_ms: u32, sample_rate: u32, hop_size: usize) -> usize {
+    // 计算满足 duration < max_duration_ms 的最大样本数。
+    // 要求 samples * 1000 < max_duration_ms * sample_rate，取整后:
+    //   max_samples = (max_duration_ms * sample_rate - 1) / 1000
+    let product = (max_duration_ms as u64)
+        .saturating_mul(sample_rate as u64)
+        .saturating_sub(1);
+    let max_samples = (product / 1000) as usize;
+
+    // 每块的最大帧数: (end - start) * hop_size <= max_samples
+    (max_samples / hop_size).max(1)
+}
+
+/// 将帧区间换算为样本区间 `(start_sample, end_sample)`
+///
+/// 除第一个切片外，每个切片的起点向前延伸 `overlap_samples` 个样本，使相邻切片重叠；
+/// 结果截断到 `[0, total_samples]` 内。
+pub fn to_sample_ranges(
+    chunks: &[(usize, usize)],
+    hop_size: usize,
+    total_samples: usize,
+    overlap_samples: usize,
+) -> Vec<(usize, usize)> {
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, &(start_frame, end_frame))| {
+            let mut start = (start_frame * hop_size).min(total_samples);
+            if i > 0 {
+                start = start.saturating_sub(overlap_samples);
+            }
+            (start, (end_frame * hop_size).min(total_samples))
+        })
+        .collect()
+}
+
+/// 固定长度的滑动窗口 `(start_sample, end_sample)`，相邻窗口的起点相隔 `window - overlap`
+///
+/// 所有窗口都是完整的 `window` 个样本: 末尾不足一个步长的剩余部分由一个与文件末尾对齐的
+/// 窗口覆盖；整个文件短于一个窗口时返回整个文件。
+pub fn fixed_windows(total_samples: usize, window: usize, overlap: usize) -> Vec<(usize, usize)> {
+    if total_samples == 0 || window == 0 {
+        return Vec::new();
+    }
+    if total_samples <= window {
+        return vec![(0, total_samples)];
+    }
+    let step = window.saturating_sub(overlap).max(1);
+    let mut windows: Vec<(usize, usize)> = (0..=total_samples - window)
+        .step_by(step)
+        .map(|start| (start, start + window))
+        .collect();
+    if windows.last().is_some_and(|&(_, end)| end < total_samples) {
+        windows.push((total_samples - window, total_samples));
+    }
+    windows
+}
+
+/// 把样本区间的边界移到 `max_shift` 个样本内最近的过零点，避免切口处的不连续产生爆音
+///
+/// 过零点 `i` 指 `samples[i - 1]` 与 `samples[i]` 异号或其中之一为零。范围内没有过零点的边界、
+/// 文件首尾保持不变；同一位置的边界总是移到同一个过零点，首尾相接的切片移动后仍然相接。
+pub fn snap_to_zero_crossings<S: Sample>(
+    samples: &[S],
+    ranges: &[(usize, usize)],
+    max_shift: usize,
+) -> Vec<(usize, usize)> {
+    let is_crossing = |i: usize| {
+        let (a, b) = (samples[i - 1].to_f32(), samples[i].to_f32());
+        a == 0.0 || b == 0.0 || (a < 0.0) != (b < 0.0)
+    };
+    let snap = |pos: usize| {
+        if pos == 0 || pos >= samples.len() {
+            return pos;
+        }
+        (0..=max_shift)
+            .flat_map(|d| [pos.checked_sub(d), pos.checked_add(d)])
+            .flatten()
+            .find(|&i| i > 0 && i < samples.len() && is_crossing(i))
+            .unwrap_or(pos)
+    };
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            let snapped = (snap(start), snap(end));
+            if snapped.0 < snapped.1 {
+                snapped
+            } else {
+                (start, end)
+            }
+        })
+        .collect()
+}
+
+/// 短于 `--min-output-ms` 的切片的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum ShortOutputPolicy {
+    /// 丢弃，不写出文件
+    #[default]
+    Drop,
+    /// 向两侧对称扩展到最小时长 (受文件边界限制)
+    Extend,
+}
+
+/// 把样本区间向两侧对称扩展到至少 `min_len` 个样本，一侧到达 `[0, total_samples]`
+/// 的边界时由另一侧补足；整个文件都不足 `min_len` 时返回 `None`
+pub fn extend_to_min_length(
+    start: usize,
+    end: usize,
+    min_len: usize,
+    total_samples: usize,
+) -> Option<(usize, usize)> {
+    if total_samples < min_len {
+        return None;
     }
+    let length = end.saturating_sub(start);
+    if length >= min_len {
+        return Some((start, end));
+    }
+    let missing = min_len - length;
+    let start = start
+        .saturating_sub(missing / 2)
+        .min(total_samples - min_len);
+    Some((start, start + min_len))
+}
+
+/// 有效音频占比的统计方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize)]
+pub enum RatioMode {
+    /// 检测器判定为有声的帧占比 (`SegmentStats::voiced_ratio`)，与切片检测使用同一阈值与指标
+    #[default]
+    Voiced,
+    /// 幅度超过过滤阈值的样本占比
+    Samples,
+    /// RMS 超过过滤阈值的 20ms 帧占比，对低电平但连续的语音更稳健
+    Frames,
+}
 
-    // 计算RMS能量
-    let rms = (samples.iter().map(|&x| x * x).sum::<f32>() / samples.len() as f32).sqrt();
+/// 有效音频占比按帧统计时的帧长 (ms)
+const RATIO_FRAME_MS: u32 = 20;
 
-    // 如果整体RMS低于阈值，认为是静音
-    if rms < threshold {
-        return true;
+/// 切片后的二次静音过滤
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SilenceFilter {
+    /// 静音阈值 (线性幅度)
+    pub threshold: f32,
+    /// 最小有效音频占比
+    pub min_audio_ratio: f32,
+    pub ratio_mode: RatioMode,
+}
+
+impl SilenceFilter {
+    /// 阈值的 dBFS 表示
+    pub fn threshold_db(&self) -> f32 {
+        20.0 * self.threshold.max(f32::MIN_POSITIVE).log10()
     }
 
-    // 检查有效音频占比
-    let non_silent_samples = samples.iter().filter(|&&x| x.abs() > threshold).count();
-    let audio_ratio = non_silent_samples as f32 / samples.len() as f32;
+    /// 检测音频切片是否主要是静音
+    ///
+    /// 整体能量取自切片的帧级统计，与检测阶段一致地在 dB 域与阈值比较。有效音频占比默认
+    /// 取统计中检测器判定为有声的帧占比，检测判为有声的切片不会因另一套阈值而被丢弃；
+    /// `Samples`/`Frames` 模式按过滤阈值重新统计 `samples`。
+    pub fn is_silence(&self, samples: &[f32], stats: &SegmentStats, sample_rate: u32) -> bool {
+        if samples.is_empty() {
+            return true;
+        }
+
+        // 如果整体RMS低于阈值，认为是静音
+        if stats.rms_db < power_db(self.threshold * self.threshold) {
+            return true;
+        }
+
+        self.audio_ratio(samples, stats, sample_rate) < self.min_audio_ratio
+    }
 
-    audio_ratio < min_audio_ratio
+    /// 有效音频占比
+    fn audio_ratio(&self, samples: &[f32], stats: &SegmentStats, sample_rate: u32) -> f32 {
+        match self.ratio_mode {
+            RatioMode::Voiced => stats.voiced_ratio,
+            RatioMode::Samples => {
+                let non_silent = samples
+                    .iter()
+                    .filter(|&&x| x.abs() > self.threshold)
+                    .count();
+                non_silent as f32 / samples.len() as f32
+            }
+            RatioMode::Frames => {
+                let frame_len = ((sample_rate * RATIO_FRAME_MS / 1000) as usize).max(1);
+                let threshold_power = self.threshold * self.threshold;
+                let mut frames = 0;
+                let mut active = 0;
+                for frame in samples.chunks(frame_len) {
+                    frames += 1;
+                    if window_stats(frame)
+                        .is_some_and(|(mean_square, _)| mean_square > threshold_power)
+                    {
+                        active += 1;
+                    }
+                }
+                active as f32 / frames as f32
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::enforce_max_duration;
+    use super::{
+        BoundaryConfig, DetectionMetric, MaxDurationMode, RatioMode, SegmentStats, SilenceFilter,
+        SliceState, Slicer, SlicerConfig, enforce_max_duration, enforce_min_gap,
+        extend_to_min_length, fixed_windows, merge_by_pauses, pack_to_target,
+        pack_to_target_optimal, pause_lengths, snap_to_zero_crossings, to_sample_ranges,
+    };
+    use super::{auto_threshold_db, noise_floor_db};
+
+    fn test_config() -> SlicerConfig {
+        SlicerConfig {
+            sample_rate: 1000,
+            threshold_db: -40.0,
+            min_length_ms: 100,
+            min_interval_ms: 20,
+            hop_size_ms: 10,
+            max_silence_ms: 50,
+            metric: DetectionMetric::Rms,
+            peak_threshold_db: -35.0,
+            coarse_hop_factor: 1,
+            refine_cuts: false,
+        }
+    }
+
+    fn test_slicer() -> Slicer {
+        Slicer::new(test_config()).unwrap()
+    }
 
     fn duration_ms(chunk: (usize, usize), hop_size: usize, sample_rate: u32) -> f64 {
         (chunk.1 - chunk.0) as f64 * hop_size as f64 / sample_rate as f64 * 1000.0
     }
 
+    #[test]
+    fn auto_threshold_follows_the_noise_floor() {
+        assert_eq!(noise_floor_db(&[]), None);
+        // 九成帧为 -40 dB 的语音，一成为噪声底: 手机录音 -50 dB，录音棚 -75 dB
+        let frames = |floor: f32| {
+            let mut rms = vec![0.01; 90];
+            rms.extend([floor; 10]);
+            rms
+        };
+        let phone = noise_floor_db(&frames(0.00316)).unwrap();
+        assert!((phone + 50.0).abs() < 0.1, "{phone}");
+        assert!((auto_threshold_db(phone, 10.0) + 40.0).abs() < 0.1);
+        let studio = noise_floor_db(&frames(0.000178)).unwrap();
+        assert!((auto_threshold_db(studio, 10.0) + 65.0).abs() < 0.1);
+        // 数字静音与没有停顿的录音
+        let silent = noise_floor_db(&[0.0; 10]).unwrap();
+        assert_eq!(auto_threshold_db(silent, 10.0), -80.0);
+        assert_eq!(auto_threshold_db(-30.0, 10.0), -25.0);
+    }
+
     #[test]
     fn disabled_returns_input_unchanged() {
         let chunks = vec![(0, 100), (100, 200)];
@@ -269,4 +1866,627 @@ mod tests {
             assert!(duration_ms(c, 80, 16000) < 1000.0);
         }
     }
+
+    #[test]
+    fn refined_cut_moves_to_quietest_frame() {
+        // 有声 300ms，低电平尾音 40ms，数字静音 110ms，有声 300ms
+        let mut samples = vec![0.5; 300];
+        samples.extend(vec![0.008; 40]);
+        samples.extend(vec![0.0; 110]);
+        samples.extend(vec![0.5; 300]);
+
+        assert_eq!(
+            test_slicer().slice_frames(&samples),
+            vec![(0, 32), (32, 75)]
+        );
+        let refined = Slicer::new(SlicerConfig {
+            refine_cuts: true,
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(refined.slice_frames(&samples), vec![(0, 34), (34, 75)]);
+    }
+
+    #[test]
+    fn silent_ranges_cover_quiet_frames() {
+        // 1000Hz, hop=10 样本: 200 样本有声 + 300 样本静音 + 200 样本有声
+        let mut samples = vec![0.5; 200];
+        samples.extend(vec![0.0; 300]);
+        samples.extend(vec![0.5; 200]);
+
+        let ranges = test_slicer().silent_ranges(&samples);
+        assert_eq!(ranges.len(), 1);
+        let (start, end) = ranges[0];
+        // 分析窗口跨越边界，静音区间应落在静音段内部
+        assert!(start >= 20 && end <= 50, "区间 {:?} 超出静音段", ranges[0]);
+    }
+
+    #[test]
+    fn integer_samples_detect_like_float_samples() {
+        let mut samples = vec![0.5; 200];
+        samples.extend(vec![0.001; 300]);
+        samples.extend((0..400).map(|i| 0.3 * (i as f32 * 0.7).sin()));
+        let integers: Vec<i16> = samples.iter().map(|&x| (x * 32768.0) as i16).collect();
+
+        let slicer = test_slicer();
+        let boundaries = BoundaryConfig::default();
+        assert_eq!(
+            slicer.detect_chunks(&integers, &boundaries),
+            slicer.detect_chunks(&samples, &boundaries)
+        );
+        let stats = slicer.segment_stats(&integers, 0..200);
+        assert!((stats.rms_db - slicer.segment_stats(&samples, 0..200).rms_db).abs() < 1e-3);
+    }
+
+    #[test]
+    fn relax_splits_long_chunk_at_shallow_dip() {
+        // 较浅的停顿 (约 -26dB) 高于 -40dB 阈值，只有提高阈值后才能切开
+        let mut samples = vec![0.5; 200];
+        samples.extend(vec![0.05; 300]);
+        samples.extend(vec![0.5; 200]);
+        let slicer = test_slicer();
+        let chunks = slicer.slice_frames(&samples);
+        assert_eq!(chunks, vec![(0, 70)]);
+
+        let relaxed = slicer.relax_long_chunks(&samples, &chunks, 50, 10.0, 3);
+        assert_eq!(relaxed.len(), 2);
+        assert!(relaxed.iter().all(|&(start, end)| end - start <= 50));
+        assert_eq!(relaxed[1].1, 70);
+
+        // 迭代次数不足时保留原切片
+        assert_eq!(
+            slicer.relax_long_chunks(&samples, &chunks, 50, 10.0, 1),
+            chunks
+        );
+    }
+
+    #[test]
+    fn phrase_split_avoids_held_notes() {
+        // 220 Hz 长音 3 秒，0.1 秒的弱音过渡后接 330 Hz 长音 2.9 秒
+        let sample_rate = 16000;
+        let tone = |freq: f32, amplitude: f32, secs: f32| {
+            (0..(secs * sample_rate as f32) as usize).map(move |i| {
+                amplitude
+                    * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin()
+            })
+        };
+        let samples: Vec<f32> = tone(220.0, 0.5, 3.0)
+            .chain(tone(330.0, 0.15, 0.1))
+            .chain(tone(330.0, 0.5, 2.9))
+            .collect();
+        let slicer = Slicer::new(SlicerConfig {
+            sample_rate,
+            ..test_config()
+        })
+        .unwrap();
+        let chunks = vec![(0, 600)];
+
+        let hard = enforce_max_duration(&chunks, 5000, sample_rate, slicer.hop_size());
+        assert_eq!(hard[0], (0, 499));
+        let phrase = slicer.split_at_low_energy(&samples, &chunks, 5000, true);
+        assert_eq!(phrase.len(), 2);
+        assert!((298..=302).contains(&phrase[0].1), "{phrase:?}");
+        assert_eq!(phrase[1].1, 600);
+    }
+
+    #[test]
+    fn quietest_split_uses_energy_dip() {
+        // 6 秒连续语音，3.5 秒处有 100ms 的弱音
+        let mut samples = vec![0.5; 6000];
+        samples[3500..3600].fill(0.2);
+        let boundaries = BoundaryConfig {
+            max_duration_ms: 5000,
+            max_duration_mode: MaxDurationMode::Quietest,
+            ..Default::default()
+        };
+        let chunks = test_slicer().refine_chunks(&samples, &[(0, 600)], &boundaries);
+        assert_eq!(chunks, vec![(0, 358), (358, 600)]);
+    }
+
+    #[test]
+    fn segments_borrow_from_input() {
+        let mut samples = vec![0.5; 200];
+        samples.extend(vec![0.0; 300]);
+        samples.extend(vec![0.5; 200]);
+        let slicer = test_slicer();
+        let boundaries = BoundaryConfig {
+            overlap_ms: 20,
+            ..Default::default()
+        };
+        let ranges = slicer.segment_ranges(&samples, &boundaries);
+        let chunks = slicer.slice(&samples);
+        assert_eq!(ranges.len(), chunks.len());
+        assert_eq!(ranges[1].0.start, chunks[1].0.0 * slicer.hop_size() - 20);
+        for (range, stats) in &ranges {
+            assert_eq!(*stats, slicer.segment_stats(&samples, range.clone()));
+        }
+        // 与切片状态机同一阈值判定: 除切点前保留的 min_interval 帧静音外都是有声帧
+        assert!((chunks[0].1.voiced_ratio - 20.0 / 22.0).abs() < 1e-6);
+
+        let segments: Vec<(&[f32], _)> = slicer.segments(&samples, &boundaries).collect();
+        for ((segment, stats), (range, range_stats)) in segments.iter().zip(&ranges) {
+            assert_eq!(segment.as_ptr(), samples[range.start..].as_ptr());
+            assert_eq!(segment.len(), range.len());
+            assert_eq!(stats, range_stats);
+        }
+    }
+
+    #[test]
+    fn trailing_silence_is_closed() {
+        let mut samples = vec![0.5; 200];
+        samples.extend(vec![0.0; 300]);
+        let ranges = test_slicer().silent_ranges(&samples);
+        assert_eq!(ranges.last().unwrap().1, 50);
+    }
+
+    #[test]
+    fn peak_metric_catches_transients() {
+        // 静音中夹杂单个样本的瞬态: RMS 低于阈值，峰值高于阈值
+        let mut samples = vec![0.0; 500];
+        samples[250] = 0.04;
+
+        let rms_slicer = test_slicer();
+        let peak_slicer = Slicer::new(SlicerConfig {
+            metric: DetectionMetric::Peak,
+            ..test_config()
+        })
+        .unwrap();
+        let hybrid_slicer = Slicer::new(SlicerConfig {
+            metric: DetectionMetric::Hybrid,
+            ..test_config()
+        })
+        .unwrap();
+
+        assert_eq!(rms_slicer.silent_ranges(&samples), vec![(0, 50)]);
+        assert_eq!(peak_slicer.silent_ranges(&samples).len(), 2);
+        assert_eq!(hybrid_slicer.silent_ranges(&samples).len(), 2);
+    }
+
+    #[test]
+    fn loud_events_bridge_short_gaps_and_add_context() {
+        let slicer = Slicer::new(SlicerConfig {
+            threshold_db: -20.0,
+            ..test_config()
+        })
+        .unwrap();
+        // 安静背景 (-40dB) 中: 两次相隔 30ms 的响声、一次过短的咔嗒声、一次单独的响声
+        let mut samples = vec![0.01; 1000];
+        samples[200..260].fill(0.5);
+        samples[290..320].fill(0.5);
+        samples[500..510].fill(0.5);
+        samples[800..860].fill(0.5);
+
+        let events = slicer.loud_events(&samples, 3, 5, &mut ());
+        // 分析窗口为 2 帧，响声从前一帧开始可见: 19..32 (两次响声合并)、79..86，各扩展 5 帧
+        assert_eq!(events, vec![(14, 37), (74, 91)]);
+    }
+
+    #[test]
+    fn pause_lengths_use_overlapping_silence() {
+        let chunks = vec![(0, 10), (10, 20), (25, 30)];
+        let silent = vec![(5, 12), (18, 26)];
+        assert_eq!(pause_lengths(&chunks, &silent), vec![7, 8]);
+    }
+
+    #[test]
+    fn sentence_merge_never_crosses_long_pause() {
+        // hop=1, sample_rate=1000 => 1 帧 = 1ms
+        let chunks = vec![(0, 10), (10, 20), (20, 30), (30, 40)];
+        let out = merge_by_pauses(&chunks, &[5, 50, 5], 100_000, 20, 1000, 1);
+        assert_eq!(out, vec![(0, 20), (20, 40)]);
+    }
+
+    #[test]
+    fn sentence_merge_splits_at_longest_pause() {
+        let chunks = vec![(0, 10), (10, 20), (20, 30), (30, 40)];
+        let out = merge_by_pauses(&chunks, &[5, 15, 3], 30, 100, 1000, 1);
+        assert_eq!(out, vec![(0, 20), (20, 40)]);
+    }
+
+    #[test]
+    fn target_packing_stops_near_target() {
+        // hop=1, sample_rate=1000 => 1 帧 = 1ms；每块 20ms，目标 50ms
+        let chunks: Vec<(usize, usize)> = (0..6).map(|i| (i * 20, i * 20 + 20)).collect();
+        let out = pack_to_target(&chunks, 50, 1000, 1000, 1);
+        assert_eq!(out, vec![(0, 60), (60, 120)]);
+    }
+
+    #[test]
+    fn optimal_packing_minimizes_deviation() {
+        // 30 + 30 + 40 + 60，目标 60ms: 最优为 [30+30] [40] [60]
+        let chunks = vec![(0, 30), (30, 60), (60, 100), (100, 160)];
+        let out = pack_to_target_optimal(&chunks, 60, 1000, 1000, 1);
+        assert_eq!(out, vec![(0, 60), (60, 100), (100, 160)]);
+    }
+
+    #[test]
+    fn optimal_packing_respects_max() {
+        let chunks: Vec<(usize, usize)> = (0..4).map(|i| (i * 20, i * 20 + 20)).collect();
+        let out = pack_to_target_optimal(&chunks, 80, 40, 1000, 1);
+        for &(start, end) in &out {
+            assert!(end - start <= 40);
+        }
+    }
+
+    #[test]
+    fn onset_backoff_follows_decreasing_energy() {
+        // 能量逐渐上升的起音，切片起点位于有声帧 (帧 30)
+        let mut samples = vec![0.0; 200];
+        samples.extend((0..100).map(|i| 0.001 * 1.06f32.powi(i)));
+        samples.extend(vec![0.5; 200]);
+        let slicer = test_slicer();
+
+        let out = slicer.apply_onset_backoff(&samples, &[(30, 50)], 5);
+        assert_eq!(out, vec![(25, 50)]);
+
+        // 起点之后的起音前已有足够的静音时保持不变
+        let out = slicer.apply_onset_backoff(&samples, &[(5, 50)], 5);
+        assert_eq!(out, vec![(5, 50)]);
+    }
+
+    #[test]
+    fn onset_backoff_extends_detected_slices() {
+        // 响亮段之后是低于阈值、逐渐增强的起始辅音，再接下一个响亮段
+        let mut samples = vec![0.5; 300];
+        samples.extend((0..80).map(|i| 0.0001 * 1.06f32.powi(i)));
+        samples.extend(vec![0.5; 300]);
+        let slicer = test_slicer();
+
+        let chunks = slicer.slice_frames(&samples);
+        assert_eq!(chunks.len(), 2);
+        let boundaries = BoundaryConfig {
+            onset_backoff_ms: 100,
+            ..Default::default()
+        };
+        let out = slicer.refine_chunks(&samples, &chunks, &boundaries);
+        assert_eq!(out[0], chunks[0]);
+        assert!(out[1].0 < chunks[1].0, "{out:?} {chunks:?}");
+        assert_eq!(out[1].1, chunks[1].1);
+    }
+
+    #[test]
+    fn min_gap_replaces_boundary() {
+        let out = enforce_min_gap(&[(0, 100), (100, 200), (250, 300)], 10);
+        assert_eq!(out, vec![(0, 95), (105, 200), (250, 300)]);
+    }
+
+    #[test]
+    fn min_gap_merges_overlapping_short_chunks() {
+        // 第二个切片被回退到与第一个重叠，裁剪后会变为空，应合并
+        let out = enforce_min_gap(&[(0, 100), (96, 102)], 10);
+        assert_eq!(out, vec![(0, 102)]);
+    }
+
+    #[test]
+    fn sample_ranges_apply_overlap() {
+        let out = to_sample_ranges(&[(0, 10), (10, 20), (20, 30)], 80, 2300, 100);
+        assert_eq!(out, vec![(0, 800), (700, 1600), (1500, 2300)]);
+    }
+
+    #[test]
+    fn boundaries_snap_to_nearest_zero_crossing() {
+        // 周期 40 个样本的正弦: 过零点在 20 的整数倍处
+        let samples: Vec<f32> = (0..400)
+            .map(|i| (i as f32 / 40.0 * std::f32::consts::TAU + 0.01).sin())
+            .collect();
+        let ranges = [(0, 95), (95, 213), (213, 400)];
+        assert_eq!(
+            snap_to_zero_crossings(&samples, &ranges, 10),
+            vec![(0, 100), (100, 220), (220, 400)]
+        );
+        // 搜索范围内没有过零点时保持不变
+        assert_eq!(snap_to_zero_crossings(&samples, &ranges, 3), ranges);
+        let silence = vec![0i16; 100];
+        assert_eq!(
+            snap_to_zero_crossings(&silence, &[(17, 60)], 10),
+            [(17, 60)]
+        );
+    }
+
+    #[test]
+    fn segment_ranges_end_on_zero_crossings() {
+        // 周期 38 个样本的正弦，中间一段衰减到阈值以下；过零点不在帧边界 (hop 为 10 个样本) 上
+        let samples: Vec<f32> = (0..800)
+            .map(|i| {
+                let gain = if (300..500).contains(&i) { 0.001 } else { 0.5 };
+                gain * (i as f32 / 38.0 * std::f32::consts::TAU + 0.3).sin()
+            })
+            .collect();
+        let slicer = test_slicer();
+        let is_crossing = |i: usize| {
+            i == 0
+                || i == samples.len()
+                || samples[i - 1] == 0.0
+                || samples[i] == 0.0
+                || (samples[i - 1] < 0.0) != (samples[i] < 0.0)
+        };
+
+        let plain = slicer.segment_ranges(&samples, &BoundaryConfig::default());
+        assert!(plain.iter().any(|(range, _)| !is_crossing(range.start)));
+        let boundaries = BoundaryConfig {
+            overlap_ms: 30,
+            zero_cross_max_shift_ms: 10,
+            ..Default::default()
+        };
+        let ranges = slicer.segment_ranges(&samples, &boundaries);
+        assert_eq!(ranges.len(), 2);
+        for (range, _) in &ranges {
+            assert!(
+                is_crossing(range.start) && is_crossing(range.end),
+                "{range:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn short_ranges_extend_within_bounds() {
+        assert_eq!(extend_to_min_length(100, 200, 50, 1000), Some((100, 200)));
+        assert_eq!(extend_to_min_length(500, 500, 100, 1000), Some((450, 550)));
+        assert_eq!(extend_to_min_length(10, 20, 100, 1000), Some((0, 100)));
+        assert_eq!(
+            extend_to_min_length(990, 1000, 100, 1000),
+            Some((900, 1000))
+        );
+        assert_eq!(extend_to_min_length(0, 10, 100, 50), None);
+    }
+
+    #[test]
+    fn fixed_windows_overlap_and_cover_the_tail() {
+        assert_eq!(
+            fixed_windows(1000, 400, 100),
+            [(0, 400), (300, 700), (600, 1000)]
+        );
+        assert_eq!(
+            fixed_windows(1050, 400, 100),
+            [(0, 400), (300, 700), (600, 1000), (650, 1050)]
+        );
+        assert_eq!(fixed_windows(1000, 500, 0), [(0, 500), (500, 1000)]);
+        assert_eq!(fixed_windows(300, 400, 100), [(0, 300)]);
+        assert_eq!(fixed_windows(0, 400, 100), []);
+    }
+
+    #[test]
+    fn coarse_analysis_matches_fine() {
+        // 长静音中夹杂短促突发、一个非有限样本，以及末尾不足一块的残余帧
+        let mut samples = vec![0.0005; 3000];
+        for i in (400..3000).step_by(700) {
+            for s in &mut samples[i..i + 30] {
+                *s = 0.3;
+            }
+        }
+        samples[2000] = f32::NAN;
+        samples.extend(vec![0.2; 37]);
+
+        for metric in [
+            DetectionMetric::Rms,
+            DetectionMetric::Peak,
+            DetectionMetric::Hybrid,
+        ] {
+            let config = SlicerConfig {
+                metric,
+                max_silence_ms: 200,
+                ..test_config()
+            };
+            let fine = Slicer::new(config.clone()).unwrap();
+            let coarse = Slicer::new(SlicerConfig {
+                coarse_hop_factor: 8,
+                ..config
+            })
+            .unwrap();
+            assert_eq!(coarse.coarse_hop_factor, 8);
+            assert_eq!(
+                fine.frame_silence(&samples),
+                coarse.frame_silence(&samples),
+                "{metric:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn coarse_analysis_keeps_cuts_in_voiced_audio() {
+        // 以有声为主: 有声段内有短于两块的停顿与接近阈值的弱音，段间为长静音
+        let mut samples = vec![];
+        for i in 0..10 {
+            samples.extend((0..400 + i * 37).map(|j| if j % 2 == 0 { 0.4 } else { -0.4 }));
+            samples.extend(vec![0.0; 40 + i]);
+            samples.extend(vec![0.012; 150]);
+            samples.extend(vec![0.0; 250 + i * 13]);
+        }
+
+        for metric in [
+            DetectionMetric::Rms,
+            DetectionMetric::Peak,
+            DetectionMetric::Hybrid,
+        ] {
+            let config = SlicerConfig {
+                metric,
+                max_silence_ms: 200,
+                ..test_config()
+            };
+            let fine = Slicer::new(config.clone()).unwrap().slice_frames(&samples);
+            assert!(fine.len() > 5, "{metric:?}");
+            for factor in [2, 4, 8, 10] {
+                let coarse = Slicer::new(SlicerConfig {
+                    coarse_hop_factor: factor,
+                    ..config.clone()
+                })
+                .unwrap();
+                assert_eq!(coarse.slice_frames(&samples), fine, "{metric:?} x{factor}");
+            }
+        }
+        // 步长限制在最大静音长度的一半以内
+        let clamped = Slicer::new(SlicerConfig {
+            coarse_hop_factor: 32,
+            ..test_config()
+        })
+        .unwrap();
+        assert_eq!(clamped.coarse_hop_factor, 2);
+    }
+
+    #[test]
+    fn block_analysis_matches_whole_signal() {
+        let mut samples = vec![];
+        for i in 0..12 {
+            samples.extend(vec![0.5; 150 + i * 20]);
+            samples.extend(vec![0.0; 60 + i * 15]);
+        }
+
+        let slicer = Slicer::new(SlicerConfig {
+            coarse_hop_factor: 4,
+            max_silence_ms: 100,
+            ..test_config()
+        })
+        .unwrap();
+        let whole = slicer.slice_in_blocks(&samples, usize::MAX, &mut ());
+        assert!(whole.len() > 1);
+        for block in [1, 3, 7, 64] {
+            assert_eq!(
+                slicer.slice_in_blocks(&samples, block, &mut ()),
+                whole,
+                "block={block}"
+            );
+        }
+    }
+
+    #[test]
+    fn masked_detection_matches_energy_detection() {
+        let mut samples = vec![];
+        for i in 0..6 {
+            samples.extend(vec![0.5; 150 + i * 20]);
+            samples.extend(vec![0.0; 60 + i * 15]);
+        }
+        let slicer = test_slicer();
+        let silence = slicer.frame_silence(&samples);
+        assert_eq!(
+            slicer.detect_chunks_masked(&silence, &mut ()),
+            slicer.slice_frames(&samples)
+        );
+    }
+
+    #[test]
+    fn fed_chunks_match_whole_signal() {
+        let mut samples = vec![];
+        for i in 0..12 {
+            samples.extend(vec![0.5; 150 + i * 20]);
+            samples.extend(vec![0.0; 60 + i * 15]);
+        }
+        samples.extend(vec![0.5; 137]);
+
+        let mut slicer = test_slicer();
+        let whole: Vec<(usize, usize)> = slicer
+            .slice_frames(&samples)
+            .iter()
+            .map(|&(start, end)| (start * 10, (end * 10).min(samples.len())))
+            .collect();
+        assert!(whole.len() > 1);
+        for block in [1, 7, 64, 1000, samples.len()] {
+            let mut slices = vec![];
+            for chunk in samples.chunks(block) {
+                slices.extend(slicer.feed(chunk));
+                // 只缓冲当前片段 (最长约 600 样本) 与判定停顿所需的样本
+                assert!(slicer.stream.buffer.len() <= 800, "block={block}");
+            }
+            // finish 之后切片器可以处理下一个流
+            slices.extend(slicer.finish());
+            let ranges: Vec<(usize, usize)> = slices
+                .iter()
+                .map(|slice| (slice.start_sample, slice.end_sample))
+                .collect();
+            assert_eq!(ranges, whole, "block={block}");
+            for slice in &slices {
+                assert_eq!(slice.samples, samples[slice.start_sample..slice.end_sample]);
+            }
+        }
+    }
+
+    #[test]
+    fn max_latency_flushes_continuous_speech() {
+        let slicer = test_slicer();
+        let mut state = SliceState::with_max_latency(30);
+        let mut flushed = vec![];
+        // 逐帧喂入 70 帧连续有声: 不等停顿，每 30 帧输出一次
+        for frame in 0..70 {
+            let chunks = slicer.advance(&mut state, &[false], &mut ());
+            if !chunks.is_empty() {
+                flushed.push((frame, chunks));
+            }
+        }
+        assert_eq!(flushed, vec![(29, vec![(0, 30)]), (59, vec![(30, 60)])]);
+        // 停顿后的自然切分不受影响
+        let chunks = slicer.advance(&mut state, &[true; 8], &mut ());
+        assert!(chunks.is_empty());
+        assert_eq!(
+            slicer.advance(&mut state, &[false], &mut ()),
+            vec![(60, 72)]
+        );
+    }
+
+    #[test]
+    fn non_finite_frames_are_skipped() {
+        let mut samples = vec![0.5; 300];
+        samples[150] = f32::NAN;
+        samples[160] = f32::INFINITY;
+
+        let slicer = test_slicer();
+        let silence = slicer.frame_silence(&samples);
+        // 覆盖非有限样本的帧被视为静音，其余帧不受影响
+        assert!(silence[15]);
+        assert!(!silence[0] && !silence[29]);
+        assert!(slicer.frame_rms(&samples).iter().all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn segment_stats_match_detection() {
+        let slicer = test_slicer();
+        // 前 200 个样本为 -20dBFS 方波，后 200 个为静音
+        let mut samples: Vec<f32> = (0..200)
+            .map(|i| if i % 2 == 0 { 0.1 } else { -0.1 })
+            .collect();
+        samples.extend(std::iter::repeat_n(0.0, 200));
+
+        let stats = slicer.segment_stats(&samples, 0..400);
+        assert!((stats.peak_db + 20.0).abs() < 0.01);
+        // 一半帧为有声，平均能量低 3dB
+        assert!((stats.rms_db + 23.01).abs() < 0.5, "{}", stats.rms_db);
+        assert!((stats.voiced_ratio - 0.5).abs() < 0.1);
+
+        let loud = slicer.segment_stats(&samples, 0..200);
+        assert!((loud.rms_db + 20.0).abs() < 0.01);
+        assert_eq!(loud.voiced_ratio, 1.0);
+
+        let filter = |threshold| SilenceFilter {
+            threshold,
+            min_audio_ratio: 0.1,
+            ratio_mode: RatioMode::Samples,
+        };
+        assert!((filter(0.01).threshold_db() + 40.0).abs() < 1e-4);
+        assert!(!filter(0.01).is_silence(&samples[..200], &loud, 1000));
+        assert!(filter(0.2).is_silence(&samples[..200], &loud, 1000));
+    }
+
+    #[test]
+    fn frame_ratio_counts_quiet_continuous_speech() {
+        // 低电平连续信号: 大部分样本幅度低于阈值，但每帧 RMS 均超过阈值
+        let samples: Vec<f32> = (0..1000)
+            .map(|i| if i % 10 == 0 { 0.05 } else { 0.004 })
+            .collect();
+        let stats = test_slicer().segment_stats(&samples, 0..samples.len());
+        let mut filter = SilenceFilter {
+            threshold: 0.01,
+            min_audio_ratio: 0.5,
+            ratio_mode: RatioMode::Samples,
+        };
+        assert!(filter.is_silence(&samples, &stats, 1000));
+        filter.ratio_mode = RatioMode::Frames;
+        assert!(!filter.is_silence(&samples, &stats, 1000));
+        // 默认按检测器的帧判定统计，与切片检测的结论一致
+        filter.ratio_mode = RatioMode::default();
+        assert_eq!(stats.voiced_ratio, 1.0);
+        assert!(!filter.is_silence(&samples, &stats, 1000));
+        let quiet = SegmentStats {
+            voiced_ratio: 0.4,
+            ..stats
+        };
+        assert!(filter.is_silence(&samples, &quiet, 1000));
+    }
 }
@@ -0,0 +1,197 @@
+//! 频谱门限静音检测
+//!
+//! 对每个分析帧做短时傅里叶变换，把能量按对数间隔的频带汇总，与整段录音估计的逐频带
+//! 噪声底 (各频带能量的低百分位) 比较。恒定的宽带底噪 (嘶声) 抬高所有频带的噪声底，
+//! 语音只在部分频带明显高于它，单一的 RMS 阈值无法把两者分开。
+
+use realfft::RealFftPlanner;
+
+use crate::audio::Sample;
+
+/// 频带数的上限 (低采样率下频点不足时减少)
+const BANDS: usize = 16;
+/// 每个频带至少包含的频点数，频点太少的频带能量起伏大，容易被底噪偶然打开
+const MIN_BAND_BINS: usize = 4;
+/// 频带能量在相邻帧间做滑动平均的帧数 (奇数)
+const SMOOTH_FRAMES: usize = 5;
+/// 最低频带的下限 (Hz)
+const LOWEST_HZ: f32 = 80.0;
+/// 最高频带的上限 (Hz)，高于奈奎斯特频率时取奈奎斯特频率
+const HIGHEST_HZ: f32 = 8000.0;
+/// 分析窗口时长 (ms)，样本数向上取整到 2 的幂
+const WINDOW_MS: u32 = 32;
+/// 噪声底取各频带能量 (所有帧) 的该百分位
+const NOISE_PERCENTILE: usize = 10;
+/// 高于噪声底的频带占全部频带的比例不低于该值时判为有声
+const MIN_OPEN_FRACTION: f32 = 0.25;
+/// 能量取对数时的下限，避免 log(0)
+const ENERGY_FLOOR: f32 = 1e-12;
+
+/// 各频带在 FFT 输出中的频点区间: 按对数间隔划分，相邻频带首尾相接，
+/// 不足 `MIN_BAND_BINS` 个频点的频带向上扩展，到达上限频率为止
+fn band_bins(sample_rate: u32, fft_len: usize) -> Vec<(usize, usize)> {
+    let top_hz = HIGHEST_HZ.min(sample_rate as f32 / 2.0);
+    let bin = |hz: f32| (hz * fft_len as f32 / sample_rate as f32).round() as usize;
+    let top = bin(top_hz).min(fft_len / 2);
+    let ratio = (top_hz / LOWEST_HZ).powf(1.0 / BANDS as f32);
+    let mut bands = Vec::with_capacity(BANDS);
+    let mut start = bin(LOWEST_HZ);
+    for k in 1..=BANDS {
+        let end = bin(LOWEST_HZ * ratio.powi(k as i32)).max(start + MIN_BAND_BINS);
+        if end > top {
+            break;
+        }
+        bands.push((start, end));
+        start = end;
+    }
+    bands
+}
+
+/// 每帧各频带的平均能量 (dB，经相邻帧滑动平均)，第 `i` 帧的窗口以样本
+/// `i * hop_size + hop_size / 2` 为中心
+fn band_energy_db<S: Sample>(
+    samples: &[S],
+    sample_rate: u32,
+    hop_size: usize,
+    frame_count: usize,
+) -> Vec<Vec<f32>> {
+    let fft_len = ((sample_rate * WINDOW_MS / 1000) as usize)
+        .next_power_of_two()
+        .max(4);
+    let fft = RealFftPlanner::<f32>::new().plan_fft_forward(fft_len);
+    let hann: Vec<f32> = (0..fft_len)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / fft_len as f32).cos())
+        .collect();
+    let bands = band_bins(sample_rate, fft_len);
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+    let mut scratch = fft.make_scratch_vec();
+
+    let power: Vec<Vec<f32>> = (0..frame_count)
+        .map(|i| {
+            let center = i * hop_size + hop_size / 2;
+            for (j, slot) in input.iter_mut().enumerate() {
+                // 窗口超出信号的部分补零，非有限样本按 0 计
+                let x = (center + j)
+                    .checked_sub(fft_len / 2)
+                    .and_then(|k| samples.get(k))
+                    .map_or(0.0, |x| x.to_f32());
+                *slot = if x.is_finite() { x * hann[j] } else { 0.0 };
+            }
+            // 输入与输出长度由同一计划生成，不会出错
+            let _ = fft.process_with_scratch(&mut input, &mut spectrum, &mut scratch);
+            bands
+                .iter()
+                .map(|&(start, end)| {
+                    let sum: f32 = spectrum[start..end].iter().map(|c| c.norm_sqr()).sum();
+                    sum / (end - start) as f32
+                })
+                .collect()
+        })
+        .collect();
+
+    let reach = SMOOTH_FRAMES / 2;
+    (0..frame_count)
+        .map(|i| {
+            let neighbours = &power[i.saturating_sub(reach)..(i + reach + 1).min(frame_count)];
+            (0..bands.len())
+                .map(|band| {
+                    let mean = neighbours.iter().map(|frame| frame[band]).sum::<f32>()
+                        / neighbours.len() as f32;
+                    10.0 * mean.max(ENERGY_FLOOR).log10()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// 判断 `frame_count` 个分析帧 (第 `i` 帧从样本 `i * hop_size` 开始) 是否为静音:
+/// 能量比该频带噪声底高出 `margin_db` 以上的频带不足四分之一即为静音
+///
+/// 噪声底按整段信号估计，假定录音中至少有一成的帧只有底噪。
+pub fn frame_silence<S: Sample>(
+    samples: &[S],
+    sample_rate: u32,
+    hop_size: usize,
+    frame_count: usize,
+    margin_db: f32,
+) -> Vec<bool> {
+    let energy = band_energy_db(samples, sample_rate, hop_size, frame_count);
+    let band_count = energy.first().map_or(0, Vec::len);
+    if band_count == 0 {
+        return vec![true; frame_count];
+    }
+    let mut column = Vec::with_capacity(energy.len());
+    let floor: Vec<f32> = (0..band_count)
+        .map(|band| {
+            column.clear();
+            column.extend(energy.iter().map(|frame| frame[band]));
+            let index = (column.len() - 1) * NOISE_PERCENTILE / 100;
+            *column.select_nth_unstable_by(index, f32::total_cmp).1
+        })
+        .collect();
+    let min_open = ((band_count as f32 * MIN_OPEN_FRACTION).ceil() as usize).max(1);
+    energy
+        .iter()
+        .map(|frame| {
+            let open = frame
+                .iter()
+                .zip(&floor)
+                .filter(|&(&db, &floor_db)| db > floor_db + margin_db)
+                .count();
+            open < min_open
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BANDS, MIN_BAND_BINS, band_bins, frame_silence};
+    use std::f32::consts::PI;
+
+    #[test]
+    fn bands_are_contiguous_and_wide_enough() {
+        for (sample_rate, fft_len) in [(8000, 256), (16000, 512), (48000, 2048)] {
+            let bands = band_bins(sample_rate, fft_len);
+            assert!(!bands.is_empty() && bands.len() <= BANDS, "{bands:?}");
+            assert!(
+                bands
+                    .iter()
+                    .all(|&(start, end)| end - start >= MIN_BAND_BINS)
+            );
+            assert!(bands.windows(2).all(|pair| pair[0].1 == pair[1].0));
+            assert!(bands.last().unwrap().1 <= fft_len / 2);
+        }
+    }
+
+    #[test]
+    fn separates_voiced_tone_from_steady_hiss() {
+        let sample_rate = 16000;
+        let mut seed = 12345u32;
+        let mut noise = || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+        };
+        // 3 秒底噪，第 1~2 秒叠加一个与底噪 RMS 相当的带泛音的音符
+        let samples: Vec<f32> = (0..3 * sample_rate)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let voiced = if (1.0..2.0).contains(&t) {
+                    (1..=8)
+                        .map(|h| 0.03 / h as f32 * (2.0 * PI * 220.0 * h as f32 * t).sin())
+                        .sum()
+                } else {
+                    0.0
+                };
+                0.1 * noise() + voiced
+            })
+            .collect();
+
+        let hop = 160;
+        let silence = frame_silence(&samples, sample_rate, hop, samples.len() / hop, 6.0);
+        // 避开音符起止处的过渡帧
+        assert!(silence[..90].iter().all(|&s| s));
+        assert!(silence[110..190].iter().all(|&s| !s));
+        assert!(silence[210..].iter().all(|&s| s));
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::Result;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Sender, channel};
+use std::thread::JoinHandle;
+
+use crate::refilter::move_file;
+
+/// 后台移动的统计结果
+#[derive(Debug, Default)]
+pub struct MoveReport {
+    /// 移动完成的目录数
+    pub dirs: usize,
+    pub files: usize,
+    pub bytes: u64,
+    /// 移动失败的目录及原因，失败时文件保留在暂存目录
+    pub errors: Vec<String>,
+}
+
+/// 后台移动线程: 把暂存目录中写完的切片目录移动到 (较慢的) 输出位置
+///
+/// 处理线程写完一个文件后调用 `enqueue`，编码吞吐不受网络存储延迟影响。
+/// 每个目录移动完成后以目标目录调用 `on_moved`。
+pub struct Mover {
+    scratch: PathBuf,
+    sender: Sender<(PathBuf, PathBuf, Option<File>)>,
+    handle: JoinHandle<MoveReport>,
+}
+
+impl Mover {
+    pub fn spawn(scratch: &Path, on_moved: impl Fn(&Path) + Send + 'static) -> Self {
+        let (sender, receiver) = channel::<(PathBuf, PathBuf, Option<File>)>();
+        let handle = std::thread::spawn(move || {
+            let mut report = MoveReport::default();
+            // 认领锁 (append 模式) 在目录移动完成后才释放
+            for (from, to, _claim) in receiver {
+                match move_dir(&from, &to) {
+                    Ok((files, bytes)) => {
+                        report.dirs += 1;
+                        report.files += files;
+                        report.bytes += bytes;
+                        on_moved(&to);
+                    }
+                    Err(e) => report.errors.push(format!("{}: {e}", from.display())),
+                }
+            }
+            report
+        });
+        Self {
+            scratch: scratch.to_path_buf(),
+            sender,
+            handle,
+        }
+    }
+
+    /// 排队移动一个写完的目录，`claim` 为目标目录的认领锁 (见 `claim_dir`)，移动完成后释放
+    pub fn enqueue(&self, from: PathBuf, to: PathBuf, claim: Option<File>) {
+        // 接收端只在 finish 时结束，发送不会失败
+        let _ = self.sender.send((from, to, claim));
+    }
+
+    /// 等待所有排队的移动完成，并清理暂存目录中留下的空目录
+    pub fn finish(self) -> MoveReport {
+        drop(self.sender);
+        let report = self.handle.join().expect("后台移动线程异常退出");
+        remove_empty_dirs(&self.scratch);
+        report
+    }
+}
+
+/// 移动目录中的所有文件 (含子目录)，返回 (文件数, 字节数)
+fn move_dir(from: &Path, to: &Path) -> Result<(usize, u64)> {
+    std::fs::create_dir_all(to)?;
+    let mut files = 0;
+    let mut bytes = 0;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            let (sub_files, sub_bytes) = move_dir(&path, &target)?;
+            files += sub_files;
+            bytes += sub_bytes;
+        } else {
+            bytes += entry.metadata()?.len();
+            move_file(&path, &target)?;
+            files += 1;
+        }
+    }
+    std::fs::remove_dir(from)?;
+    Ok((files, bytes))
+}
+
+/// 自底向上删除空目录 (根目录本身保留)
+fn remove_empty_dirs(root: &Path) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path);
+            let _ = std::fs::remove_dir(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mover;
+
+    #[test]
+    fn moves_staged_dirs_and_cleans_up() {
+        let root = std::env::temp_dir().join(format!("slicer_staging_{}", std::process::id()));
+        let scratch = root.join("scratch");
+        let output = root.join("out");
+        let staged = scratch.join("speaker/rec");
+        std::fs::create_dir_all(&staged).unwrap();
+        std::fs::write(staged.join("slice_000.wav"), b"1234").unwrap();
+        std::fs::write(staged.join("manifest.json"), b"{}").unwrap();
+
+        let mover = Mover::spawn(&scratch, |_| {});
+        mover.enqueue(staged.clone(), output.join("speaker/rec"), None);
+        let report = mover.finish();
+
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!((report.dirs, report.files, report.bytes), (1, 2, 6));
+        assert!(output.join("speaker/rec/slice_000.wav").is_file());
+        assert!(!scratch.join("speaker").exists());
+        assert!(scratch.is_dir());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+use crate::atomic::write_atomic;
+use crate::audit::csv_field;
+
+/// 逐文件统计表文件名 (写在输出目录下)
+pub const PER_FILE_STATS_FILE_NAME: &str = "per_file_stats.csv";
+
+/// 文件的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+impl FileStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// 逐文件统计表中的一行，时间单位均为秒
+#[derive(Debug, Clone)]
+pub struct FileStatsRow {
+    pub path: String,
+    pub status: FileStatus,
+    /// 失败原因或跳过原因
+    pub message: Option<String>,
+    pub audio_duration: f64,
+    pub slices: usize,
+    pub kept_duration: f64,
+    pub processing_time: f64,
+    pub load_time: f64,
+    pub slice_time: f64,
+    pub merge_time: f64,
+    pub save_time: f64,
+}
+
+/// 写出逐文件统计表，供在表格软件中排序和透视
+///
+/// 数字使用固定格式 (小数点为 `.`，不分组)，不受 `--locale` 影响；失败或跳过的文件
+/// 没有可用的时长时对应列留空。
+///
+/// 已有的统计表中本次运行没有处理的文件的行保留 (多个运行追加写入同一输出目录时
+/// 不会互相覆盖)，本次运行处理过的文件的行被替换；调用方应持有输出目录的索引锁。
+pub fn write_per_file_stats(output: &Path, rows: &[FileStatsRow]) -> Result<()> {
+    let path = output.join(PER_FILE_STATS_FILE_NAME);
+    let current: HashSet<&str> = rows.iter().map(|row| row.path.as_str()).collect();
+    let kept: Vec<String> = match std::fs::read_to_string(&path) {
+        Ok(text) => csv_records(&text)
+            .into_iter()
+            .skip(1)
+            .filter(|record| !current.contains(first_field(record).as_str()))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    std::fs::create_dir_all(output)?;
+    write_atomic(&path, |writer| write_rows(writer, &kept, rows))
+}
+
+/// 按记录拆分 CSV 文本；引号内的换行属于字段内容
+fn csv_records(text: &str) -> Vec<String> {
+    let mut records = Vec::new();
+    let mut record = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '\n' if !quoted => {
+                records.push(std::mem::take(&mut record));
+                continue;
+            }
+            _ => {}
+        }
+        record.push(c);
+    }
+    if !record.is_empty() {
+        records.push(record);
+    }
+    records
+}
+
+/// CSV 记录的第一个字段 (去掉 `csv_field` 加上的引号)
+fn first_field(record: &str) -> String {
+    let Some(rest) = record.strip_prefix('"') else {
+        return record.split(',').next().unwrap_or_default().to_string();
+    };
+    let mut field = String::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            if chars.peek() != Some(&'"') {
+                break;
+            }
+            chars.next();
+        }
+        field.push(c);
+    }
+    field
+}
+
+fn write_rows(writer: &mut impl Write, kept: &[String], rows: &[FileStatsRow]) -> Result<()> {
+    writeln!(
+        writer,
+        "path,status,duration_sec,slices,kept_sec,retention_pct,rtf,load_sec,slice_sec,merge_sec,save_sec,total_sec,message"
+    )?;
+    for record in kept {
+        writeln!(writer, "{record}")?;
+    }
+    for row in rows {
+        let processed = row.status == FileStatus::Ok;
+        let ratio = |numerator: f64, decimals: usize| {
+            if processed && row.audio_duration > 0.0 {
+                format!("{:.decimals$}", numerator / row.audio_duration)
+            } else {
+                String::new()
+            }
+        };
+        let seconds = |value: f64| {
+            if processed {
+                format!("{value:.3}")
+            } else {
+                String::new()
+            }
+        };
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&row.path),
+            row.status.as_str(),
+            seconds(row.audio_duration),
+            if processed {
+                row.slices.to_string()
+            } else {
+                String::new()
+            },
+            seconds(row.kept_duration),
+            ratio(row.kept_duration * 100.0, 2),
+            ratio(row.processing_time, 4),
+            // 跳过的文件也经过了加载
+            if row.status == FileStatus::Skipped {
+                format!("{:.3}", row.load_time)
+            } else {
+                seconds(row.load_time)
+            },
+            seconds(row.slice_time),
+            seconds(row.merge_time),
+            seconds(row.save_time),
+            seconds(row.processing_time),
+            csv_field(row.message.as_deref().unwrap_or_default()),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileStatsRow, FileStatus, PER_FILE_STATS_FILE_NAME, write_per_file_stats};
+
+    fn row(path: &str, status: FileStatus) -> FileStatsRow {
+        FileStatsRow {
+            path: path.to_string(),
+            status,
+            message: None,
+            audio_duration: 10.0,
+            slices: 3,
+            kept_duration: 8.0,
+            processing_time: 0.5,
+            load_time: 0.1,
+            slice_time: 0.2,
+            merge_time: 0.0,
+            save_time: 0.2,
+        }
+    }
+
+    #[test]
+    fn writes_one_row_per_file() {
+        let dir = std::env::temp_dir().join(format!("slicer_summary_{}", std::process::id()));
+        let failed = FileStatsRow {
+            message: Some("解码失败, 格式不支持".to_string()),
+            ..row("in/b.m4a", FileStatus::Failed)
+        };
+        write_per_file_stats(&dir, &[row("in/a.wav", FileStatus::Ok), failed]).unwrap();
+
+        let text = std::fs::read_to_string(dir.join(PER_FILE_STATS_FILE_NAME)).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "in/a.wav,ok,10.000,3,8.000,80.00,0.0500,0.100,0.200,0.000,0.200,0.500,"
+        );
+        assert_eq!(
+            lines[2],
+            "in/b.m4a,failed,,,,,,,,,,,\"解码失败, 格式不支持\""
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_rows_of_other_runs() {
+        let dir = std::env::temp_dir().join(format!("slicer_summary_merge_{}", std::process::id()));
+        let failed = FileStatsRow {
+            message: Some("第一行\n\"第二行\"".to_string()),
+            ..row("in/a,b.wav", FileStatus::Failed)
+        };
+        write_per_file_stats(&dir, &[failed, row("in/c.wav", FileStatus::Ok)]).unwrap();
+        // 另一运行处理了 in/a,b.wav (这次成功) 与 in/d.wav
+        write_per_file_stats(
+            &dir,
+            &[
+                row("in/a,b.wav", FileStatus::Ok),
+                row("in/d.wav", FileStatus::Ok),
+            ],
+        )
+        .unwrap();
+
+        let text = std::fs::read_to_string(dir.join(PER_FILE_STATS_FILE_NAME)).unwrap();
+        let paths: Vec<String> = super::csv_records(&text)
+            .iter()
+            .skip(1)
+            .map(|record| super::first_field(record))
+            .collect();
+        assert_eq!(paths, ["in/c.wav", "in/a,b.wav", "in/d.wav"]);
+        assert!(!text.contains("第二行"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
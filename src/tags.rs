@@ -0,0 +1,191 @@
+use anyhow::Result;
+use std::fs::OpenOptions;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::loudness::{R128_REFERENCE_LUFS, REPLAYGAIN_REFERENCE_LUFS, integrated_loudness};
+
+/// 写入输出文件的元数据标签 (键, 值)
+pub type Tags = Vec<(String, String)>;
+
+/// 计算切片的 ReplayGain 2.0 与 R128 增益标签
+///
+/// 返回标签与 ReplayGain 音轨增益 (dB)；切片过短或全为静音时无法测得响度，返回空标签。
+pub fn loudness_tags(samples: &[f32], sample_rate: u32) -> (Tags, Option<f32>) {
+    let Some(loudness) = integrated_loudness(samples, sample_rate) else {
+        return (Tags::new(), None);
+    };
+    let track_gain = REPLAYGAIN_REFERENCE_LUFS - loudness;
+    let peak = samples.iter().fold(0.0f32, |max, &x| max.max(x.abs()));
+    // R128_TRACK_GAIN 为 Q7.8 定点数
+    let r128_gain = ((R128_REFERENCE_LUFS - loudness) * 256.0)
+        .round()
+        .clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+
+    let tags = vec![
+        (
+            "REPLAYGAIN_TRACK_GAIN".to_string(),
+            format!("{track_gain:.2} dB"),
+        ),
+        ("REPLAYGAIN_TRACK_PEAK".to_string(), format!("{peak:.6}")),
+        ("R128_TRACK_GAIN".to_string(), r128_gain.to_string()),
+    ];
+    (tags, Some(track_gain))
+}
+
+/// 溯源标签：源文件、在源文件中的起止时间、切片器版本与参数指纹
+pub fn provenance_tags(source: &str, start_sec: f64, end_sec: f64, params_hash: &str) -> Tags {
+    vec![
+        ("SOURCE".to_string(), source.to_string()),
+        ("SOURCE_START".to_string(), format!("{start_sec:.6}")),
+        ("SOURCE_END".to_string(), format!("{end_sec:.6}")),
+        (
+            "SLICER_VERSION".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        ),
+        ("SLICER_PARAMS_HASH".to_string(), params_hash.to_string()),
+    ]
+}
+
+/// 64 位 FNV-1a 哈希的十六进制表示，用作稳定的参数指纹
+pub fn fingerprint(data: &[u8]) -> String {
+    let hash = data.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// 在 WAV 文件末尾追加 `id3 ` 块写入标签 (ID3v2.4 TXXX 帧)，并更新 RIFF 长度
+///
+/// foobar2000、Mp3tag、mutagen 等工具均可读取 WAV 中的 ID3 块。
+pub fn append_wav_tags(path: &Path, tags: &[(String, String)]) -> Result<()> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+
+    let tag = id3v2_tag(tags);
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let end = file.seek(SeekFrom::End(0))?;
+    // 写入前检查，超出 RIFF 长度上限时文件保持原样
+    let riff_size = tagged_riff_size(end, tag.len())?;
+
+    file.write_all(b"id3 ")?;
+    file.write_all(&(tag.len() as u32).to_le_bytes())?;
+    file.write_all(&tag)?;
+    if tag.len() % 2 == 1 {
+        file.write_all(&[0])?;
+    }
+
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&riff_size.to_le_bytes())?;
+    Ok(())
+}
+
+/// 长度为 `end` 的 WAV 文件追加 `tag_len` 字节的 `id3 ` 块 (含块头与补齐字节) 后的 RIFF 长度
+fn tagged_riff_size(end: u64, tag_len: usize) -> Result<u32> {
+    anyhow::ensure!(end >= 12, "不是有效的 WAV 文件，无法写入标签");
+    let new_end = end + 8 + tag_len as u64 + (tag_len % 2) as u64;
+    u32::try_from(new_end - 8).map_err(|_| anyhow::anyhow!("WAV 文件过大，无法写入标签"))
+}
+
+/// 构造只包含 TXXX 帧的 ID3v2.4 标签
+fn id3v2_tag(tags: &[(String, String)]) -> Vec<u8> {
+    let mut frames = Vec::new();
+    for (key, value) in tags {
+        // 文本编码 0x03 = UTF-8，描述与值以 0 分隔
+        let mut body = vec![0x03];
+        body.extend_from_slice(key.as_bytes());
+        body.push(0);
+        body.extend_from_slice(value.as_bytes());
+
+        frames.extend_from_slice(b"TXXX");
+        frames.extend_from_slice(&syncsafe(body.len() as u32));
+        frames.extend_from_slice(&[0, 0]);
+        frames.extend_from_slice(&body);
+    }
+
+    let mut tag = Vec::with_capacity(frames.len() + 10);
+    tag.extend_from_slice(b"ID3");
+    tag.extend_from_slice(&[4, 0, 0]);
+    tag.extend_from_slice(&syncsafe(frames.len() as u32));
+    tag.extend_from_slice(&frames);
+    tag
+}
+
+/// ID3v2 同步安全整数 (每字节 7 位)
+fn syncsafe(value: u32) -> [u8; 4] {
+    [
+        ((value >> 21) & 0x7f) as u8,
+        ((value >> 14) & 0x7f) as u8,
+        ((value >> 7) & 0x7f) as u8,
+        (value & 0x7f) as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append_wav_tags, fingerprint, loudness_tags, syncsafe, tagged_riff_size};
+
+    #[test]
+    fn syncsafe_encoding() {
+        assert_eq!(syncsafe(0x7f), [0, 0, 0, 0x7f]);
+        assert_eq!(syncsafe(0x80), [0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        assert_eq!(fingerprint(b""), "cbf29ce484222325");
+        assert_eq!(fingerprint(b"a"), "af63dc4c8601ec8c");
+        assert_ne!(fingerprint(b"-40"), fingerprint(b"-41"));
+    }
+
+    #[test]
+    fn replaygain_of_quiet_tone_is_positive() {
+        let samples: Vec<f32> = (0..32000).map(|i| 0.05 * (i as f32 * 0.2).sin()).collect();
+        let (tags, gain) = loudness_tags(&samples, 16000);
+        assert!(gain.unwrap() > 0.0);
+        assert_eq!(tags[0].0, "REPLAYGAIN_TRACK_GAIN");
+        assert!(loudness_tags(&[0.0; 32000], 16000).0.is_empty());
+    }
+
+    #[test]
+    fn riff_size_is_checked_before_writing() {
+        assert_eq!(tagged_riff_size(44, 11).unwrap(), 44 + 8 + 12 - 8);
+        assert!(tagged_riff_size(8, 10).is_err());
+        let limit = u32::MAX as u64 + 8;
+        assert_eq!(tagged_riff_size(limit - 18, 10).unwrap(), u32::MAX);
+        assert!(tagged_riff_size(limit - 18, 11).is_err());
+    }
+
+    #[test]
+    fn tagged_wav_stays_readable() {
+        let path = std::env::temp_dir().join(format!("tags-test-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..100 {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let tags = vec![("SOURCE".to_string(), "a.wav".to_string())];
+        append_wav_tags(&path, &tags).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let riff_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        assert_eq!(riff_size + 8, bytes.len());
+        assert!(bytes.windows(4).any(|w| w == b"TXXX"));
+
+        let samples: Vec<i16> = hound::WavReader::open(&path)
+            .unwrap()
+            .samples::<i16>()
+            .map(|s| s.unwrap())
+            .collect();
+        assert_eq!(samples.len(), 100);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::atomic::create_file;
+use crate::slicer::{BoundaryDecision, DetectionObserver, SlicerConfig, TraceEvent};
+
+/// 检测跟踪日志文件名
+pub const TRACE_FILE_NAME: &str = "detection_trace.log";
+
+/// 逐文件的检测跟踪日志: 阈值穿越、静音长度、每个候选边界的取舍原因，
+/// 以及后续每个处理阶段增删的切片
+pub struct DetectionTrace {
+    sample_rate: u32,
+    hop_size: usize,
+    lines: Vec<String>,
+    /// 上一阶段的切片，用于比较本阶段的变化
+    previous: Vec<(usize, usize)>,
+}
+
+impl DetectionTrace {
+    /// `config.sample_rate` 应为实际采样率
+    pub fn new(source: &str, config: &SlicerConfig, hop_size: usize) -> Self {
+        let lines = vec![
+            format!("# 检测跟踪: {source}"),
+            format!(
+                "# 阈值 {} dB, 指标 {:?}, 最小片段 {}ms, 最小间隔 {}ms, 最大静音 {}ms, hop {}ms",
+                config.threshold_db,
+                config.metric,
+                config.min_length_ms,
+                config.min_interval_ms,
+                config.max_silence_ms,
+                config.hop_size_ms
+            ),
+        ];
+        Self {
+            sample_rate: config.sample_rate,
+            hop_size,
+            lines,
+            previous: Vec::new(),
+        }
+    }
+
+    fn seconds(&self, frame: usize) -> f64 {
+        (frame * self.hop_size) as f64 / self.sample_rate as f64
+    }
+
+    /// `HH:MM:SS.mmm` 形式的时间码
+    fn timecode(&self, frame: usize) -> String {
+        format_timecode(self.seconds(frame))
+    }
+
+    fn span(&self, (start, end): (usize, usize)) -> String {
+        format!("{} - {}", self.timecode(start), self.timecode(end))
+    }
+
+    fn millis(&self, frames: usize) -> u64 {
+        (self.seconds(frames) * 1000.0).round() as u64
+    }
+
+    /// 记录保存阶段被丢弃的切片 (样本区间) 及原因
+    pub fn reject(&mut self, samples: Range<usize>, reason: &str) {
+        let seconds = |sample: usize| sample as f64 / self.sample_rate as f64;
+        self.lines.push(format!(
+            "[{}] 丢弃切片 (至 {}): {reason}",
+            format_timecode(seconds(samples.start)),
+            format_timecode(seconds(samples.end))
+        ));
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut text = self.lines.join("\n");
+        text.push('\n');
+        create_file(path)?.write_all(text.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl DetectionObserver for DetectionTrace {
+    fn event(&mut self, event: TraceEvent) {
+        let line = match event {
+            TraceEvent::EnterSilence { frame } => {
+                format!("[{}] 低于阈值，进入静音", self.timecode(frame))
+            }
+            TraceEvent::SilenceRun {
+                start,
+                end,
+                decision,
+            } => {
+                let length = self.millis(end - start);
+                let verdict = match decision {
+                    BoundaryDecision::PauseTooShort => "不长于最大静音，不切分".to_string(),
+                    BoundaryDecision::Cut { clip } => format!("切分，切片 {}", self.span(clip)),
+                    BoundaryDecision::ClipTooShort { clip } => format!(
+                        "切分，但片段 {} 只有 {}ms，短于最小片段长度被丢弃",
+                        self.span(clip),
+                        self.millis(clip.1 - clip.0)
+                    ),
+                };
+                format!(
+                    "[{}] 高于阈值，静音 {length}ms (自 {}): {verdict}",
+                    self.timecode(end),
+                    self.timecode(start)
+                )
+            }
+            TraceEvent::LatencyFlush { clip } => format!(
+                "[{}] 连续有声达到最大延迟，强制输出切片 {}",
+                self.timecode(clip.1),
+                self.span(clip)
+            ),
+            TraceEvent::Tail { clip, kept } => {
+                let verdict = if kept {
+                    "保留".to_string()
+                } else {
+                    format!(
+                        "只有 {}ms，短于最小片段长度被丢弃",
+                        self.millis(clip.1 - clip.0)
+                    )
+                };
+                format!(
+                    "[{}] 文件结束，剩余片段 {}: {verdict}",
+                    self.timecode(clip.1),
+                    self.span(clip)
+                )
+            }
+        };
+        self.lines.push(line);
+    }
+
+    fn stage(&mut self, name: &'static str, chunks: &[(usize, usize)]) {
+        let before: HashSet<_> = self.previous.iter().copied().collect();
+        let after: HashSet<_> = chunks.iter().copied().collect();
+        let removed: Vec<String> = self
+            .previous
+            .iter()
+            .filter(|chunk| !after.contains(chunk))
+            .map(|&chunk| self.span(chunk))
+            .collect();
+        let added: Vec<String> = chunks
+            .iter()
+            .filter(|chunk| !before.contains(chunk))
+            .map(|&chunk| self.span(chunk))
+            .collect();
+
+        let mut line = format!(
+            "== {name}: {} -> {} 个切片",
+            self.previous.len(),
+            chunks.len()
+        );
+        // 第一个阶段的切片已由上面的逐条判定列出
+        if !self.previous.is_empty() {
+            for (label, spans) in [("移除", &removed), ("新增", &added)] {
+                if !spans.is_empty() {
+                    line.push_str(&format!("\n   {label}: {}", spans.join(", ")));
+                }
+            }
+        }
+        self.lines.push(line);
+        self.previous = chunks.to_vec();
+    }
+}
+
+fn format_timecode(seconds: f64) -> String {
+    let millis = (seconds * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DetectionTrace;
+    use crate::slicer::{BoundaryConfig, DetectionMetric, Slicer, SlicerConfig};
+
+    #[test]
+    fn records_decisions_and_stages() {
+        let config = SlicerConfig {
+            sample_rate: 1000,
+            threshold_db: -40.0,
+            min_length_ms: 100,
+            min_interval_ms: 20,
+            hop_size_ms: 10,
+            max_silence_ms: 50,
+            metric: DetectionMetric::Rms,
+            peak_threshold_db: -35.0,
+            coarse_hop_factor: 1,
+            refine_cuts: false,
+        };
+        let slicer = Slicer::new(config.clone()).unwrap();
+        // 有声 200ms，短停顿 30ms，有声 200ms，长静音 300ms，有声 200ms
+        let mut samples = vec![0.5; 200];
+        samples.extend(vec![0.0; 30]);
+        samples.extend(vec![0.5; 200]);
+        samples.extend(vec![0.0; 300]);
+        samples.extend(vec![0.5; 200]);
+
+        let mut trace = DetectionTrace::new("a.wav", &config, slicer.hop_size());
+        let boundaries = BoundaryConfig {
+            enable_merge: true,
+            max_merge_duration_ms: 10_000,
+            ..Default::default()
+        };
+        let chunks = slicer.detect_chunks_observed(&samples, &boundaries, &mut trace);
+        let merged = slicer.refine_chunks_observed(&samples, &chunks, &boundaries, &mut trace);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(merged.len(), 1);
+
+        let log = trace.lines.join("\n");
+        assert!(log.contains("不长于最大静音，不切分"), "{log}");
+        assert!(log.contains("切分，切片 00:00:00.000 - "), "{log}");
+        assert!(log.contains("== 检测: 0 -> 2 个切片"), "{log}");
+        assert!(log.contains("== 合并: 2 -> 1 个切片"), "{log}");
+        assert!(log.contains("移除: "), "{log}");
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::fs::{File, FileTimes};
+use std::path::{Path, PathBuf};
+
+use crate::archive::{is_archive_file, split_archive_path};
+
+/// 把输入目录树的时间戳与权限复制到输出目录树的对应目录，返回 (成功数, 失败信息)
+///
+/// 镜像的目录沿用对应输入目录的修改/访问时间与权限；每个文件的切片目录沿用源文件的
+/// 时间戳与其所在输入目录的权限。必须在所有输出写完后调用，否则写入会再次更新目录时间。
+/// 不存在的输出目录 (如处理失败的文件) 被忽略。
+pub fn preserve_tree_metadata(
+    input_base: &Path,
+    output: &Path,
+    files: &[(PathBuf, PathBuf)],
+) -> (usize, Vec<String>) {
+    // 输出目录 -> (时间戳来源, 权限来源)
+    let mut targets: BTreeMap<PathBuf, (PathBuf, PathBuf)> = BTreeMap::new();
+    for (file, file_output_dir) in files {
+        let Some(parent) = file.parent() else {
+            continue;
+        };
+        targets.insert(
+            file_output_dir.clone(),
+            (file.clone(), parent.to_path_buf()),
+        );
+        for dir in parent.ancestors() {
+            let Ok(relative) = dir.strip_prefix(input_base) else {
+                break;
+            };
+            targets
+                .entry(output.join(relative))
+                .or_insert_with(|| (dir.to_path_buf(), dir.to_path_buf()));
+        }
+    }
+
+    let mut copied = 0;
+    let mut errors = Vec::new();
+    for (target, (time_source, permission_source)) in &targets {
+        if !target.is_dir() {
+            continue;
+        }
+        match copy_metadata(target, time_source, permission_source) {
+            Ok(()) => copied += 1,
+            Err(e) => errors.push(format!("{}: {e}", target.display())),
+        }
+    }
+    (copied, errors)
+}
+
+/// 路径本身是压缩包或位于压缩包内时返回该压缩包
+fn containing_archive(path: &Path) -> Option<&Path> {
+    if is_archive_file(path) && path.is_file() {
+        return Some(path);
+    }
+    split_archive_path(path).map(|(archive, _)| archive)
+}
+
+fn copy_metadata(
+    target: &Path,
+    time_source: &Path,
+    permission_source: &Path,
+) -> std::io::Result<()> {
+    // 压缩包本身及其中的成员与目录沿用压缩包的时间戳、压缩包所在目录的权限
+    let time_source = containing_archive(time_source).unwrap_or(time_source);
+    let permission_source = containing_archive(permission_source)
+        .and_then(Path::parent)
+        .unwrap_or(permission_source);
+    let times = std::fs::metadata(time_source)?;
+    let mut file_times = FileTimes::new().set_modified(times.modified()?);
+    if let Ok(accessed) = times.accessed() {
+        file_times = file_times.set_accessed(accessed);
+    }
+    // 先设置时间再设置权限，避免只读权限导致无法修改时间
+    File::open(target)?.set_times(file_times)?;
+    std::fs::set_permissions(target, std::fs::metadata(permission_source)?.permissions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preserve_tree_metadata;
+    use std::fs::{File, FileTimes};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn copies_directory_and_file_times() {
+        let root = std::env::temp_dir().join(format!("slicer_tree_{}", std::process::id()));
+        let input = root.join("in");
+        let output = root.join("out");
+        std::fs::create_dir_all(input.join("speaker")).unwrap();
+        std::fs::create_dir_all(output.join("speaker/rec")).unwrap();
+        let file = input.join("speaker/rec.wav");
+        std::fs::write(&file, b"").unwrap();
+
+        let dir_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let file_time = SystemTime::UNIX_EPOCH + Duration::from_secs(2_000_000);
+        File::open(&file)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(file_time))
+            .unwrap();
+        File::open(input.join("speaker"))
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(dir_time))
+            .unwrap();
+
+        let files = [(file, output.join("speaker/rec"))];
+        let (copied, errors) = preserve_tree_metadata(&input, &output, &files);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(copied, 3);
+        let modified = |path: &str| {
+            std::fs::metadata(output.join(path))
+                .unwrap()
+                .modified()
+                .unwrap()
+        };
+        assert_eq!(modified("speaker"), dir_time);
+        assert_eq!(modified("speaker/rec"), file_time);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+//! 基于 Silero VAD (ONNX 模型) 的语音活动检测
+//!
+//! 通过 ONNX Runtime 运行 Silero VAD v5，在 16 kHz 信号上逐 32ms 窗口估计语音概率。
+//! 运行库在运行时动态加载 (`ORT_DYLIB_PATH` 或系统默认的库名)，程序本身不链接 ONNX Runtime；
+//! 运行库或模型不可用时由调用方回退到能量阈值检测。
+//!
+//! 推理需要在构建时启用 `vad` 特性；未启用时 `check_model` 总是返回错误。
+
+use anyhow::{Result, bail};
+#[cfg(feature = "vad")]
+use ort::session::Session;
+#[cfg(feature = "vad")]
+use ort::value::Tensor;
+use serde::Serialize;
+#[cfg(feature = "vad")]
+use std::borrow::Cow;
+#[cfg(feature = "vad")]
+use std::panic::catch_unwind;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "vad")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "vad")]
+use crate::dsp::resample;
+
+/// 模型的输入采样率
+const VAD_SAMPLE_RATE: u32 = 16000;
+/// 每次推理的窗口长度 (样本)
+const VAD_WINDOW: usize = 512;
+/// 每个窗口前附带的上一窗口末尾样本数
+#[cfg(feature = "vad")]
+const VAD_CONTEXT: usize = 64;
+/// 循环状态的形状
+#[cfg(feature = "vad")]
+const VAD_STATE_SHAPE: [usize; 3] = [2, 1, 128];
+
+/// 语音活动检测的参数
+#[derive(Debug, Clone, Serialize)]
+pub struct VadConfig {
+    /// Silero VAD 的 ONNX 模型文件
+    pub model: PathBuf,
+    /// 语音概率不低于该值的窗口判为有声
+    pub threshold: f32,
+}
+
+/// 未启用 `vad` 特性时的错误信息
+pub const UNSUPPORTED: &str = "构建时未启用 vad 特性，需以 --features vad 重新编译";
+
+#[cfg(feature = "vad")]
+fn open_session(model: &Path) -> Result<Session> {
+    Ok(Session::builder()?
+        .with_intra_threads(1)?
+        .commit_from_file(model)?)
+}
+
+/// 加载 ONNX Runtime 运行库，每个进程只尝试一次，之后返回同样的结果
+///
+/// ort 在运行库无法加载或版本不兼容时 panic 而不是返回错误 (`ort::init_from` 同样只在
+/// 首次使用时加载，失败也是 panic)，这里临时替换 panic hook 并转为错误。panic hook 是
+/// 全局的，因此只能经 `check_model` 在启动处理线程之前调用。
+#[cfg(feature = "vad")]
+fn load_runtime() -> Result<()> {
+    static LOADED: OnceLock<Result<(), String>> = OnceLock::new();
+    let loaded = LOADED.get_or_init(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let result = catch_unwind(|| ort::init().commit());
+        std::panic::set_hook(hook);
+        match result {
+            Ok(committed) => committed.map(|_| ()).map_err(|e| e.to_string()),
+            Err(panic) => Err(panic
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default()),
+        }
+    });
+    match loaded {
+        Ok(()) => Ok(()),
+        Err(message) => bail!("无法加载 ONNX Runtime: {message}"),
+    }
+}
+
+/// 确认运行库与模型可用 (加载一次模型)
+///
+/// 首次调用会加载运行库 (见 `load_runtime`)，只应在主线程启动处理线程之前调用；成功之后
+/// 运行库保持加载，`frame_silence` 不再需要处理 panic。
+#[cfg(feature = "vad")]
+pub(crate) fn check_model(config: &VadConfig) -> Result<()> {
+    if !config.model.is_file() {
+        bail!("VAD 模型不存在: {}", config.model.display());
+    }
+    load_runtime()?;
+    open_session(&config.model).map(|_| ())
+}
+
+/// 确认运行库与模型可用: 未启用 `vad` 特性时总是返回错误
+#[cfg(not(feature = "vad"))]
+pub(crate) fn check_model(_config: &VadConfig) -> Result<()> {
+    bail!(UNSUPPORTED)
+}
+
+/// 每个 `VAD_WINDOW` 窗口的语音概率 (末尾不足一个窗口的部分补零)
+#[cfg(feature = "vad")]
+fn speech_probabilities(model: &Path, samples: &[f32], sample_rate: u32) -> Result<Vec<f32>> {
+    let mut session = open_session(model)?;
+    let samples = if sample_rate == VAD_SAMPLE_RATE {
+        Cow::Borrowed(samples)
+    } else {
+        Cow::Owned(resample(samples, sample_rate, VAD_SAMPLE_RATE))
+    };
+    let mut input = vec![0.0f32; VAD_CONTEXT + VAD_WINDOW];
+    let mut state = vec![0.0f32; VAD_STATE_SHAPE.iter().product()];
+    let mut probabilities = Vec::with_capacity(samples.len().div_ceil(VAD_WINDOW));
+    for window in samples.chunks(VAD_WINDOW) {
+        // 上一窗口的末尾作为本窗口的上下文
+        input.copy_within(VAD_WINDOW.., 0);
+        input[VAD_CONTEXT..].fill(0.0);
+        for (slot, &x) in input[VAD_CONTEXT..].iter_mut().zip(window) {
+            *slot = if x.is_finite() { x } else { 0.0 };
+        }
+        let outputs = session.run(ort::inputs![
+            "input" => Tensor::from_array(([1, VAD_CONTEXT + VAD_WINDOW], input.clone()))?,
+            "state" => Tensor::from_array((VAD_STATE_SHAPE, state))?,
+            "sr" => Tensor::from_array(((), vec![VAD_SAMPLE_RATE as i64]))?,
+        ])?;
+        let (_, probability) = outputs["output"].try_extract_tensor::<f32>()?;
+        probabilities.push(probability.first().copied().unwrap_or(0.0));
+        state = outputs["stateN"].try_extract_tensor::<f32>()?.1.to_vec();
+    }
+    Ok(probabilities)
+}
+
+#[cfg(not(feature = "vad"))]
+fn speech_probabilities(_model: &Path, _samples: &[f32], _sample_rate: u32) -> Result<Vec<f32>> {
+    bail!(UNSUPPORTED)
+}
+
+/// 按窗口语音概率得到 `frame_count` 个分析帧 (第 `i` 帧从样本 `i * hop_size` 开始) 的静音判定:
+/// 帧中点所在窗口的概率低于阈值即为静音
+fn frames_from_probabilities(
+    probabilities: &[f32],
+    threshold: f32,
+    sample_rate: u32,
+    hop_size: usize,
+    frame_count: usize,
+) -> Vec<bool> {
+    (0..frame_count)
+        .map(|i| {
+            let center_sec = (i * hop_size + hop_size / 2) as f64 / sample_rate as f64;
+            let window = (center_sec * VAD_SAMPLE_RATE as f64) as usize / VAD_WINDOW;
+            probabilities
+                .get(window)
+                .is_none_or(|&probability| probability < threshold)
+        })
+        .collect()
+}
+
+/// 用模型判定每个分析帧是否为静音，供 `Slicer::detect_chunks_masked` 使用
+///
+/// 需先用 `check_model` 确认运行库可用。
+pub fn frame_silence(
+    config: &VadConfig,
+    samples: &[f32],
+    sample_rate: u32,
+    hop_size: usize,
+    frame_count: usize,
+) -> Result<Vec<bool>> {
+    let probabilities = speech_probabilities(&config.model, samples, sample_rate)?;
+    Ok(frames_from_probabilities(
+        &probabilities,
+        config.threshold,
+        sample_rate,
+        hop_size,
+        frame_count,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VAD_WINDOW, VadConfig, check_model, frames_from_probabilities};
+
+    #[test]
+    fn maps_window_probabilities_to_frames() {
+        // 44.1 kHz、10ms 帧: 每个 32ms 窗口约覆盖 3.2 帧
+        let silence = frames_from_probabilities(&[0.9, 0.1, 0.7], 0.5, 44100, 441, 12);
+        assert_eq!(
+            silence,
+            [
+                false, false, false, true, true, true, false, false, false, false, true, true
+            ]
+        );
+        // 16 kHz 下帧与窗口一一对应
+        let silence = frames_from_probabilities(&[0.2, 0.8], 0.5, 16000, VAD_WINDOW, 2);
+        assert_eq!(silence, [true, false]);
+    }
+
+    #[test]
+    fn missing_model_is_an_error() {
+        let config = VadConfig {
+            model: std::env::temp_dir().join("no_such_silero_vad.onnx"),
+            threshold: 0.5,
+        };
+        assert!(check_model(&config).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::VerifyArgs;
+use crate::atomic::IndexLock;
+use crate::audit::AUDIT_DIR_NAME;
+use crate::catalog::Catalog;
+use crate::layout::is_shard_dir;
+use crate::manifest::{FileManifest, MANIFEST_FILE_NAME, read_manifest, write_manifest};
+use crate::refilter::find_manifests;
+
+/// 视为切片输出的文件扩展名，只有这些文件会被判定为孤立文件
+const OUTPUT_EXTENSIONS: &[&str] = &["wav", "flac", "mp3", "opus"];
+
+/// 发现不一致时的修复方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FixAction {
+    /// 删除磁盘上存在但清单中没有记录的切片文件 (包括所在目录没有清单的切片文件)
+    PruneOrphans,
+    /// 把文件已不存在的清单记录标记为 stale (文件恢复后取消标记)，
+    /// 并移除对已不存在的增强版本、波形峰值与噪声样本的引用
+    MarkStale,
+}
+
+/// 单个清单目录的检查结果
+#[derive(Debug, Default)]
+pub struct DirReport {
+    /// 清单中有记录但磁盘上不存在的文件 (已标记 stale 的记录除外)
+    pub missing: Vec<String>,
+    /// 磁盘上存在但清单中没有记录的切片文件
+    pub orphans: Vec<PathBuf>,
+}
+
+/// 清单引用的所有文件 (切片、增强版本、波形峰值与噪声样本)
+pub fn referenced_files(manifest: &FileManifest) -> HashSet<&str> {
+    let mut files: HashSet<&str> = manifest
+        .slices
+        .iter()
+        .flat_map(|record| {
+            std::iter::once(record.file.as_str()).chain(
+                record
+                    .augmented
+                    .iter()
+                    .map(|augmented| augmented.file.as_str())
+                    .chain(record.peaks.as_deref()),
+            )
+        })
+        .collect();
+    files.extend(manifest.noise_profile.as_deref());
+    files.extend(manifest.peaks.as_deref());
+    files
+}
+
+/// 双向比对清单与其所在目录
+pub fn check_dir(dir: &Path, manifest: &FileManifest) -> Result<DirReport> {
+    let referenced = referenced_files(manifest);
+    let stale: HashSet<&str> = manifest
+        .slices
+        .iter()
+        .filter(|record| record.stale)
+        .map(|record| record.file.as_str())
+        .collect();
+    let mut missing: Vec<String> = referenced
+        .iter()
+        .filter(|file| !stale.contains(*file) && !dir.join(file).is_file())
+        .map(|file| file.to_string())
+        .collect();
+    missing.sort();
+
+    // 切片目录及其中的分片目录 (`--shard-output`) 下的文件，以相对于切片目录的路径比对
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        if path.is_dir() && is_shard_dir(&name) {
+            for entry in std::fs::read_dir(&path)? {
+                let path = entry?.path();
+                let file = path.file_name().unwrap_or_default().to_string_lossy();
+                files.push((format!("{name}/{file}"), path));
+            }
+        } else {
+            files.push((name, path));
+        }
+    }
+    let mut orphans = Vec::new();
+    for (name, path) in files {
+        if path.is_file() && is_output_file(&path) && !referenced.contains(name.as_str()) {
+            orphans.push(path);
+        }
+    }
+    orphans.sort();
+    Ok(DirReport { missing, orphans })
+}
+
+/// 按文件是否存在更新记录的 stale 标记，并移除对已不存在的增强版本、波形峰值与噪声样本的引用，
+/// 返回是否有改动
+///
+/// 这些派生文件没有 stale 标记，重新生成前无法恢复引用，因此直接从清单中移除。
+fn mark_stale(dir: &Path, manifest: &mut FileManifest) -> bool {
+    let exists = |file: &str| dir.join(file).is_file();
+    let mut changed = false;
+    for record in &mut manifest.slices {
+        let stale = !exists(&record.file);
+        changed |= record.stale != stale;
+        record.stale = stale;
+
+        let before = record.augmented.len();
+        record.augmented.retain(|augmented| exists(&augmented.file));
+        changed |= record.augmented.len() != before;
+        if record.peaks.as_deref().is_some_and(|file| !exists(file)) {
+            record.peaks = None;
+            changed = true;
+        }
+    }
+    for file in [&mut manifest.noise_profile, &mut manifest.peaks] {
+        if file.as_deref().is_some_and(|file| !exists(file)) {
+            *file = None;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// 不在任何清单目录 (及其分片目录) 中的切片输出文件，如清单丢失的切片目录中的文件
+///
+/// 抽检样本目录 (`audit`) 中的文件不是切片输出，不计入。
+pub fn unmanaged_files(output: &Path, manifests: &[PathBuf]) -> Vec<PathBuf> {
+    let manifest_dirs: HashSet<&Path> = manifests.iter().filter_map(|path| path.parent()).collect();
+    let audit_dir = output.join(AUDIT_DIR_NAME);
+    let is_managed = |dir: &Path| {
+        manifest_dirs.contains(dir)
+            || dir.parent().is_some_and(|parent| {
+                manifest_dirs.contains(parent)
+                    && is_shard_dir(&dir.file_name().unwrap_or_default().to_string_lossy())
+            })
+    };
+    let mut files: Vec<PathBuf> = WalkDir::new(output)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != audit_dir)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_output_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .filter(|path| path.parent().is_some_and(|dir| !is_managed(dir)))
+        .collect();
+    files.sort();
+    files
+}
+
+fn is_output_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| OUTPUT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// `verify` 子命令: 比对输出目录中的清单 (及目录数据库) 与磁盘文件
+///
+/// 报告清单中缺失的文件与磁盘上的孤立切片；未指定 `--fix` 且发现不一致时返回错误，
+/// 便于在脚本中检查。
+pub fn run_verify(args: VerifyArgs) -> Result<()> {
+    let output = &args.output;
+    if !output.is_dir() {
+        return Err(anyhow::anyhow!("输出目录不存在: {}", output.display()));
+    }
+    // 读取-修改-写回清单期间阻止其他运行更新同一数据集
+    let _lock = IndexLock::acquire(output)?;
+    let prune = args.fix.contains(&FixAction::PruneOrphans);
+    let stale = args.fix.contains(&FixAction::MarkStale);
+
+    let manifests = find_manifests(output);
+    println!("📂 检查切片清单: {}个", manifests.len());
+
+    let mut missing_count = 0;
+    let mut orphan_count = 0;
+    for manifest_path in &manifests {
+        let dir = manifest_path.parent().unwrap_or(output);
+        let mut manifest = read_manifest(manifest_path)?;
+        let report = check_dir(dir, &manifest)?;
+
+        for file in &report.missing {
+            println!("   ❌ 缺失: {}", dir.join(file).display());
+        }
+        for path in &report.orphans {
+            println!("   ⚠️ 孤立: {}", path.display());
+            if prune {
+                std::fs::remove_file(path)?;
+            }
+        }
+        missing_count += report.missing.len();
+        orphan_count += report.orphans.len();
+
+        if stale && mark_stale(dir, &mut manifest) {
+            write_manifest(&dir.join(MANIFEST_FILE_NAME), &manifest)?;
+        }
+    }
+
+    for path in unmanaged_files(output, &manifests) {
+        println!("   ⚠️ 孤立 (所在目录没有清单): {}", path.display());
+        if prune {
+            std::fs::remove_file(&path)?;
+        }
+        orphan_count += 1;
+    }
+
+    let mut catalog_missing = 0;
+    if let Some(path) = &args.catalog {
+        let prefix = std::fs::canonicalize(output)?;
+        for file in Catalog::open(path)?.latest_slice_files(&prefix)? {
+            if !Path::new(&file).is_file() {
+                println!("   ❌ 目录数据库中缺失: {file}");
+                catalog_missing += 1;
+            }
+        }
+    }
+
+    println!("📊 检查结果:");
+    println!("   - 清单中缺失的文件: {missing_count} 个");
+    println!("   - 孤立的切片文件: {orphan_count} 个");
+    if args.catalog.is_some() {
+        println!("   - 目录数据库中缺失的文件: {catalog_missing} 个");
+    }
+    if prune && orphan_count > 0 {
+        println!("   - 已删除孤立文件");
+    }
+    if stale && missing_count > 0 {
+        println!("   - 已将缺失文件的清单记录标记为 stale，并移除缺失派生文件的引用");
+    }
+
+    let unresolved = (if prune { 0 } else { orphan_count })
+        + (if stale { 0 } else { missing_count })
+        + catalog_missing;
+    if unresolved > 0 {
+        return Err(anyhow::anyhow!("发现 {unresolved} 处不一致"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_dir, mark_stale, unmanaged_files};
+    use crate::manifest::AugmentedRecord;
+    use crate::manifest::test_manifest;
+
+    #[test]
+    fn finds_missing_and_orphans() {
+        let dir = std::env::temp_dir().join(format!("slicer_verify_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(dir.join("001")).unwrap();
+        for file in [
+            "slice_000.wav",
+            "slice_009.wav",
+            "mask.csv",
+            "001/slice_002.wav",
+        ] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+
+        let mut manifest = test_manifest(&["slice_000.wav", "slice_001.wav", "001/slice_002.wav"]);
+        let report = check_dir(&dir, &manifest).unwrap();
+        assert_eq!(report.missing, vec!["slice_001.wav"]);
+        assert_eq!(report.orphans, vec![dir.join("slice_009.wav")]);
+
+        assert!(mark_stale(&dir, &mut manifest));
+        assert!(!manifest.slices[0].stale);
+        assert!(manifest.slices[1].stale);
+        assert!(!mark_stale(&dir, &mut manifest));
+        assert!(check_dir(&dir, &manifest).unwrap().missing.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn mark_stale_resolves_derived_files() {
+        let dir =
+            std::env::temp_dir().join(format!("slicer_verify_derived_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["slice_000.wav", "slice_000_speed1.1.wav"] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+
+        let mut manifest = test_manifest(&["slice_000.wav"]);
+        manifest.slices[0].augmented = ["slice_000_speed1.1.wav", "slice_000_speed0.9.wav"]
+            .map(|file| AugmentedRecord {
+                file: file.to_string(),
+                speed: 1.0,
+            })
+            .to_vec();
+        manifest.slices[0].peaks = Some("slice_000.peaks.json".to_string());
+        manifest.noise_profile = Some("noise.wav".to_string());
+        assert_eq!(check_dir(&dir, &manifest).unwrap().missing.len(), 3);
+
+        assert!(mark_stale(&dir, &mut manifest));
+        assert!(!manifest.slices[0].stale);
+        assert_eq!(manifest.slices[0].augmented.len(), 1);
+        assert_eq!(manifest.slices[0].peaks, None);
+        assert_eq!(manifest.noise_profile, None);
+        assert!(check_dir(&dir, &manifest).unwrap().missing.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reports_slices_outside_manifest_dirs() {
+        let root = std::env::temp_dir().join(format!("slicer_verify_lost_{}", std::process::id()));
+        for file in [
+            "a/manifest.json",
+            "a/slice_000.wav",
+            "a/001/slice_001.wav",
+            "b/slice_000.wav",
+            "b/notes.txt",
+            "audit/sample_000.wav",
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+
+        let files = unmanaged_files(&root, &[root.join("a/manifest.json")]);
+        assert_eq!(files, vec![root.join("b/slice_000.wav")]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,269 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::atomic::create_file;
+
+/// 时间线图宽度 (像素 / SVG 列数)
+const WIDTH: usize = 1600;
+/// 时间线图高度
+const HEIGHT: usize = 240;
+/// 纵轴显示的最低电平 (dBFS)
+const FLOOR_DB: f32 = -80.0;
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const KEPT_FILL: [u8; 3] = [204, 236, 204];
+const FILTERED_FILL: [u8; 3] = [240, 212, 212];
+const ENVELOPE: [u8; 3] = [40, 70, 140];
+const THRESHOLD: [u8; 3] = [220, 40, 40];
+const BOUNDARY: [u8; 3] = [30, 120, 30];
+
+/// 时间线可视化的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VizFormat {
+    /// PNG 图片 (timeline.png)
+    Png,
+    /// 内嵌 SVG 的 HTML 页面 (timeline.html)，悬停可查看切片信息
+    Html,
+}
+
+impl VizFormat {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            VizFormat::Png => "timeline.png",
+            VizFormat::Html => "timeline.html",
+        }
+    }
+}
+
+/// 检测出的一个片段 (源采样率下的样本位置)
+#[derive(Debug, Clone)]
+pub struct VizSegment {
+    pub start_sample: usize,
+    pub end_sample: usize,
+    /// 输出的切片文件名，被二次过滤丢弃的片段为 `None`
+    pub file: Option<String>,
+}
+
+/// 单个文件的检测时间线
+pub struct Timeline<'a> {
+    /// 每帧 RMS (线性幅度)
+    pub rms: &'a [f32],
+    pub hop_size: usize,
+    pub sample_rate: u32,
+    pub threshold_db: f32,
+    pub segments: &'a [VizSegment],
+}
+
+impl Timeline<'_> {
+    fn total_samples(&self) -> usize {
+        (self.rms.len() * self.hop_size).max(1)
+    }
+
+    /// 样本位置对应的列
+    fn column(&self, sample: usize) -> usize {
+        (sample * WIDTH / self.total_samples()).min(WIDTH - 1)
+    }
+
+    /// 电平对应的纵坐标 (0 为顶部)
+    fn row(db: f32) -> usize {
+        let level = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+        ((1.0 - level) * (HEIGHT - 1) as f32).round() as usize
+    }
+
+    /// 每列取所覆盖帧的最大 RMS (dBFS)
+    fn column_envelope(&self) -> Vec<f32> {
+        let mut envelope = vec![FLOOR_DB; WIDTH];
+        for (i, &rms) in self.rms.iter().enumerate() {
+            let column = self.column(i * self.hop_size);
+            let db = 20.0 * rms.max(1e-10).log10();
+            envelope[column] = envelope[column].max(db);
+        }
+        envelope
+    }
+
+    /// 渲染为 RGB 像素
+    fn render(&self) -> Vec<u8> {
+        let mut pixels = BACKGROUND.repeat(WIDTH * HEIGHT);
+        let mut set = |x: usize, y: usize, color: [u8; 3]| {
+            let offset = (y * WIDTH + x) * 3;
+            pixels[offset..offset + 3].copy_from_slice(&color);
+        };
+
+        for segment in self.segments {
+            let fill = if segment.file.is_some() {
+                KEPT_FILL
+            } else {
+                FILTERED_FILL
+            };
+            let (start, end) = (
+                self.column(segment.start_sample),
+                self.column(segment.end_sample),
+            );
+            for x in start..=end {
+                for y in 0..HEIGHT {
+                    set(x, y, fill);
+                }
+            }
+        }
+
+        for (x, &db) in self.column_envelope().iter().enumerate() {
+            for y in Self::row(db)..HEIGHT {
+                set(x, y, ENVELOPE);
+            }
+        }
+
+        for segment in self.segments {
+            for x in [
+                self.column(segment.start_sample),
+                self.column(segment.end_sample),
+            ] {
+                for y in 0..HEIGHT {
+                    set(x, y, BOUNDARY);
+                }
+            }
+        }
+
+        let threshold_row = Self::row(self.threshold_db);
+        for x in 0..WIDTH {
+            set(x, threshold_row, THRESHOLD);
+        }
+        pixels
+    }
+
+    /// 写出 PNG 图片
+    pub fn write_png(&self, path: &Path) -> Result<()> {
+        let writer = BufWriter::new(create_file(path)?);
+        let mut encoder = png::Encoder::new(writer, WIDTH as u32, HEIGHT as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.render())?;
+        Ok(())
+    }
+
+    /// 写出内嵌 SVG 的 HTML 页面
+    pub fn write_html(&self, path: &Path, title: &str) -> Result<()> {
+        let mut writer = BufWriter::new(create_file(path)?);
+        let rgb = |[r, g, b]: [u8; 3]| format!("rgb({r},{g},{b})");
+        let seconds = |sample: usize| sample as f64 / self.sample_rate as f64;
+
+        writeln!(
+            writer,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title></head><body>",
+            escape(title)
+        )?;
+        writeln!(writer, "<h3>{}</h3>", escape(title))?;
+        writeln!(
+            writer,
+            "<svg viewBox=\"0 0 {WIDTH} {HEIGHT}\" width=\"100%\" preserveAspectRatio=\"none\" style=\"background:{}\">",
+            rgb(BACKGROUND)
+        )?;
+
+        for segment in self.segments {
+            let start = self.column(segment.start_sample);
+            let end = self.column(segment.end_sample);
+            let (fill, label) = match &segment.file {
+                Some(file) => (KEPT_FILL, escape(file)),
+                None => (FILTERED_FILL, "已过滤".to_string()),
+            };
+            writeln!(
+                writer,
+                "<rect x=\"{start}\" y=\"0\" width=\"{}\" height=\"{HEIGHT}\" fill=\"{}\" stroke=\"{}\"><title>{label} {:.3}s - {:.3}s</title></rect>",
+                (end - start).max(1),
+                rgb(fill),
+                rgb(BOUNDARY),
+                seconds(segment.start_sample),
+                seconds(segment.end_sample)
+            )?;
+        }
+
+        let points: Vec<String> = self
+            .column_envelope()
+            .iter()
+            .enumerate()
+            .map(|(x, &db)| format!("{x},{}", Self::row(db)))
+            .collect();
+        writeln!(
+            writer,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" />",
+            points.join(" "),
+            rgb(ENVELOPE)
+        )?;
+
+        let threshold_row = Self::row(self.threshold_db);
+        writeln!(
+            writer,
+            "<line x1=\"0\" y1=\"{threshold_row}\" x2=\"{WIDTH}\" y2=\"{threshold_row}\" stroke=\"{}\"><title>阈值 {}dB</title></line>",
+            rgb(THRESHOLD),
+            self.threshold_db
+        )?;
+        writeln!(writer, "</svg>")?;
+        writeln!(
+            writer,
+            "<p>时长 {:.2}s，{} 个片段 (绿色: 已输出，红色: 被二次过滤)，纵轴 {FLOOR_DB}dB 至 0dB</p>",
+            seconds(self.total_samples()),
+            self.segments.len()
+        )?;
+        writeln!(writer, "</body></html>")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 按格式写出到 `dir` 下
+    pub fn write(&self, format: VizFormat, dir: &Path, title: &str) -> Result<()> {
+        let path = dir.join(format.file_name());
+        match format {
+            VizFormat::Png => self.write_png(&path),
+            VizFormat::Html => self.write_html(&path, title),
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HEIGHT, Timeline, VizSegment, WIDTH};
+
+    fn timeline<'a>(rms: &'a [f32], segments: &'a [VizSegment]) -> Timeline<'a> {
+        Timeline {
+            rms,
+            hop_size: 10,
+            sample_rate: 1000,
+            threshold_db: -40.0,
+            segments,
+        }
+    }
+
+    #[test]
+    fn envelope_takes_column_maximum() {
+        let mut rms = vec![0.0; WIDTH * 2];
+        rms[1] = 1.0;
+        let envelope = timeline(&rms, &[]).column_envelope();
+        assert_eq!(envelope.len(), WIDTH);
+        assert!(envelope[0].abs() < 1e-4);
+        assert_eq!(envelope[1], super::FLOOR_DB);
+    }
+
+    #[test]
+    fn renders_segments_and_threshold() {
+        let rms = vec![0.1; 100];
+        let segments = [VizSegment {
+            start_sample: 0,
+            end_sample: 500,
+            file: Some("slice_000.wav".to_string()),
+        }];
+        let pixels = timeline(&rms, &segments).render();
+        assert_eq!(pixels.len(), WIDTH * HEIGHT * 3);
+        let pixel = |x: usize, y: usize| &pixels[(y * WIDTH + x) * 3..(y * WIDTH + x) * 3 + 3];
+        assert_eq!(pixel(WIDTH / 4, 0), super::KEPT_FILL);
+        assert_eq!(pixel(WIDTH * 3 / 4, 0), super::BACKGROUND);
+        assert_eq!(pixel(WIDTH * 3 / 4, Timeline::row(-40.0)), super::THRESHOLD);
+    }
+}